@@ -1,24 +1,115 @@
+//! [age](https://age-encryption.org) identities.
+
 use anyhow::Context;
 use x25519_dalek::{PublicKey, StaticSecret};
 
-pub fn identity(private_key: [u8; 32]) -> anyhow::Result<String> {
-    let public_key = PublicKey::from(&StaticSecret::from(private_key));
+pub use bech32::Variant;
+
+const PUBLIC_KEY_HRP: &str = "age";
+const SECRET_KEY_HRP: &str = "age-secret-key-";
 
+/// Renders the X25519 identity in the `age-keygen` format, including the public key comment.
+///
+/// The secret key is the canonical bech32 string uppercased as a whole, as `age-keygen` does:
+///
+/// ```
+/// use argon2derive::age::{self, Variant};
+///
+/// let identity = age::identity([7; 32], Variant::Bech32).unwrap();
+/// let secret_key = identity.lines().nth(1).unwrap();
+/// assert!(secret_key.starts_with("AGE-SECRET-KEY-1"));
+///
+/// let (hrp, data, variant) = bech32::decode(secret_key).unwrap();
+/// assert_eq!(hrp, "age-secret-key-");
+/// assert_eq!(variant, Variant::Bech32);
+/// assert_eq!(bech32::FromBase32::from_base32(&data), Ok(vec![7; 32]));
+/// ```
+pub fn identity(private_key: [u8; 32], variant: Variant) -> anyhow::Result<String> {
     Ok(format!(
         "# public key: {}\n{}\n",
-        bech32_encode("age", public_key.as_bytes())?,
-        bech32_encode("AGE-SECRET-KEY-", &private_key)?.to_uppercase(),
+        recipient(private_key, variant)?,
+        secret_key(private_key, variant)?,
     ))
 }
 
-fn bech32_encode(hrp: &str, bytes: &[u8]) -> anyhow::Result<String> {
-    // let mut data = vec![1]; // version
-    // data.extend_from_slice(bytes);
+/// Renders the secret key of the X25519 identity (`AGE-SECRET-KEY-1...`).
+pub fn secret_key(private_key: [u8; 32], variant: Variant) -> anyhow::Result<String> {
+    Ok(bech32_encode(SECRET_KEY_HRP, &private_key, variant)?.to_uppercase())
+}
+
+/// Renders the public key of the X25519 identity (`age1...`).
+pub fn recipient(private_key: [u8; 32], variant: Variant) -> anyhow::Result<String> {
+    let public_key = PublicKey::from(&StaticSecret::from(private_key));
+    bech32_encode(PUBLIC_KEY_HRP, public_key.as_bytes(), variant)
+}
+
+/// Encodes the bytes as a canonical (lowercase) bech32 string, `hrp` must be lowercase.
+fn bech32_encode(hrp: &str, bytes: &[u8], variant: Variant) -> anyhow::Result<String> {
+    debug_assert_eq!(hrp, hrp.to_lowercase());
+    bech32::encode(hrp, bech32::ToBase32::to_base32(&bytes), variant).context("bech32::encode")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variants_differ_by_checksum_only() {
+        let bech32 = recipient([7; 32], Variant::Bech32).unwrap();
+        let bech32m = recipient([7; 32], Variant::Bech32m).unwrap();
+
+        // 6 characters of checksum.
+        let (payload, checksum) = bech32.split_at(bech32.len() - 6);
+        assert!(bech32m.starts_with(payload));
+        assert!(!bech32m.ends_with(checksum));
+
+        let (_, data, variant) = bech32::decode(&bech32m).unwrap();
+        assert_eq!(variant, Variant::Bech32m);
+        assert_eq!(data, bech32::decode(&bech32).unwrap().1);
+    }
+
+    #[test]
+    fn identity_matches_age_keygen_format() {
+        let identity = identity([7; 32], Variant::Bech32).unwrap();
+        let lines: Vec<_> = identity.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            format!(
+                "# public key: {}",
+                recipient([7; 32], Variant::Bech32).unwrap()
+            )
+        );
+        assert_eq!(lines[1], secret_key([7; 32], Variant::Bech32).unwrap());
+        assert!(identity.ends_with('\n'));
+    }
+
+    #[test]
+    fn secret_key_round_trip() {
+        for key in [[0; 32], [7; 32], [0xff; 32]] {
+            let secret_key = secret_key(key, Variant::Bech32).unwrap();
+            assert!(secret_key.starts_with("AGE-SECRET-KEY-1"));
+            assert_eq!(secret_key, secret_key.to_uppercase());
+
+            let (hrp, data, variant) = bech32::decode(&secret_key).unwrap();
+            assert_eq!(hrp, SECRET_KEY_HRP);
+            assert_eq!(variant, Variant::Bech32);
+            assert_eq!(bech32::FromBase32::from_base32(&data), Ok(key.to_vec()));
+        }
+    }
+
+    #[test]
+    fn accepted_by_age() {
+        use std::str::FromStr as _;
 
-    bech32::encode(
-        hrp,
-        bech32::ToBase32::to_base32(&bytes),
-        bech32::Variant::Bech32,
-    )
-    .context("bech32::encode")
+        for key in [[0; 32], [7; 32], [0xff; 32]] {
+            let secret_key = secret_key(key, Variant::Bech32).unwrap();
+            let identity = ::age::x25519::Identity::from_str(&secret_key).unwrap();
+            assert_eq!(
+                identity.to_public().to_string(),
+                recipient(key, Variant::Bech32).unwrap()
+            );
+        }
+    }
 }
@@ -0,0 +1,94 @@
+//! Encryption of the derived secrets to age recipients, using the `age` crate.
+//!
+//! Unlike the derived age identities, which only need bech32, this implements the age file format
+//! itself, so the ciphertext can be decrypted by any age implementation (e.g. `age -d -i <identity>`).
+
+use std::io::Write as _;
+
+use age::{
+    Encryptor,
+    armor::{ArmoredWriter, Format},
+    x25519::Recipient,
+};
+use anyhow::Context as _;
+
+/// Parses an X25519 recipient (`age1...`).
+pub(super) fn parse_recipient(s: &str) -> anyhow::Result<Recipient> {
+    s.parse()
+        .map_err(|err| anyhow::anyhow!("Invalid age recipient: {err}"))
+}
+
+/// Encrypts the plaintext to all of the recipients, optionally ASCII armored.
+pub(super) fn encrypt(
+    recipients: &[Recipient],
+    plaintext: &[u8],
+    armor: bool,
+) -> anyhow::Result<Vec<u8>> {
+    let recipients = recipients.iter().map(|r| r as &dyn age::Recipient);
+    let encryptor = Encryptor::with_recipients(recipients).context("Encryptor::with_recipients")?;
+
+    let format = match armor {
+        true => Format::AsciiArmor,
+        false => Format::Binary,
+    };
+
+    let mut ciphertext = Vec::new();
+    let output = ArmoredWriter::wrap_output(&mut ciphertext, format)?;
+    let mut writer = encryptor.wrap_output(output)?;
+    writer.write_all(plaintext)?;
+    writer.finish().and_then(ArmoredWriter::finish)?;
+
+    Ok(ciphertext)
+}
+
+#[cfg(test)]
+mod tests {
+    use age::x25519::Identity;
+
+    use super::*;
+
+    #[test]
+    fn decrypted_by_every_recipient() {
+        let identities = [Identity::generate(), Identity::generate()];
+        let recipients: Vec<_> = identities.iter().map(Identity::to_public).collect();
+
+        for armor in [false, true] {
+            let ciphertext = encrypt(&recipients, b"secret", armor).unwrap();
+            assert_eq!(
+                ciphertext.starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----\n"),
+                armor
+            );
+            assert_eq!(ciphertext.starts_with(b"age-encryption.org/v1\n"), !armor);
+
+            for identity in &identities {
+                assert_eq!(age::decrypt(identity, &ciphertext).unwrap(), b"secret");
+            }
+            assert!(age::decrypt(&Identity::generate(), &ciphertext).is_err());
+        }
+    }
+
+    #[test]
+    fn recipients() {
+        let recipient = Identity::generate().to_public().to_string();
+        assert_eq!(parse_recipient(&recipient).unwrap().to_string(), recipient);
+
+        for invalid in [
+            "",
+            "age1",
+            &recipient[..recipient.len() - 1],
+            &recipient.to_uppercase().replacen("AGE1", "age1", 1),
+            "AGE-SECRET-KEY-1QQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQQ",
+        ] {
+            let err = parse_recipient(invalid).unwrap_err();
+            assert!(
+                err.to_string().starts_with("Invalid age recipient: "),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn no_recipients() {
+        assert!(encrypt(&[], b"secret", false).is_err());
+    }
+}
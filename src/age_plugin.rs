@@ -0,0 +1,552 @@
+//! [age plugin](https://c2sp.org/age-plugin) (`age-plugin-argon2derive`) decrypting the files
+//! encrypted to the derived age recipients, without exporting the identities.
+//!
+//! The plugin identities (`AGE-PLUGIN-ARGON2DERIVE-1...`) encode the name of the secret and the
+//! Argon2 parameters, including the salt with the name already appended, so the plugin needs
+//! neither the config nor the CLI options. Only the passphrase is asked for, via age.
+//! The derived X25519 keys are the same as the ones of the `age` command.
+
+use std::io::{self, BufRead, Write};
+
+use age::{Identity as _, secrecy::ExposeSecret as _};
+use age_core::format::Stanza;
+use anyhow::Context as _;
+use argon2derive::{age as age_keys, argon2};
+use base64::{Engine as _, engine::general_purpose};
+use unicode_normalization::UnicodeNormalization as _;
+use zeroize::Zeroizing;
+
+/// HRP of the plugin identities, age deriving the name of the plugin binary from it.
+const IDENTITY_HRP: &str = "age-plugin-argon2derive-";
+
+/// Version of the encoding of the plugin identities.
+const IDENTITY_VERSION: u8 = 1;
+
+/// Supported state machine of the plugin protocol.
+pub(super) const IDENTITY_V1: &str = "identity-v1";
+
+/// Tag of the stanzas of the age X25519 recipients.
+const X25519_TAG: &str = "X25519";
+
+/// Length of the lines of the stanza bodies (in base64 characters).
+const BODY_LINE_LEN: usize = 64;
+
+/// Everything needed to derive the key of a plugin identity, except for the passphrase.
+#[derive(Debug)]
+pub(super) struct Identity {
+    pub name: String,
+    /// Unicode normalization of the passphrase (`none`, `nfc` or `nfkc`).
+    pub normalization: String,
+    /// Parameters of the first pass, the name being already appended to the salt.
+    pub params: argon2::Parameters,
+    pub cascade: Vec<argon2::Parameters>,
+}
+
+impl Identity {
+    /// Encodes the identity as `AGE-PLUGIN-ARGON2DERIVE-1...`.
+    ///
+    /// The payload is `version || normalization || name || pass count || passes || salt`, the passes being
+    /// `algorithm (u8) || memory || time || parallelism` and the variable length fields prefixed with
+    /// their length, all integers being big-endian `u32` unless noted otherwise.
+    pub(super) fn encode(&self) -> anyhow::Result<String> {
+        let mut payload = vec![IDENTITY_VERSION, normalization_id(&self.normalization)];
+        put_bytes(&mut payload, self.name.as_bytes());
+
+        payload.push(
+            u8::try_from(self.cascade.len() + 1)
+                .ok()
+                .context("too many cascade passes")?,
+        );
+        for pass in [&self.params].into_iter().chain(&self.cascade) {
+            payload.push(algorithm_id(pass.algorithm));
+            for value in [pass.memory, pass.time, pass.parallelism] {
+                payload.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+
+        put_bytes(&mut payload, &self.params.salt);
+
+        let data = bech32::ToBase32::to_base32(&payload);
+        Ok(bech32::encode(IDENTITY_HRP, data, bech32::Variant::Bech32)
+            .context("bech32::encode")?
+            .to_uppercase())
+    }
+
+    /// Decodes an identity encoded by [`Self::encode`].
+    pub(super) fn decode(s: &str) -> anyhow::Result<Self> {
+        let (hrp, data, variant) = bech32::decode(s).context("invalid bech32")?;
+        if hrp != IDENTITY_HRP || variant != bech32::Variant::Bech32 {
+            return Err(anyhow::anyhow!("not an argon2derive plugin identity"));
+        }
+        let payload: Vec<u8> = bech32::FromBase32::from_base32(&data).context("invalid bech32")?;
+
+        let mut reader = Reader(&payload);
+        let version = reader.u8()?;
+        if version != IDENTITY_VERSION {
+            return Err(anyhow::anyhow!(
+                "unsupported identity version {version}, upgrade argon2derive"
+            ));
+        }
+
+        let normalization = match reader.u8()? {
+            0 => "none",
+            1 => "nfc",
+            2 => "nfkc",
+            id => return Err(anyhow::anyhow!("unknown normalization {id}")),
+        };
+        let name = String::from_utf8(reader.bytes()?.to_vec()).context("invalid name")?;
+
+        let mut passes = Vec::new();
+        for _ in 0..reader.u8()? {
+            passes.push(argon2::Parameters {
+                algorithm: match reader.u8()? {
+                    0 => argon2::Algorithm::Argon2d,
+                    2 => argon2::Algorithm::Argon2id,
+                    id => return Err(anyhow::anyhow!("unknown algorithm {id}")),
+                },
+                memory: reader.u32()?,
+                time: reader.u32()?,
+                parallelism: reader.u32()?,
+                salt: Vec::new(),
+            });
+        }
+        if passes.is_empty() {
+            return Err(anyhow::anyhow!("missing Argon2 parameters"));
+        }
+
+        let mut params = passes.remove(0);
+        params.salt = reader.bytes()?.to_vec();
+        if !reader.0.is_empty() {
+            return Err(anyhow::anyhow!("trailing data"));
+        }
+
+        Ok(Self {
+            name,
+            normalization: normalization.to_owned(),
+            params,
+            cascade: passes,
+        })
+    }
+
+    /// Applies the normalization of the identity to the passphrase.
+    fn normalize(&self, passphrase: &str) -> Vec<u8> {
+        match self.normalization.as_str() {
+            "nfc" => passphrase.nfc().collect::<String>().into_bytes(),
+            "nfkc" => passphrase.nfkc().collect::<String>().into_bytes(),
+            _ => passphrase.as_bytes().to_vec(),
+        }
+    }
+}
+
+fn normalization_id(normalization: &str) -> u8 {
+    match normalization {
+        "nfc" => 1,
+        "nfkc" => 2,
+        _ => 0,
+    }
+}
+
+/// Argon2 type identifiers, as defined by RFC 9106.
+fn algorithm_id(algorithm: argon2::Algorithm) -> u8 {
+    match algorithm {
+        argon2::Algorithm::Argon2d => 0,
+        argon2::Algorithm::Argon2id => 2,
+    }
+}
+
+fn put_bytes(payload: &mut Vec<u8>, bytes: &[u8]) {
+    payload.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    payload.extend_from_slice(bytes);
+}
+
+/// Cursor over the payload of an identity.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(anyhow::anyhow!("truncated identity"));
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> anyhow::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> anyhow::Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn bytes(&mut self) -> anyhow::Result<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
+
+/// Reads a stanza of the protocol: `-> <tag> [args...]` followed by the base64 body,
+/// the last line of which is shorter than [`BODY_LINE_LEN`] (possibly empty).
+fn read_stanza(input: &mut impl BufRead) -> anyhow::Result<Stanza> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Err(anyhow::anyhow!("unexpected end of the protocol"));
+    }
+
+    let mut header = line
+        .trim_end_matches('\n')
+        .strip_prefix("-> ")
+        .context("expected a stanza")?
+        .split(' ')
+        .map(str::to_owned);
+    let tag = header
+        .next()
+        .filter(|tag| !tag.is_empty())
+        .context("missing stanza tag")?;
+    let args = header.collect();
+
+    let mut body = String::new();
+    loop {
+        line.clear();
+        input.read_line(&mut line)?;
+        let chunk = line
+            .strip_suffix('\n')
+            .context("unexpected end of the stanza body")?;
+        body.push_str(chunk);
+        if chunk.len() < BODY_LINE_LEN {
+            break;
+        }
+    }
+    let body = general_purpose::STANDARD_NO_PAD
+        .decode(body)
+        .context("invalid stanza body")?;
+
+    Ok(Stanza { tag, args, body })
+}
+
+/// Writes a stanza of the protocol, see [`read_stanza`].
+fn write_stanza(output: &mut impl Write, tag: &str, args: &[&str], body: &[u8]) -> io::Result<()> {
+    let mut header = format!("-> {tag}");
+    for arg in args {
+        header.push(' ');
+        header.push_str(arg);
+    }
+    writeln!(output, "{header}")?;
+
+    let body = general_purpose::STANDARD_NO_PAD.encode(body);
+    let mut lines = body.as_bytes().chunks(BODY_LINE_LEN);
+    for chunk in lines.by_ref() {
+        output.write_all(chunk)?;
+        output.write_all(b"\n")?;
+    }
+    // A full last line needs to be followed by an empty one to terminate the body.
+    if body.len().is_multiple_of(BODY_LINE_LEN) {
+        output.write_all(b"\n")?;
+    }
+    output.flush()
+}
+
+/// Plugin side of the protocol, the commands of the second phase awaiting the response of age.
+struct Plugin<R, W> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Plugin<R, W> {
+    /// Sends a command of the second phase, returning the response (`ok`, `fail` or `unsupported`).
+    fn send(&mut self, tag: &str, args: &[&str], body: &[u8]) -> anyhow::Result<Stanza> {
+        write_stanza(&mut self.output, tag, args, body)?;
+        let response = read_stanza(&mut self.input)?;
+        match response.tag.as_str() {
+            "ok" | "fail" | "unsupported" => Ok(response),
+            tag => Err(anyhow::anyhow!("unexpected response `{tag}`")),
+        }
+    }
+
+    /// Reports an error to age, which prints the message to the user.
+    fn error(&mut self, kind: &str, indices: &[&str], message: &str) -> anyhow::Result<()> {
+        let mut args = vec![kind];
+        args.extend_from_slice(indices);
+        self.send("error", &args, message.as_bytes()).map(drop)
+    }
+}
+
+/// Runs the `identity-v1` state machine: receives the identities and the stanzas of the files,
+/// then asks for the passphrases of the identities having a chance to unwrap the file keys.
+///
+/// `derive` derives the X25519 private key of the identity from the normalized passphrase.
+pub(super) fn run_identity_v1(
+    input: impl BufRead,
+    output: impl Write,
+    mut derive: impl FnMut(&Identity, &[u8]) -> anyhow::Result<Zeroizing<[u8; 32]>>,
+) -> anyhow::Result<()> {
+    let mut plugin = Plugin { input, output };
+
+    // Phase 1: identities and stanzas, unknown commands being ignored as the protocol requires.
+    let mut identities = Vec::new();
+    let mut files: Vec<Vec<Stanza>> = Vec::new();
+    loop {
+        let stanza = read_stanza(&mut plugin.input)?;
+        match (stanza.tag.as_str(), stanza.args.as_slice()) {
+            ("add-identity", [identity]) => identities.push(Identity::decode(identity)),
+            ("recipient-stanza", [file, tag, args @ ..]) => {
+                let file: usize = file.parse().context("invalid file index")?;
+                if files.len() <= file {
+                    files.resize_with(file + 1, Vec::new);
+                }
+                files[file].push(Stanza {
+                    tag: tag.clone(),
+                    args: args.to_vec(),
+                    body: stanza.body,
+                });
+            }
+            ("done", _) => break,
+            _ => {}
+        }
+    }
+
+    // Phase 2
+    for (idx, identity) in identities.iter().enumerate() {
+        if let Err(err) = identity {
+            plugin.error("identity", &[&idx.to_string()], &format!("{err:#}"))?;
+        }
+    }
+
+    let has_x25519 = files
+        .iter()
+        .flatten()
+        .any(|stanza| stanza.tag == X25519_TAG);
+    let mut keys = Vec::new();
+    for identity in identities.iter().flatten().filter(|_| has_x25519) {
+        let prompt = format!(
+            "Passphrase of the argon2derive identity `{}`:",
+            identity.name
+        );
+        let response = plugin.send("request-secret", &[], prompt.as_bytes())?;
+        if response.tag != "ok" {
+            continue;
+        }
+
+        let passphrase = Zeroizing::new(
+            String::from_utf8(response.body).context("passphrase isn't valid UTF-8")?,
+        );
+        let passphrase = Zeroizing::new(identity.normalize(&passphrase));
+        match derive(identity, &passphrase).and_then(|key| {
+            age_keys::secret_key(*key, age_keys::Variant::Bech32)
+                .map(Zeroizing::new)
+                .and_then(|key| {
+                    key.parse::<age::x25519::Identity>()
+                        .map_err(anyhow::Error::msg)
+                })
+        }) {
+            Ok(key) => keys.push(key),
+            Err(err) => plugin.error("internal", &[], &format!("{err:#}"))?,
+        }
+    }
+
+    'files: for (file, stanzas) in files.iter().enumerate() {
+        for (idx, stanza) in stanzas.iter().enumerate() {
+            for key in &keys {
+                match key.unwrap_stanza(stanza) {
+                    Some(Ok(file_key)) => {
+                        plugin.send("file-key", &[&file.to_string()], file_key.expose_secret())?;
+                        continue 'files;
+                    }
+                    Some(Err(err)) => {
+                        let indices = [file.to_string(), idx.to_string()];
+                        plugin.error("stanza", &[&indices[0], &indices[1]], &err.to_string())?;
+                        continue 'files;
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+
+    write_stanza(&mut plugin.output, "done", &[], &[])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use age::Recipient as _;
+    use age_core::format::FileKey;
+
+    use super::*;
+
+    fn identity() -> Identity {
+        let pass = |algorithm, memory, time, parallelism| argon2::Parameters {
+            algorithm,
+            memory,
+            time,
+            parallelism,
+            salt: Vec::new(),
+        };
+        Identity {
+            name: "email".to_owned(),
+            normalization: "nfkc".to_owned(),
+            params: argon2::Parameters {
+                salt: b"somesaltemail".to_vec(),
+                ..pass(argon2::Algorithm::Argon2id, 64, 1, 1)
+            },
+            cascade: vec![pass(argon2::Algorithm::Argon2d, 32, 2, 2)],
+        }
+    }
+
+    /// Encodes the payload the way [`Identity::encode`] does.
+    fn encode(payload: &[u8]) -> String {
+        let data = bech32::ToBase32::to_base32(&payload);
+        bech32::encode(IDENTITY_HRP, data, bech32::Variant::Bech32).unwrap()
+    }
+
+    #[test]
+    fn identity_roundtrip() {
+        let encoded = identity().encode().unwrap();
+        assert!(
+            encoded.starts_with("AGE-PLUGIN-ARGON2DERIVE-1"),
+            "{encoded}"
+        );
+
+        let decoded = Identity::decode(&encoded).unwrap();
+        assert_eq!(decoded.name, "email");
+        assert_eq!(decoded.normalization, "nfkc");
+        assert_eq!(decoded.params.salt, b"somesaltemail");
+        assert_eq!(
+            decoded.params.canonical_costs(),
+            "argon2id$v=19$m=64,t=1,p=1"
+        );
+        assert_eq!(decoded.cascade.len(), 1);
+        assert_eq!(
+            decoded.cascade[0].canonical_costs(),
+            "argon2d$v=19$m=32,t=2,p=2"
+        );
+        assert!(decoded.cascade[0].salt.is_empty());
+    }
+
+    #[test]
+    fn invalid_identities() {
+        let (hrp, data, _) = bech32::decode(&identity().encode().unwrap()).unwrap();
+        let payload: Vec<u8> = bech32::FromBase32::from_base32(&data).unwrap();
+        assert_eq!(hrp, IDENTITY_HRP);
+
+        let error = |payload: &[u8]| Identity::decode(&encode(payload)).unwrap_err().to_string();
+        assert_eq!(
+            error(&[&[2], &payload[1..]].concat()),
+            "unsupported identity version 2, upgrade argon2derive"
+        );
+        assert_eq!(error(&[&payload[..], &[0]].concat()), "trailing data");
+        assert_eq!(error(&payload[..payload.len() - 1]), "truncated identity");
+        // Version, normalization, empty name and no passes.
+        assert_eq!(error(&[1, 0, 0, 0, 0, 0, 0]), "missing Argon2 parameters");
+
+        let data = bech32::ToBase32::to_base32(&payload);
+        let other_hrp = bech32::encode("age-secret-key-", data, bech32::Variant::Bech32).unwrap();
+        assert!(Identity::decode(&other_hrp).is_err());
+    }
+
+    #[test]
+    fn stanza_framing() {
+        // 48 bytes fill a line exactly, so the body is terminated by an empty line.
+        for (len, lines) in [(0, 1), (10, 1), (48, 2), (60, 2), (96, 3)] {
+            let body = vec![0x5a; len];
+            let mut buf = Vec::new();
+            write_stanza(&mut buf, "tag", &["a", "b"], &body).unwrap();
+            assert_eq!(buf.split(|&b| b == b'\n').count() - 2, lines, "{len}");
+
+            let stanza = read_stanza(&mut buf.as_slice()).unwrap();
+            assert_eq!(
+                (stanza.tag.as_str(), stanza.args),
+                ("tag", vec!["a".to_owned(), "b".to_owned()])
+            );
+            assert_eq!(stanza.body, body);
+        }
+
+        assert!(read_stanza(&mut b"tag\n\n".as_slice()).is_err());
+        assert!(read_stanza(&mut b"-> \n\n".as_slice()).is_err());
+        assert!(read_stanza(&mut b"-> tag\nAA".as_slice()).is_err());
+    }
+
+    /// Runs the state machine against the scripted messages of age, returning the stanzas sent by the plugin.
+    fn run(file_key: &FileKey, passphrase: &str) -> Vec<Stanza> {
+        let derive = |_: &Identity, passphrase: &[u8]| {
+            let mut key = [0; 32];
+            key[..passphrase.len()].copy_from_slice(passphrase);
+            Ok(Zeroizing::new(key))
+        };
+        let secret_key = age_keys::secret_key(
+            *derive(&identity(), b"pass").unwrap(),
+            age_keys::Variant::Bech32,
+        )
+        .unwrap();
+        let recipient = secret_key
+            .parse::<age::x25519::Identity>()
+            .unwrap()
+            .to_public();
+        let (stanzas, _) = recipient.wrap_file_key(file_key).unwrap();
+
+        let mut input = Vec::new();
+        let identity = identity().encode().unwrap();
+        write_stanza(&mut input, "add-identity", &[&identity], &[]).unwrap();
+        write_stanza(&mut input, "unknown-command", &[], &[]).unwrap();
+        for stanza in &stanzas {
+            let mut args = vec!["0", &stanza.tag];
+            args.extend(stanza.args.iter().map(String::as_str));
+            write_stanza(&mut input, "recipient-stanza", &args, &stanza.body).unwrap();
+        }
+        write_stanza(&mut input, "done", &[], &[]).unwrap();
+        // Responses to `request-secret` and `file-key`.
+        write_stanza(&mut input, "ok", &[], passphrase.as_bytes()).unwrap();
+        write_stanza(&mut input, "ok", &[], &[]).unwrap();
+
+        let mut output = Vec::new();
+        run_identity_v1(input.as_slice(), &mut output, derive).unwrap();
+
+        let mut output = output.as_slice();
+        let mut stanzas = Vec::new();
+        while !output.is_empty() {
+            stanzas.push(read_stanza(&mut output).unwrap());
+        }
+        stanzas
+    }
+
+    #[test]
+    fn unwraps_the_file_key() {
+        let file_key = FileKey::new(Box::new([7; 16]));
+
+        let stanzas = run(&file_key, "pass");
+        let tags: Vec<_> = stanzas.iter().map(|s| s.tag.as_str()).collect();
+        assert_eq!(tags, ["request-secret", "file-key", "done"]);
+        assert_eq!(
+            stanzas[0].body,
+            b"Passphrase of the argon2derive identity `email`:"
+        );
+        assert_eq!(stanzas[1].args, ["0"]);
+        assert_eq!(stanzas[1].body, file_key.expose_secret());
+
+        let tags: Vec<_> = run(&file_key, "wrong").into_iter().map(|s| s.tag).collect();
+        assert_eq!(tags, ["request-secret", "done"]);
+    }
+
+    #[test]
+    fn reports_invalid_identities() {
+        let mut input = Vec::new();
+        write_stanza(
+            &mut input,
+            "add-identity",
+            &["AGE-PLUGIN-ARGON2DERIVE-1INVALID"],
+            &[],
+        )
+        .unwrap();
+        write_stanza(&mut input, "done", &[], &[]).unwrap();
+        write_stanza(&mut input, "ok", &[], &[]).unwrap();
+
+        let mut output = Vec::new();
+        run_identity_v1(input.as_slice(), &mut output, |_, _| unreachable!()).unwrap();
+        let error = read_stanza(&mut output.as_slice()).unwrap();
+        assert_eq!(
+            (error.tag.as_str(), error.args),
+            ("error", vec!["identity".to_owned(), "0".to_owned()])
+        );
+    }
+}
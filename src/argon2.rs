@@ -1,33 +1,120 @@
-use std::{fmt, str::FromStr};
+//! Argon2 parameters and hashing.
 
-use anyhow::Context as _;
+use std::{error, fmt, mem, str::FromStr};
 
-use crate::Cli;
+use sha2::{Digest as _, Sha256};
+use zeroize::{Zeroize as _, Zeroizing};
 
-pub(super) const MIN_SALT_LEN: usize = 8;
+/// The only Argon2 version supported by the backend (0x13).
+pub const VERSION: u32 = 19;
 
-pub(super) struct Parameters {
+/// Minimum length of the final salt (including the name of the secret).
+pub const MIN_SALT_LEN: usize = 8;
+
+/// Memory cost (in KiB) below which the derivation is considered weak, 64 MiB.
+pub const MIN_RECOMMENDED_MEMORY: u32 = 64 * 1024;
+
+/// Minimum output length (in bytes) supported by Argon2.
+pub const MIN_OUTPUT_LEN: u32 = 4;
+
+/// Upper bound of the output length (in bytes), 1 GiB.
+pub const MAX_OUTPUT_LEN: u32 = 1024 * 1024 * 1024;
+
+/// Output length (in bytes) above which `--expand-output` only derives a base key of [`EXPANSION_BASE_LEN`] bytes
+/// with Argon2, expanded to the requested length with BLAKE3, 64 KiB. See [`expand`].
+pub const EXPANSION_THRESHOLD: u32 = 64 * 1024;
+
+/// Length of the base key of the expanded outputs.
+pub const EXPANSION_BASE_LEN: u32 = 64;
+
+/// BLAKE3 context of the output expansion, see [`expand`].
+///
+/// Versioned, so a different expansion can be introduced without changing the existing secrets.
+const EXPANSION_CONTEXT: &str = "argon2derive output expansion v1";
+
+/// Length of the intermediate outputs of a cascade, each fed as the password into the next pass.
+pub const CASCADE_LINK_LEN: u32 = 64;
+
+/// Domain separation prefix of the hashed names, see [`Parameters::append_hashed_name`].
+///
+/// Versioned, so a different hashing scheme can be introduced without changing the existing secrets.
+const HASHED_NAME_CONTEXT: &[u8] = b"argon2derive hashed name v1\0";
+
+/// Domain separation prefix of the bound parameters, see [`Parameters::bind_params`].
+///
+/// Versioned, so the canonical form can be changed without changing the existing secrets.
+const BOUND_PARAMS_CONTEXT: &[u8] = b"\0argon2derive bound params v1\0";
+
+/// Argon2 parameters.
+#[derive(Clone, Debug)]
+pub struct Parameters {
     pub algorithm: Algorithm,
+    /// Memory cost in KiB.
     pub memory: u32,
+    /// Number of iterations.
     pub time: u32,
+    /// Number of lanes, also used as the number of threads.
+    ///
+    /// Unlike the number of threads, the number of lanes affects the output.
     pub parallelism: u32,
     pub salt: Vec<u8>,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default)]
 pub enum Algorithm {
     Argon2d,
+    #[default]
     Argon2id,
 }
 
+/// Errors of building or validating [`Parameters`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamError {
+    MissingMemory,
+    MissingTime,
+    MissingParallelism,
+    SaltTooShort,
+    InvalidAlgorithm(String),
+    ZeroTime,
+    ZeroParallelism,
+    MemoryTooLow,
+}
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingMemory | Self::MissingTime | Self::MissingParallelism => {
+                f.write_str("--memory, --time and --parallelism must be specified")
+            }
+            Self::SaltTooShort => write!(
+                f,
+                "Final argon2 salt (`--salt` + `--name`) is too short, should be >= {MIN_SALT_LEN} bytes"
+            ),
+            Self::InvalidAlgorithm(algo) => write!(f, "Invalid algorithm: {algo}"),
+            Self::ZeroTime => f.write_str("Time cost should be >= 1"),
+            Self::ZeroParallelism => f.write_str("Parallelism should be >= 1"),
+            Self::MemoryTooLow => f.write_str("Memory cost should be >= 8 KiB per thread"),
+        }
+    }
+}
+
+impl error::Error for ParamError {}
+
 impl FromStr for Algorithm {
-    type Err = anyhow::Error;
+    type Err = ParamError;
 
-    fn from_str(s: &str) -> anyhow::Result<Self> {
-        Ok(match s {
-            "argon2d" => Self::Argon2d,
-            "argon2id" => Self::Argon2id,
-            other => return Err(anyhow::anyhow!("Invalid algorithm: {other}")),
+    /// Case-insensitive, ignores `-`/`_` and accepts short aliases (`id`, `d`).
+    fn from_str(s: &str) -> Result<Self, ParamError> {
+        let normalized: String = s
+            .chars()
+            .filter(|c| !matches!(c, '-' | '_'))
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+
+        Ok(match normalized.as_str() {
+            "argon2d" | "d" => Self::Argon2d,
+            "argon2id" | "id" => Self::Argon2id,
+            _ => return Err(ParamError::InvalidAlgorithm(s.to_owned())),
         })
     }
 }
@@ -42,53 +129,319 @@ impl fmt::Display for Algorithm {
 }
 
 impl Parameters {
-    fn none_defined(cli: &Cli) -> bool {
-        cli.memory.is_none()
-            && cli.time.is_none()
-            && cli.parallelism.is_none()
-            && cli.salt.is_none()
+    /// Checks the costs against the limits of Argon2.
+    pub fn validate(&self) -> Result<(), ParamError> {
+        if self.time == 0 {
+            return Err(ParamError::ZeroTime);
+        }
+
+        if self.parallelism == 0 {
+            return Err(ParamError::ZeroParallelism);
+        }
+
+        if u64::from(self.memory) < 8 * u64::from(self.parallelism) {
+            return Err(ParamError::MemoryTooLow);
+        }
+
+        Ok(())
+    }
+
+    /// Hex encoded SHA-256 of `algorithm:memory:time:parallelism:hex(salt)`.
+    ///
+    /// Identifies the parameters without revealing the salt, e.g. to detect their accidental changes.
+    pub fn fingerprint(&self) -> String {
+        let canonical = format!(
+            "{}:{}:{}:{}:{}",
+            self.algorithm,
+            self.memory,
+            self.time,
+            self.parallelism,
+            hex::encode(&self.salt)
+        );
+
+        hex::encode(Sha256::digest(canonical))
+    }
+
+    /// Canonical form of the costs, e.g. `argon2id$v=19$m=65536,t=3,p=1` (the salt isn't included).
+    pub fn canonical_costs(&self) -> String {
+        format!(
+            "{}$v={VERSION}$m={},t={},p={}",
+            self.algorithm, self.memory, self.time, self.parallelism
+        )
+    }
+
+    /// Appends the canonical costs of these parameters and of the cascade passes to the salt.
+    ///
+    /// Appends `BOUND_PARAMS_CONTEXT` followed by the canonical costs (see [`Self::canonical_costs`])
+    /// of every pass, separated by `;`. The derivation is then explicitly tied to its costs:
+    /// changing any of them changes the salt, so the secrets differ by design rather than by accident.
+    ///
+    /// ```
+    /// use argon2derive::argon2::{Algorithm, Parameters};
+    ///
+    /// let params = Parameters {
+    ///     algorithm: Algorithm::Argon2id,
+    ///     memory: 64 * 1024,
+    ///     time: 1,
+    ///     parallelism: 1,
+    ///     salt: b"my salt".to_vec(),
+    /// };
+    /// let other = Parameters { time: 2, ..params.clone() };
+    ///
+    /// let (mut bound, mut bound_other) = (params.clone(), other.clone());
+    /// bound.bind_params(&[]);
+    /// bound_other.bind_params(&[]);
+    /// assert_ne!(bound.salt, params.salt);
+    /// assert_ne!(bound.salt, bound_other.salt);
+    ///
+    /// // Only the costs differ, yet the salts (and so the secrets) do too.
+    /// let secret = argon2derive::derive(&bound, b"passphrase", "email", 32)?;
+    /// let other_secret = argon2derive::derive(&bound_other, b"passphrase", "email", 32)?;
+    /// assert_ne!(secret, other_secret);
+    ///
+    /// // Cascade passes are bound as well.
+    /// let mut cascaded = params.clone();
+    /// cascaded.bind_params(&[other]);
+    /// assert_ne!(cascaded.salt, bound.salt);
+    /// # anyhow::Ok(())
+    /// ```
+    pub fn bind_params(&mut self, cascade: &[Parameters]) {
+        let costs: Vec<_> = [&*self]
+            .into_iter()
+            .chain(cascade)
+            .map(Self::canonical_costs)
+            .collect();
+
+        self.salt.extend_from_slice(BOUND_PARAMS_CONTEXT);
+        self.salt.extend_from_slice(costs.join(";").as_bytes());
+    }
+
+    /// Appends the name of the secret (and its generation) to the salt.
+    ///
+    /// Generation 0 appends just the name. Other generations additionally append
+    /// `0x00 || name length (u32 BE) || generation (u32 BE)`, so they can't collide with plain names.
+    pub fn append_name(&mut self, name: &str, generation: u32) -> Result<(), ParamError> {
+        self.salt.extend_from_slice(name.as_bytes());
+        self.append_generation(name.len(), generation)
+    }
+
+    /// Appends the SHA-256 of the name of the secret (and its generation) to the salt.
+    ///
+    /// Unlike [`Self::append_name`], the salt grows by 32 bytes whatever the name is.
+    /// The name is hashed along with a versioned prefix, so the secrets differ from the ones of
+    /// the raw names. The generation is appended the same way, using the length of the hash.
+    ///
+    /// ```
+    /// use argon2derive::argon2::{Algorithm, Parameters};
+    ///
+    /// let params = Parameters {
+    ///     algorithm: Algorithm::Argon2id,
+    ///     memory: 64 * 1024,
+    ///     time: 1,
+    ///     parallelism: 1,
+    ///     salt: b"my salt".to_vec(),
+    /// };
+    ///
+    /// let (mut raw, mut hashed) = (params.clone(), params.clone());
+    /// raw.append_name("email", 0)?;
+    /// hashed.append_hashed_name("email", 0)?;
+    /// assert_eq!(hashed.salt.len(), params.salt.len() + 32);
+    /// assert_ne!(raw.salt, hashed.salt);
+    ///
+    /// let mut again = params.clone();
+    /// again.append_hashed_name("email", 0)?;
+    /// assert_eq!(again.salt, hashed.salt);
+    /// # anyhow::Ok(())
+    /// ```
+    pub fn append_hashed_name(&mut self, name: &str, generation: u32) -> Result<(), ParamError> {
+        let hash = Sha256::new()
+            .chain_update(HASHED_NAME_CONTEXT)
+            .chain_update(name)
+            .finalize();
+        self.salt.extend_from_slice(&hash);
+        self.append_generation(hash.len(), generation)
     }
 
-    pub(super) fn from_cli(cli: &Cli) -> anyhow::Result<Option<Self>> {
-        if Self::none_defined(cli) {
-            return Ok(None);
+    /// Appends a hierarchical name (e.g. `work`, `aws`, `prod`) and its generation to the salt.
+    ///
+    /// Appends `0x00 || number of components (u32 BE)` followed by `length (u32 BE) || component`
+    /// for every component, so every level is delimited unambiguously: components can't collide with
+    /// each other nor with flat names (which never start with `0x00`), whatever characters they contain.
+    /// The generation is appended the same way as for [`Self::append_name`], using the encoded length.
+    ///
+    /// ```
+    /// use argon2derive::argon2::{Algorithm, Parameters};
+    ///
+    /// let params = Parameters {
+    ///     algorithm: Algorithm::Argon2id,
+    ///     memory: 64 * 1024,
+    ///     time: 1,
+    ///     parallelism: 1,
+    ///     salt: b"my salt".to_vec(),
+    /// };
+    ///
+    /// let (mut flat, mut path) = (params.clone(), params.clone());
+    /// flat.append_name("a/b", 0)?;
+    /// path.append_path(&["a", "b"], 0)?;
+    /// assert_ne!(flat.salt, path.salt);
+    ///
+    /// let mut other = params.clone();
+    /// other.append_path(&["a/b"], 0)?;
+    /// assert_ne!(other.salt, path.salt);
+    /// # anyhow::Ok(())
+    /// ```
+    pub fn append_path(&mut self, components: &[&str], generation: u32) -> Result<(), ParamError> {
+        let start = self.salt.len();
+
+        self.salt.push(0);
+        self.salt
+            .extend_from_slice(&(components.len() as u32).to_be_bytes());
+        for component in components {
+            self.salt
+                .extend_from_slice(&(component.len() as u32).to_be_bytes());
+            self.salt.extend_from_slice(component.as_bytes());
         }
 
-        Self::from_cli_opt(cli)
-            .context(super::MISSING_REQUIRED_PARAMETERS)
-            .map(Some)
+        self.append_generation(self.salt.len() - start, generation)
     }
 
-    fn from_cli_opt(cli: &Cli) -> Option<Self> {
-        let salt = cli.salt.as_ref().map(String::as_bytes);
+    /// Appends `0x00 || name length (u32 BE) || generation (u32 BE)` unless the generation is 0.
+    fn append_generation(&mut self, name_len: usize, generation: u32) -> Result<(), ParamError> {
+        if generation != 0 {
+            self.salt.push(0);
+            self.salt
+                .extend_from_slice(&(name_len as u32).to_be_bytes());
+            self.salt.extend_from_slice(&generation.to_be_bytes());
+        }
 
-        Some(Self {
-            algorithm: cli.algorithm,
-            memory: cli.memory? * 1024 * 1024,
-            time: cli.time?,
-            parallelism: cli.parallelism?,
-            salt: salt.unwrap_or_default().into(),
-        })
+        if self.salt.len() < MIN_SALT_LEN {
+            return Err(ParamError::SaltTooShort);
+        }
+
+        Ok(())
     }
 }
 
-pub(super) fn hash(
-    params: &Parameters,
-    password: &[u8],
-    output_len: u32,
-) -> anyhow::Result<Vec<u8>> {
+/// Formats a memory cost (in KiB) using the largest fitting binary unit.
+pub fn format_memory(kib: u32) -> String {
+    match kib {
+        kib if kib >= 1024 * 1024 && kib % (1024 * 1024) == 0 => format!("{} GiB", kib >> 20),
+        kib if kib >= 1024 && kib % 1024 == 0 => format!("{} MiB", kib >> 10),
+        kib => format!("{kib} KiB"),
+    }
+}
+
+/// Checks that the output length is within [`MIN_OUTPUT_LEN`]..=[`MAX_OUTPUT_LEN`].
+pub fn validate_output_len(output_len: u32) -> anyhow::Result<()> {
+    if output_len < MIN_OUTPUT_LEN {
+        return Err(anyhow::anyhow!(
+            "Output length is too short, should be >= {MIN_OUTPUT_LEN} bytes"
+        ));
+    }
+
+    if output_len > MAX_OUTPUT_LEN {
+        return Err(anyhow::anyhow!(
+            "Output length is too long, should be <= {MAX_OUTPUT_LEN} bytes"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Hashes the password using the parameters as is, see [`crate::derive`] for deriving named secrets.
+pub fn hash(params: &Parameters, password: &[u8], output_len: u32) -> anyhow::Result<Vec<u8>> {
+    validate_output_len(output_len)?;
+
     argon2_kdf::Hasher::new()
         .algorithm(params.algorithm.into())
         .hash_length(output_len)
         .custom_salt(&params.salt)
         .memory_cost_kib(params.memory)
         .iterations(params.time)
+        // `argon2_kdf` uses the same value for the lanes and the threads.
         .threads(params.parallelism)
         .hash(password)
         .map(|hash| hash.as_bytes().into())
         .map_err(Into::into)
 }
 
+/// Expands a base key (e.g. [`EXPANSION_BASE_LEN`] bytes of [`hash`]) to `output_len` bytes:
+/// `BLAKE3-XOF(derive_key(EXPANSION_CONTEXT), base key || output_len (u32 LE))`.
+///
+/// Argon2 hashes its whole output with BLAKE2b in 32-byte steps, and keeps it in memory along with
+/// the memory cost, so long outputs are cheaper this way. They differ from the Argon2 outputs of the same
+/// length though, so the expansion is opt-in (see `--expand-output`).
+///
+/// ```
+/// use argon2derive::argon2::{Algorithm, EXPANSION_BASE_LEN, Parameters, expand, hash};
+///
+/// let params = Parameters {
+///     algorithm: Algorithm::Argon2id,
+///     memory: 64,
+///     time: 1,
+///     parallelism: 1,
+///     salt: b"saltsalt".to_vec(),
+/// };
+///
+/// let base = hash(&params, b"password", EXPANSION_BASE_LEN)?;
+/// let long = expand(&base, 1024 * 1024);
+/// assert_eq!(long.len(), 1024 * 1024);
+/// assert_eq!(long, expand(&base, 1024 * 1024));
+/// // Bound to the length.
+/// assert_ne!(long[..1024], expand(&base, 1024));
+/// # anyhow::Ok(())
+/// ```
+pub fn expand(base: &[u8], output_len: u32) -> Vec<u8> {
+    let mut hasher = blake3::Hasher::new_derive_key(EXPANSION_CONTEXT);
+    hasher.update(base);
+    hasher.update(&output_len.to_le_bytes());
+
+    let mut reader = hasher.finalize_xof();
+    hasher.zeroize();
+
+    let mut output = vec![0; output_len as usize];
+    reader.fill(&mut output);
+    reader.zeroize();
+    output
+}
+
+/// Salt of the `pass`th (1-based) pass of a cascade following the first one:
+/// `"argon2derive cascade" || pass (u32 BE)`.
+pub fn cascade_salt(pass: u32) -> Vec<u8> {
+    let mut salt = b"argon2derive cascade".to_vec();
+    salt.extend_from_slice(&pass.to_be_bytes());
+    salt
+}
+
+/// Hashes the password through a cascade of Argon2 passes.
+///
+/// The first pass uses `params` as is (so the name of the secret is only mixed into its salt).
+/// Each of the `next` passes hashes the [`CASCADE_LINK_LEN`] bytes output by the previous one,
+/// salted with [`cascade_salt`] of its position (2, 3, ...), so the salts of `next` are ignored.
+/// The last pass produces `output_len` bytes. Without `next` passes it's the same as [`hash`].
+pub fn hash_cascade(
+    params: &Parameters,
+    next: &[Parameters],
+    password: &[u8],
+    output_len: u32,
+) -> anyhow::Result<Vec<u8>> {
+    let link_len = |idx: usize| match idx == next.len() {
+        true => output_len,
+        false => CASCADE_LINK_LEN,
+    };
+
+    let mut output = Zeroizing::new(hash(params, password, link_len(0))?);
+    for (idx, pass) in next.iter().enumerate() {
+        let pass = Parameters {
+            salt: cascade_salt(idx as u32 + 2),
+            ..pass.clone()
+        };
+        output = Zeroizing::new(hash(&pass, &output, link_len(idx + 1))?);
+    }
+
+    Ok(mem::take(&mut output))
+}
+
 impl From<Algorithm> for argon2_kdf::Algorithm {
     fn from(algo: Algorithm) -> Self {
         match algo {
@@ -97,3 +450,404 @@ impl From<Algorithm> for argon2_kdf::Algorithm {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_len_boundaries() {
+        assert!(validate_output_len(0).is_err());
+        assert!(validate_output_len(MIN_OUTPUT_LEN - 1).is_err());
+        assert!(validate_output_len(MIN_OUTPUT_LEN).is_ok());
+        assert!(validate_output_len(MAX_OUTPUT_LEN).is_ok());
+        assert!(validate_output_len(MAX_OUTPUT_LEN + 1).is_err());
+    }
+
+    fn params(memory: u32, time: u32, parallelism: u32) -> Parameters {
+        Parameters {
+            algorithm: Algorithm::Argon2id,
+            memory,
+            time,
+            parallelism,
+            salt: b"somesalt".to_vec(),
+        }
+    }
+
+    #[test]
+    fn lanes_affect_the_output() {
+        let one = hash(&params(64, 1, 1), b"password", 32).unwrap();
+        let two = hash(&params(64, 1, 2), b"password", 32).unwrap();
+        assert_ne!(one, two);
+    }
+
+    #[test]
+    fn expansion_vectors() {
+        let sha256 = |bytes: &[u8]| hex::encode(Sha256::digest(bytes));
+        let base = hash(&params(64, 1, 1), b"password", EXPANSION_BASE_LEN).unwrap();
+
+        assert_eq!(
+            hex::encode(expand(&base, 32)),
+            "606c3db7b4b6d19da940bea030a0d0d5c49b116f8e7b8aadece4b52d19aa42b3"
+        );
+        assert_eq!(
+            sha256(&expand(&base, EXPANSION_THRESHOLD)),
+            "39e7efd732c3b5aace3b0d8c0ae9e73707699915caa65d378d9705b3a17b7e9c"
+        );
+        assert_eq!(
+            sha256(&expand(&base, EXPANSION_THRESHOLD + 1)),
+            "df4260738fad07c61ad94c1accbbd161a8ee0afb9a85a05c166f6fd4ebbe2c0e"
+        );
+    }
+
+    #[test]
+    fn plain_outputs_around_the_expansion_threshold() {
+        let sha256 = |bytes: &[u8]| hex::encode(Sha256::digest(bytes));
+        let hash = |len| hash(&params(64, 1, 1), b"password", len).unwrap();
+
+        // `hash` never expands, whatever the length.
+        assert_eq!(
+            sha256(&hash(EXPANSION_THRESHOLD)),
+            "f2ee6d52a00fd08e06a9493b8138200ef2302d70fe37290d9931a480ab1bbcbc"
+        );
+        assert_eq!(
+            sha256(&hash(EXPANSION_THRESHOLD + 1)),
+            "0c5fdfa46f08a8ab2a898b49ced0c63277f888b5f6e324db7b359e32446b8071"
+        );
+    }
+
+    #[test]
+    fn validate_boundaries() {
+        assert_eq!(params(8, 1, 1).validate(), Ok(()));
+        assert_eq!(params(8, u32::MAX, 1).validate(), Ok(()));
+        assert_eq!(params(32, 1, 4).validate(), Ok(()));
+
+        assert_eq!(params(8, 0, 1).validate(), Err(ParamError::ZeroTime));
+        assert_eq!(params(8, 1, 0).validate(), Err(ParamError::ZeroParallelism));
+        assert_eq!(params(7, 1, 1).validate(), Err(ParamError::MemoryTooLow));
+        assert_eq!(params(31, 1, 4).validate(), Err(ParamError::MemoryTooLow));
+        assert_eq!(params(0, 1, 1).validate(), Err(ParamError::MemoryTooLow));
+
+        // 8 * parallelism overflows `u32`.
+        assert_eq!(
+            params(u32::MAX, 1, u32::MAX).validate(),
+            Err(ParamError::MemoryTooLow)
+        );
+        assert_eq!(params(u32::MAX, 1, u32::MAX / 8).validate(), Ok(()));
+    }
+
+    #[test]
+    fn salt_too_short() {
+        let mut short = params(8, 1, 1);
+        short.salt = b"salt".to_vec();
+        assert_eq!(
+            short.clone().append_name("abc", 0),
+            Err(ParamError::SaltTooShort)
+        );
+        assert_eq!(short.clone().append_name("abcd", 0), Ok(()));
+        // The generation counts towards the length.
+        assert_eq!(short.clone().append_name("", 1), Ok(()));
+
+        let mut empty = params(8, 1, 1);
+        empty.salt.clear();
+        assert_eq!(
+            empty.clone().append_name("1234567", 0),
+            Err(ParamError::SaltTooShort)
+        );
+        assert_eq!(empty.clone().append_name("12345678", 0), Ok(()));
+        assert_eq!(empty.clone().append_hashed_name("", 0), Ok(()));
+        assert_eq!(
+            empty.clone().append_path(&[], 0),
+            Err(ParamError::SaltTooShort)
+        );
+        assert_eq!(empty.append_path(&["abc"], 0), Ok(()));
+    }
+
+    #[test]
+    fn generation_vectors() {
+        // Computed with the reference implementation from the salts below.
+        let vectors: [(u32, &[u8], &str); 3] = [
+            (
+                0,
+                b"somesaltemail",
+                "60be2b8f2b729829e3d07d8cb283dcf9e5584ec86999eaa61f146d7d25d94d49",
+            ),
+            (
+                1,
+                b"somesaltemail\0\0\0\0\x05\0\0\0\x01",
+                "4c4fbb29a4a5cc6436ed620432edc3c33342df4de457e38240c574d299af6a47",
+            ),
+            (
+                255,
+                b"somesaltemail\0\0\0\0\x05\0\0\0\xff",
+                "4fd6f72b52d43229f46ab72f0d5967caf6444084631aff689f8d2f97a80e91d9",
+            ),
+        ];
+
+        for (generation, salt, expected) in vectors {
+            let mut params = params(64, 1, 1);
+            params.append_name("email", generation).unwrap();
+            assert_eq!(params.salt, salt, "generation {generation}");
+
+            let secret = hash(&params, b"password", 32).unwrap();
+            assert_eq!(hex::encode(secret), expected, "generation {generation}");
+        }
+
+        // Generation 0 is the output of the versions without generations.
+        let legacy = crate::derive(&params(64, 1, 1), b"password", "email", 32).unwrap();
+        assert_eq!(hex::encode(legacy), vectors[0].2);
+    }
+
+    #[test]
+    fn hashed_name_vectors() {
+        // SHA-256 of `argon2derive hashed name v1\0email`, computed with Python's `hashlib`.
+        let name_hash = "91e03991c24e37c0301ba71056574393f1b6c3a3c1fa4050a50d840521f3b10f";
+        let vectors = [
+            (
+                0,
+                format!("736f6d6573616c74{name_hash}"),
+                "94050955861459bd7116da8fa65efa9dc5bdf9e975d9ff4069fc62d15ebef0bb",
+            ),
+            (
+                1,
+                format!("736f6d6573616c74{name_hash}000000002000000001"),
+                "bd4eef130cd4d7fc7621b5672e6c78f31c048195adc6f9206b21aa4e540d1128",
+            ),
+        ];
+
+        for (generation, salt, expected) in &vectors {
+            let mut params = params(64, 1, 1);
+            params.append_hashed_name("email", *generation).unwrap();
+            assert_eq!(&hex::encode(&params.salt), salt, "generation {generation}");
+
+            let secret = hash(&params, b"password", 32).unwrap();
+            assert_eq!(hex::encode(secret), *expected, "generation {generation}");
+        }
+
+        let mut raw = params(64, 1, 1);
+        raw.append_name("email", 0).unwrap();
+        assert_ne!(
+            hex::encode(hash(&raw, b"password", 32).unwrap()),
+            vectors[0].2
+        );
+    }
+
+    #[test]
+    fn hashed_names_have_a_fixed_length() {
+        for name in ["", "a", "line\nbreak", &"long".repeat(1000)] {
+            let mut params = params(64, 1, 1);
+            params.append_hashed_name(name, 0).unwrap();
+            assert_eq!(params.salt.len(), b"somesalt".len() + 32, "{name:?}");
+        }
+    }
+
+    #[test]
+    fn path_encoding() {
+        let mut params = params(64, 1, 1);
+        params.append_path(&["ab", "c"], 0).unwrap();
+        let mut expected = b"somesalt".to_vec();
+        expected.extend([0, 0, 0, 0, 2]);
+        expected.extend([0, 0, 0, 2, b'a', b'b']);
+        expected.extend([0, 0, 0, 1, b'c']);
+        assert_eq!(params.salt, expected);
+
+        let mut next = self::params(64, 1, 1);
+        next.append_path(&["ab", "c"], 1).unwrap();
+        // Generation suffix using the 16 bytes of the encoded path.
+        expected.extend([0, 0, 0, 0, 16, 0, 0, 0, 1]);
+        assert_eq!(next.salt, expected);
+    }
+
+    #[test]
+    fn paths_dont_collide() {
+        let salt = |path: &[&str]| {
+            let mut params = params(64, 1, 1);
+            params.append_path(path, 0).unwrap();
+            params.salt
+        };
+        assert_ne!(salt(&["a", "b"]), salt(&["a/b"]));
+        assert_ne!(salt(&["a", "b"]), salt(&["ab"]));
+        assert_ne!(salt(&["a", "bc"]), salt(&["ab", "c"]));
+        assert_ne!(salt(&["a"]), salt(&["a", "a"]));
+
+        let mut flat = params(64, 1, 1);
+        flat.append_name("a/b", 0).unwrap();
+        assert_ne!(flat.salt, salt(&["a", "b"]));
+    }
+
+    #[test]
+    fn generations_dont_collide_with_names_ending_in_digits() {
+        let (mut name, mut generation) = (params(64, 1, 1), params(64, 1, 1));
+        name.append_name("email1", 0).unwrap();
+        generation.append_name("email", 1).unwrap();
+        assert_ne!(name.salt, generation.salt);
+    }
+
+    #[test]
+    fn bound_params_encoding() {
+        assert_eq!(
+            params(65536, 3, 4).canonical_costs(),
+            "argon2id$v=19$m=65536,t=3,p=4"
+        );
+
+        let mut bound = params(64, 1, 1);
+        bound.bind_params(&[]);
+        assert_eq!(
+            bound.salt,
+            b"somesalt\0argon2derive bound params v1\0argon2id$v=19$m=64,t=1,p=1"
+        );
+
+        let mut cascaded = params(64, 1, 1);
+        let pass = Parameters {
+            algorithm: Algorithm::Argon2d,
+            ..params(32, 2, 2)
+        };
+        cascaded.bind_params(&[pass]);
+        assert_eq!(
+            cascaded.salt,
+            b"somesalt\0argon2derive bound params v1\0argon2id$v=19$m=64,t=1,p=1;argon2d$v=19$m=32,t=2,p=2"
+        );
+    }
+
+    #[test]
+    fn fingerprint_vector() {
+        // SHA-256 of `argon2id:65536:3:4:736f6d6573616c74`, computed with Python's `hashlib`.
+        assert_eq!(
+            params(65536, 3, 4).fingerprint(),
+            "ff5f1935d6a1a65773fc167c6a2899eb83044f5c86ac702fea1780fb284b785c"
+        );
+    }
+
+    #[test]
+    fn fingerprint_covers_every_parameter() {
+        let base = params(65536, 3, 4);
+        let changed = [
+            Parameters {
+                algorithm: Algorithm::Argon2d,
+                ..base.clone()
+            },
+            params(65537, 3, 4),
+            params(65536, 4, 4),
+            params(65536, 3, 1),
+            Parameters {
+                salt: b"othersalt".to_vec(),
+                ..base.clone()
+            },
+        ];
+
+        for params in changed {
+            assert_ne!(params.fingerprint(), base.fingerprint(), "{params:?}");
+        }
+    }
+
+    #[test]
+    fn cascade_salt_layout() {
+        assert_eq!(cascade_salt(2), b"argon2derive cascade\0\0\0\x02");
+        assert_eq!(cascade_salt(258), b"argon2derive cascade\0\0\x01\x02");
+    }
+
+    #[test]
+    fn cascade_vectors() {
+        let first = params(64, 1, 1);
+        let second = Parameters {
+            algorithm: Algorithm::Argon2d,
+            // Ignored, replaced by `cascade_salt(2)`.
+            salt: b"othersalt".to_vec(),
+            ..params(32, 2, 2)
+        };
+        let third = params(16, 1, 1);
+
+        // A single pass is the plain derivation.
+        assert_eq!(
+            hash_cascade(&first, &[], b"password", 32).unwrap(),
+            hash(&first, b"password", 32).unwrap()
+        );
+
+        // The second pass hashes the 64-byte output of the first one.
+        let link = hash(&first, b"password", CASCADE_LINK_LEN).unwrap();
+        let second_salted = Parameters {
+            salt: cascade_salt(2),
+            ..second.clone()
+        };
+        let two = hash_cascade(&first, std::slice::from_ref(&second), b"password", 32).unwrap();
+        assert_eq!(two, hash(&second_salted, &link, 32).unwrap());
+        assert_eq!(
+            hex::encode(two),
+            "8559bc6a7e69e59405bbaaa4f9da934a75ae0ef882a0855eb20b14ba2f71ca98"
+        );
+
+        let three = hash_cascade(&first, &[second, third], b"password", 32).unwrap();
+        assert_eq!(
+            hex::encode(three),
+            "994f06bee582f6051f0b46988c72fa49dc023183c672f7be76497d92b1bc0ef0"
+        );
+    }
+
+    #[test]
+    fn algorithm_aliases() {
+        for s in [
+            "argon2id",
+            "Argon2id",
+            "ARGON2ID",
+            "argon2-id",
+            "argon2_id",
+            "Argon2-ID",
+            "id",
+            "ID",
+        ] {
+            assert!(matches!(s.parse(), Ok(Algorithm::Argon2id)), "{s}");
+        }
+
+        for s in [
+            "argon2d", "Argon2d", "ARGON2D", "argon2-d", "argon2_d", "d", "D",
+        ] {
+            assert!(matches!(s.parse(), Ok(Algorithm::Argon2d)), "{s}");
+        }
+
+        for s in ["argon2", "i", "argon2i", "argon 2id", "idd"] {
+            assert!(s.parse::<Algorithm>().is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn algorithm_display_is_canonical() {
+        for algorithm in [Algorithm::Argon2d, Algorithm::Argon2id] {
+            let canonical = algorithm.to_string();
+            let parsed: Algorithm = canonical.to_uppercase().parse().unwrap();
+            assert_eq!(parsed.to_string(), canonical);
+        }
+        assert_eq!(Algorithm::Argon2id.to_string(), "argon2id");
+        assert_eq!(Algorithm::Argon2d.to_string(), "argon2d");
+    }
+
+    #[test]
+    fn invalid_algorithm() {
+        assert_eq!(
+            "argon2i".parse::<Algorithm>().unwrap_err(),
+            ParamError::InvalidAlgorithm("argon2i".to_owned())
+        );
+        assert_eq!(
+            "".parse::<Algorithm>().unwrap_err(),
+            ParamError::InvalidAlgorithm(String::new())
+        );
+    }
+
+    #[test]
+    fn messages() {
+        for missing in [
+            ParamError::MissingMemory,
+            ParamError::MissingTime,
+            ParamError::MissingParallelism,
+        ] {
+            assert_eq!(
+                missing.to_string(),
+                "--memory, --time and --parallelism must be specified"
+            );
+        }
+
+        assert_eq!(
+            ParamError::InvalidAlgorithm("foo".to_owned()).to_string(),
+            "Invalid algorithm: foo"
+        );
+    }
+}
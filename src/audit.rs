@@ -0,0 +1,252 @@
+//! Opt-in audit log of the derivations (`--audit-log`), e.g. to spot misuse on shared machines.
+//!
+//! Every derivation appends a JSON line with its time, command, secret name, length, KDF and
+//! the exit status of the invocation. The passphrase, the salt and the derived values are
+//! never part of it, [`Entry`] simply has no fields for them.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write as _},
+    path::Path,
+    sync::Mutex,
+};
+
+#[cfg(unix)]
+use std::os::unix::{fs::OpenOptionsExt as _, io::AsRawFd as _};
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::argon2;
+
+/// Derivations of this invocation, completed with the exit status by [`append`].
+static DERIVED: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+
+/// Line of the audit log.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct Entry {
+    /// UTC time of the derivation, e.g. `2025-01-31T12:00:00Z`.
+    pub time: String,
+    /// Subcommand performing the derivation, e.g. `secret`.
+    pub command: String,
+    /// Name of the secret.
+    pub name: String,
+    /// Length of the derived secret, in bytes.
+    pub length: u32,
+    /// Algorithm and version of every Argon2 pass, e.g. `argon2id v19`.
+    pub kdf: String,
+    /// Exit code of the invocation, see the `--help` output.
+    pub status: u8,
+}
+
+/// Records a derivation of this invocation, see [`append`].
+pub(super) fn record(
+    name: &str,
+    length: u32,
+    params: &argon2::Parameters,
+    cascade: &[argon2::Parameters],
+) {
+    let kdf: Vec<_> = [params]
+        .into_iter()
+        .chain(cascade)
+        .map(|pass| format!("{} v{}", pass.algorithm, argon2::VERSION))
+        .collect();
+
+    DERIVED.lock().unwrap().push(Entry {
+        time: timestamp(OffsetDateTime::now_utc()),
+        command: String::new(),
+        name: name.to_owned(),
+        length,
+        kdf: kdf.join(" + "),
+        status: 0,
+    });
+}
+
+/// Whether this invocation derived anything.
+pub(super) fn is_empty() -> bool {
+    DERIVED.lock().unwrap().is_empty()
+}
+
+/// Appends the recorded derivations to the log at `path`, which is created only accessible by its owner.
+///
+/// The lines are written at once under an exclusive advisory lock (`flock`), so the lines of
+/// concurrent invocations never interleave.
+pub(super) fn append(path: &Path, command: &str, status: u8) -> anyhow::Result<()> {
+    let mut derived = DERIVED.lock().unwrap();
+    for entry in derived.iter_mut() {
+        entry.command = command.to_owned();
+        entry.status = status;
+    }
+    let lines = lines(&derived.iter().collect::<Vec<_>>()).context("audit::lines")?;
+
+    let mut options = OpenOptions::new();
+    options.append(true).create(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path).context("OpenOptions::open")?;
+    lock(&file).context("audit::lock")?;
+
+    file.write_all(lines.as_bytes())
+        .context("File::write_all")?;
+    file.sync_all().context("File::sync_all")
+}
+
+/// Renders the entries the way they are stored, a JSON object per line.
+pub(super) fn lines(entries: &[&Entry]) -> anyhow::Result<String> {
+    let mut out = String::new();
+    for entry in entries {
+        out += &serde_json::to_string(entry)?;
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Renders the entries as aligned columns, escaping the control characters of the names.
+pub(super) fn table(entries: &[&Entry]) -> String {
+    const HEADER: [&str; 6] = ["TIME", "STATUS", "COMMAND", "NAME", "LENGTH", "KDF"];
+
+    let rows: Vec<[String; 6]> = entries
+        .iter()
+        .map(|e| {
+            [
+                e.time.clone(),
+                e.status.to_string(),
+                e.command.clone(),
+                e.name.escape_debug().to_string(),
+                e.length.to_string(),
+                e.kdf.clone(),
+            ]
+        })
+        .collect();
+
+    let mut widths = HEADER.map(str::len);
+    for row in &rows {
+        for (width, field) in widths.iter_mut().zip(row) {
+            *width = (*width).max(field.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    let header = HEADER.map(str::to_owned);
+    for row in [&header].into_iter().chain(&rows) {
+        let fields: Vec<_> = row
+            .iter()
+            .zip(widths)
+            .map(|(field, width)| format!("{field:width$}"))
+            .collect();
+        out += fields.join("  ").trim_end();
+        out.push('\n');
+    }
+    out
+}
+
+/// Takes an exclusive advisory lock of the file, released when it gets closed.
+#[cfg(unix)]
+fn lock(file: &fs::File) -> io::Result<()> {
+    // SAFETY: The descriptor is owned by `file`, which outlives the call.
+    match unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+#[cfg(not(unix))]
+fn lock(_file: &fs::File) -> io::Result<()> {
+    Ok(())
+}
+
+/// Reads the entries of the log at `path`.
+pub(super) fn read(path: &Path) -> anyhow::Result<Vec<Entry>> {
+    fs::read_to_string(path)
+        .context("fs::read_to_string")?
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            serde_json::from_str(line).with_context(|| format!("line {}: invalid entry", idx + 1))
+        })
+        .collect()
+}
+
+/// Renders the time as RFC 3339 (`2025-01-31T12:00:00Z`), which sorts chronologically as a string.
+fn timestamp(time: OffsetDateTime) -> String {
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        time.year(),
+        u8::from(time.month()),
+        time.day(),
+        time.hour(),
+        time.minute(),
+        time.second(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, status: u8) -> Entry {
+        Entry {
+            time: "2025-01-31T12:00:00Z".to_owned(),
+            command: "secret".to_owned(),
+            name: name.to_owned(),
+            length: 32,
+            kdf: "argon2id v19".to_owned(),
+            status,
+        }
+    }
+
+    #[test]
+    fn timestamps() {
+        let time = OffsetDateTime::from_unix_timestamp(1_738_324_805).unwrap();
+        assert_eq!(timestamp(time), "2025-01-31T12:00:05Z");
+        assert_eq!(
+            timestamp(OffsetDateTime::UNIX_EPOCH),
+            "1970-01-01T00:00:00Z"
+        );
+    }
+
+    #[test]
+    fn only_the_metadata() {
+        let json: serde_json::Value =
+            serde_json::from_str(&lines(&[&entry("email", 0)]).unwrap()).unwrap();
+        let mut keys: Vec<_> = json.as_object().unwrap().keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["command", "kdf", "length", "name", "status", "time"]);
+    }
+
+    #[test]
+    fn read_the_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let (first, second) = (entry("email", 0), entry("two\nlines", 5));
+        fs::write(&path, lines(&[&first, &second]).unwrap() + "\n").unwrap();
+
+        let entries = read(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].name, "two\nlines");
+        assert_eq!(entries[1].status, 5);
+
+        fs::write(&path, "{}\n").unwrap();
+        let err = read(&path).unwrap_err();
+        assert!(
+            err.to_string().starts_with("line 1: invalid entry"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn aligned_table() {
+        let long = Entry {
+            length: 1024,
+            ..entry("two\nlines", 5)
+        };
+        assert_eq!(
+            table(&[&entry("email", 0), &long]),
+            "TIME                  STATUS  COMMAND  NAME        LENGTH  KDF\n\
+             2025-01-31T12:00:00Z  0       secret   email       32      argon2id v19\n\
+             2025-01-31T12:00:00Z  5       secret   two\\nlines  1024    argon2id v19\n"
+        );
+    }
+}
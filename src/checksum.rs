@@ -0,0 +1,168 @@
+//! `sha256sum` compatible manifests of the written files.
+//!
+//! Only the hashes of the files are stored, so the manifest never reveals the secrets,
+//! while `sha256sum -c` (or `verify-files`) detects corrupted files without re-deriving them.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+use sha2::{Digest as _, Sha256};
+
+use crate::{
+    error::{ErrorKind, ResultExt as _},
+    output,
+};
+
+/// Computes the hex encoded SHA-256 of the file.
+pub(super) fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Parses a `<hex SHA-256>  <filename>` line.
+pub(super) fn parse_line(line: &str) -> anyhow::Result<(&str, &str)> {
+    let (hash, filename) = line
+        .split_once("  ")
+        .context("expected `<SHA-256>  <filename>`")?;
+
+    if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(anyhow::anyhow!("invalid SHA-256"));
+    }
+
+    if filename.is_empty() {
+        return Err(anyhow::anyhow!("missing filename"));
+    }
+
+    Ok((hash, filename))
+}
+
+/// Adds the lines of `files` to the manifest at `path`, replacing the existing lines of the same files.
+///
+/// The lines are sorted by filename and the manifest is written atomically.
+pub(super) fn update(path: &Path, files: &[PathBuf]) -> anyhow::Result<()> {
+    let contents = match fs::read_to_string(path) {
+        Ok(str) => str,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => {
+            return Err(err)
+                .context("fs::read_to_string")
+                .kind(ErrorKind::Input);
+        }
+    };
+
+    let mut lines = BTreeMap::new();
+    for (idx, line) in contents.lines().enumerate().filter(|(_, l)| !l.is_empty()) {
+        let (hash, filename) = parse_line(line)
+            .with_context(|| format!("{path:?}, line {}", idx + 1))
+            .kind(ErrorKind::Input)?;
+        lines.insert(filename.to_owned(), hash.to_owned());
+    }
+
+    for file in files {
+        let filename = file
+            .to_str()
+            .filter(|f| !f.contains(['\n', '\r']))
+            .with_context(|| format!("{file:?} can't be listed in the checksum file"))
+            .kind(ErrorKind::Usage)?;
+        let hash = sha256_file(file)
+            .context("checksum::sha256_file")
+            .kind(ErrorKind::Input)?;
+        lines.insert(filename.to_owned(), hash);
+    }
+
+    output::write_to(Some(path), |out| {
+        lines
+            .iter()
+            .try_for_each(|(filename, hash)| writeln!(out, "{hash}  {filename}"))
+            .kind(ErrorKind::Output)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines() {
+        let hash = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert_eq!(
+            parse_line(&format!("{hash}  a b.txt")).unwrap(),
+            (hash, "a b.txt")
+        );
+        assert_eq!(
+            parse_line(&format!("{}  x", hash.to_uppercase()))
+                .unwrap()
+                .1,
+            "x"
+        );
+
+        for invalid in [
+            format!("{hash} a.txt"),
+            format!("{hash}  "),
+            format!("{}  a.txt", &hash[1..]),
+            format!("{}g  a.txt", &hash[1..]),
+            "a.txt".to_owned(),
+            String::new(),
+        ] {
+            assert!(parse_line(&invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn sha256() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("abc");
+        fs::write(&path, "abc").unwrap();
+
+        // FIPS 180-2, appendix B.1.
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn update_sorts_and_replaces() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = |name: &str, contents: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, contents).unwrap();
+            (path.clone(), sha256_file(&path).unwrap())
+        };
+        let manifest = dir.path().join("SHA256SUMS");
+
+        let (b, b_hash) = file("b", "1");
+        let (a, _) = file("a", "2");
+        update(&manifest, &[b.clone(), a]).unwrap();
+
+        let (a, a_hash) = file("a", "3");
+        update(&manifest, std::slice::from_ref(&a)).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&manifest).unwrap(),
+            format!("{a_hash}  {}\n{b_hash}  {}\n", a.display(), b.display())
+        );
+    }
+
+    #[test]
+    fn update_rejects_invalid_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = dir.path().join("SHA256SUMS");
+        fs::write(&manifest, "not a checksum line\n").unwrap();
+
+        let file = dir.path().join("a");
+        fs::write(&file, "a").unwrap();
+        let err = update(&manifest, &[file]).unwrap_err();
+        assert_eq!(crate::error::exit_code(&err), 8);
+        assert_eq!(
+            fs::read_to_string(&manifest).unwrap(),
+            "not a checksum line\n"
+        );
+    }
+}
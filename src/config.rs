@@ -1,46 +1,615 @@
-use std::{fs, io, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt as _, PermissionsExt as _};
+
+use anyhow::Context as _;
+use base64::{Engine as _, engine::general_purpose};
 use directories::ProjectDirs;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
+use crate::term;
+
+/// Argon2 parameters of argon2derive.
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub(super) struct File {
+    #[schemars(extend("enum" = ["argon2d", "argon2id"]))]
     pub algorithm: String,
+    /// Memory cost, in KiB.
     pub memory: u32,
+    /// Time cost, in iterations.
     pub time: u32,
+    /// Number of lanes, which affects the derived secrets.
     pub parallelism: u32,
+    /// Salt, if it's printable UTF-8.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub salt: Option<String>,
+    /// Base64 encoded salt, used instead of `salt` if the salt isn't printable UTF-8.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub salt_b64: Option<String>,
+    /// Additional source of the salt, see `--salt-source`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("enum" = ["machine-id", null]))]
+    pub salt_source: Option<String>,
+    /// Fail instead of warning about an empty salt, see `--require-salt`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub require_salt: bool,
+    /// Version of the passphrase preprocessing, missing in the configs generated before it was introduced
+    /// (meaning version 1, see `LEGACY_PASSPHRASE_VERSION`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passphrase_version: Option<u32>,
+    /// Fingerprint of the parameters the config was generated with, see `--allow-param-change`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    /// Short fingerprint of the passphrase the config is bound to, see `configure --bind-passphrase`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passphrase_fingerprint: Option<String>,
+    /// Argon2 passes following the one using the parameters above, see `--cascade`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passes: Option<Vec<Pass>>,
+    /// Named salts replacing the one above, see `--namespace`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub salts: BTreeMap<String, NamedSalt>,
+    /// Log the derivations using this config get appended to, see `--audit-log`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audit_log: Option<PathBuf>,
+}
+
+/// Salt of a namespace (`[salts.<NAME>]`), stored the same way as the main one.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub(super) struct NamedSalt {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub salt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub salt_b64: Option<String>,
+}
+
+/// Costs of a cascade pass, the salt of which is fixed.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub(super) struct Pass {
+    #[schemars(extend("enum" = ["argon2d", "argon2id"]))]
+    pub algorithm: String,
+    pub memory: u32,
+    pub time: u32,
+    pub parallelism: u32,
+}
+
+impl Pass {
+    fn display(&self) -> String {
+        format!(
+            "{} (memory: {} KiB, time: {}, parallelism: {})",
+            self.algorithm, self.memory, self.time, self.parallelism
+        )
+    }
 }
 
+/// Version of the passphrase preprocessing, stored in the config to keep the derived secrets stable.
+///
+/// 1. Used as is.
+/// 2. NFC normalized.
+pub(super) const PASSPHRASE_VERSION: u32 = 2;
+pub(super) const LEGACY_PASSPHRASE_VERSION: u32 = 1;
+
 impl File {
+    /// Reads the file in the format detected by the extension, see [`format`].
     pub(super) fn read(path: &PathBuf) -> anyhow::Result<Option<Self>> {
         match fs::read_to_string(path) {
-            Ok(str) => Ok(Some(toml::from_str(&str)?)),
+            Ok(str) => Ok(Some(Self::parse(&str, format(path))?)),
             Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
             Err(err) => Err(err.into()),
         }
     }
 
+    /// Reads the file in a format other than TOML, detected by the extension (`json`, `yaml` or `yml`).
+    pub(super) fn import(path: &PathBuf) -> anyhow::Result<Self> {
+        match format(path) {
+            "toml" => Err(anyhow::anyhow!(
+                "unsupported extension, expected .json, .yaml or .yml"
+            )),
+            format => Self::parse(&fs::read_to_string(path)?, format),
+        }
+    }
+
+    fn parse(str: &str, format: &str) -> anyhow::Result<Self> {
+        match format {
+            "json" => serde_json::from_str(str).context("invalid JSON"),
+            "yaml" => serde_yaml::from_str(str).context("invalid YAML"),
+            _ => toml::from_str(str).context("invalid TOML"),
+        }
+    }
+
+    /// Writes the file in the format detected by the extension, see [`format`].
+    ///
+    /// The file is written atomically: the contents go into a temporary file next to `path`,
+    /// created only accessible by its owner, which then gets renamed into place.
+    /// So the config is never exposed, nor left half-written.
     pub(super) fn write(&self, path: &PathBuf) -> anyhow::Result<()> {
         if let Some(dir) = path.parent() {
             fs::create_dir_all(dir)?;
         }
 
-        let str = toml::to_string_pretty(self)?;
-        Ok(fs::write(path, str)?)
+        let str = match format(path) {
+            "json" => serde_json::to_string_pretty(self)? + "\n",
+            "yaml" => serde_yaml::to_string(self)?,
+            _ => toml::to_string_pretty(self)?,
+        };
+
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(format!(".tmp-{:016x}", rand::random::<u64>()));
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        let result = options
+            .open(&tmp)
+            .and_then(|mut file| {
+                file.write_all(str.as_bytes())?;
+                file.sync_all()
+            })
+            .and_then(|()| fs::rename(&tmp, path));
+
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+
+        Ok(result?)
+    }
+
+    /// Whether the file at `path` is accessible by users other than its owner.
+    #[cfg(unix)]
+    pub(super) fn is_exposed(path: &PathBuf) -> anyhow::Result<bool> {
+        Ok(fs::metadata(path)?.permissions().mode() & 0o077 != 0)
+    }
+
+    #[cfg(not(unix))]
+    pub(super) fn is_exposed(_path: &PathBuf) -> anyhow::Result<bool> {
+        Ok(false)
+    }
+
+    pub(super) fn passphrase_version(&self) -> u32 {
+        self.passphrase_version.unwrap_or(LEGACY_PASSPHRASE_VERSION)
+    }
+
+    /// Decoded salt, `salt_b64` taking precedence over `salt`.
+    pub(super) fn salt(&self) -> anyhow::Result<Vec<u8>> {
+        decode_salt(self.salt.as_deref(), self.salt_b64.as_deref())
+    }
+
+    /// Decoded salt of the namespace.
+    pub(super) fn namespace_salt(&self, namespace: &str) -> anyhow::Result<Vec<u8>> {
+        let Some(named) = self.salts.get(namespace) else {
+            return Err(anyhow::anyhow!(
+                "Unknown namespace `{namespace}`, the config defines: {}",
+                self.display_namespaces()
+            ));
+        };
+
+        decode_salt(named.salt.as_deref(), named.salt_b64.as_deref())
+            .with_context(|| format!("[salts.{namespace}]"))
+    }
+
+    fn display_passphrase_fingerprint(&self) -> &str {
+        self.passphrase_fingerprint.as_deref().unwrap_or("none")
+    }
+
+    fn display_audit_log(&self) -> String {
+        match &self.audit_log {
+            Some(path) => format!("{path:?}"),
+            None => "none".to_owned(),
+        }
+    }
+
+    fn display_namespaces(&self) -> String {
+        match self.salts.is_empty() {
+            true => "none".to_owned(),
+            false => self.salts.keys().cloned().collect::<Vec<_>>().join(", "),
+        }
+    }
+
+    /// Stores the salt as plain text if it's printable UTF-8, as base64 otherwise.
+    pub(super) fn set_salt(&mut self, salt: Vec<u8>) {
+        (self.salt, self.salt_b64) = match String::from_utf8(salt) {
+            Ok(salt) if salt.is_empty() => (None, None),
+            Ok(salt) if !salt.chars().any(char::is_control) => (Some(salt), None),
+            Ok(salt) => (None, Some(general_purpose::STANDARD.encode(salt))),
+            Err(err) => (None, Some(general_purpose::STANDARD.encode(err.as_bytes()))),
+        };
+    }
+
+    /// Renders the salt, never printing raw binary data.
+    fn display_salt(&self) -> String {
+        match (&self.salt_b64, self.salt()) {
+            (_, Ok(salt)) if salt.is_empty() => String::new(),
+            (Some(b64), Ok(salt)) => format!("{b64} (base64, {} bytes)", salt.len()),
+            (Some(b64), Err(_)) => format!("{b64} (invalid base64)"),
+            (None, _) => {
+                let salt = self.salt.as_deref().unwrap_or_default();
+                format!("{salt} ({} bytes)", salt.len())
+            }
+        }
+    }
+
+    fn display_salt_source(&self) -> &str {
+        match self.salt_source.as_deref() {
+            Some("machine-id") => "machine-id (bound to this machine)",
+            Some(salt_source) => salt_source,
+            None => "none",
+        }
     }
 
     pub(super) fn eprint(&self) {
-        let salt = self.salt.as_deref();
+        term::detail(format_args!("Algorithm: {}", self.algorithm));
+        term::detail(format_args!("Memory: {} (KiB)", self.memory));
+        term::detail(format_args!("Time: {} (iterations)", self.time));
+        term::detail(format_args!("Parallelism: {} (lanes)", self.parallelism));
+        term::detail(format_args!("Salt: {}", self.display_salt()));
+        term::detail(format_args!("Salt source: {}", self.display_salt_source()));
+        term::detail(format_args!("Require salt: {}", self.require_salt));
+        term::detail(format_args!("Namespaces: {}", self.display_namespaces()));
+        term::detail(format_args!("Audit log: {}", self.display_audit_log()));
+        term::detail(format_args!(
+            "Passphrase fingerprint: {}",
+            self.display_passphrase_fingerprint()
+        ));
+        term::detail(format_args!(
+            "Passphrase version: {}",
+            self.passphrase_version()
+        ));
+
+        for (idx, pass) in self.passes.iter().flatten().enumerate() {
+            term::detail(format_args!("Cascade pass {}: {}", idx + 2, pass.display()));
+        }
+    }
+
+    fn display_passes(&self) -> String {
+        let passes: Vec<_> = self.passes.iter().flatten().map(Pass::display).collect();
+        match passes.is_empty() {
+            true => "none".to_owned(),
+            false => passes.join(", "),
+        }
+    }
 
-        eprintln!("Algorithm: {}", self.algorithm);
-        eprintln!("Memory: {} (KiB)", self.memory);
-        eprintln!("Time: {} (iterations)", self.time);
-        eprintln!("Parallelism: {} (threads)", self.parallelism);
-        eprintln!("Salt: {}", salt.unwrap_or_default());
+    /// Prints the values of both files, marking the ones changed by `new`.
+    pub(super) fn eprint_diff(&self, new: &Self) {
+        let rows = [
+            ("Algorithm", self.algorithm.clone(), new.algorithm.clone()),
+            (
+                "Memory (KiB)",
+                self.memory.to_string(),
+                new.memory.to_string(),
+            ),
+            (
+                "Time (iterations)",
+                self.time.to_string(),
+                new.time.to_string(),
+            ),
+            (
+                "Parallelism (lanes)",
+                self.parallelism.to_string(),
+                new.parallelism.to_string(),
+            ),
+            ("Salt", self.display_salt(), new.display_salt()),
+            (
+                "Salt source",
+                self.display_salt_source().to_owned(),
+                new.display_salt_source().to_owned(),
+            ),
+            (
+                "Require salt",
+                self.require_salt.to_string(),
+                new.require_salt.to_string(),
+            ),
+            (
+                "Namespaces",
+                self.display_namespaces(),
+                new.display_namespaces(),
+            ),
+            (
+                "Audit log",
+                self.display_audit_log(),
+                new.display_audit_log(),
+            ),
+            (
+                "Passphrase fingerprint",
+                self.display_passphrase_fingerprint().to_owned(),
+                new.display_passphrase_fingerprint().to_owned(),
+            ),
+            (
+                "Passphrase version",
+                self.passphrase_version().to_string(),
+                new.passphrase_version().to_string(),
+            ),
+            (
+                "Cascade passes",
+                self.display_passes(),
+                new.display_passes(),
+            ),
+        ];
+
+        for (name, old, new) in rows {
+            if old == new {
+                term::detail(format_args!("  {name}: {old}"));
+            } else {
+                term::info(format_args!("* {name}: {old} -> {new}"));
+            }
+        }
+    }
+}
+
+/// JSON Schema of [`File`], derived from its definition so it covers every field.
+pub(super) fn schema() -> schemars::Schema {
+    schemars::schema_for!(File)
+}
+
+/// Decodes a salt stored as `salt` or `salt_b64`, the latter taking precedence.
+fn decode_salt(salt: Option<&str>, salt_b64: Option<&str>) -> anyhow::Result<Vec<u8>> {
+    match (salt_b64, salt) {
+        (Some(b64), _) => general_purpose::STANDARD
+            .decode(b64)
+            .context("invalid salt_b64"),
+        (None, Some(salt)) => Ok(salt.as_bytes().into()),
+        (None, None) => Ok(Vec::new()),
+    }
+}
+
+/// Config file in the current directory (without the extension), taking precedence over the one in [`default_dir`].
+const LOCAL_FILE: &str = "argon2derive";
+
+/// Supported config formats.
+pub(super) const FORMATS: [&str; 3] = ["toml", "json", "yaml"];
+
+/// Extensions of the config files in the order of precedence, when looking for one.
+const EXTENSIONS: [&str; 4] = ["toml", "json", "yaml", "yml"];
+
+/// Format of the config file, detected by the extension: `json`, `yaml` (`.yaml` or `.yml`) or `toml` (anything else).
+pub(super) fn format(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => "json",
+        Some("yaml" | "yml") => "yaml",
+        _ => "toml",
     }
 }
 
 pub(super) fn default_dir() -> Option<PathBuf> {
     ProjectDirs::from("", "", super::APP_NAME).map(|dirs| dirs.config_dir().into())
 }
+
+/// Copies the file at `path` to `<path>.bak-<unix timestamp>`, returning the path of the copy.
+pub(super) fn backup(path: &PathBuf) -> anyhow::Result<PathBuf> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".bak-{timestamp}"));
+    let backup = PathBuf::from(backup);
+
+    fs::copy(path, &backup)?;
+    Ok(backup)
+}
+
+/// Finds the config file to use when no path was explicitly provided.
+///
+/// Returns the default location (`config.toml`) if there's no config file of any supported format,
+/// neither in the current directory nor in the default one.
+pub(super) fn locate() -> Option<PathBuf> {
+    let existing = |stem: PathBuf| {
+        EXTENSIONS
+            .into_iter()
+            .map(|ext| stem.with_extension(ext))
+            .find(|path| path.is_file())
+    };
+
+    if let Some(local) = existing(PathBuf::from(LOCAL_FILE)) {
+        return Some(local);
+    }
+
+    let dir = default_dir()?;
+    Some(existing(dir.join("config")).unwrap_or_else(|| dir.join("config.toml")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+algorithm = "argon2id"
+memory = 65536
+time = 3
+parallelism = 1
+salt = "my salt"
+"#;
+
+    #[test]
+    fn require_salt() {
+        assert!(!File::parse(CONFIG, "toml").unwrap().require_salt);
+
+        let config = File::parse(&format!("{CONFIG}require_salt = true\n"), "toml").unwrap();
+        assert!(config.require_salt);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        config.write(&path).unwrap();
+        assert!(File::read(&path).unwrap().unwrap().require_salt);
+    }
+
+    #[test]
+    fn namespace_salts() {
+        let config = format!(
+            "{CONFIG}\n[salts.work]\nsalt = \"work salt\"\n\n[salts.bin]\nsalt_b64 = \"AP8=\"\n"
+        );
+        let config = File::parse(&config, "toml").unwrap();
+
+        assert_eq!(config.namespace_salt("work").unwrap(), b"work salt");
+        assert_eq!(config.namespace_salt("bin").unwrap(), [0x00, 0xff]);
+
+        let err = config.namespace_salt("home").unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "Unknown namespace `home`, the config defines: bin, work"
+        );
+    }
+
+    #[test]
+    fn schema_covers_every_field() {
+        let schema = serde_json::to_value(schema()).unwrap();
+        assert_eq!(
+            schema["$schema"],
+            "https://json-schema.org/draft/2020-12/schema"
+        );
+
+        let config = format!(
+            "{CONFIG}salt_b64 = \"AP8=\"\nsalt_source = \"machine-id\"\nrequire_salt = true\npassphrase_version = 2\n\
+             fingerprint = \"ab\"\npassphrase_fingerprint = \"abcd\"\naudit_log = \"audit.log\"\n\
+             [[passes]]\nalgorithm = \"argon2d\"\nmemory = 8\ntime = 1\nparallelism = 1\n\
+             [salts.work]\nsalt_b64 = \"AP8=\"\n"
+        );
+        let config = serde_json::to_value(File::parse(&config, "toml").unwrap()).unwrap();
+        for key in config.as_object().unwrap().keys() {
+            assert!(schema["properties"].get(key).is_some(), "{key}");
+        }
+        // Every field is set.
+        assert_eq!(
+            config.as_object().unwrap().len(),
+            schema["properties"].as_object().unwrap().len()
+        );
+
+        let required = schema["required"].as_array().unwrap();
+        for key in ["algorithm", "memory", "time", "parallelism"] {
+            assert!(required.contains(&key.into()), "{key}");
+        }
+        assert_eq!(
+            schema["properties"]["algorithm"]["enum"],
+            serde_json::json!(["argon2d", "argon2id"])
+        );
+    }
+
+    #[test]
+    fn write_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = File::parse(CONFIG, "toml").unwrap();
+
+        for name in ["config.toml", "config.json", "config.yaml"] {
+            let path = dir.path().join("nested").join(name);
+            config.write(&path).unwrap();
+
+            let read = File::read(&path).unwrap().unwrap();
+            assert_eq!(read.memory, 65536);
+            assert_eq!(read.salt.as_deref(), Some("my salt"));
+        }
+
+        // Nothing but the configs is left behind.
+        assert_eq!(fs::read_dir(dir.path().join("nested")).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn import_fixtures() {
+        let dir = tempfile::tempdir().unwrap();
+        let fixtures = [
+            (
+                "params.json",
+                r#"{"algorithm": "argon2id", "memory": 65536, "time": 3, "parallelism": 1, "salt": "my salt"}"#,
+            ),
+            (
+                "params.yaml",
+                "algorithm: argon2id\nmemory: 65536\ntime: 3\nparallelism: 1\nsalt: my salt\n",
+            ),
+            (
+                "params.yml",
+                "algorithm: argon2id\nmemory: 65536\ntime: 3\nparallelism: 1\nsalt: my salt\n",
+            ),
+        ];
+
+        let expected =
+            argon2derive::argon2::Parameters::try_from(File::parse(CONFIG, "toml").unwrap())
+                .unwrap();
+        for (name, contents) in fixtures {
+            let path = dir.path().join(name);
+            fs::write(&path, contents).unwrap();
+
+            let params =
+                argon2derive::argon2::Parameters::try_from(File::import(&path).unwrap()).unwrap();
+            assert_eq!(params.fingerprint(), expected.fingerprint(), "{name}");
+        }
+
+        let toml = dir.path().join("params.toml");
+        fs::write(&toml, CONFIG).unwrap();
+        assert!(File::import(&toml).is_err());
+
+        let invalid = dir.path().join("invalid.json");
+        fs::write(&invalid, "algorithm = \"argon2id\"").unwrap();
+        assert!(File::import(&invalid).is_err());
+    }
+
+    #[test]
+    fn unset_fields_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = File::parse(CONFIG, "toml").unwrap();
+        config.set_salt(b"\xff\x00\x01".to_vec());
+
+        for name in ["config.toml", "config.json", "config.yaml"] {
+            let path = dir.path().join(name);
+            config.write(&path).unwrap();
+
+            let written = fs::read_to_string(&path).unwrap();
+            assert!(!written.contains("null"), "{name}: {written}");
+            assert!(
+                !written.contains("\"salt\"") && !written.contains("salt ="),
+                "{name}: {written}"
+            );
+            assert!(!written.contains("salt:"), "{name}: {written}");
+
+            let read = File::read(&path).unwrap().unwrap();
+            assert_eq!(read.salt, None, "{name}");
+            assert_eq!(read.salt().unwrap(), b"\xff\x00\x01", "{name}");
+        }
+    }
+
+    #[test]
+    fn salt_display() {
+        let mut config = File::parse(CONFIG, "toml").unwrap();
+        assert_eq!(config.display_salt(), "my salt (7 bytes)");
+
+        config.set_salt(b"\xff\x00\x01".to_vec());
+        assert_eq!(config.salt.as_deref(), None);
+        assert_eq!(config.display_salt(), "/wAB (base64, 3 bytes)");
+
+        config.set_salt(Vec::new());
+        assert_eq!(config.display_salt(), "");
+
+        config.salt_b64 = Some("not base64!".into());
+        assert_eq!(config.display_salt(), "not base64! (invalid base64)");
+        assert!(config.salt().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_is_private() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let config = File::parse(CONFIG, "toml").unwrap();
+
+        config.write(&path).unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        assert!(!File::is_exposed(&path).unwrap());
+
+        // An exposed config gets replaced by a private one.
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(File::is_exposed(&path).unwrap());
+        config.write(&path).unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+    }
+}
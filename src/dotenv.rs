@@ -0,0 +1,129 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write as _},
+    path::Path,
+};
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt as _;
+
+/// Converts a secret name into a dotenv key (e.g. `db-pass` -> `DB_PASS`).
+pub(super) fn key(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_uppercase(),
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Sets `key` to `value` in the dotenv file at `path`.
+///
+/// An existing assignment of `key` is updated in place, otherwise a new line is appended.
+/// If the file doesn't exist it gets created (with 0600 permissions on Unix).
+pub(super) fn upsert(path: &Path, key: &str, value: &str) -> anyhow::Result<()> {
+    let contents = match fs::read_to_string(path) {
+        Ok(str) => Some(str),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut found = false;
+
+    let mut lines: Vec<String> = contents
+        .iter()
+        .flat_map(|str| str.lines())
+        .map(|l| match l.split_once('=') {
+            // Keep whatever precedes `=` (e.g. `export `) intact.
+            Some((lhs, _)) if assigned_key(l) == Some(key) => {
+                found = true;
+                format!("{lhs}={value}")
+            }
+            _ => l.to_owned(),
+        })
+        .collect();
+
+    if !found {
+        lines.push(format!("{key}={value}"));
+    }
+
+    let mut str = lines.join("\n");
+    str.push('\n');
+
+    let mut file = OpenOptions::new();
+    file.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    if contents.is_none() {
+        file.mode(0o600);
+    }
+
+    Ok(file.open(path)?.write_all(str.as_bytes())?)
+}
+
+/// Returns the key assigned by a dotenv line, if it's an assignment.
+fn assigned_key(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    let line = line.strip_prefix("export ").unwrap_or(line);
+
+    line.split_once('=').map(|(key, _)| key.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_from_name() {
+        assert_eq!(key("db-pass"), "DB_PASS");
+        assert_eq!(key("api.Key2"), "API_KEY2");
+        assert_eq!(key("ключ"), "____");
+    }
+
+    #[test]
+    fn upsert_updates_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".env");
+        fs::write(
+            &path,
+            "# comment\nexport DB_PASS=old\nDB_PASSWORD=keep\nOTHER = 1\n",
+        )
+        .unwrap();
+
+        upsert(&path, "DB_PASS", "new=value").unwrap();
+        upsert(&path, "OTHER", "2").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "# comment\nexport DB_PASS=new=value\nDB_PASSWORD=keep\nOTHER =2\n"
+        );
+    }
+
+    #[test]
+    fn upsert_appends_missing_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".env");
+        fs::write(&path, "A=1").unwrap();
+
+        upsert(&path, "B", "2").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "A=1\nB=2\n");
+    }
+
+    #[test]
+    fn upsert_creates_private_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".env");
+
+        upsert(&path, "A", "1").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "A=1\n");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+            assert_eq!(
+                fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+                0o600
+            );
+        }
+    }
+}
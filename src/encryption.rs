@@ -0,0 +1,360 @@
+//! Symmetric file encryption using XChaCha20-Poly1305 in the STREAM construction.
+//!
+//! File layout:
+//! ```text
+//! magic (8) | version (1) | name length (2, BE) | name | nonce (19) | chunks...
+//! ```
+//! The whole header is authenticated as associated data of every chunk.
+//! Each chunk carries `CHUNK_SIZE` bytes of plaintext (the last one is shorter, possibly empty)
+//! followed by a 16-byte authentication tag.
+
+use std::io::{self, Read, Write};
+
+use anyhow::Context as _;
+use chacha20poly1305::{
+    KeyInit as _, XChaCha20Poly1305,
+    aead::{
+        Payload,
+        stream::{DecryptorBE32, EncryptorBE32},
+    },
+};
+
+use crate::{
+    error::{ErrorKind, ResultExt as _},
+    interrupt,
+};
+
+const MAGIC: &[u8; 8] = b"a2dcrypt";
+const VERSION: u8 = 1;
+
+const NONCE_LEN: usize = 19;
+const TAG_LEN: usize = 16;
+const CHUNK_SIZE: usize = 64 * 1024;
+
+pub(super) struct Header {
+    /// Name of the secret the key was derived from.
+    pub name: String,
+    nonce: [u8; NONCE_LEN],
+}
+
+impl Header {
+    pub(super) fn new(name: String) -> Self {
+        Self {
+            name,
+            nonce: rand::random(),
+        }
+    }
+
+    pub(super) fn read(input: &mut impl Read) -> anyhow::Result<Self> {
+        let mut magic = [0; MAGIC.len()];
+        let mut version = [0];
+        input
+            .read_exact(&mut magic)
+            .and_then(|_| input.read_exact(&mut version))
+            .context("read header")?;
+
+        if &magic != MAGIC {
+            return Err(anyhow::anyhow!("Not an {} encrypted file", super::APP_NAME));
+        }
+
+        if version[0] != VERSION {
+            return Err(anyhow::anyhow!(
+                "Unsupported encrypted file version: {}",
+                version[0]
+            ));
+        }
+
+        let mut name_len = [0; 2];
+        input.read_exact(&mut name_len).context("read header")?;
+
+        let mut name = vec![0; u16::from_be_bytes(name_len).into()];
+        let mut nonce = [0; NONCE_LEN];
+        input
+            .read_exact(&mut name)
+            .and_then(|_| input.read_exact(&mut nonce))
+            .context("read header")?;
+
+        Ok(Self {
+            name: String::from_utf8(name).context("invalid name in header")?,
+            nonce,
+        })
+    }
+
+    fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let name_len = u16::try_from(self.name.len()).context("name is too long")?;
+
+        let mut bytes = Vec::with_capacity(MAGIC.len() + 3 + self.name.len() + NONCE_LEN);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&name_len.to_be_bytes());
+        bytes.extend_from_slice(self.name.as_bytes());
+        bytes.extend_from_slice(&self.nonce);
+        Ok(bytes)
+    }
+}
+
+pub(super) fn encrypt(
+    key: &[u8; 32],
+    header: &Header,
+    input: &mut impl Read,
+    output: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let aad = header.to_bytes()?;
+    output.write_all(&aad).kind(ErrorKind::Output)?;
+
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let mut encryptor = EncryptorBE32::from_aead(cipher, (&header.nonce).into());
+
+    let mut buf = vec![0; CHUNK_SIZE];
+    loop {
+        let len = read_full(input, &mut buf)?;
+        // Checked before the chunk is processed, as it may have been cut short by the signal.
+        interrupt::check()?;
+        let payload = Payload {
+            msg: &buf[..len],
+            aad: &aad,
+        };
+
+        if len < CHUNK_SIZE {
+            let chunk = encryptor.encrypt_last(payload).map_err(encryption_error)?;
+            return output.write_all(&chunk).kind(ErrorKind::Output);
+        }
+
+        let chunk = encryptor.encrypt_next(payload).map_err(encryption_error)?;
+        output.write_all(&chunk).kind(ErrorKind::Output)?;
+    }
+}
+
+/// Decrypts the data following the already consumed `header`.
+///
+/// Every chunk is authenticated before being written to `output`.
+pub(super) fn decrypt(
+    key: &[u8; 32],
+    header: &Header,
+    input: &mut impl Read,
+    output: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let aad = header.to_bytes()?;
+
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let mut decryptor = DecryptorBE32::from_aead(cipher, (&header.nonce).into());
+
+    let mut buf = vec![0; CHUNK_SIZE + TAG_LEN];
+    loop {
+        let len = read_full(input, &mut buf)?;
+        // Checked before the chunk is processed, as it may have been cut short by the signal.
+        interrupt::check()?;
+        let payload = Payload {
+            msg: &buf[..len],
+            aad: &aad,
+        };
+
+        if len < buf.len() {
+            let chunk = decryptor.decrypt_last(payload).map_err(decryption_error)?;
+            return output.write_all(&chunk).kind(ErrorKind::Output);
+        }
+
+        let chunk = decryptor.decrypt_next(payload).map_err(decryption_error)?;
+        output.write_all(&chunk).kind(ErrorKind::Output)?;
+    }
+}
+
+/// Reads until `buf` is full or EOF is reached, returning the number of bytes read.
+///
+/// Returns early if a read is interrupted by a signal within an [`interrupt::Deferred`] region.
+fn read_full(input: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match input.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted && interrupt::cancelled() => break,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(len)
+}
+
+fn encryption_error(_: chacha20poly1305::aead::Error) -> anyhow::Error {
+    anyhow::anyhow!("Encryption failed")
+}
+
+fn decryption_error(_: chacha20poly1305::aead::Error) -> anyhow::Error {
+    ErrorKind::Mismatch.error(
+        "Decryption failed: the file is corrupted, truncated or was encrypted with a different key",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 32] = [7; 32];
+
+    fn encrypt_bytes(key: &[u8; 32], name: &str, plaintext: &[u8]) -> Vec<u8> {
+        let mut ciphertext = Vec::new();
+        encrypt(
+            key,
+            &Header::new(name.to_owned()),
+            &mut &*plaintext,
+            &mut ciphertext,
+        )
+        .unwrap();
+        ciphertext
+    }
+
+    fn decrypt_bytes(key: &[u8; 32], mut ciphertext: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let header = Header::read(&mut ciphertext)?;
+        let mut plaintext = Vec::new();
+        decrypt(key, &header, &mut ciphertext, &mut plaintext)?;
+        Ok(plaintext)
+    }
+
+    #[track_caller]
+    fn assert_mismatch(result: anyhow::Result<Vec<u8>>) {
+        let err = result.expect_err("decryption should fail");
+        assert_eq!(ErrorKind::of(&err), Some(ErrorKind::Mismatch), "{err:#}");
+    }
+
+    fn header_len(name: &str) -> usize {
+        MAGIC.len() + 3 + name.len() + NONCE_LEN
+    }
+
+    fn plaintext(len: usize) -> Vec<u8> {
+        (0..len).map(|idx| (idx % 251) as u8).collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let lens = [
+            0,
+            1,
+            CHUNK_SIZE - 1,
+            CHUNK_SIZE,
+            CHUNK_SIZE + 1,
+            3 * CHUNK_SIZE,
+            3 * CHUNK_SIZE + 123,
+        ];
+
+        for len in lens {
+            let plaintext = plaintext(len);
+            let ciphertext = encrypt_bytes(&KEY, "backup", &plaintext);
+
+            // A tag per full chunk, plus the last (possibly empty) chunk.
+            let chunks = len / CHUNK_SIZE + 1;
+            assert_eq!(
+                ciphertext.len(),
+                header_len("backup") + len + chunks * TAG_LEN
+            );
+            assert_eq!(
+                decrypt_bytes(&KEY, &ciphertext).unwrap(),
+                plaintext,
+                "{len}"
+            );
+        }
+    }
+
+    #[test]
+    fn header_round_trip() {
+        let header = Header::new("backup".to_owned());
+        let bytes = header.to_bytes().unwrap();
+        assert_eq!(bytes.len(), header_len("backup"));
+        assert_eq!(&bytes[..MAGIC.len()], MAGIC);
+
+        let read = Header::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read.name, "backup");
+        assert_eq!(read.nonce, header.nonce);
+    }
+
+    #[test]
+    fn nonces_are_random() {
+        let plaintext = plaintext(100);
+        assert_ne!(
+            encrypt_bytes(&KEY, "backup", &plaintext),
+            encrypt_bytes(&KEY, "backup", &plaintext)
+        );
+    }
+
+    #[test]
+    fn detects_tampering() {
+        let plaintext = plaintext(2 * CHUNK_SIZE + 10);
+        let ciphertext = encrypt_bytes(&KEY, "backup", &plaintext);
+        let start = header_len("backup");
+
+        // The first byte of every chunk, and the last tag byte.
+        let offsets = [
+            start,
+            start + CHUNK_SIZE + TAG_LEN,
+            start + 2 * (CHUNK_SIZE + TAG_LEN),
+            ciphertext.len() - 1,
+        ];
+        for offset in offsets {
+            let mut tampered = ciphertext.clone();
+            tampered[offset] ^= 1;
+            assert_mismatch(decrypt_bytes(&KEY, &tampered));
+        }
+    }
+
+    #[test]
+    fn detects_truncation_at_chunk_boundaries() {
+        // A multiple of the chunk size, so the last chunk is an empty one.
+        let plaintext = plaintext(2 * CHUNK_SIZE);
+        let ciphertext = encrypt_bytes(&KEY, "backup", &plaintext);
+        let start = header_len("backup");
+        assert_eq!(
+            ciphertext.len(),
+            start + 2 * (CHUNK_SIZE + TAG_LEN) + TAG_LEN
+        );
+
+        let boundaries = [
+            start,
+            start + CHUNK_SIZE + TAG_LEN,
+            start + 2 * (CHUNK_SIZE + TAG_LEN),
+        ];
+        for len in boundaries {
+            assert_mismatch(decrypt_bytes(&KEY, &ciphertext[..len]));
+        }
+
+        // Within a chunk.
+        assert_mismatch(decrypt_bytes(&KEY, &ciphertext[..start + 100]));
+        assert_mismatch(decrypt_bytes(&KEY, &ciphertext[..ciphertext.len() - 1]));
+    }
+
+    #[test]
+    fn detects_appended_data() {
+        let mut ciphertext = encrypt_bytes(&KEY, "backup", &plaintext(10));
+        ciphertext.push(0);
+        assert_mismatch(decrypt_bytes(&KEY, &ciphertext));
+    }
+
+    #[test]
+    fn authenticates_the_header() {
+        let ciphertext = encrypt_bytes(&KEY, "backup", &plaintext(10));
+
+        // Another name of the same length, still decrypted with the same key.
+        let mut renamed = ciphertext.clone();
+        renamed[MAGIC.len() + 3] = b'h';
+        assert_mismatch(decrypt_bytes(&KEY, &renamed));
+
+        let mut nonce = ciphertext.clone();
+        nonce[header_len("backup") - 1] ^= 1;
+        assert_mismatch(decrypt_bytes(&KEY, &nonce));
+
+        let mut version = ciphertext.clone();
+        version[MAGIC.len()] = 2;
+        let err = decrypt_bytes(&KEY, &version).unwrap_err();
+        assert_eq!(err.to_string(), "Unsupported encrypted file version: 2");
+
+        let mut magic = ciphertext;
+        magic[0] ^= 1;
+        assert!(decrypt_bytes(&KEY, &magic).is_err());
+    }
+
+    #[test]
+    fn wrong_key() {
+        let ciphertext = encrypt_bytes(&KEY, "backup", &plaintext(10));
+        assert_mismatch(decrypt_bytes(&[8; 32], &ciphertext));
+    }
+}
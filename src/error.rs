@@ -0,0 +1,294 @@
+use std::{error, fmt};
+
+use serde::Serialize;
+
+use crate::argon2::ParamError;
+
+/// Class of a failure, determining the exit code of the process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ErrorKind {
+    /// Invalid usage or parameters.
+    Usage,
+    /// Missing or invalid config file.
+    Config,
+    /// Failure to read the passphrase.
+    Passphrase,
+    /// Argon2 failure (e.g. memory allocation).
+    Derivation,
+    /// Failure to write the output.
+    Output,
+    /// Verification mismatch.
+    Mismatch,
+    /// Failure to read the input data (e.g. a missing file).
+    Input,
+    /// Interrupted by Ctrl-C (SIGINT) or SIGTERM.
+    Interrupted,
+}
+
+/// Exit codes, as documented in the `--help` output.
+pub(super) const EXIT_CODES_HELP: &str = "\
+Exit codes:
+    0  Success
+    1  Unclassified error
+    2  Invalid usage or parameters
+    3  Missing or invalid config file
+    4  Passphrase input error
+    5  Derivation failure (e.g. memory allocation)
+    6  Output write failure
+    7  Verification mismatch
+    8  Input read failure (e.g. missing input file)
+  130  Interrupted (Ctrl-C or SIGTERM)";
+
+impl ErrorKind {
+    pub(super) const fn exit_code(self) -> u8 {
+        match self {
+            Self::Usage => 2,
+            Self::Config => 3,
+            Self::Passphrase => 4,
+            Self::Derivation => 5,
+            Self::Output => 6,
+            Self::Mismatch => 7,
+            Self::Input => 8,
+            // 128 + SIGINT, as reported by shells.
+            Self::Interrupted => 130,
+        }
+    }
+
+    /// Name of the kind in the JSON error output.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Usage => "usage",
+            Self::Config => "config",
+            Self::Passphrase => "passphrase",
+            Self::Derivation => "derivation",
+            Self::Output => "output",
+            Self::Mismatch => "mismatch",
+            Self::Input => "input",
+            Self::Interrupted => "interrupted",
+        }
+    }
+
+    fn hint(self) -> Option<&'static str> {
+        Some(match self {
+            Self::Usage => "See `--help` for the usage",
+            Self::Config => {
+                "Generate a config using the `configure` command or specify the parameters via the CLI"
+            }
+            Self::Derivation => "Try lowering the memory cost",
+            Self::Passphrase | Self::Output | Self::Mismatch | Self::Input | Self::Interrupted => {
+                return None;
+            }
+        })
+    }
+
+    /// Finds the outermost classified error in the chain.
+    pub(super) fn of(err: &anyhow::Error) -> Option<Self> {
+        err.chain().find_map(|err| {
+            if let Some(err) = err.downcast_ref::<Error>() {
+                Some(err.kind)
+            } else if err.is::<ParamError>() {
+                Some(Self::Usage)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Creates a new error of this kind.
+    pub(super) fn error<M>(self, msg: M) -> anyhow::Error
+    where
+        M: fmt::Display + fmt::Debug + Send + Sync + 'static,
+    {
+        Error {
+            kind: self,
+            inner: anyhow::Error::msg(msg),
+        }
+        .into()
+    }
+}
+
+/// Exit code of the process failed with the provided error.
+pub(super) fn exit_code(err: &anyhow::Error) -> u8 {
+    ErrorKind::of(err).map_or(1, ErrorKind::exit_code)
+}
+
+/// Renders the error as a single-line JSON object.
+pub(super) fn to_json(err: &anyhow::Error) -> String {
+    #[derive(Serialize)]
+    struct Json<'a> {
+        error: String,
+        kind: &'a str,
+        code: u8,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        hint: Option<&'a str>,
+    }
+
+    let json = Json {
+        error: format!("{err:#}"),
+        kind: ErrorKind::of(err).map_or("unclassified", ErrorKind::name),
+        code: exit_code(err),
+        hint: ErrorKind::of(err).and_then(ErrorKind::hint),
+    };
+
+    serde_json::to_string(&json).expect("JSON serialization of strings can't fail")
+}
+
+/// Error tagged with an [`ErrorKind`], otherwise transparent.
+#[derive(Debug)]
+struct Error {
+    kind: ErrorKind,
+    inner: anyhow::Error,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.inner.source()
+    }
+}
+
+pub(super) trait ResultExt<T> {
+    /// Classifies the error as being of the provided kind.
+    fn kind(self, kind: ErrorKind) -> anyhow::Result<T>;
+}
+
+impl<T, E: Into<anyhow::Error>> ResultExt<T> for Result<T, E> {
+    fn kind(self, kind: ErrorKind) -> anyhow::Result<T> {
+        self.map_err(|err| {
+            Error {
+                kind,
+                inner: err.into(),
+            }
+            .into()
+        })
+    }
+}
+
+/// Stops the command once the plan of `--dry-run` or `--describe` is printed, `main` exiting with 0.
+#[derive(Debug)]
+pub(super) struct PlanPrinted;
+
+impl PlanPrinted {
+    /// Whether the error is (or was caused by) [`PlanPrinted`].
+    pub(super) fn is(err: &anyhow::Error) -> bool {
+        // `Error` forwards to its inner error, skipping it in the chain.
+        err.chain().any(|err| {
+            err.is::<Self>()
+                || err
+                    .downcast_ref::<Error>()
+                    .is_some_and(|err| Self::is(&err.inner))
+        })
+    }
+}
+
+impl fmt::Display for PlanPrinted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the plan was printed instead of deriving")
+    }
+}
+
+impl error::Error for PlanPrinted {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_codes_match_the_help() {
+        for kind in [
+            ErrorKind::Usage,
+            ErrorKind::Config,
+            ErrorKind::Passphrase,
+            ErrorKind::Derivation,
+            ErrorKind::Output,
+            ErrorKind::Mismatch,
+            ErrorKind::Input,
+            ErrorKind::Interrupted,
+        ] {
+            let code = format!("{:>5}  ", kind.exit_code());
+            assert!(
+                EXIT_CODES_HELP.lines().any(|line| line.starts_with(&code)),
+                "{kind:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn classification() {
+        let unclassified = anyhow::anyhow!("unclassified");
+        assert_eq!(exit_code(&unclassified), 1);
+
+        let err = ErrorKind::Config.error("missing config file");
+        assert_eq!(exit_code(&err), 3);
+
+        // The outermost kind wins.
+        let err = Err::<(), _>(ErrorKind::Input.error("inner"))
+            .kind(ErrorKind::Output)
+            .unwrap_err();
+        assert_eq!(ErrorKind::of(&err), Some(ErrorKind::Output));
+
+        // Context doesn't hide the kind.
+        let err = ErrorKind::Mismatch.error("mismatch").context("context");
+        assert_eq!(exit_code(&err), 7);
+
+        let err = anyhow::Error::from(ParamError::ZeroTime).context("context");
+        assert_eq!(exit_code(&err), 2);
+    }
+
+    #[test]
+    fn json() {
+        let err = ErrorKind::Usage.error("bad").context("outer");
+        assert_eq!(
+            to_json(&err),
+            r#"{"error":"outer: bad","kind":"usage","code":2,"hint":"See `--help` for the usage"}"#
+        );
+
+        let err = anyhow::anyhow!("oops");
+        assert_eq!(
+            to_json(&err),
+            r#"{"error":"oops","kind":"unclassified","code":1}"#
+        );
+    }
+
+    #[test]
+    fn kind_names() {
+        let kinds = [
+            ErrorKind::Usage,
+            ErrorKind::Config,
+            ErrorKind::Passphrase,
+            ErrorKind::Derivation,
+            ErrorKind::Output,
+            ErrorKind::Mismatch,
+            ErrorKind::Input,
+            ErrorKind::Interrupted,
+        ];
+        let names: std::collections::HashSet<_> = kinds.iter().map(|kind| kind.name()).collect();
+        assert_eq!(names.len(), kinds.len());
+        assert!(!names.contains("unclassified"));
+
+        for kind in kinds {
+            let json: serde_json::Value =
+                serde_json::from_str(&to_json(&kind.error("msg"))).unwrap();
+            assert_eq!(json["kind"], kind.name());
+            assert_eq!(json["code"], kind.exit_code());
+        }
+    }
+
+    #[test]
+    fn plan_printed() {
+        let err = anyhow::Error::from(PlanPrinted);
+        assert!(PlanPrinted::is(&err));
+        assert!(PlanPrinted::is(&err.context("derive_secret")));
+        assert!(PlanPrinted::is(
+            &Err::<(), _>(PlanPrinted)
+                .kind(ErrorKind::Usage)
+                .unwrap_err()
+        ));
+        assert!(!PlanPrinted::is(&ErrorKind::Usage.error("usage")));
+    }
+}
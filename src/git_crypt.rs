@@ -0,0 +1,128 @@
+//! [git-crypt](https://www.agwa.name/projects/git-crypt/) key files.
+//!
+//! Layout (version 2, all integers are 32-bit big-endian):
+//! ```text
+//! "\0GITCRYPTKEY" | format version | header fields... | 0 | key entry fields... | 0
+//! ```
+//! Every field is `id | length | value`, the key entry holding the key version,
+//! a 32-byte AES key and a 64-byte HMAC key.
+
+use zeroize::Zeroizing;
+
+const MAGIC: &[u8; 12] = b"\0GITCRYPTKEY";
+const FORMAT_VERSION: u32 = 2;
+
+const HEADER_FIELD_END: u32 = 0;
+const HEADER_FIELD_KEY_NAME: u32 = 1;
+
+const KEY_FIELD_END: u32 = 0;
+const KEY_FIELD_VERSION: u32 = 1;
+const KEY_FIELD_AES_KEY: u32 = 3;
+const KEY_FIELD_HMAC_KEY: u32 = 5;
+
+const AES_KEY_LEN: usize = 32;
+const HMAC_KEY_LEN: usize = 64;
+
+/// Length of the key material to derive, the AES key followed by the HMAC key.
+pub(super) const KEY_MATERIAL_LEN: u32 = (AES_KEY_LEN + HMAC_KEY_LEN) as u32;
+
+/// Validates the name of a non-default key (`git-crypt init -k <name>`).
+pub(super) fn parse_key_name(s: &str) -> anyhow::Result<String> {
+    if s == "default" {
+        return Err(anyhow::anyhow!(
+            "`default` is reserved, omit the name instead"
+        ));
+    }
+
+    if s.is_empty()
+        || !s
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_".contains(c))
+    {
+        return Err(anyhow::anyhow!(
+            "Key names may only contain ASCII letters, digits, `-` and `_`"
+        ));
+    }
+
+    Ok(s.to_owned())
+}
+
+/// Serializes a key file holding a single key (version 0) made of the derived key material.
+pub(super) fn key_file(key_material: &[u8], key_name: Option<&str>) -> Zeroizing<Vec<u8>> {
+    let (aes_key, hmac_key) = key_material.split_at(AES_KEY_LEN);
+    assert_eq!(hmac_key.len(), HMAC_KEY_LEN);
+
+    let mut out = Zeroizing::new(MAGIC.to_vec());
+    out.extend_from_slice(&FORMAT_VERSION.to_be_bytes());
+
+    if let Some(name) = key_name {
+        field(&mut out, HEADER_FIELD_KEY_NAME, name.as_bytes());
+    }
+    out.extend_from_slice(&HEADER_FIELD_END.to_be_bytes());
+
+    field(&mut out, KEY_FIELD_VERSION, &0u32.to_be_bytes());
+    field(&mut out, KEY_FIELD_AES_KEY, aes_key);
+    field(&mut out, KEY_FIELD_HMAC_KEY, hmac_key);
+    out.extend_from_slice(&KEY_FIELD_END.to_be_bytes());
+
+    out
+}
+
+fn field(out: &mut Vec<u8>, id: u32, value: &[u8]) {
+    out.extend_from_slice(&id.to_be_bytes());
+    out.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    out.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Key material of the fixtures: the AES key is `00..1f`, the HMAC key `20..5f`.
+    fn key_material() -> Vec<u8> {
+        (0..KEY_MATERIAL_LEN as u8).collect()
+    }
+
+    /// Key file as serialized by git-crypt's `Key_file::store` (`key.cpp`), field by field.
+    fn fixture(key_name: Option<&[u8]>) -> Vec<u8> {
+        let mut out = b"\0GITCRYPTKEY\0\0\0\x02".to_vec();
+        if let Some(name) = key_name {
+            out.extend_from_slice(b"\0\0\0\x01");
+            out.extend_from_slice(&(name.len() as u32).to_be_bytes());
+            out.extend_from_slice(name);
+        }
+        out.extend_from_slice(b"\0\0\0\0");
+
+        // Key version 0.
+        out.extend_from_slice(b"\0\0\0\x01\0\0\0\x04\0\0\0\0");
+        out.extend_from_slice(b"\0\0\0\x03\0\0\0\x20");
+        out.extend(0..0x20);
+        out.extend_from_slice(b"\0\0\0\x05\0\0\0\x40");
+        out.extend(0x20..0x60);
+        out.extend_from_slice(b"\0\0\0\0");
+        out
+    }
+
+    #[test]
+    fn default_key_layout() {
+        let key_file = key_file(&key_material(), None);
+        assert_eq!(*key_file, fixture(None));
+        assert_eq!(key_file.len(), 16 + 4 + 12 + 8 + 32 + 8 + 64 + 4);
+    }
+
+    #[test]
+    fn named_key_layout() {
+        let key_file = key_file(&key_material(), Some("deploy"));
+        assert_eq!(*key_file, fixture(Some(b"deploy")));
+    }
+
+    #[test]
+    fn key_names() {
+        for valid in ["deploy", "ci_2", "A-b"] {
+            assert_eq!(parse_key_name(valid).unwrap(), valid);
+        }
+        for invalid in ["default", "", "a b", "a/b", "ключ"] {
+            assert!(parse_key_name(invalid).is_err(), "{invalid}");
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! `htpasswd` lines (bcrypt) for web server basic auth.
+
+use anyhow::Context as _;
+use bcrypt::Version;
+
+/// BLAKE3 key derivation context of `--deterministic-salt`.
+const SALT_CONTEXT: &str = "argon2derive 2026-10-16 htpasswd bcrypt salt";
+
+/// bcrypt only uses the first 72 bytes of the password.
+const MAX_PASSWORD_LEN: usize = 72;
+
+/// Renders `user:$2y$<cost>$<salt><hash>`, understood by both Apache and nginx.
+///
+/// The bcrypt salt is random, unless `deterministic_salt` is set, in which case it's derived
+/// from the user and the password (so the line only changes along with either of them).
+pub(super) fn line(
+    user: &str,
+    password: &str,
+    cost: u32,
+    deterministic_salt: bool,
+) -> anyhow::Result<String> {
+    if user.is_empty() || user.contains([':', '\n', '\r']) {
+        return Err(anyhow::anyhow!("Invalid htpasswd user: {user:?}"));
+    }
+
+    if password.len() > MAX_PASSWORD_LEN {
+        return Err(anyhow::anyhow!(
+            "The password is {} bytes long, while bcrypt only supports up to {MAX_PASSWORD_LEN}",
+            password.len()
+        ));
+    }
+
+    let salt = if deterministic_salt {
+        let mut hasher = blake3::Hasher::new_derive_key(SALT_CONTEXT);
+        hasher.update(&(user.len() as u32).to_be_bytes());
+        hasher.update(user.as_bytes());
+        hasher.update(password.as_bytes());
+        let hash = hasher.finalize();
+        hash.as_bytes()[..16].try_into().unwrap()
+    } else {
+        rand::random()
+    };
+
+    let hash = bcrypt::hash_with_salt(password, cost, salt).context("bcrypt::hash_with_salt")?;
+    Ok(format!("{user}:{}", hash.format_for_version(Version::TwoY)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deterministic_vectors() {
+        // Checked with libxcrypt's `crypt(3)`, under both the `$2y$` and `$2b$` prefixes.
+        let vectors = [
+            (
+                "admin",
+                "password",
+                4,
+                "admin:$2y$04$H.uN04.FSY45Ww.6O6qyoe.NQWtkqhTUubltESFGROzumdBSiRoX.",
+            ),
+            (
+                "alice",
+                "correct horse",
+                5,
+                "alice:$2y$05$r6HhYHbxxWKMUImqObgKJOh2lTMhSNGHPSqQbLIn7lOsaiLX7tar.",
+            ),
+        ];
+
+        for (user, password, cost, expected) in vectors {
+            assert_eq!(line(user, password, cost, true).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn prefixes() {
+        let line = line("admin", "password", 4, false).unwrap();
+        let hash = line.strip_prefix("admin:").unwrap();
+        assert!(hash.starts_with("$2y$04$"), "{hash}");
+        assert!(bcrypt::verify("password", hash).unwrap());
+        assert!(!bcrypt::verify("passwore", hash).unwrap());
+
+        // `$2b$` only differs by its prefix.
+        let two_b = hash.replacen("$2y$", "$2b$", 1);
+        assert!(bcrypt::verify("password", &two_b).unwrap());
+    }
+
+    #[test]
+    fn salts() {
+        let random = line("admin", "password", 4, false).unwrap();
+        assert_ne!(line("admin", "password", 4, false).unwrap(), random);
+
+        let salt = |line: &str| line.split('$').nth(3).unwrap()[..22].to_owned();
+        let derived = line("admin", "password", 4, true).unwrap();
+        assert_ne!(
+            salt(&line("root", "password", 4, true).unwrap()),
+            salt(&derived)
+        );
+        assert_ne!(
+            salt(&line("admin", "passwore", 4, true).unwrap()),
+            salt(&derived)
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        for user in ["", "a:b", "a\nb", "a\rb"] {
+            assert!(line(user, "password", 4, true).is_err(), "{user:?}");
+        }
+
+        assert!(line("admin", &"a".repeat(MAX_PASSWORD_LEN), 4, true).is_ok());
+        assert!(line("admin", &"a".repeat(MAX_PASSWORD_LEN + 1), 4, true).is_err());
+        assert!(line("admin", "password", 3, true).is_err());
+    }
+}
@@ -0,0 +1,185 @@
+//! Handling of Ctrl-C (SIGINT) and SIGTERM.
+//!
+//! A hidden passphrase prompt disables the echo of the terminal, which the default action of
+//! the signals would leave disabled, and an interrupted atomic write would leave its temporary file
+//! behind. So by default a signal restores the terminal settings saved by [`install`], removes
+//! the registered [`TempFile`] and exits with the code of [`ErrorKind::Interrupted`] right away.
+//! The memory of the process (including the passphrase) is then released without being wiped,
+//! as that isn't possible from a signal handler. Argon2 can't be cancelled anyway.
+//!
+//! Within a [`Deferred`] region (e.g. streaming encryption or a session) a signal only sets
+//! a flag instead, which the region checks at its natural boundaries via [`check`]. The resulting
+//! error unwinds normally, wiping the secrets and removing the temporary file on its way.
+//! A second signal exits right away, in case the region is stuck.
+
+use crate::error::ErrorKind;
+
+/// Fails with [`ErrorKind::Interrupted`] if a signal was received within a [`Deferred`] region.
+pub(super) fn check() -> anyhow::Result<()> {
+    match imp::cancelled() {
+        true => Err(error()),
+        false => Ok(()),
+    }
+}
+
+pub(super) fn error() -> anyhow::Error {
+    ErrorKind::Interrupted.error("Interrupted")
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::ffi::{CString, c_char};
+    use std::mem::MaybeUninit;
+    use std::os::unix::ffi::OsStrExt as _;
+    use std::path::Path;
+    use std::ptr;
+    use std::sync::atomic::{AtomicBool, AtomicI32, AtomicPtr, AtomicUsize, Ordering};
+
+    use crate::error::ErrorKind;
+
+    /// Terminal to restore, -1 if there's none.
+    static TTY_FD: AtomicI32 = AtomicI32::new(-1);
+    static mut TERMIOS: MaybeUninit<libc::termios> = MaybeUninit::uninit();
+    /// Path of the temporary file to remove, null if there's none.
+    static TEMP_FILE: AtomicPtr<c_char> = AtomicPtr::new(ptr::null_mut());
+    /// Number of the active [`Deferred`] regions.
+    static DEFERRED: AtomicUsize = AtomicUsize::new(0);
+    static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+    /// Saves the terminal settings and installs the handler of SIGINT and SIGTERM.
+    pub(in super::super) fn install() {
+        // SAFETY: The path is a valid C string, the descriptor is only used by this module.
+        let fd = unsafe { libc::open(c"/dev/tty".as_ptr(), libc::O_RDWR | libc::O_CLOEXEC) };
+        if fd >= 0 {
+            // SAFETY: `TERMIOS` is only written here, before the handler that reads it is installed.
+            let saved = unsafe { libc::tcgetattr(fd, (&raw mut TERMIOS).cast()) == 0 };
+            if saved {
+                TTY_FD.store(fd, Ordering::SeqCst);
+            } else {
+                // SAFETY: The descriptor was opened above.
+                unsafe { libc::close(fd) };
+            }
+        }
+
+        // SAFETY: The handler only performs async-signal-safe calls.
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = on_signal as extern "C" fn(libc::c_int) as usize;
+            libc::sigemptyset(&mut action.sa_mask);
+
+            // Without `SA_RESTART`, so blocking reads return early and can check the flag.
+            libc::sigaction(libc::SIGINT, &action, ptr::null_mut());
+            libc::sigaction(libc::SIGTERM, &action, ptr::null_mut());
+        }
+    }
+
+    pub(in super::super) fn cancelled() -> bool {
+        CANCELLED.load(Ordering::SeqCst)
+    }
+
+    /// Region where a signal sets the flag checked by [`super::check`] instead of exiting.
+    pub(in super::super) struct Deferred(());
+
+    impl Deferred {
+        pub(in super::super) fn enter() -> Self {
+            DEFERRED.fetch_add(1, Ordering::SeqCst);
+            Self(())
+        }
+    }
+
+    impl Drop for Deferred {
+        fn drop(&mut self) {
+            DEFERRED.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Temporary file to remove if the process exits on a signal.
+    pub(in super::super) struct TempFile(());
+
+    impl TempFile {
+        pub(in super::super) fn register(path: &Path) -> Self {
+            if let Ok(path) = CString::new(path.as_os_str().as_bytes()) {
+                let old = TEMP_FILE.swap(path.into_raw(), Ordering::SeqCst);
+                free(old);
+            }
+            Self(())
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            free(TEMP_FILE.swap(ptr::null_mut(), Ordering::SeqCst));
+        }
+    }
+
+    fn free(path: *mut c_char) {
+        if !path.is_null() {
+            // SAFETY: Non-null pointers only come from `CString::into_raw` in `TempFile::register`.
+            drop(unsafe { CString::from_raw(path) });
+        }
+    }
+
+    extern "C" fn on_signal(_: libc::c_int) {
+        if DEFERRED.load(Ordering::SeqCst) > 0 && !CANCELLED.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let fd = TTY_FD.load(Ordering::SeqCst);
+        let temp_file = TEMP_FILE.load(Ordering::SeqCst);
+        // SAFETY: `tcsetattr`, `unlink`, `write` and `_exit` are async-signal-safe,
+        // `TERMIOS` was initialized before `TTY_FD` was set.
+        unsafe {
+            if fd >= 0 {
+                libc::tcsetattr(fd, libc::TCSANOW, (&raw const TERMIOS).cast());
+            }
+            if !temp_file.is_null() {
+                libc::unlink(temp_file);
+            }
+            libc::write(libc::STDERR_FILENO, c"\n".as_ptr().cast(), 1);
+            libc::_exit(ErrorKind::Interrupted.exit_code().into());
+        }
+    }
+}
+
+/// Signals aren't handled on other platforms, the default action applies.
+#[cfg(not(unix))]
+mod imp {
+    use std::path::Path;
+
+    pub(in super::super) fn install() {}
+
+    pub(in super::super) fn cancelled() -> bool {
+        false
+    }
+
+    pub(in super::super) struct Deferred(());
+
+    impl Deferred {
+        pub(in super::super) fn enter() -> Self {
+            Self(())
+        }
+    }
+
+    pub(in super::super) struct TempFile(());
+
+    impl TempFile {
+        pub(in super::super) fn register(_path: &Path) -> Self {
+            Self(())
+        }
+    }
+}
+
+pub(super) use imp::{Deferred, TempFile, cancelled, install};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn not_cancelled_by_default() {
+        let _deferred = Deferred::enter();
+        assert!(!cancelled());
+        assert!(check().is_ok());
+        assert_eq!(crate::error::exit_code(&error()), 130);
+    }
+}
@@ -0,0 +1,190 @@
+//! Kubernetes `Secret` manifests.
+
+use std::collections::BTreeMap;
+
+use anyhow::Context as _;
+use base64::{Engine as _, engine::general_purpose};
+use serde::Serialize;
+use zeroize::Zeroizing;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest<'a> {
+    api_version: &'static str,
+    kind: &'static str,
+    metadata: Metadata<'a>,
+    #[serde(rename = "type")]
+    type_: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    string_data: Option<BTreeMap<String, &'a str>>,
+}
+
+#[derive(Serialize)]
+struct Metadata<'a> {
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<&'a str>,
+}
+
+/// Validates a resource name (`metadata.name`), which must be a DNS subdomain (RFC 1123).
+pub(super) fn parse_name(s: &str) -> anyhow::Result<String> {
+    let valid = !s.is_empty()
+        && s.len() <= 253
+        && s.split('.').all(|label| {
+            !label.is_empty()
+                && label.starts_with(|c: char| c.is_ascii_alphanumeric())
+                && label.ends_with(|c: char| c.is_ascii_alphanumeric())
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        });
+
+    match valid {
+        true => Ok(s.to_owned()),
+        false => Err(anyhow::anyhow!(
+            "Expected a lowercase DNS subdomain name (e.g. `app-secrets`)"
+        )),
+    }
+}
+
+/// Turns the name of a secret into a valid data key, replacing the characters other than
+/// ASCII letters, digits, `-`, `_` and `.` with `_`.
+pub(super) fn key(name: &str) -> String {
+    let key: String = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect();
+
+    match key.as_str() {
+        "." | ".." => key.replace('.', "_"),
+        _ => key,
+    }
+}
+
+/// Renders a YAML document of an `Opaque` secret holding the provided `(key, value)` entries,
+/// base64 encoded in `data` or as is in `stringData`.
+pub(super) fn secret_manifest(
+    name: &str,
+    namespace: Option<&str>,
+    entries: &[(String, &str)],
+    string_data: bool,
+) -> anyhow::Result<Zeroizing<String>> {
+    let values = entries.iter().map(|(key, value)| (key.clone(), *value));
+
+    let (data, string_data) = if string_data {
+        (None, Some(values.collect()))
+    } else {
+        let data = values
+            .map(|(key, value)| (key, general_purpose::STANDARD.encode(value)))
+            .collect();
+        (Some(data), None)
+    };
+
+    let manifest = Manifest {
+        api_version: "v1",
+        kind: "Secret",
+        metadata: Metadata { name, namespace },
+        type_: "Opaque",
+        data,
+        string_data,
+    };
+
+    serde_yaml::to_string(&manifest)
+        .map(Zeroizing::new)
+        .context("serde_yaml::to_string")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Values YAML would misread if they weren't quoted or escaped.
+    const TRICKY: [&str; 9] = [
+        "yes",
+        "null",
+        "0123",
+        "1e3",
+        "- item",
+        "key: value",
+        "# comment",
+        " padded ",
+        "two\nlines\n",
+    ];
+
+    fn entries() -> Vec<(String, &'static str)> {
+        TRICKY
+            .iter()
+            .enumerate()
+            .map(|(idx, value)| (format!("key-{idx}"), *value))
+            .collect()
+    }
+
+    #[test]
+    fn data_round_trip() {
+        let manifest = secret_manifest("app-secrets", Some("prod"), &entries(), false).unwrap();
+        let doc: serde_yaml::Value = serde_yaml::from_str(&manifest).unwrap();
+
+        assert_eq!(doc["apiVersion"], "v1");
+        assert_eq!(doc["kind"], "Secret");
+        assert_eq!(doc["type"], "Opaque");
+        assert_eq!(doc["metadata"]["name"], "app-secrets");
+        assert_eq!(doc["metadata"]["namespace"], "prod");
+        assert!(doc.get("stringData").is_none());
+
+        for (key, value) in entries() {
+            let encoded = doc["data"][key.as_str()].as_str().unwrap();
+            let decoded = general_purpose::STANDARD.decode(encoded).unwrap();
+            assert_eq!(decoded, value.as_bytes(), "{key}");
+        }
+    }
+
+    #[test]
+    fn string_data_round_trip() {
+        let manifest = secret_manifest("app-secrets", None, &entries(), true).unwrap();
+        let doc: serde_yaml::Value = serde_yaml::from_str(&manifest).unwrap();
+
+        assert!(doc["metadata"].get("namespace").is_none());
+        assert!(doc.get("data").is_none());
+        for (key, value) in entries() {
+            assert_eq!(
+                doc["stringData"][key.as_str()].as_str(),
+                Some(value),
+                "{key}"
+            );
+        }
+    }
+
+    #[test]
+    fn keys() {
+        assert_eq!(key("db-pass"), "db-pass");
+        assert_eq!(key("DB_PASS.v2"), "DB_PASS.v2");
+        assert_eq!(key("github/token"), "github_token");
+        assert_eq!(key("ünïcode key"), "_n_code_key");
+        assert_eq!(key("."), "_");
+        assert_eq!(key(".."), "__");
+        assert_eq!(key("..."), "...");
+    }
+
+    #[test]
+    fn names() {
+        for valid in ["app", "app-secrets", "a.b-c.d1", &"a".repeat(253)] {
+            assert!(parse_name(valid).is_ok(), "{valid}");
+        }
+        for invalid in [
+            "",
+            "App",
+            "-app",
+            "app-",
+            "a..b",
+            "app_secrets",
+            &"a".repeat(254),
+        ] {
+            assert!(parse_name(invalid).is_err(), "{invalid}");
+        }
+    }
+}
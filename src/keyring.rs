@@ -0,0 +1,271 @@
+//! Storage of secrets in the OS keyring.
+//!
+//! The platform tools are used, the secret only ever being passed via a pipe:
+//! - `secret-tool` (libsecret, e.g. GNOME Keyring or KWallet) on Linux and other Unix systems
+//! - `security` (Keychain) on macOS
+//!
+//! Windows isn't supported yet, as it has no tool storing generic credentials without
+//! passing the secret on the command line.
+//!
+//! Entries are tagged as created by argon2derive, only those can be deleted.
+
+use std::fmt;
+use std::io::{self, Write as _};
+use std::process::{Command, Output, Stdio};
+use std::str::FromStr;
+
+use anyhow::Context as _;
+
+/// Value of the tag marking the entries created by argon2derive.
+const TAG: &str = "argon2derive";
+
+/// Keyring entry, written as `<service>/<account>`.
+#[derive(Debug, Clone)]
+pub(super) struct Entry {
+    service: String,
+    account: String,
+}
+
+impl FromStr for Entry {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let Some((service, account)) = s.split_once('/') else {
+            return Err(anyhow::anyhow!("Expected <SERVICE>/<ACCOUNT>"));
+        };
+
+        if service.is_empty() || account.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Neither the service nor the account can be empty"
+            ));
+        }
+
+        if s.contains(|c: char| c.is_control() || c == '"' || c == '\\') {
+            return Err(anyhow::anyhow!(
+                "The entry can't contain control characters, quotes or `\\`"
+            ));
+        }
+
+        Ok(Self {
+            service: service.to_owned(),
+            account: account.to_owned(),
+        })
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.service, self.account)
+    }
+}
+
+/// Whether the entry exists, regardless of who created it.
+pub(super) fn exists(entry: &Entry) -> anyhow::Result<bool> {
+    backend::exists(entry, false)
+}
+
+/// Stores the secret, replacing the existing entry if any.
+pub(super) fn store(entry: &Entry, secret: &str) -> anyhow::Result<()> {
+    backend::store(entry, secret)
+}
+
+/// Deletes the entry if it was created by argon2derive, returning whether it did exist.
+pub(super) fn delete(entry: &Entry) -> anyhow::Result<bool> {
+    if !backend::exists(entry, true)? {
+        return Ok(false);
+    }
+
+    backend::delete(entry)?;
+    Ok(true)
+}
+
+/// Runs the tool, piping `stdin` into it.
+#[cfg_attr(windows, allow(dead_code))]
+fn run(program: &str, args: &[&str], stdin: Option<&str>) -> anyhow::Result<Output> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => anyhow::anyhow!("`{program}` not found in PATH"),
+            _ => anyhow::Error::new(err).context(format!("spawn {program}")),
+        })?;
+
+    let mut pipe = child.stdin.take().expect("stdin should be piped");
+    let written = pipe.write_all(stdin.unwrap_or_default().as_bytes());
+    drop(pipe);
+
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("wait for {program}"))?;
+    // A failing tool may exit before reading its stdin, its own error is the one to report then.
+    if output.status.success() {
+        written.with_context(|| format!("write to {program}"))?;
+    }
+
+    Ok(output)
+}
+
+/// Turns an unsuccessful exit into an error, including what the tool printed to stderr.
+#[cfg_attr(windows, allow(dead_code))]
+fn check(program: &str, output: &Output) -> anyhow::Result<()> {
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(anyhow::anyhow!(
+        "`{program}` failed ({}): {}",
+        output.status,
+        stderr.trim()
+    ))
+}
+
+#[cfg(target_os = "macos")]
+mod backend {
+    use zeroize::Zeroizing;
+
+    use super::*;
+
+    const SECURITY: &str = "security";
+
+    pub(super) fn exists(entry: &Entry, ours: bool) -> anyhow::Result<bool> {
+        let mut args = vec![
+            "find-generic-password",
+            "-s",
+            &entry.service,
+            "-a",
+            &entry.account,
+        ];
+        if ours {
+            args.extend(["-D", TAG]);
+        }
+
+        // Without `-w` the password isn't printed, only the attributes.
+        Ok(run(SECURITY, &args, None)?.status.success())
+    }
+
+    pub(super) fn store(entry: &Entry, secret: &str) -> anyhow::Result<()> {
+        if secret.contains(|c: char| c.is_control() || c == '"' || c == '\\') {
+            return Err(anyhow::anyhow!(
+                "The Keychain backend doesn't support secrets containing control characters, quotes or `\\`"
+            ));
+        }
+
+        // The command is read from stdin (`-i`), so the secret doesn't appear in the process list.
+        let command = Zeroizing::new(format!(
+            "add-generic-password -U -D {TAG} -s \"{}\" -a \"{}\" -w \"{secret}\"\n",
+            entry.service, entry.account
+        ));
+        let output = run(SECURITY, &["-i"], Some(&command))?;
+        check(SECURITY, &output)
+    }
+
+    pub(super) fn delete(entry: &Entry) -> anyhow::Result<()> {
+        let args = [
+            "delete-generic-password",
+            "-D",
+            TAG,
+            "-s",
+            &entry.service,
+            "-a",
+            &entry.account,
+        ];
+        check(SECURITY, &run(SECURITY, &args, None)?)
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod backend {
+    use zeroize::Zeroizing;
+
+    use super::*;
+
+    const SECRET_TOOL: &str = "secret-tool";
+
+    fn attributes(entry: &Entry, ours: bool) -> Vec<&str> {
+        let mut attributes = vec!["service", &entry.service, "account", &entry.account];
+        if ours {
+            attributes.extend(["application", TAG]);
+        }
+        attributes
+    }
+
+    pub(super) fn exists(entry: &Entry, ours: bool) -> anyhow::Result<bool> {
+        let mut args = vec!["lookup"];
+        args.extend(attributes(entry, ours));
+
+        // `lookup` prints the secret, which is wiped right away.
+        let output = run(SECRET_TOOL, &args, None)?;
+        let _secret = Zeroizing::new(output.stdout);
+        Ok(output.status.success())
+    }
+
+    pub(super) fn store(entry: &Entry, secret: &str) -> anyhow::Result<()> {
+        let label = format!("--label={entry}");
+        let mut args = vec!["store", &label];
+        args.extend(attributes(entry, true));
+
+        let output = run(SECRET_TOOL, &args, Some(secret))?;
+        check(SECRET_TOOL, &output)
+    }
+
+    pub(super) fn delete(entry: &Entry) -> anyhow::Result<()> {
+        let mut args = vec!["clear"];
+        args.extend(attributes(entry, true));
+        check(SECRET_TOOL, &run(SECRET_TOOL, &args, None)?)
+    }
+}
+
+#[cfg(windows)]
+mod backend {
+    use super::*;
+
+    fn unsupported() -> anyhow::Error {
+        anyhow::anyhow!("Storing secrets in the Windows Credential Manager isn't supported yet")
+    }
+
+    pub(super) fn exists(_entry: &Entry, _ours: bool) -> anyhow::Result<bool> {
+        Err(unsupported())
+    }
+
+    pub(super) fn store(_entry: &Entry, _secret: &str) -> anyhow::Result<()> {
+        Err(unsupported())
+    }
+
+    pub(super) fn delete(_entry: &Entry) -> anyhow::Result<()> {
+        Err(unsupported())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries() {
+        let entry: Entry = "work/db".parse().unwrap();
+        assert_eq!(
+            (entry.service.as_str(), entry.account.as_str()),
+            ("work", "db")
+        );
+        assert_eq!(entry.to_string(), "work/db");
+
+        // The account may contain `/`.
+        let entry: Entry = "work/db/admin".parse().unwrap();
+        assert_eq!(entry.account, "db/admin");
+
+        for invalid in [
+            "work",
+            "/db",
+            "work/",
+            "wo\"rk/db",
+            "work/d\\b",
+            "work/d\nb",
+        ] {
+            assert!(invalid.parse::<Entry>().is_err(), "{invalid:?}");
+        }
+    }
+}
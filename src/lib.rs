@@ -0,0 +1,40 @@
+//! Deterministically derive secrets from a passphrase using Argon2.
+//!
+//! The secrets are bound to their names, so the same passphrase and parameters
+//! can produce any number of independent secrets.
+//!
+//! ```
+//! use argon2derive::argon2::{Algorithm, Parameters};
+//!
+//! let params = Parameters {
+//!     algorithm: Algorithm::Argon2id,
+//!     memory: 64 * 1024,
+//!     time: 3,
+//!     parallelism: 1,
+//!     salt: b"my salt".to_vec(),
+//! };
+//!
+//! let secret = argon2derive::derive(&params, b"passphrase", "email", 32)?;
+//! assert_eq!(secret.len(), 32);
+//! # anyhow::Ok(())
+//! ```
+
+pub mod age;
+pub mod argon2;
+pub mod slip10;
+
+/// Derives the secret of the provided name and length (in bytes).
+///
+/// Produces the same secrets as the `secret` command of the CLI
+/// with the default `--generation` and no passphrase normalization.
+pub fn derive(
+    params: &argon2::Parameters,
+    passphrase: &[u8],
+    name: &str,
+    output_len: u32,
+) -> anyhow::Result<Vec<u8>> {
+    let mut params = params.clone();
+    params.append_name(name, 0)?;
+
+    argon2::hash(&params, passphrase, output_len)
+}
@@ -0,0 +1,144 @@
+//! Source code literals of the secrets (Rust / C byte arrays) and `xxd`-style dumps.
+
+use std::fmt::Write as _;
+
+/// Bytes per line of the array literals.
+const ARRAY_LINE_LEN: usize = 12;
+
+/// Bytes per line of [`hexdump`], as printed by `xxd`.
+const DUMP_LINE_LEN: usize = 16;
+
+/// Validates an identifier of the array literals, which needs to be valid in both Rust and C.
+pub(super) fn parse_ident(s: &str) -> anyhow::Result<String> {
+    let valid = s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    match valid {
+        true => Ok(s.to_owned()),
+        false => Err(anyhow::anyhow!(
+            "Expected ASCII letters, digits and `_`, not starting with a digit (e.g. `API_KEY`)"
+        )),
+    }
+}
+
+/// Turns the name of a secret into an uppercase identifier (e.g. `db-pass` -> `DB_PASS`, `2fa` -> `_2FA`).
+pub(super) fn ident(name: &str) -> String {
+    let ident: String = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_uppercase(),
+            _ => '_',
+        })
+        .collect();
+
+    match ident.starts_with(|c: char| c.is_ascii_digit()) || ident.is_empty() {
+        true => format!("_{ident}"),
+        false => ident,
+    }
+}
+
+/// Renders `const <IDENT>: [u8; <LEN>] = [...];`.
+pub(super) fn rust_array(ident: &str, bytes: &[u8]) -> String {
+    let mut out = format!("const {ident}: [u8; {}] = [\n", bytes.len());
+    array_lines(&mut out, bytes);
+    out.push_str("];\n");
+    out
+}
+
+/// Renders `const uint8_t <IDENT>[<LEN>] = {...};` (requiring `<stdint.h>`).
+pub(super) fn c_array(ident: &str, bytes: &[u8]) -> String {
+    let mut out = format!("const uint8_t {ident}[{}] = {{\n", bytes.len());
+    array_lines(&mut out, bytes);
+    out.push_str("};\n");
+    out
+}
+
+/// Writes the bytes as indented lines of `0x3a, 0x91, ...`, with a trailing comma.
+fn array_lines(out: &mut String, bytes: &[u8]) {
+    for line in bytes.chunks(ARRAY_LINE_LEN) {
+        out.push_str("   ");
+        for byte in line {
+            let _ = write!(out, " 0x{byte:02x},");
+        }
+        out.push('\n');
+    }
+}
+
+/// Renders the bytes the way `xxd` does:
+/// `00000000: 3a91 0c5e ...  :..^` with the offset, 2-byte groups and the printable ASCII characters.
+pub(super) fn hexdump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+
+    for (idx, line) in bytes.chunks(DUMP_LINE_LEN).enumerate() {
+        let _ = write!(out, "{:08x}:", idx * DUMP_LINE_LEN);
+
+        for pos in 0..DUMP_LINE_LEN {
+            if pos % 2 == 0 {
+                out.push(' ');
+            }
+            match line.get(pos) {
+                Some(byte) => {
+                    let _ = write!(out, "{byte:02x}");
+                }
+                None => out.push_str("  "),
+            }
+        }
+
+        out.push_str("  ");
+        out.extend(line.iter().map(|&byte| match byte {
+            0x20..=0x7e => byte as char,
+            _ => '.',
+        }));
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idents() {
+        assert_eq!(ident("db-pass"), "DB_PASS");
+        assert_eq!(ident("api.key/v2"), "API_KEY_V2");
+        assert_eq!(ident("2fa"), "_2FA");
+        assert_eq!(ident("ключ"), "____");
+        assert_eq!(ident(""), "_");
+
+        assert_eq!(parse_ident("API_KEY").unwrap(), "API_KEY");
+        assert_eq!(parse_ident("_2fa").unwrap(), "_2fa");
+        for invalid in ["", "2FA", "API-KEY", "KEY ", "ключ"] {
+            assert!(parse_ident(invalid).is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn arrays() {
+        let bytes: Vec<u8> = (0..13).collect();
+
+        assert_eq!(
+            rust_array("KEY", &bytes),
+            "const KEY: [u8; 13] = [\n    \
+             0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b,\n    \
+             0x0c,\n\
+             ];\n"
+        );
+        assert_eq!(
+            c_array("KEY", &[0xff, 0x10]),
+            "const uint8_t KEY[2] = {\n    0xff, 0x10,\n};\n"
+        );
+    }
+
+    #[test]
+    fn hexdump_matches_xxd() {
+        // `printf 'Hello, world! \x00\x01\xffabcdefghijk' | xxd`
+        assert_eq!(
+            hexdump(b"Hello, world! \x00\x01\xffabcdefghijk"),
+            "00000000: 4865 6c6c 6f2c 2077 6f72 6c64 2120 0001  Hello, world! ..\n\
+             00000010: ff61 6263 6465 6667 6869 6a6b            .abcdefghijk\n"
+        );
+        assert_eq!(hexdump(&[]), "");
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    fmt,
+    io::{self, Read},
+    str::FromStr,
+};
+
+use anyhow::Context as _;
+use hmac::{Hmac, Mac};
+use sha2::{Sha256, Sha512};
+
+const HMAC_KEY: &str = "HMAC accepts keys of any length";
+
+#[derive(Clone, Copy, Debug)]
+pub enum Algorithm {
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+impl FromStr for Algorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s {
+            "sha256" => Self::Sha256,
+            "sha512" => Self::Sha512,
+            "blake3" => Self::Blake3,
+            other => return Err(anyhow::anyhow!("Invalid MAC algorithm: {other}")),
+        })
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Algorithm::Sha256 => f.write_str("sha256"),
+            Algorithm::Sha512 => f.write_str("sha512"),
+            Algorithm::Blake3 => f.write_str("blake3"),
+        }
+    }
+}
+
+/// Computes the MAC of the whole `input` (HMAC for SHA-2, keyed mode for BLAKE3).
+pub(super) fn compute(
+    algo: Algorithm,
+    key: &[u8; 32],
+    input: &mut impl Read,
+) -> anyhow::Result<Vec<u8>> {
+    Ok(match algo {
+        Algorithm::Sha256 => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(key).expect(HMAC_KEY);
+            read_chunks(input, |chunk| mac.update(chunk))?;
+            mac.finalize().into_bytes().to_vec()
+        }
+        Algorithm::Sha512 => {
+            let mut mac = Hmac::<Sha512>::new_from_slice(key).expect(HMAC_KEY);
+            read_chunks(input, |chunk| mac.update(chunk))?;
+            mac.finalize().into_bytes().to_vec()
+        }
+        Algorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new_keyed(key);
+            read_chunks(input, |chunk| {
+                hasher.update(chunk);
+            })?;
+            hasher.finalize().as_bytes().to_vec()
+        }
+    })
+}
+
+/// Parses a `<hex MAC>  <filename>` line.
+pub(super) fn parse_line(line: &str) -> anyhow::Result<(Vec<u8>, &str)> {
+    let (mac, filename) = line
+        .split_once("  ")
+        .context("expected `<MAC>  <filename>`")?;
+
+    if filename.is_empty() {
+        return Err(anyhow::anyhow!("missing filename"));
+    }
+
+    let mac = hex::decode(mac).context("invalid MAC")?;
+    Ok((mac, filename))
+}
+
+fn read_chunks(input: &mut impl Read, mut f: impl FnMut(&[u8])) -> io::Result<()> {
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        match input.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => f(&buf[..n]),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mac(algo: Algorithm, key: &[u8; 32], input: &[u8]) -> String {
+        hex::encode(compute(algo, key, &mut &*input).unwrap())
+    }
+
+    #[test]
+    fn hmac_vectors() {
+        // Computed by Python's `hmac` module.
+        let key: [u8; 32] = std::array::from_fn(|idx| idx as u8);
+        assert_eq!(
+            mac(Algorithm::Sha256, &key, b"hello world"),
+            "411b9a51e8565e1fc79643b2a6c4672f4a3c3e573c33d0995a08748cb6128e8e"
+        );
+        assert_eq!(
+            mac(Algorithm::Sha512, &key, b"hello world"),
+            "092397a41ed0912042c6db22500a2317b418844c41186472d4b00d3e5dabdbed\
+             ca7592694d47af298e7162ebbaf87e5dd6da221b46bb059b7b2432fa74717ced"
+        );
+    }
+
+    #[test]
+    fn blake3_vector() {
+        // `keyed_hash` of the empty input in the official BLAKE3 test vectors.
+        assert_eq!(
+            mac(Algorithm::Blake3, b"whats the Elvish word for friend", b""),
+            "92b2b75604ed3c761f9d6f62392c8a9227ad0ea3f09573e783f1498a4ed60d26"
+        );
+    }
+
+    #[test]
+    fn spans_chunks() {
+        let input = vec![1; 200 * 1024];
+        let key = [7; 32];
+        let expected = Hmac::<Sha256>::new_from_slice(&key)
+            .unwrap()
+            .chain_update(&input)
+            .finalize()
+            .into_bytes();
+        assert_eq!(mac(Algorithm::Sha256, &key, &input), hex::encode(expected));
+    }
+
+    #[test]
+    fn parses_lines() {
+        let (mac, filename) = parse_line("00ff  my file.txt").unwrap();
+        assert_eq!(mac, [0x00, 0xff]);
+        assert_eq!(filename, "my file.txt");
+
+        assert_eq!(parse_line("00ff  -").unwrap().1, "-");
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        for line in [
+            "00ff file.txt",
+            "00ff  ",
+            "xyz  file.txt",
+            "0ff  file.txt",
+            "",
+        ] {
+            assert!(parse_line(line).is_err(), "{line:?}");
+        }
+    }
+
+    #[test]
+    fn parses_algorithms() {
+        for algo in ["sha256", "sha512", "blake3"] {
+            assert_eq!(algo.parse::<Algorithm>().unwrap().to_string(), algo);
+        }
+        assert!("md5".parse::<Algorithm>().is_err());
+    }
+}
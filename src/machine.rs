@@ -0,0 +1,112 @@
+//! Binding of the salt to the machine the tool is run on.
+
+use std::env::{self, VarError};
+
+use anyhow::Context as _;
+
+/// Overrides the machine id, e.g. to reproduce machine-bound secrets elsewhere.
+pub(super) const ID_ENV: &str = "ARGON2DERIVE_MACHINE_ID";
+
+/// BLAKE3 key derivation context, the raw machine id never makes it into the salt.
+const CONTEXT: &str = "argon2derive 2026-10-16 machine-id salt";
+
+/// Extends the salt with a hash of the machine id.
+///
+/// The result is `salt length (u32 BE) || salt || BLAKE3(machine id)`,
+/// so the boundary between the salt and the hash is unambiguous.
+pub(super) fn bind_salt(salt: &[u8]) -> anyhow::Result<Vec<u8>> {
+    Ok(bind(salt, &id()?))
+}
+
+fn bind(salt: &[u8], id: &str) -> Vec<u8> {
+    let hash = blake3::derive_key(CONTEXT, id.as_bytes());
+
+    let mut bound = Vec::with_capacity(4 + salt.len() + hash.len());
+    bound.extend_from_slice(&(salt.len() as u32).to_be_bytes());
+    bound.extend_from_slice(salt);
+    bound.extend_from_slice(&hash);
+    bound
+}
+
+fn id() -> anyhow::Result<String> {
+    let id = match env::var(ID_ENV) {
+        Ok(id) => id,
+        Err(VarError::NotPresent) => platform_id()?,
+        Err(err) => return Err(err).context(ID_ENV),
+    };
+
+    let id = id.trim();
+    if id.is_empty() {
+        return Err(anyhow::anyhow!("Machine id is empty"));
+    }
+
+    Ok(id.to_owned())
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn platform_id() -> anyhow::Result<String> {
+    const PATHS: [&str; 2] = ["/etc/machine-id", "/var/lib/dbus/machine-id"];
+
+    PATHS
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .with_context(|| format!("Failed to read the machine id from {PATHS:?}, set ${ID_ENV}"))
+}
+
+#[cfg(target_os = "macos")]
+fn platform_id() -> anyhow::Result<String> {
+    // "IOPlatformUUID" = "XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX"
+    command_output("ioreg", &["-rd1", "-c", "IOPlatformExpertDevice"])?
+        .lines()
+        .find(|line| line.contains("\"IOPlatformUUID\""))
+        .and_then(|line| line.split('"').nth(3))
+        .map(str::to_owned)
+        .context("IOPlatformUUID not found in the `ioreg` output")
+}
+
+#[cfg(windows)]
+fn platform_id() -> anyhow::Result<String> {
+    const KEY: &str = r"HKLM\SOFTWARE\Microsoft\Cryptography";
+
+    // MachineGuid    REG_SZ    xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx
+    command_output("reg", &["query", KEY, "/v", "MachineGuid"])?
+        .lines()
+        .find(|line| line.contains("MachineGuid"))
+        .and_then(|line| line.split_whitespace().last())
+        .map(str::to_owned)
+        .context("MachineGuid not found in the `reg query` output")
+}
+
+#[cfg(any(target_os = "macos", windows))]
+fn command_output(program: &str, args: &[&str]) -> anyhow::Result<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("run `{program}`"))?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("`{program}` failed ({})", output.status));
+    }
+
+    String::from_utf8(output.stdout).with_context(|| format!("`{program}` output"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bound_salt_layout() {
+        let bound = bind(b"salt", "0123456789abcdef0123456789abcdef");
+        assert_eq!(bound.len(), 4 + 4 + 32);
+        assert_eq!(bound[..8], *b"\0\0\0\x04salt");
+        // Changing the context would change every machine-bound secret.
+        assert_eq!(
+            hex::encode(&bound[8..]),
+            "d87c35faa59cfc0ad9ec94eb62d88df69067732f3bb7377e2d33faf68f587763"
+        );
+
+        assert_eq!(bind(b"", "id")[..4], [0; 4]);
+        assert_ne!(bind(b"salt", "id"), bind(b"salt", "other id"));
+    }
+}
@@ -1,24 +1,77 @@
-use std::io::{self, IsTerminal, Write};
-use std::path::PathBuf;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead as _, BufReader, BufWriter, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::Context;
+use argon2derive::{age, argon2, slip10};
 use base64::{Engine as _, engine::general_purpose};
-use clap::{Args, Parser, Subcommand};
+use clap::{ArgGroup, Args, CommandFactory as _, FromArgMatches as _, Parser, Subcommand};
+use error::{ErrorKind, PlanPrinted, ResultExt as _};
 use rpassword::read_password;
+use subtle::ConstantTimeEq as _;
+use unicode_normalization::UnicodeNormalization as _;
+use zeroize::Zeroizing;
 
-mod age;
-mod argon2;
+mod age_encrypt;
+mod age_plugin;
+mod audit;
+mod checksum;
 mod config;
+mod dotenv;
+mod encryption;
+mod error;
+mod git_crypt;
+mod htpasswd;
+mod interrupt;
+mod k8s;
+mod keyring;
+mod literal;
+mod mac;
+mod machine;
+mod memory;
+mod otp;
+mod output;
+mod pass;
+mod password;
+mod phc;
+mod rsa;
+mod session;
+mod shamir;
+mod systemd;
+mod table;
+mod term;
+mod timing;
+mod tls;
+#[cfg(feature = "tui")]
+mod tune;
+mod words;
 
 const APP_NAME: &str = "argon2derive";
 
-const MISSING_REQUIRED_PARAMETERS: &str = "--memory, --time and --parallelism must be specified";
+/// Name of the binary age looks for to handle the `AGE-PLUGIN-ARGON2DERIVE-1...` identities.
+const AGE_PLUGIN_BINARY: &str = "age-plugin-argon2derive";
+
+/// Memory (in MiB) per iteration below which the parameters are likely mistaken, see `--no-sanity`.
+///
+/// RFC 9106 recommends either 2 GiB with 1 iteration, or 64 MiB with 3 iterations.
+const MIN_MIB_PER_ITERATION: u32 = 8;
+
+/// Threads per CPU above which the parallelism is likely mistaken, see `--no-sanity`.
+const MAX_THREADS_PER_CPU: u32 = 4;
+
+/// Appended to the salt of the passphrase fingerprint, see [`passphrase_fingerprint`].
+const PASSPHRASE_FINGERPRINT_CONTEXT: &[u8] = b"\0argon2derive passphrase fingerprint v1";
 
 /// Determenistically derive secrets from a passphrase using Argon2
 ///
 /// You can pipe your passphrase into stdin or you will be asked to type it.
 #[derive(Debug, Parser)]
-#[clap(name = APP_NAME, version = "0.1.0")]
+#[clap(name = APP_NAME, version = "0.1.0", after_long_help = error::EXIT_CODES_HELP)]
 struct Cli {
     /// Argon2 algorithm to use
     ///
@@ -30,14 +83,10 @@ struct Cli {
     /// `argon2d`:
     /// State of the art in the realm of GPU/ASIC resistance. It is, however, vulnarable to side-chain attacks.
     /// Use it only if you know exactly what you are doing, and if you will only be using this tool on trusted machines.
-    #[arg(
-        global = true,
-        long,
-        short,
-        default_value = "argon2id",
-        verbatim_doc_comment
-    )]
-    algorithm: argon2::Algorithm,
+    ///
+    /// Defaults to the one in the config file, `argon2id` if there's none.
+    #[arg(global = true, long, short, verbatim_doc_comment)]
+    algorithm: Option<argon2::Algorithm>,
 
     /// Argon2 memory cost (in GiB)
     ///
@@ -45,7 +94,7 @@ struct Cli {
     ///
     /// Set this value to the largest amount of memory your system can afford to allocate.
     /// If you need to use this tool on different systems tune the memory cost to accomodate your lowest specced machine.
-    #[arg(global = true, long, short, verbatim_doc_comment)]
+    #[arg(global = true, long, short, value_parser = clap::value_parser!(u32).range(..4096), verbatim_doc_comment)]
     memory: Option<u32>,
 
     /// Argon2 time cost
@@ -58,9 +107,27 @@ struct Cli {
     #[arg(global = true, long, short, verbatim_doc_comment)]
     time: Option<u32>,
 
+    /// Pick the time cost fitting this derivation time (in milliseconds) on this machine, instead of `--time`
+    ///
+    /// A single iteration is measured using the other costs, and the time cost is scaled to fit the budget.
+    /// The picked value depends on the speed of this machine, so the secrets CAN'T be reproduced elsewhere
+    /// (or after an upgrade) unless you pin the printed time cost via `--time` or `configure`.
+    /// Refused if the config stores a time cost, unless `--override-time` is provided.
+    #[arg(global = true, long, value_name = "MS", conflicts_with = "time", value_parser = clap::value_parser!(u64).range(1..), verbatim_doc_comment)]
+    time_budget: Option<u64>,
+
+    /// Let `--time-budget` replace the time cost stored in the config
+    #[arg(global = true, long, requires = "time_budget")]
+    override_time: bool,
+
+    /// Don't check the passphrase against the fingerprint bound to the config (see `configure --bind-passphrase`)
+    #[arg(global = true, long)]
+    ignore_fingerprint: bool,
+
     /// Argon2 parallelism
     ///
-    /// Number of system threads to use for the derivation.
+    /// Number of lanes the memory is split into, computed by as many system threads.
+    /// The derived secrets depend on it, so it needs to stay the same across machines.
     ///
     /// Set this value to the number of (logical) cores of your CPU.
     /// If you need to use this tool on different systems tune the parallelism in respect of your most frequently used machine.
@@ -76,12 +143,80 @@ struct Cli {
     #[arg(global = true, long, short, verbatim_doc_comment)]
     salt: Option<String>,
 
+    /// Additional source of the Argon2 salt
+    ///
+    /// `machine-id`:
+    /// Binds the salt to this machine by mixing in a hash of its identifier
+    /// (`/etc/machine-id` on Linux, `IOPlatformUUID` on macOS, `MachineGuid` on Windows),
+    /// combined with `--salt` if provided.
+    /// The secrets derived this way CAN'T be derived on any other machine, nor after reinstalling the OS.
+    /// The identifier can be overridden via the `ARGON2DERIVE_MACHINE_ID` env var.
+    ///
+    /// Stored in the config by `configure`, `none` disables the config value.
+    #[arg(global = true, long, value_parser = ["none", "machine-id"], verbatim_doc_comment)]
+    salt_source: Option<String>,
+
+    /// Derive the secrets using the salt of this namespace instead of the main one
+    ///
+    /// Namespaces (e.g. `work`, `personal`) are defined in the config, each with its own salt:
+    ///   [salts.work]
+    ///   salt = "..."
+    /// Secrets of the same name differ between namespaces, while the config and the passphrase are shared.
+    #[arg(global = true, long, conflicts_with = "salt", verbatim_doc_comment)]
+    namespace: Option<String>,
+
+    /// Fail instead of warning if the salt is empty
+    ///
+    /// Stored in the config by `configure` (as `require_salt = true`), so it can be enforced by a shared config.
+    #[arg(global = true, long, verbatim_doc_comment)]
+    require_salt: bool,
+
+    /// Additional Argon2 pass to chain after the one using the parameters above (repeatable)
+    ///
+    /// Format: `<ALGORITHM>:<MEMORY>:<TIME>:<PARALLELISM>`, with the memory in GiB, e.g. `--cascade argon2d:1:10:4`.
+    /// Each pass hashes the 64-byte output of the previous one using a fixed salt,
+    /// so an attacker needs to be efficient at all of the chosen memory / time trade-offs.
+    /// The name of the secret is only mixed into the salt of the first pass.
+    ///
+    /// Stored in the config (`[[passes]]`) by `configure`, replaces the passes of the config.
+    #[arg(global = true, long, value_parser = parse_pass, verbatim_doc_comment)]
+    cascade: Vec<argon2::Parameters>,
+
+    /// Don't ask for a confirmation if the parameters differ from the saved config
+    ///
+    /// Configs generated by `configure` store a fingerprint of their parameters.
+    /// If the parameters in effect (e.g. overridden via the CLI or edited by hand) don't match it,
+    /// a confirmation is asked for, or the command fails if there's no terminal to ask it on.
+    #[arg(global = true, long, verbatim_doc_comment)]
+    allow_param_change: bool,
+
     /// Path to the configuration file containing Argon2 parameters
     ///
-    /// If not provided, the OS-specific config directories will be searched.
-    #[arg(global = true, long, short, verbatim_doc_comment)]
+    /// If not provided, the following locations are searched, the first existing one is used:
+    ///   - `./argon2derive.toml`
+    ///   - `config.toml` in the OS-specific config directory (respects `$XDG_CONFIG_HOME` on Linux)
+    ///
+    /// `.json`, `.yaml` and `.yml` files are searched for as well (in this order, after `.toml`),
+    /// the format of the config always being detected by the extension.
+    ///
+    /// `configure` writes to the OS-specific config directory unless `./argon2derive.toml` exists.
+    #[arg(
+        global = true,
+        long,
+        short,
+        env = "ARGON2DERIVE_CONFIG",
+        verbatim_doc_comment
+    )]
     config: Option<PathBuf>,
 
+    /// Path to a JSON (`.json`) or YAML (`.yaml`, `.yml`) file to read the Argon2 parameters from
+    ///
+    /// Takes the place of the config file when reading, e.g. to share the parameters with other tooling.
+    /// The fields are the same as in the config file, e.g. `{"algorithm": "argon2id", "memory": 4194304, ...}`
+    /// (the memory being in KiB). Subcommands writing the config still write TOML to `--config`.
+    #[arg(global = true, long, verbatim_doc_comment)]
+    parameters_from: Option<PathBuf>,
+
     /// Makes passphrase to be displayed while typing
     ///
     /// By default the passphrase input is being masked, this flag reverses that behaviour.
@@ -89,6 +224,296 @@ struct Cli {
     #[arg(global = true, long, verbatim_doc_comment)]
     expose_passphrase: bool,
 
+    /// Whitespace trimming of the passphrase read from stdin
+    ///
+    /// Applies when the passphrase is piped in or typed with `--expose-passphrase`:
+    ///   none     Use the line as is, including the trailing newline (`\n` or `\r\n`)
+    ///   newline  Strip exactly one trailing `\n` or `\r\n`
+    ///   all      Strip all leading and trailing whitespace
+    ///
+    /// Note that `echo pass |` produces a trailing newline while `printf pass |` doesn't,
+    /// so with `none` these derive different secrets.
+    /// `none` is the default to keep the secrets derived by the previous versions of this tool intact.
+    #[arg(global = true, long, value_parser = ["none", "newline", "all"], default_value = "none", verbatim_doc_comment)]
+    passphrase_trim: String,
+
+    /// Prompt for the passphrase on the terminal even if stdin is not a terminal
+    ///
+    /// Enabled automatically for the commands reading their data from stdin (e.g. `encrypt` without an input file),
+    /// unless `--stdin-passphrase` is specified.
+    #[arg(global = true, long, visible_alias = "prompt", verbatim_doc_comment)]
+    passphrase_tty: bool,
+
+    /// Read the passphrase from stdin even if stdin is a terminal
+    ///
+    /// Disables the terminal detection, the passphrase is read as a line without being prompted for.
+    /// For the commands reading their data from stdin, the first line is the passphrase and the rest is the data.
+    #[arg(
+        global = true,
+        long,
+        conflicts_with = "passphrase_tty",
+        verbatim_doc_comment
+    )]
+    stdin_passphrase: bool,
+
+    /// Read the whole stdin (until EOF) as the passphrase, e.g. for passphrases spanning multiple lines
+    ///
+    /// Implies `--stdin-passphrase`, `--passphrase-trim` applies to the end of the input.
+    /// Not supported by the commands reading their data from stdin.
+    #[arg(
+        global = true,
+        long,
+        conflicts_with_all = ["passphrase_tty", "passphrase_fd"],
+        verbatim_doc_comment
+    )]
+    passphrase_stdin_all: bool,
+
+    /// Read the passphrase from an inherited file descriptor (Unix only)
+    ///
+    /// The first line is read, so the passphrase never appears in the process arguments or on disk,
+    /// e.g. `argon2derive --passphrase-fd 3 secret email 3< <(pass show master)`.
+    /// `--passphrase-trim` applies the same way as for stdin.
+    #[arg(global = true, long, conflicts_with_all = ["passphrase_tty", "stdin_passphrase"], verbatim_doc_comment)]
+    passphrase_fd: Option<i32>,
+
+    /// Read the passphrase as raw bytes from a file, e.g. a key exported by a hardware token
+    ///
+    /// The bytes are used as is: no UTF-8 decoding, trimming, `--ascii-only` or `--normalize`.
+    #[arg(
+        global = true,
+        long,
+        value_name = "PATH",
+        group = "raw_passphrase",
+        conflicts_with_all = ["passphrase_tty", "stdin_passphrase", "passphrase_stdin_all", "passphrase_fd"],
+        verbatim_doc_comment
+    )]
+    passphrase_raw_file: Option<PathBuf>,
+
+    /// Read the whole stdin (until EOF) as a raw bytes passphrase, see `--passphrase-raw-file`
+    ///
+    /// Not supported by the commands reading their data from stdin.
+    #[arg(
+        global = true,
+        long,
+        group = "raw_passphrase",
+        conflicts_with_all = ["passphrase_tty", "stdin_passphrase", "passphrase_stdin_all", "passphrase_fd"],
+        verbatim_doc_comment
+    )]
+    passphrase_raw_stdin: bool,
+
+    /// Write the output to an inherited file descriptor instead of stdout (Unix only)
+    ///
+    /// Applies to the derived secrets and the encrypted / decrypted data, stdout then carries nothing.
+    /// Mutually exclusive with the `--output` of `encrypt` and `decrypt`.
+    #[arg(global = true, long, verbatim_doc_comment)]
+    output_fd: Option<i32>,
+
+    /// Descriptor of `--output-fd`, taken on the first write and kept open for the following ones.
+    #[arg(skip)]
+    output_file: OnceLock<File>,
+
+    /// Add the SHA-256 of every file written by the command to this manifest
+    ///
+    /// The lines (`<SHA-256>  <filename>`, as printed by `sha256sum`) replace the existing lines of the same files
+    /// and are sorted by filename, the manifest being written atomically. Only hashes of the files are stored.
+    /// Check the files later via `argon2derive verify-files --checksum-file <PATH>` or `sha256sum -c <PATH>`.
+    #[arg(global = true, long, value_name = "PATH", verbatim_doc_comment)]
+    checksum_file: Option<PathBuf>,
+
+    /// Append a line per derivation to this log, e.g. to spot misuse on shared machines
+    ///
+    /// Each line is a JSON object with the time, subcommand, secret name, length, KDF and exit status,
+    /// never the passphrase, the salt or the derived value. The lines are appended when the command exits,
+    /// under an advisory lock, to a file only accessible by its owner. View them via `argon2derive audit show`.
+    /// Stored in the config (`audit_log`) by `configure`, which applies it to every derivation using the config.
+    #[arg(global = true, long, value_name = "PATH", verbatim_doc_comment)]
+    audit_log: Option<PathBuf>,
+
+    /// Reject passphrases containing non-ASCII characters
+    ///
+    /// The same Unicode text may be encoded differently depending on the OS, keyboard layout or input method,
+    /// which makes the derived secrets non-reproducible on another machine.
+    /// Checked before `--normalize` is applied.
+    #[arg(
+        global = true,
+        long,
+        conflicts_with_all = ["passphrase_raw_file", "passphrase_raw_stdin"],
+        verbatim_doc_comment
+    )]
+    ascii_only: bool,
+
+    /// Unicode normalization of the passphrase
+    ///
+    /// Makes non-ASCII passphrases derive the same secrets regardless of how they were typed
+    /// (e.g. `é` as a single code point or as `e` followed by a combining accent).
+    /// ASCII passphrases are never affected.
+    ///
+    /// Defaults to `nfc` for the configs created by this version of the tool (`passphrase_version = 2`),
+    /// and to `none` for older configs (even once reconfigured) and the parameters provided via the CLI,
+    /// so existing secrets don't change.
+    #[arg(
+        global = true,
+        long,
+        value_parser = ["none", "nfc", "nfkc"],
+        conflicts_with_all = ["passphrase_raw_file", "passphrase_raw_stdin"],
+        verbatim_doc_comment
+    )]
+    normalize: Option<String>,
+
+    /// Don't normalize the passphrase, same as `--normalize none`
+    ///
+    /// Reproduces the secrets derived from non-normalized passphrases using a `passphrase_version = 2` config.
+    #[arg(
+        global = true,
+        long,
+        conflicts_with = "normalize",
+        verbatim_doc_comment
+    )]
+    no_normalize: bool,
+
+    /// Generation of the derived secret
+    ///
+    /// Increment it to rotate a compromised secret without changing its name, salt or passphrase.
+    /// Generation 0 produces the same secrets as the versions of this tool without this option.
+    #[arg(global = true, long, default_value_t = 0, verbatim_doc_comment)]
+    generation: u32,
+
+    /// Append the SHA-256 of the name to the salt instead of the name itself
+    ///
+    /// The salt then has the same length whatever the name is, so names of any length or content
+    /// (e.g. containing newlines) behave the same. Produces different secrets than the raw names,
+    /// so it has to be provided every time the secrets are derived.
+    #[arg(global = true, long, verbatim_doc_comment)]
+    hash_name: bool,
+
+    /// Expand the outputs longer than 64 KiB with BLAKE3 from a 64-byte Argon2 output
+    ///
+    /// Argon2 is slow to produce long outputs and keeps them in memory along with the memory cost.
+    /// Produces different secrets than plain Argon2 above 64 KiB (the shorter ones are unchanged),
+    /// so it has to be provided every time such secrets are derived.
+    #[arg(global = true, long, verbatim_doc_comment)]
+    expand_output: bool,
+
+    /// Treat `/` in the names as separators of hierarchy levels (e.g. `work/aws/prod`)
+    ///
+    /// Every level is appended to the salt length-prefixed, so `work/aws/prod` can't collide with
+    /// a flat name, nor with another split into levels (use `\/` for a `/` within a level and `\\` for `\`).
+    /// Produces different secrets than the flat names, so it has to be provided every time the secrets are derived.
+    #[arg(
+        global = true,
+        long,
+        conflicts_with = "hash_name",
+        verbatim_doc_comment
+    )]
+    hierarchical_names: bool,
+
+    /// Bind the derivation to its cost parameters by appending them to the salt
+    ///
+    /// The canonical costs (e.g. `argon2id$v=19$m=4194304,t=3,p=4`) of every pass, including `--cascade`,
+    /// are appended to the salt along with a versioned prefix. An accidental change of the parameters
+    /// (or a downgrade) then produces an obviously different secret by design, not just by chance.
+    /// Produces different secrets than the unbound parameters, so it has to be provided every time the secrets are derived.
+    #[arg(global = true, long, verbatim_doc_comment)]
+    bind_params: bool,
+
+    /// Derive every secret twice and fail if the outputs differ
+    ///
+    /// Guards against secrets corrupted by memory errors (e.g. a flipped bit on a machine without ECC RAM),
+    /// which couldn't be derived ever again. The derivations run one after another, so the peak memory usage
+    /// stays the same while the derivation takes twice as long.
+    /// Also a self-test of the Argon2 backend, whose multi-threaded derivation must be deterministic.
+    #[arg(
+        global = true,
+        long,
+        visible_alias = "check-determinism",
+        verbatim_doc_comment
+    )]
+    paranoid: bool,
+
+    /// Derive even if the memory cost exceeds the available memory of the system
+    ///
+    /// By default such derivations fail before the passphrase is prompted for,
+    /// instead of running out of memory (or swapping) in the middle of the derivation.
+    #[arg(global = true, long, verbatim_doc_comment)]
+    force_memory: bool,
+
+    /// Print the effective derivation parameters and exit
+    ///
+    /// Neither the passphrase gets prompted, nor the derivation gets performed.
+    #[arg(global = true, long, verbatim_doc_comment)]
+    dry_run: bool,
+
+    /// Print the resolved derivation plan as a JSON object and exit, along with the origin of every parameter
+    ///
+    /// Like `--dry-run`, but machine-readable: the costs, salt lengths, name, output length and encoding,
+    /// each parameter being attributed to where it came from (`cli`, `env`, `config`, `time-budget`,
+    /// `namespace` or `default`), e.g. to find out why a secret differs from the expected one.
+    /// Neither the passphrase gets prompted, nor the derivation gets performed.
+    #[arg(global = true, long, conflicts_with = "dry_run", verbatim_doc_comment)]
+    describe: bool,
+
+    /// Whether `--config` was taken from the `ARGON2DERIVE_CONFIG` env var, for `--describe`.
+    #[arg(skip)]
+    config_from_env: bool,
+
+    /// Format of the error output
+    ///
+    /// `json` prints a single JSON object (`{"error": ..., "kind": ..., "code": ..., "hint": ...}`) to stderr,
+    /// where `error` is the message, `kind` names its class (e.g. `config`, `unclassified`) and `code` matches the exit code.
+    #[arg(global = true, long, value_parser = ["human", "json"], default_value = "human", verbatim_doc_comment)]
+    error_format: String,
+
+    /// Shorthand for `--error-format json`
+    #[arg(global = true, long, conflicts_with = "error_format")]
+    json_errors: bool,
+
+    /// Don't print warnings
+    ///
+    /// Suppresses warnings about potentially weak parameters (empty salt, low memory cost, etc).
+    #[arg(global = true, long, verbatim_doc_comment)]
+    no_warn: bool,
+
+    /// Don't check the parameters for likely mistakes
+    ///
+    /// By default a warning is printed if the memory cost is unusually low relative to the time cost
+    /// (e.g. swapped `--memory` and `--time` values), or if the parallelism greatly exceeds the number of CPUs.
+    /// The checks are advisory and never affect the derived secrets.
+    #[arg(global = true, long, verbatim_doc_comment)]
+    no_sanity: bool,
+
+    /// Don't print informational messages
+    ///
+    /// Suppresses everything printed to stderr except for prompts, warnings and errors,
+    /// e.g. the used config, the derivation progress and timing.
+    /// Warnings are controlled by `--no-warn`.
+    #[arg(global = true, long, short, verbatim_doc_comment)]
+    quiet: bool,
+
+    /// Don't style the output
+    ///
+    /// Styles are only applied to stderr when it's a terminal. Setting the `NO_COLOR` env var has the same effect.
+    #[arg(global = true, long, verbatim_doc_comment)]
+    no_color: bool,
+
+    /// Terminate the printed secret with a newline
+    ///
+    /// By default single-value secrets (e.g. `secret`) are only terminated with a newline
+    /// if stdout is a terminal, so `$(argon2derive ...)` command substitutions and pipes
+    /// receive the bare secret. Line-based outputs (e.g. `age` identities, PEM keys, shares)
+    /// always end with a newline, unless `--no-newline` is set.
+    /// Doesn't affect secrets written to files (e.g. via `--dotenv`).
+    #[arg(
+        global = true,
+        long,
+        overrides_with = "no_newline",
+        verbatim_doc_comment
+    )]
+    newline: bool,
+
+    /// Never terminate the printed secret with a newline
+    #[arg(global = true, long, overrides_with = "newline", verbatim_doc_comment)]
+    no_newline: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -99,181 +524,4186 @@ enum Commands {
     Configure(ConfigureArgs),
 
     /// Derive a raw secret
-    Secret(SecretArgs),
+    Secret(Box<SecretArgs>),
 
     /// Derive an age keypair
     Age(AgeArgs),
-}
 
-#[derive(Debug, Args)]
-struct ConfigureArgs {
-    /// Whether to overwrite an existing config file
-    #[arg(long, short)]
-    overwrite: bool,
-}
+    /// age plugin decrypting the files encrypted to the derived age recipients
+    ///
+    /// Invoked by age as `age-plugin-argon2derive` (a symlink to this binary, on the `PATH`), for the identities
+    /// generated by `plugin --keygen <NAME>`, e.g. `age -d -i identity.txt file.age`.
+    /// The identities encode the name and the Argon2 parameters, the passphrase being asked for by age.
+    #[command(verbatim_doc_comment)]
+    Plugin(PluginArgs),
 
-#[derive(Debug, Args)]
-struct SecretArgs {
-    /// Name of the secret
+    /// Derive an RSA keypair, for legacy consumers only
     ///
-    /// Appended to Argon2 salt in order to derive the secret.
-    name: String,
+    /// Prints the private key to stdout and the OpenSSH public key line to stderr.
+    /// The primes are searched for deterministically, which takes a while, especially for 4096 bits.
+    /// Prefer `age` keys wherever possible.
+    #[command(verbatim_doc_comment)]
+    Rsa(RsaArgs),
 
-    /// Length in bytes
-    #[arg(short, long, default_value_t = 32)]
-    length: u32,
+    /// Derive a self-signed TLS certificate (Ed25519)
+    ///
+    /// The certificate is byte-identical across runs, so it can be pinned and re-derived at any time.
+    /// Prints the certificate followed by the private key, unless `--output` is provided.
+    #[command(verbatim_doc_comment)]
+    TlsCert(TlsCertArgs),
 
-    /// Encoding format
-    #[arg(short, long, value_parser = ["hex", "base64"], default_value = "hex")]
-    encoding: String,
-}
+    /// Derive a git-crypt key file
+    ///
+    /// Unlock a repository using `git-crypt unlock <file>`, or initialize one with the derived key
+    /// by copying the file to `.git/git-crypt/keys/<KEY_NAME or default>`.
+    #[command(verbatim_doc_comment)]
+    GitCrypt(GitCryptArgs),
 
-#[derive(Debug, Args)]
-struct AgeArgs {
-    /// Name of the keypair
+    /// Print the current TOTP code (RFC 6238) of a derived secret
     ///
-    /// Appended to Argon2 salt in order to derive the keypair.
-    name: String,
-}
+    /// Computes the codes an authenticator app enrolled with the secret would show,
+    /// the secret being `secret <NAME> --length 20` (32 for `--algo sha256`, 64 for `--algo sha512`).
+    #[command(verbatim_doc_comment)]
+    Totp(TotpArgs),
 
-impl Cli {
-    fn derive_secret(&self, name: &str, output_len: u32) -> anyhow::Result<Vec<u8>> {
-        let mut params = match argon2::Parameters::from_cli(self)? {
-            Some(params) => params,
-            None => self
-                .read_config()?
-                .context("missing config file")?
-                .try_into()?,
-        };
+    /// Print an HOTP code (RFC 4226) of a derived secret
+    Hotp(HotpArgs),
 
-        if params.salt.is_empty() {
-            eprintln!("\nWARNING: Your salt is empty!");
-        }
+    /// Verify a passphrase against an Argon2 PHC string
+    ///
+    /// Exits with 0 if the passphrase matches, 7 otherwise.
+    Check(CheckArgs),
 
-        params.salt.extend_from_slice(name.as_bytes());
-        if params.salt.len() < argon2::MIN_SALT_LEN {
-            return Err(anyhow::anyhow!(
-                "Final argon2 salt (`--salt` + `--name`) is too short, should be >= {} bytes",
-                argon2::MIN_SALT_LEN
-            ));
-        }
+    /// Encrypt a file using a derived key
+    ///
+    /// Uses XChaCha20-Poly1305 in the STREAM construction, so files of any size can be processed.
+    /// The name of the secret is stored (unencrypted) in the header of the encrypted file.
+    Encrypt(EncryptArgs),
 
-        let mut passphrase = String::new();
+    /// Decrypt a file previously encrypted by the `encrypt` command
+    Decrypt(DecryptArgs),
 
-        let stdin = io::stdin();
-        if stdin.is_terminal() {
-            eprint!("\nEnter passphrase: ");
-            io::stderr().flush()?;
+    /// Compute MACs of files using a derived key
+    ///
+    /// Prints `<MAC>  <filename>` lines, similar to `sha256sum`.
+    Hmac(HmacArgs),
 
-            if self.expose_passphrase {
-                stdin.read_line(&mut passphrase)?;
-            } else {
-                passphrase = read_password()?;
-            }
-        } else {
-            stdin.read_line(&mut passphrase)?;
-        }
+    /// Split a derived secret into Shamir shares
+    ///
+    /// Any `--threshold` of the produced shares are enough to reconstruct the secret
+    /// (using the `combine` command), while fewer reveal nothing about it.
+    /// Besides its share of the secret, a share only carries a random identifier of the split.
+    Split(SplitArgs),
 
-        if passphrase.is_empty() {
-            return Err(anyhow::anyhow!("Empty passphrase!"));
-        }
+    /// Reconstruct a secret from Shamir shares
+    Combine(CombineArgs),
 
-        eprintln!("\nDeriving...");
+    /// Derive a set of numeric recovery codes (e.g. 2FA backup codes)
+    RecoveryCodes(RecoveryCodesArgs),
 
-        argon2::hash(&params, passphrase.as_bytes(), output_len)
-    }
+    /// Decode a secret printed using `--encoding words`
+    DecodeWords(DecodeWordsArgs),
 
-    fn read_config(&self) -> anyhow::Result<Option<config::File>> {
-        let path = self.config_path()?;
-        let cfg = config::File::read(&path).context("config::File::read")?;
-        if let Some(cfg) = &cfg {
-            eprintln!("\nUsing config ({path:?}):");
-            cfg.eprint();
-        }
+    /// Generate a random passphrase to derive your secrets from
+    ///
+    /// The words are picked from the BIP39 English wordlist using the OS randomness,
+    /// neither the config nor Argon2 are involved.
+    #[command(verbatim_doc_comment)]
+    GeneratePassphrase(GeneratePassphraseArgs),
 
-        Ok(cfg)
-    }
+    /// Derive many secrets from a passphrase typed once
+    ///
+    /// Reads commands (e.g. `secret db-pass 32 hex`, `age laptop`) from stdin, type `help` for the list.
+    /// The passphrase is kept in memory until the session ends, Argon2 is run for every derivation.
+    #[command(verbatim_doc_comment)]
+    Session(SessionArgs),
 
-    fn write_config(&self, cfg: &config::File) -> anyhow::Result<()> {
-        let path = self.config_path()?;
+    /// Derive secrets of several types from a passphrase typed once
+    ///
+    /// Every output is derived from `<NAME>:<TYPE>` (`age` or `secret`), so outputs of different types
+    /// never share bytes. e.g. `multi laptop --age --secret 32:hex` prints the same secrets
+    /// as `age laptop:age` followed by `secret laptop:secret`.
+    #[command(verbatim_doc_comment)]
+    Multi(MultiArgs),
 
-        eprintln!("\nWriting config ({path:?}):");
-        cfg.eprint();
+    /// Derive the outgoing and the incoming generation of a secret from a passphrase typed once
+    ///
+    /// Helps rotating a key (see `--generation`), e.g. to re-encrypt data from the old key to the new one.
+    #[command(verbatim_doc_comment)]
+    Rotate(RotateArgs),
 
-        cfg.write(&path).context("config::File::write")?;
+    /// Print the public keys derived from a name in every supported format
+    ///
+    /// Derives the secret once and prints the public key of each subcommand using it as a private key,
+    /// e.g. to cross-reference the keys registered on various services. Never prints secrets.
+    #[command(verbatim_doc_comment)]
+    Whoami(WhoamiArgs),
 
-        Ok(())
-    }
+    /// Manage the OS keyring entries created by `secret --store-keyring`
+    Keyring(KeyringArgs),
 
-    fn config_path(&self) -> anyhow::Result<PathBuf> {
-        self.config
-            .clone()
-            .or_else(|| config::default_dir().map(|dir| dir.join("config.toml")))
-            .context(
-                "Unable to figure out the default config location and --config wasn't provided",
-            )
-    }
-}
+    /// Inspect the log of `--audit-log`
+    Audit(AuditArgs),
 
-fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+    /// Verify the files listed in the manifest of `--checksum-file`
+    ///
+    /// Prints `<filename>: OK` or `<filename>: FAILED` for every line of the manifest,
+    /// exits with 7 if any of the files fails the verification. No passphrase is needed.
+    #[command(verbatim_doc_comment)]
+    VerifyFiles,
 
-    match &cli.command {
-        Commands::Configure(args) => {
-            if cli.read_config()?.is_some() && !args.overwrite {
-                return Err(anyhow::anyhow!(
-                    "Config file already exists! Use --overwite if you want to overwrite the file."
-                ));
-            }
+    /// Print the JSON Schema of the config file, e.g. to validate configs in CI
+    ///
+    /// Applies to the TOML and YAML configs as well, as they have the same structure.
+    #[command(verbatim_doc_comment)]
+    ConfigSchema,
 
-            let cfg = argon2::Parameters::from_cli(&cli)?
-                .context(MISSING_REQUIRED_PARAMETERS)
-                .map(config::File::from)?;
+    /// Generate a shell completion script
+    ///
+    /// e.g. `argon2derive completions zsh > ~/.zfunc/_argon2derive`
+    Completions(CompletionsArgs),
 
-            cli.write_config(&cfg)?;
-        }
-        Commands::Secret(args) => {
-            let bytes = &cli.derive_secret(&args.name, args.length)?;
-            let encoded = match args.encoding.as_str() {
-                "hex" => hex::encode(bytes),
-                "base64" => general_purpose::STANDARD.encode(bytes),
-                _ => unreachable!(),
-            };
-            eprintln!("\nSecret:");
-            print!("{encoded}");
-        }
-        Commands::Age(args) => {
-            let identity = age::identity(cli.derive_secret(&args.name, 32)?.try_into().unwrap())?;
-            eprintln!("\nAge Identity:");
-            print!("{identity}");
-        }
-    }
+    /// Generate man pages
+    #[command(hide = true)]
+    Man(ManArgs),
 
-    Ok(())
+    /// Interactively tune the Argon2 parameters and save them to the config
+    ///
+    /// Starts from the parameters provided via the CLI or the config file.
+    /// Every change gets measured by deriving a dummy secret, so choose your memory cost wisely.
+    #[cfg(feature = "tui")]
+    #[command(verbatim_doc_comment)]
+    Tune,
 }
 
-impl TryFrom<config::File> for argon2::Parameters {
-    type Error = anyhow::Error;
+impl Commands {
+    /// Whether the command reads its input data from stdin.
+    fn reads_stdin(&self) -> bool {
+        match self {
+            Self::Encrypt(args) => args.input.is_none(),
+            Self::Decrypt(args) => args.input.is_none(),
+            Self::Hmac(args) => args.files.is_empty() && args.check.is_none(),
+            Self::Session(_) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+struct ConfigureArgs {
+    /// Whether to overwrite an existing config file
+    ///
+    /// If not provided and stderr is a terminal, you will be asked for a confirmation instead.
+    #[arg(long, short, visible_alias = "force", verbatim_doc_comment)]
+    overwrite: bool,
+
+    /// Don't back up the overwritten config file
+    ///
+    /// By default the previous config is copied to `<config>.bak-<unix timestamp>`.
+    #[arg(long, verbatim_doc_comment)]
+    no_backup: bool,
+
+    /// Format of the config file
+    ///
+    /// Defaults to the format of the existing config file, TOML if there's none.
+    /// Switching the format replaces the extension of the config file, the previous file gets moved
+    /// to `<config>.bak-<unix timestamp>`. Must match the extension of `--config` if that's provided.
+    #[arg(long, value_parser = config::FORMATS, verbatim_doc_comment)]
+    format: Option<String>,
+
+    /// Bind the config to the passphrase, so deriving with a different one fails (e.g. using the wrong config)
+    ///
+    /// Stores a 16-bit fingerprint of the passphrase, computed by an additional derivation using the parameters
+    /// of the config. Any passphrase has a 1 in 65536 chance to match it, so it doesn't help guessing
+    /// the passphrase any more than any derived secret does. Checking it doubles the derivation time.
+    #[arg(long, verbatim_doc_comment)]
+    bind_passphrase: bool,
+}
+
+#[derive(Debug, Args)]
+struct SecretArgs {
+    /// Name of the secret
+    ///
+    /// Appended to Argon2 salt in order to derive the secret.
+    name: String,
+
+    /// Length in bytes
+    ///
+    /// With `--expand-output`, secrets longer than 64 KiB are expanded with BLAKE3 from a 64-byte Argon2 output.
+    #[arg(short, long, default_value_t = 32, verbatim_doc_comment)]
+    length: u32,
+
+    /// Length in bits, instead of `--length`
+    ///
+    /// If it isn't a multiple of 8, the next whole byte is derived and its excess (least significant) bits
+    /// are zeroed, e.g. `--bits 12` produces 2 bytes ending with 4 zero bits.
+    #[arg(long, conflicts_with_all = ["length", "template"], verbatim_doc_comment)]
+    bits: Option<u32>,
+
+    /// Number of bytes to output from the window at `--offset`, instead of `--length`
+    ///
+    /// `--offset` + `--take` bytes are derived, of which only `bytes[offset..offset + take]` are output.
+    /// The window is stable for fixed inputs. Argon2 mixes the total length into the derivation though,
+    /// so windows only come from the same derivation if their `--offset` + `--take` are equal,
+    /// e.g. `--offset 0 --take 32` and `--offset 32 --take 32` don't share it, while
+    /// `--offset 0 --take 32` and `--offset 16 --take 16` do.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..), conflicts_with_all = ["length", "bits", "template"], verbatim_doc_comment)]
+    take: Option<u32>,
+
+    /// Number of derived bytes to skip before the window of `--take`
+    #[arg(long, requires = "take")]
+    offset: Option<u32>,
+
+    /// Encoding format
+    ///
+    /// `words` encodes the secret as BIP39 words with a checksum word after every 4 words,
+    /// which is convenient for paper backups. Use `decode-words` to convert it back.
+    ///
+    /// `words-password` renders a memorable password of BIP39 words instead (e.g. `cactus-orbit-...`),
+    /// shaped by `--words`, `--separator`, `--capitalize` and `--suffix`. `--length` is ignored.
+    ///
+    /// `rust-array` and `c-array` render a constant to embed into source code, named `--ident`:
+    /// `const KEY: [u8; 32] = [0x3a, 0x91, ...];` and `const uint8_t KEY[32] = {0x3a, 0x91, ...};`.
+    /// `hexdump` renders an `xxd`-style dump (offsets, hex and ASCII columns).
+    #[arg(short, long, value_parser = ["hex", "base64", "words", "words-password", "rust-array", "c-array", "hexdump"], default_value = "hex", verbatim_doc_comment)]
+    encoding: String,
+
+    /// Identifier of `--encoding rust-array` and `c-array` (the uppercased name of the secret if not provided)
+    #[arg(long, value_parser = literal::parse_ident)]
+    ident: Option<String>,
+
+    /// Number of words of `--encoding words-password`
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u16).range(1..))]
+    words: u16,
+
+    /// Separator between the words of `--encoding words-password`
+    #[arg(long, default_value = "-")]
+    separator: String,
+
+    /// Capitalize the words of `--encoding words-password`
+    #[arg(long)]
+    capitalize: bool,
+
+    /// Append a digit and a symbol to `--encoding words-password`, for sites demanding them
+    #[arg(long)]
+    suffix: bool,
+
+    /// Password pattern to render the secret as, instead of `--encoding`
+    ///
+    /// `A` is an uppercase letter, `a` a lowercase one, `0` a digit and `s` a symbol (neither quotes nor `\`).
+    /// Punctuation and spaces are used as is, other letters and digits need to be escaped using `\`.
+    /// E.g. `Aaaaaaaa-0000-ssss`. Every character is picked uniformly within its class.
+    #[arg(long, conflicts_with_all = ["length", "encoding"], verbatim_doc_comment)]
+    template: Option<password::Template>,
+
+    /// Path to a dotenv file to write the secret into
+    ///
+    /// Instead of being printed, the secret is stored as `NAME=value` (with the name uppercased).
+    /// An existing entry with the same name gets updated in place.
+    #[arg(long, verbatim_doc_comment)]
+    dotenv: Option<PathBuf>,
+
+    /// Name of a systemd credential to write the secret into
+    ///
+    /// Instead of being printed, the secret is written to `$CREDENTIALS_DIRECTORY/<CREDENTIAL>`.
+    #[arg(long, conflicts_with = "dotenv", verbatim_doc_comment)]
+    credential: Option<String>,
+
+    /// Output format
+    ///
+    /// `systemd-creds` encrypts the (encoded) secret using `systemd-creds encrypt`,
+    /// the credential name being `--credential` or the name of the secret.
+    ///
+    /// `htpasswd` renders a `<USER>:$2y$...` line for web server basic auth (Apache, nginx),
+    /// the (encoded) secret being the password hashed using bcrypt.
+    ///
+    /// `k8s-secret` renders a Kubernetes `Secret` manifest (YAML) named `--k8s-name`,
+    /// holding the (encoded) secret under the name of the secret (`_` replacing unsupported characters).
+    #[arg(long, value_parser = ["plain", "systemd-creds", "htpasswd", "k8s-secret"], default_value = "plain", conflicts_with = "dotenv", verbatim_doc_comment)]
+    format: String,
+
+    /// User of `--format htpasswd`
+    #[arg(long, required_if_eq("format", "htpasswd"))]
+    user: Option<String>,
+
+    /// bcrypt cost of `--format htpasswd`
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(4..=31))]
+    bcrypt_cost: u32,
+
+    /// Name of the Kubernetes secret of `--format k8s-secret`
+    #[arg(long, value_parser = k8s::parse_name, required_if_eq("format", "k8s-secret"))]
+    k8s_name: Option<String>,
+
+    /// Namespace of the Kubernetes secret of `--format k8s-secret`
+    ///
+    /// Unrelated to the global `--namespace`, which selects a salt of the config.
+    #[arg(long, value_parser = k8s::parse_name, verbatim_doc_comment)]
+    k8s_namespace: Option<String>,
+
+    /// Put the secret into `stringData` rather than (base64 encoded) `data` with `--format k8s-secret`
+    #[arg(long)]
+    string_data: bool,
+
+    /// Derive the bcrypt salt of `--format htpasswd` instead of picking a random one
+    ///
+    /// Makes the whole line reproducible, e.g. to keep config files stable.
+    #[arg(long, verbatim_doc_comment)]
+    deterministic_salt: bool,
+
+    /// Path of a password store entry to insert the secret into
+    ///
+    /// Instead of being printed, the secret is piped into `pass insert`, so it never touches the disk unencrypted.
+    #[arg(long, conflicts_with_all = ["dotenv", "credential", "format"], verbatim_doc_comment)]
+    pass_insert: Option<String>,
+
+    /// Command to use for `--pass-insert`, e.g. `gopass`
+    #[arg(long, default_value = "pass", requires = "pass_insert")]
+    pass_command: String,
+
+    /// OS keyring entry (`<SERVICE>/<ACCOUNT>`) to store the secret into
+    ///
+    /// Instead of being printed, the secret is piped into `secret-tool` (Linux) or `security` (macOS).
+    /// Use `keyring delete` to remove the entry.
+    #[arg(long, conflicts_with_all = ["dotenv", "credential", "format", "pass_insert"], verbatim_doc_comment)]
+    store_keyring: Option<keyring::Entry>,
+
+    /// Overwrite the existing entry of `--store-keyring`
+    #[arg(long, requires = "store_keyring")]
+    force: bool,
+
+    /// age recipient (`age1...`) to encrypt the secret to instead of printing it (repeatable)
+    ///
+    /// The secret never hits the terminal or the disk unencrypted, decrypt it using `age -d -i <IDENTITY>`.
+    /// The ciphertext is written to `--output`, or to stdout if not provided.
+    #[arg(short = 'r', long, value_name = "RECIPIENT", value_parser = age_encrypt::parse_recipient, conflicts_with_all = ["dotenv", "credential", "pass_insert", "store_keyring"], verbatim_doc_comment)]
+    encrypt_to: Vec<::age::x25519::Recipient>,
+
+    /// Encode the ciphertext of `--encrypt-to` using the ASCII armor
+    #[arg(long, requires = "encrypt_to")]
+    armor: bool,
+
+    /// File to write the ciphertext of `--encrypt-to` to
+    ///
+    /// The file is written atomically, readable by the owner only.
+    #[arg(short, long, requires = "encrypt_to", verbatim_doc_comment)]
+    output: Option<PathBuf>,
+
+    /// Compare the secret against this value instead of printing it
+    ///
+    /// The value is in the selected `--encoding` (or `--template`), `hex`, `base64` and `words` values being decoded
+    /// first, so e.g. the case of hex digits doesn't matter. The comparison is constant time, a one-line verdict
+    /// is printed to stderr and nothing to stdout. Exits with 0 on a match, with 7 on a mismatch.
+    /// Note that the value is visible in the process list, prefer `--expect-file`.
+    #[arg(
+        long,
+        value_name = "VALUE",
+        conflicts_with_all = ["dotenv", "credential", "format", "pass_insert", "store_keyring", "encrypt_to"],
+        verbatim_doc_comment
+    )]
+    expect: Option<String>,
+
+    /// Same as `--expect`, reading the value from a file (trailing line breaks are ignored)
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["expect", "dotenv", "credential", "format", "pass_insert", "store_keyring", "encrypt_to"],
+        verbatim_doc_comment
+    )]
+    expect_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct AgeArgs {
+    /// Name of the keypair
+    ///
+    /// Appended to Argon2 salt in order to derive the keypair.
+    name: String,
+
+    /// Use bech32m instead of bech32 encoding
+    ///
+    /// The age spec mandates bech32, so the resulting keys won't be accepted by age itself.
+    #[arg(long, verbatim_doc_comment)]
+    bech32m: bool,
+
+    /// Comment to identify the keypair by, printed as a `# comment:` line
+    ///
+    /// Purely cosmetic, it's neither part of the key material nor of the recipient.
+    #[arg(long, value_parser = parse_comment, verbatim_doc_comment)]
+    comment: Option<String>,
+
+    /// SLIP-0010 derivation path of the key, e.g. `m/44'/0'/0'` (hardened indices only)
+    ///
+    /// The 64-byte Argon2 output is used as the seed, the key being derived the way Trezor derives
+    /// X25519 keys (`curve25519 seed`). Without a path, the key is the 32-byte Argon2 output.
+    #[arg(long, verbatim_doc_comment)]
+    path: Option<slip10::Path>,
+}
+
+#[derive(Debug, Args)]
+struct RsaArgs {
+    /// Name of the keypair
+    ///
+    /// Appended to Argon2 salt in order to derive the keypair.
+    name: String,
+
+    /// Key size in bits
+    #[arg(long, value_parser = ["2048", "3072", "4096"], default_value = "3072")]
+    bits: String,
+
+    /// Private key format
+    #[arg(long, value_parser = ["pkcs1", "pkcs8"], default_value = "pkcs8")]
+    format: String,
+
+    /// Comment of the OpenSSH public key line
+    #[arg(long, value_parser = parse_comment)]
+    comment: Option<String>,
+
+    /// Print the OpenSSH public key line instead of the private key
+    ///
+    /// e.g. to append it to `authorized_keys`. Combined with `--fingerprint`, the fingerprint follows it.
+    #[arg(long, verbatim_doc_comment)]
+    public: bool,
+
+    /// Print the SHA256 fingerprint of the public key instead of the private key, as `ssh-keygen -lf` does
+    ///
+    /// e.g. to check the key against the one registered on a server without uploading it.
+    #[arg(long, verbatim_doc_comment)]
+    fingerprint: bool,
+}
+
+#[derive(Debug, Args)]
+#[command(group(ArgGroup::new("mode").required(true).args(["age_plugin", "keygen"])))]
+struct PluginArgs {
+    /// State machine of the age plugin protocol, provided by age (only `identity-v1` is supported)
+    #[arg(long, value_name = "STATE_MACHINE")]
+    age_plugin: Option<String>,
+
+    /// Print the plugin identity (`AGE-PLUGIN-ARGON2DERIVE-1...`) of this name, along with its recipient
+    ///
+    /// The recipient is the one printed by `age <NAME>`, so the files already encrypted to it can be decrypted.
+    /// The identity isn't secret on its own, but it contains the salt. The machine bound salts aren't supported.
+    #[arg(long, value_name = "NAME", verbatim_doc_comment)]
+    keygen: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct TlsCertArgs {
+    /// Name of the key
+    ///
+    /// Appended to Argon2 salt in order to derive the key.
+    name: String,
+
+    /// Common name of the certificate subject (the name of the key if not provided)
+    #[arg(long)]
+    subject: Option<String>,
+
+    /// Subject alternative name, a DNS name or an IP address (repeatable)
+    #[arg(long)]
+    dns: Vec<String>,
+
+    /// SLIP-0010 derivation path of the Ed25519 key, e.g. `m/44'/0'/0'` (hardened indices only)
+    ///
+    /// The 64-byte Argon2 output is used as the seed, so the key matches the one of any SLIP-0010
+    /// implementation given that seed. Without a path, the key is the 32-byte Argon2 output.
+    #[arg(long, verbatim_doc_comment)]
+    path: Option<slip10::Path>,
+
+    /// Start of the validity period (`YYYY-MM-DD`, midnight UTC)
+    ///
+    /// Fixed rather than the current date, as it's part of the certificate.
+    #[arg(long, value_parser = tls::parse_date, default_value = "2025-01-01", verbatim_doc_comment)]
+    not_before: time::Date,
+
+    /// Length of the validity period in days
+    #[arg(long, default_value_t = 3650)]
+    days: u32,
+
+    /// Directory to write `cert.pem` and `key.pem` into
+    ///
+    /// The files are written atomically, readable by the owner only.
+    #[arg(short, long, verbatim_doc_comment)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct GitCryptArgs {
+    /// Name of the key
+    ///
+    /// Appended to Argon2 salt in order to derive the key.
+    name: String,
+
+    /// Name of the git-crypt key (`git-crypt init -k <KEY_NAME>`), the default key if not provided
+    #[arg(long, value_parser = git_crypt::parse_key_name)]
+    key_name: Option<String>,
+
+    /// Output file (stdout if not provided)
+    ///
+    /// The file is written atomically, readable by the owner only.
+    #[arg(short, long, verbatim_doc_comment)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct TotpArgs {
+    /// Name of the secret
+    ///
+    /// Appended to Argon2 salt in order to derive the secret.
+    name: String,
+
+    /// Number of digits of the code
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u32).range(6..=8))]
+    digits: u32,
+
+    /// Length of the time step in seconds
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+    period: u64,
+
+    /// HMAC algorithm
+    #[arg(long, value_parser = otp::ALGORITHMS, default_value = "sha1")]
+    algo: String,
+
+    /// Seconds to add to the system clock, e.g. `--offset -30` if the clock is 30 seconds ahead
+    #[arg(long, default_value_t = 0, allow_hyphen_values = true)]
+    offset: i64,
+
+    /// Keep printing a new code every time step, until interrupted
+    ///
+    /// The time remaining until the next code is shown on stderr.
+    #[arg(long, verbatim_doc_comment)]
+    watch: bool,
+}
+
+#[derive(Debug, Args)]
+struct HotpArgs {
+    /// Name of the secret
+    ///
+    /// Appended to Argon2 salt in order to derive the secret.
+    name: String,
+
+    /// Value of the counter
+    #[arg(long)]
+    counter: u64,
+
+    /// Number of digits of the code
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u32).range(6..=8))]
+    digits: u32,
+
+    /// HMAC algorithm
+    #[arg(long, value_parser = otp::ALGORITHMS, default_value = "sha1")]
+    algo: String,
+}
+
+#[derive(Debug, Args)]
+struct CheckArgs {
+    /// PHC string (`$argon2id$v=19$m=...,t=...,p=...$<salt>$<hash>`)
+    #[arg(required_unless_present = "phc_file")]
+    phc: Option<String>,
+
+    /// Path to a file containing the PHC string
+    #[arg(long, conflicts_with = "phc")]
+    phc_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct EncryptArgs {
+    /// Name of the key
+    ///
+    /// Appended to Argon2 salt in order to derive the key.
+    #[arg(long, short)]
+    name: String,
+
+    /// Input file (stdin if not provided)
+    input: Option<PathBuf>,
+
+    /// Output file (stdout if not provided)
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct DecryptArgs {
+    /// Name of the key
+    ///
+    /// Defaults to the name stored in the header of the encrypted file.
+    #[arg(long, short)]
+    name: Option<String>,
+
+    /// Input file (stdin if not provided)
+    input: Option<PathBuf>,
+
+    /// Output file (stdout if not provided)
+    ///
+    /// The file only appears once the whole input has been successfully decrypted.
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct HmacArgs {
+    /// Name of the key
+    ///
+    /// Appended to Argon2 salt in order to derive the key.
+    #[arg(long, short)]
+    name: String,
+
+    /// MAC algorithm (`sha256`, `sha512` or `blake3`)
+    #[arg(long, default_value = "sha256")]
+    algo: mac::Algorithm,
+
+    /// Files to compute MACs of (stdin if none provided)
+    #[arg(conflicts_with = "check")]
+    files: Vec<PathBuf>,
+
+    /// Verify the MACs listed in the provided file
+    ///
+    /// The file should contain lines previously printed by this command.
+    /// Exits with 7 if any of the files fails the verification.
+    #[arg(long, verbatim_doc_comment)]
+    check: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct SplitArgs {
+    /// Name of the secret
+    ///
+    /// Appended to Argon2 salt in order to derive the secret.
+    name: String,
+
+    /// Length in bytes
+    #[arg(short, long, default_value_t = 32)]
+    length: u32,
+
+    /// Number of shares to produce
+    #[arg(long)]
+    shares: u8,
+
+    /// Number of shares required to reconstruct the secret
+    #[arg(long)]
+    threshold: u8,
+}
+
+#[derive(Debug, Args)]
+struct CombineArgs {
+    /// Shares produced by the `split` command
+    #[arg(required = true)]
+    shares: Vec<String>,
+
+    /// Encoding format
+    #[arg(short, long, value_parser = ["hex", "base64"], default_value = "hex")]
+    encoding: String,
+}
+
+#[derive(Debug, Args)]
+struct RecoveryCodesArgs {
+    /// Name of the set of codes
+    ///
+    /// Appended to Argon2 salt in order to derive the codes.
+    name: String,
+
+    /// Number of codes
+    #[arg(long, default_value_t = 10)]
+    count: u16,
+
+    /// Number of digits in each code
+    #[arg(short, long, default_value_t = 8, value_parser = clap::value_parser!(u8).range(1..))]
+    length: u8,
+}
+
+#[derive(Debug, Args)]
+struct GeneratePassphraseArgs {
+    /// Number of words
+    #[arg(short, long, default_value_t = 7, value_parser = clap::value_parser!(u16).range(1..))]
+    words: u16,
+
+    /// Separator between the words
+    #[arg(long, default_value = " ")]
+    separator: String,
+}
+
+#[derive(Debug, Args)]
+struct SessionArgs {
+    /// Inactivity timeout in seconds, after which the passphrase is wiped and the session ends
+    ///
+    /// 0 disables the timeout.
+    #[arg(long, default_value_t = 300, verbatim_doc_comment)]
+    timeout: u64,
+}
+
+#[derive(Debug, Args)]
+#[command(group = ArgGroup::new("outputs").required(true).multiple(true))]
+struct MultiArgs {
+    /// Name of the secrets
+    ///
+    /// Appended to Argon2 salt, followed by the type tag, in order to derive each secret.
+    name: String,
+
+    /// Derive an age keypair
+    #[arg(long, group = "outputs")]
+    age: bool,
+
+    /// Use Bech32m instead of Bech32 for the age keypair
+    #[arg(long, requires = "age")]
+    bech32m: bool,
+
+    /// Derive a raw secret, as `<LENGTH>:<ENCODING>` (`hex`, `base64` or `words`, repeatable)
+    #[arg(long, value_parser = parse_secret_spec, group = "outputs")]
+    secret: Vec<SecretSpec>,
+
+    /// Output format
+    ///
+    /// `csv` (RFC 4180) and `tsv` print a `name,length,encoding,value` row per secret
+    /// (the age secret key for `--age`), preceded by a header row.
+    /// TSV can't represent line breaks, so it doesn't support the `words` encoding.
+    #[arg(long, value_parser = ["plain", "csv", "tsv"], default_value = "plain", verbatim_doc_comment)]
+    format: String,
+
+    /// Don't print the header row of `--format csv` or `tsv`, e.g. to append to an existing file
+    #[arg(long)]
+    no_header: bool,
+}
+
+#[derive(Debug, Args)]
+struct RotateArgs {
+    /// Name of the secret
+    ///
+    /// Appended to Argon2 salt in order to derive the secret.
+    name: String,
+
+    /// Generation of the outgoing secret
+    #[arg(long)]
+    from_generation: u32,
+
+    /// Generation of the incoming secret (`--from-generation` + 1 if not provided)
+    #[arg(long)]
+    to_generation: Option<u32>,
+
+    /// Length in bytes
+    #[arg(short, long, default_value_t = 32, conflicts_with = "age")]
+    length: u32,
+
+    /// Encoding format
+    #[arg(short, long, value_parser = ["hex", "base64", "words"], default_value = "hex", conflicts_with = "age")]
+    encoding: String,
+
+    /// Derive age identities instead of raw secrets
+    ///
+    /// Additionally prints both recipients to stderr, ready to be saved as an `age -R` recipients file
+    /// while the data is encrypted to both keys.
+    #[arg(long, verbatim_doc_comment)]
+    age: bool,
+
+    /// Use Bech32m instead of Bech32 for the age identities
+    #[arg(long, requires = "age")]
+    bech32m: bool,
+
+    /// Output format
+    ///
+    /// `json` prints a single `{"old": ..., "new": ...}` object.
+    #[arg(long, value_parser = ["plain", "json"], default_value = "plain", verbatim_doc_comment)]
+    format: String,
+}
+
+impl RotateArgs {
+    /// Outgoing and incoming generations, which have to differ.
+    fn generations(&self) -> anyhow::Result<(u32, u32)> {
+        let from = self.from_generation;
+        let to = match self.to_generation {
+            Some(to) => to,
+            None => from.checked_add(1).ok_or_else(|| {
+                ErrorKind::Usage.error("--from-generation is the last one, provide --to-generation")
+            })?,
+        };
+        if from == to {
+            return Err(
+                ErrorKind::Usage.error("--from-generation and --to-generation should differ")
+            );
+        }
+        Ok((from, to))
+    }
+}
+
+/// Raw secret requested by `multi --secret`.
+#[derive(Debug, Clone)]
+struct SecretSpec {
+    length: u32,
+    encoding: &'static str,
+}
+
+#[derive(Debug, Args)]
+struct DecodeWordsArgs {
+    /// Words to decode (stdin if not provided)
+    ///
+    /// Line numbers (`1:`, `2:`, ...) are ignored.
+    words: Vec<String>,
+
+    /// Encoding format
+    #[arg(short, long, value_parser = ["hex", "base64"], default_value = "hex")]
+    encoding: String,
+}
+
+#[derive(Debug, Args)]
+struct WhoamiArgs {
+    /// Name of the keys
+    ///
+    /// Appended to Argon2 salt in order to derive the keys.
+    name: String,
+
+    /// Include the RSA key of the provided size (`rsa --bits`), which takes a while to derive
+    #[arg(long, value_parser = ["2048", "3072", "4096"])]
+    rsa_bits: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct KeyringArgs {
+    #[command(subcommand)]
+    command: KeyringCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum KeyringCommand {
+    /// Delete an entry
+    ///
+    /// Only the entries created by argon2derive can be deleted.
+    #[command(verbatim_doc_comment)]
+    Delete(KeyringDeleteArgs),
+}
+
+#[derive(Debug, Args)]
+struct KeyringDeleteArgs {
+    /// Entry to delete (`<SERVICE>/<ACCOUNT>`)
+    entry: keyring::Entry,
+}
+
+#[derive(Debug, Args)]
+struct AuditArgs {
+    #[command(subcommand)]
+    command: AuditCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum AuditCommand {
+    /// Print the entries of the log, oldest first
+    ///
+    /// Reads `--audit-log`, or the `audit_log` of the config if not provided.
+    #[command(verbatim_doc_comment)]
+    Show(AuditShowArgs),
+}
+
+#[derive(Debug, Args)]
+struct AuditShowArgs {
+    /// Only show the derivations of this secret name
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Only show the derivations performed by this subcommand (e.g. `secret`)
+    #[arg(long)]
+    command: Option<String>,
+
+    /// Only show the derivations since this UTC date or time (e.g. `2025-01-31` or `2025-01-31T12:00`)
+    #[arg(long, value_name = "TIME", value_parser = parse_since)]
+    since: Option<String>,
+
+    /// Only show the derivations of the failed invocations
+    #[arg(long)]
+    failed: bool,
+
+    /// Output format
+    ///
+    /// `table` aligns the entries into columns, `json` prints them as they are stored (a JSON object per line).
+    #[arg(long, value_parser = ["table", "json"], default_value = "table", verbatim_doc_comment)]
+    format: String,
+}
+
+#[derive(Debug, Args)]
+struct CompletionsArgs {
+    /// Shell to generate the completions for
+    shell: clap_complete::Shell,
+}
+
+#[derive(Debug, Args)]
+struct ManArgs {
+    /// Directory to write the man pages (main page plus one per subcommand) into
+    ///
+    /// Only the main page is printed to stdout if not provided.
+    #[arg(long, short)]
+    dir: Option<PathBuf>,
+}
+
+impl Cli {
+    /// Derives the secret of the provided name.
+    ///
+    /// Fails with [`PlanPrinted`] once the plan of `--dry-run` or `--describe` is printed instead.
+    fn derive_secret(&self, name: &str, output_len: u32) -> anyhow::Result<Vec<u8>> {
+        argon2::validate_output_len(output_len).kind(ErrorKind::Usage)?;
+
+        let (mut params, source) = self.prepare_params()?;
+        let salt_len = params.salt.len();
+
+        self.append_name(&mut params, name, self.generation)?;
+
+        if self.describe {
+            self.print_description(&params, &source, name, salt_len, output_len)?;
+            return Err(PlanPrinted.into());
+        }
+        if self.dry_run {
+            self.print_plan(
+                &mut io::stdout().lock(),
+                &params,
+                &source,
+                salt_len,
+                output_len,
+            )
+            .kind(ErrorKind::Output)?;
+            return Err(PlanPrinted.into());
+        }
+
+        audit::record(name, output_len, &params, self.cascade(&source));
+        let passphrase = self.read_source_passphrase(&source)?;
+
+        self.hash(&params, self.cascade(&source), &passphrase, output_len)
+    }
+
+    /// Resolves and validates the Argon2 parameters, before the name of the secret is appended.
+    fn prepare_params(&self) -> anyhow::Result<(argon2::Parameters, ParamsSource)> {
+        let (mut params, source) = self.resolve_params()?;
+
+        if self.salt_source(&source) == "machine-id" {
+            params.salt = machine::bind_salt(&params.salt).context("machine::bind_salt")?;
+            warn_machine_bound();
+        } else if params.salt.is_empty() {
+            if self.require_salt || source.require_salt() {
+                return Err(ErrorKind::Usage.error(
+                    "Your salt is empty, provide one via --salt or the config (the salt is required)",
+                ));
+            }
+            term::warn("Your salt is empty!");
+        }
+
+        params.validate()?;
+        self.check_params(&params);
+
+        for pass in self.cascade(&source) {
+            pass.validate()?;
+        }
+
+        if self.bind_params {
+            params.bind_params(self.cascade(&source));
+            term::detail(format_args!(
+                "Bound to the parameters: {}",
+                params.canonical_costs()
+            ));
+        }
+
+        if !self.plan_only() {
+            // The passes run one after another, so only the largest one needs to fit.
+            let passes = self.cascade(&source).iter();
+            self.check_memory(passes.fold(params.memory, |max, pass| max.max(pass.memory)))?;
+        }
+
+        Ok((params, source))
+    }
+
+    /// Appends the name of the secret to the salt, according to `--hash-name` or `--hierarchical-names`.
+    fn append_name(
+        &self,
+        params: &mut argon2::Parameters,
+        name: &str,
+        generation: u32,
+    ) -> anyhow::Result<()> {
+        if self.hierarchical_names {
+            let path = parse_name_path(name).kind(ErrorKind::Usage)?;
+            let path: Vec<_> = path.iter().map(String::as_str).collect();
+            params.append_path(&path, generation)?;
+        } else if self.hash_name {
+            params.append_hashed_name(name, generation)?;
+        } else {
+            params.append_name(name, generation)?;
+        }
+        Ok(())
+    }
+
+    /// Derives the 32-byte private key of the curve, via SLIP-0010 if a path is provided.
+    fn derive_curve_key(
+        &self,
+        name: &str,
+        curve: slip10::Curve,
+        path: Option<&slip10::Path>,
+    ) -> anyhow::Result<Zeroizing<[u8; 32]>> {
+        let Some(path) = path else {
+            let key = Zeroizing::new(self.derive_secret(name, 32)?);
+            return Ok(Zeroizing::new(key.as_slice().try_into().unwrap()));
+        };
+
+        // Fails before the passphrase is asked for.
+        path.check(curve).kind(ErrorKind::Usage)?;
+
+        let seed = Zeroizing::new(self.derive_secret(name, 64)?);
+        let node = slip10::derive(curve, &seed, path).kind(ErrorKind::Usage)?;
+        term::detail(format_args!("SLIP-0010 path: {path}"));
+        Ok(Zeroizing::new(node.private_key))
+    }
+
+    /// Derives the secret of the provided name using the prepared parameters.
+    fn derive_named(
+        &self,
+        params: &argon2::Parameters,
+        cascade: &[argon2::Parameters],
+        passphrase: &[u8],
+        name: &str,
+        output_len: u32,
+    ) -> anyhow::Result<Vec<u8>> {
+        self.derive_generation(
+            params,
+            cascade,
+            passphrase,
+            name,
+            self.generation,
+            output_len,
+        )
+    }
+
+    /// Derives the secret of the provided name and generation using the prepared parameters.
+    fn derive_generation(
+        &self,
+        params: &argon2::Parameters,
+        cascade: &[argon2::Parameters],
+        passphrase: &[u8],
+        name: &str,
+        generation: u32,
+        output_len: u32,
+    ) -> anyhow::Result<Vec<u8>> {
+        argon2::validate_output_len(output_len).kind(ErrorKind::Usage)?;
+
+        let mut params = params.clone();
+        self.append_name(&mut params, name, generation)?;
+
+        audit::record(name, output_len, &params, cascade);
+        self.hash(&params, cascade, passphrase, output_len)
+    }
+
+    fn hash(
+        &self,
+        params: &argon2::Parameters,
+        cascade: &[argon2::Parameters],
+        passphrase: &[u8],
+        output_len: u32,
+    ) -> anyhow::Result<Vec<u8>> {
+        if self.expand_output && output_len > argon2::EXPANSION_THRESHOLD {
+            let base = Zeroizing::new(self.hash_with(
+                argon2::hash_cascade,
+                params,
+                cascade,
+                passphrase,
+                argon2::EXPANSION_BASE_LEN,
+            )?);
+            return Ok(argon2::expand(&base, output_len));
+        }
+
+        self.hash_with(
+            argon2::hash_cascade,
+            params,
+            cascade,
+            passphrase,
+            output_len,
+        )
+    }
+
+    /// [`Cli::hash`] using the provided hash function, which the tests replace to simulate memory errors.
+    fn hash_with(
+        &self,
+        mut hash: impl FnMut(
+            &argon2::Parameters,
+            &[argon2::Parameters],
+            &[u8],
+            u32,
+        ) -> anyhow::Result<Vec<u8>>,
+        params: &argon2::Parameters,
+        cascade: &[argon2::Parameters],
+        passphrase: &[u8],
+        output_len: u32,
+    ) -> anyhow::Result<Vec<u8>> {
+        term::progress("\nDeriving...");
+
+        let start = Instant::now();
+        let secret = hash(params, cascade, passphrase, output_len).kind(ErrorKind::Derivation)?;
+
+        interrupt::check()?;
+        term::progress(format!("Derived in {:.2}s", start.elapsed().as_secs_f64()));
+
+        if self.paranoid {
+            term::progress("Deriving again to verify the output (--paranoid)...");
+
+            let start = Instant::now();
+            let check = Zeroizing::new(
+                hash(params, cascade, passphrase, output_len).kind(ErrorKind::Derivation)?,
+            );
+            if !bool::from(secret.ct_eq(&check)) {
+                return Err(ErrorKind::Derivation.error(
+                    "The two derivations produced different outputs! \
+                     The memory of this machine may be faulty (run a memory test before deriving any secrets) \
+                     or the Argon2 backend isn't deterministic.",
+                ));
+            }
+
+            term::progress(format!("Verified in {:.2}s", start.elapsed().as_secs_f64()));
+        }
+
+        Ok(secret)
+    }
+
+    /// Names of the Argon2 parameters provided via the CLI.
+    fn cli_params(&self) -> Vec<&'static str> {
+        [
+            ("--algorithm", self.algorithm.is_some()),
+            ("--memory", self.memory.is_some()),
+            ("--time", self.time.is_some()),
+            ("--time-budget", self.time_budget.is_some()),
+            ("--parallelism", self.parallelism.is_some()),
+            ("--salt", self.salt.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(name, defined)| defined.then_some(name))
+        .collect()
+    }
+
+    /// Whether only the plan gets printed (`--dry-run` or `--describe`), without deriving anything.
+    fn plan_only(&self) -> bool {
+        self.dry_run || self.describe
+    }
+
+    /// Origins of the resolved parameters, following the precedence of [`Self::merge_params`]
+    /// and [`Self::resolve_params`].
+    fn origins(&self, source: &ParamsSource) -> Origins {
+        let config = matches!(source, ParamsSource::Config { .. });
+        let origin = |cli: bool, config: bool| match (cli, config) {
+            (true, _) => Origin::Cli,
+            (false, true) => Origin::Config,
+            (false, false) => Origin::Default,
+        };
+
+        Origins {
+            config: match (config, &self.config) {
+                (false, _) => None,
+                (true, _) if self.parameters_from.is_some() => Some(Origin::Cli),
+                (true, Some(_)) if self.config_from_env => Some(Origin::Env),
+                (true, Some(_)) => Some(Origin::Cli),
+                (true, None) => Some(Origin::Default),
+            },
+            algorithm: origin(self.algorithm.is_some(), config),
+            memory: origin(self.memory.is_some(), config),
+            time: match self.time_budget {
+                Some(_) => Origin::TimeBudget,
+                None => origin(self.time.is_some(), config),
+            },
+            parallelism: origin(self.parallelism.is_some(), config),
+            salt: match &self.namespace {
+                Some(_) => Origin::Namespace,
+                None => origin(self.salt.is_some(), config),
+            },
+            salt_source: match source {
+                _ if self.salt_source.is_some() => Origin::Cli,
+                ParamsSource::Config {
+                    salt_source: Some(_),
+                    ..
+                } => Origin::Config,
+                _ => Origin::Default,
+            },
+            cascade: match source {
+                _ if !self.cascade.is_empty() => Origin::Cli,
+                ParamsSource::Config { passes, .. } if !passes.is_empty() => Origin::Config,
+                _ => Origin::Default,
+            },
+            normalization: origin(self.normalize.is_some() || self.no_normalize, config),
+        }
+    }
+
+    /// Prints the plan of `--describe` as a JSON object.
+    fn print_description(
+        &self,
+        params: &argon2::Parameters,
+        source: &ParamsSource,
+        name: &str,
+        salt_len: usize,
+        output_len: u32,
+    ) -> anyhow::Result<()> {
+        #[derive(serde::Serialize)]
+        struct Param<T> {
+            value: T,
+            origin: Origin,
+        }
+
+        #[derive(serde::Serialize)]
+        struct Pass {
+            algorithm: String,
+            memory: u32,
+            time: u32,
+            parallelism: u32,
+        }
+
+        #[derive(serde::Serialize)]
+        struct Plan<'a> {
+            config: Option<Param<&'a Path>>,
+            algorithm: Param<String>,
+            /// In KiB.
+            memory: Param<u32>,
+            time: Param<u32>,
+            parallelism: Param<u32>,
+            salt_length: Param<usize>,
+            salt_source: Param<&'a str>,
+            namespace: Option<&'a str>,
+            final_salt_length: usize,
+            cascade: Param<Vec<Pass>>,
+            argon2_version: u32,
+            name: &'a str,
+            name_mode: &'a str,
+            generation: u32,
+            output_length: u32,
+            encoding: &'a str,
+            normalization: Param<&'a str>,
+            bind_params: bool,
+        }
+
+        let origins = self.origins(source);
+        let pass = |pass: &argon2::Parameters| Pass {
+            algorithm: pass.algorithm.to_string(),
+            memory: pass.memory,
+            time: pass.time,
+            parallelism: pass.parallelism,
+        };
+
+        let plan = Plan {
+            config: match (source, origins.config) {
+                (ParamsSource::Config { path, .. }, Some(origin)) => Some(Param {
+                    value: path,
+                    origin,
+                }),
+                _ => None,
+            },
+            algorithm: Param {
+                value: params.algorithm.to_string(),
+                origin: origins.algorithm,
+            },
+            memory: Param {
+                value: params.memory,
+                origin: origins.memory,
+            },
+            time: Param {
+                value: params.time,
+                origin: origins.time,
+            },
+            parallelism: Param {
+                value: params.parallelism,
+                origin: origins.parallelism,
+            },
+            salt_length: Param {
+                value: salt_len,
+                origin: origins.salt,
+            },
+            salt_source: Param {
+                value: self.salt_source(source),
+                origin: origins.salt_source,
+            },
+            namespace: self.namespace.as_deref(),
+            final_salt_length: params.salt.len(),
+            cascade: Param {
+                value: self.cascade(source).iter().map(pass).collect(),
+                origin: origins.cascade,
+            },
+            argon2_version: argon2::VERSION,
+            name,
+            name_mode: match (self.hash_name, self.hierarchical_names) {
+                (true, _) => "hashed",
+                (_, true) => "hierarchical",
+                _ => "raw",
+            },
+            generation: self.generation,
+            output_length: output_len,
+            encoding: match &self.command {
+                Commands::Secret(args) => args.encoding.as_str(),
+                _ => "-",
+            },
+            normalization: Param {
+                value: self.normalization(source.passphrase_version()),
+                origin: origins.normalization,
+            },
+            bind_params: self.bind_params,
+        };
+
+        let json = serde_json::to_string_pretty(&plan).context("serde_json::to_string_pretty")?;
+        writeln!(io::stdout(), "{json}").kind(ErrorKind::Output)
+    }
+
+    /// Argon2 parameters provided via the CLI, taking the missing ones from `base`.
+    fn merge_params(
+        &self,
+        base: Option<argon2::Parameters>,
+    ) -> Result<argon2::Parameters, argon2::ParamError> {
+        let base = base.as_ref();
+
+        Ok(argon2::Parameters {
+            algorithm: self
+                .algorithm
+                .or(base.map(|p| p.algorithm))
+                .unwrap_or_default(),
+            memory: self
+                .memory
+                .map(|gib| gib * 1024 * 1024)
+                .or(base.map(|p| p.memory))
+                .ok_or(argon2::ParamError::MissingMemory)?,
+            time: self
+                .time
+                .or(base.map(|p| p.time))
+                // Replaced by `apply_time_budget` once the other costs are known.
+                .or(self.time_budget.map(|_| 1))
+                .ok_or(argon2::ParamError::MissingTime)?,
+            parallelism: self
+                .parallelism
+                .or(base.map(|p| p.parallelism))
+                .ok_or(argon2::ParamError::MissingParallelism)?,
+            salt: match (&self.salt, base) {
+                (Some(salt), _) => salt.as_bytes().into(),
+                (None, Some(base)) => base.salt.clone(),
+                (None, None) => Vec::new(),
+            },
+        })
+    }
+
+    fn required_params(&self) -> Result<argon2::Parameters, argon2::ParamError> {
+        self.merge_params(None)
+    }
+
+    /// Resolves the Argon2 parameters from the CLI, falling back to the config file for the missing ones.
+    ///
+    /// The config file isn't used at all if the CLI provides all the costs, see [`Self::warn_ignored_salt`].
+    fn resolve_params(&self) -> anyhow::Result<(argon2::Parameters, ParamsSource)> {
+        let overrides = self.cli_params();
+        let complete = self.memory.is_some()
+            && self.time.is_some()
+            && self.parallelism.is_some()
+            && self.namespace.is_none();
+
+        let cfg = if complete {
+            self.warn_ignored_salt();
+            None
+        } else {
+            self.read_config()?
+        };
+        let cfg = match cfg {
+            Some(cfg) => cfg,
+            None if self.namespace.is_some() => {
+                return Err(ErrorKind::Config.error("--namespace requires a config file"));
+            }
+            None if !overrides.is_empty() => {
+                let mut params = self.required_params()?;
+                self.apply_time_budget(&mut params)?;
+                return Ok((params, ParamsSource::Cli));
+            }
+            None => return Err(ErrorKind::Config.error("missing config file")),
+        };
+
+        if !overrides.is_empty() {
+            term::detail(format_args!(
+                "Overridden by the command line: {}",
+                overrides.join(", ")
+            ));
+        }
+
+        let passphrase_version = cfg.passphrase_version();
+        let salt_source = cfg.salt_source.clone();
+        let require_salt = cfg.require_salt;
+        let fingerprint = cfg.fingerprint.clone();
+        let passphrase_fingerprint = match &cfg.passphrase_fingerprint {
+            Some(hex) => Some(
+                hex::decode(hex)
+                    .ok()
+                    .and_then(|tag| <[u8; 2]>::try_from(tag).ok())
+                    .context("invalid passphrase_fingerprint, expected 4 hex digits")
+                    .kind(ErrorKind::Config)?,
+            ),
+            None => None,
+        };
+        let namespace_salt = match &self.namespace {
+            Some(namespace) => Some(cfg.namespace_salt(namespace).kind(ErrorKind::Config)?),
+            None => None,
+        };
+        let passes = cfg
+            .passes
+            .iter()
+            .flatten()
+            .map(argon2::Parameters::try_from)
+            .collect::<anyhow::Result<_>>()
+            .kind(ErrorKind::Config)?;
+        let cfg_params: argon2::Parameters = cfg.try_into().kind(ErrorKind::Config)?;
+        let mut params = self.merge_params(Some(cfg_params.clone()))?;
+
+        if fingerprint.is_some_and(|fingerprint| fingerprint != params.fingerprint()) {
+            self.confirm_param_change()?;
+        }
+
+        if self.time_budget.is_some() {
+            if !self.override_time {
+                return Err(ErrorKind::Usage.error(format!(
+                    "The config stores a time cost ({} iterations), which --time-budget would replace, \
+                     deriving different secrets! Use --override-time if you intend to.",
+                    params.time
+                )));
+            }
+            self.apply_time_budget(&mut params)?;
+        }
+
+        // The fingerprint covers the main salt, the namespaces only replace it afterwards.
+        if let (Some(namespace), Some(salt)) = (&self.namespace, namespace_salt) {
+            term::detail(format_args!("Namespace: {namespace}"));
+            params.salt = salt;
+        }
+
+        let source = ParamsSource::Config {
+            path: self.read_config_path()?,
+            passphrase_version,
+            salt_source,
+            require_salt,
+            passes,
+            passphrase_fingerprint: passphrase_fingerprint.map(|tag| (cfg_params, tag)),
+            overridden: !overrides.is_empty(),
+        };
+
+        Ok((params, source))
+    }
+
+    /// Warns if the ignored config holds a salt, which the CLI doesn't replace.
+    ///
+    /// The config is read silently, a missing or invalid one is ignored as a whole anyway.
+    fn warn_ignored_salt(&self) {
+        if self.salt.is_some() {
+            return;
+        }
+
+        let Ok(path) = self.read_config_path() else {
+            return;
+        };
+        let cfg = match &self.parameters_from {
+            Some(path) => config::File::import(path).ok(),
+            None => config::File::read(&path).ok().flatten(),
+        };
+        let Some(cfg) = cfg else {
+            return;
+        };
+
+        if cfg.salt().is_ok_and(|salt| !salt.is_empty()) || cfg.salt_source.is_some() {
+            term::warn(format!(
+                "The config ({path:?}) is ignored, including its salt, as --memory, --time and --parallelism \
+                 are all provided! Provide --salt as well, or drop one of them to use the config."
+            ));
+        }
+    }
+
+    /// Replaces the time cost with the one fitting `--time-budget`, if provided.
+    fn apply_time_budget(&self, params: &mut argon2::Parameters) -> anyhow::Result<()> {
+        let Some(budget) = self.time_budget else {
+            return Ok(());
+        };
+
+        params.validate()?;
+        // The probe allocates the whole memory cost.
+        self.check_memory(params.memory)?;
+
+        term::progress(format!("\nMeasuring the time cost fitting {budget} ms..."));
+        let (time, probe) = timing::fit_time(params, Duration::from_millis(budget))
+            .context("timing::fit_time")
+            .kind(ErrorKind::Derivation)?;
+        params.time = time;
+
+        term::warn(format!(
+            "Picked time cost {time} (1 iteration took {:.0} ms) for this machine only, \
+             pin it via `--time {time}` to derive the same secrets elsewhere!",
+            probe.as_secs_f64() * 1000.0,
+        ));
+        Ok(())
+    }
+
+    /// Asks whether to proceed with the parameters differing from the config fingerprint.
+    fn confirm_param_change(&self) -> anyhow::Result<()> {
+        term::warn(
+            "Parameters differ from your saved profile, the derived secrets will differ too!",
+        );
+
+        if self.allow_param_change || self.plan_only() {
+            return Ok(());
+        }
+
+        if !io::stderr().is_terminal()
+            || !confirm("Parameters differ from your saved profile, continue?")?
+        {
+            return Err(ErrorKind::Usage.error(
+                "Parameters differ from the saved profile, use --allow-param-change if that's intended",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Additional source of the salt, `--salt-source` taking precedence over the config.
+    fn salt_source<'a>(&'a self, source: &'a ParamsSource) -> &'a str {
+        match (&self.salt_source, source) {
+            (Some(salt_source), _) => salt_source,
+            (
+                None,
+                ParamsSource::Config {
+                    salt_source: Some(salt_source),
+                    ..
+                },
+            ) => salt_source,
+            (None, _) => "none",
+        }
+    }
+
+    /// Passes following the first one, `--cascade` taking precedence over the config.
+    fn cascade<'a>(&'a self, source: &'a ParamsSource) -> &'a [argon2::Parameters] {
+        match source {
+            _ if !self.cascade.is_empty() => &self.cascade,
+            ParamsSource::Config { passes, .. } => passes,
+            ParamsSource::Cli => &[],
+        }
+    }
+
+    /// `--cascade` passes to store in the config, if any.
+    fn cascade_config(&self) -> Option<Vec<config::Pass>> {
+        (!self.cascade.is_empty()).then(|| self.cascade.iter().map(config::Pass::from).collect())
+    }
+
+    /// Unicode normalization to apply to the passphrase.
+    fn normalization(&self, passphrase_version: u32) -> &str {
+        match &self.normalize {
+            _ if self.no_normalize => "none",
+            Some(normalization) => normalization,
+            None if passphrase_version >= 2 => "nfc",
+            None => "none",
+        }
+    }
+
+    /// Prints the plan of `--dry-run`.
+    fn print_plan(
+        &self,
+        out: &mut dyn Write,
+        params: &argon2::Parameters,
+        source: &ParamsSource,
+        salt_len: usize,
+        output_len: u32,
+    ) -> io::Result<()> {
+        let encoding = match &self.command {
+            Commands::Secret(args) => args.encoding.as_str(),
+            _ => "-",
+        };
+
+        writeln!(out, "Source: {source}")?;
+        writeln!(out, "Algorithm: {}", params.algorithm)?;
+        writeln!(
+            out,
+            "Memory: {} ({} KiB)",
+            argon2::format_memory(params.memory),
+            params.memory
+        )?;
+        writeln!(out, "Time: {} (iterations)", params.time)?;
+        writeln!(out, "Parallelism: {} (lanes)", params.parallelism)?;
+        writeln!(out, "Salt length: {salt_len} bytes")?;
+        writeln!(out, "Salt source: {}", self.salt_source(source))?;
+        writeln!(out, "Final salt length: {} bytes", params.salt.len())?;
+        writeln!(out, "Output length: {output_len} bytes")?;
+        writeln!(out, "Encoding: {encoding}")?;
+        writeln!(out, "Argon2 version: {}", argon2::VERSION)?;
+        writeln!(out, "Generation: {}", self.generation)?;
+        writeln!(
+            out,
+            "Name: {}",
+            match (self.hash_name, self.hierarchical_names) {
+                (true, _) => "hashed (SHA-256, v1)",
+                (_, true) => "hierarchical",
+                _ => "raw",
+            }
+        )?;
+        for (idx, pass) in self.cascade(source).iter().enumerate() {
+            writeln!(
+                out,
+                "Cascade pass {}: {}, {}, {} (iterations), {} (lanes)",
+                idx + 2,
+                pass.algorithm,
+                argon2::format_memory(pass.memory),
+                pass.time,
+                pass.parallelism
+            )?;
+        }
+        writeln!(
+            out,
+            "Passphrase normalization: {}",
+            self.normalization(source.passphrase_version())
+        )
+    }
+
+    /// Warns about weak or likely mistaken parameters, see `--no-sanity`.
+    fn check_params(&self, params: &argon2::Parameters) {
+        if params.memory < argon2::MIN_RECOMMENDED_MEMORY {
+            term::warn(format!(
+                "Your memory cost ({} KiB) is dangerously low, consider using at least {} KiB!",
+                params.memory,
+                argon2::MIN_RECOMMENDED_MEMORY,
+            ));
+        }
+
+        if self.no_sanity {
+            return;
+        }
+
+        let cpus = thread::available_parallelism().map_or(1, |n| n.get() as u32);
+        for warning in sanity_warnings(params, cpus) {
+            term::warn(warning);
+        }
+    }
+
+    /// Checks the memory cost against the memory of the system, see `--force-memory`.
+    fn check_memory(&self, memory_cost: u32) -> anyhow::Result<()> {
+        self.check_memory_of(memory_cost, memory::query())
+    }
+
+    /// [`Cli::check_memory`] against the provided memory of the system, `None` if it's unknown.
+    fn check_memory_of(
+        &self,
+        memory_cost: u32,
+        system: Option<memory::Memory>,
+    ) -> anyhow::Result<()> {
+        let Some(system) = system else {
+            return Ok(());
+        };
+
+        let verdict = memory::check(memory_cost, system);
+        if verdict == memory::Verdict::Fits {
+            return Ok(());
+        }
+
+        let required = memory::format_bytes(u64::from(memory_cost) * 1024);
+        if verdict == memory::Verdict::ExceedsTotal {
+            term::warn(format!(
+                "Your memory cost ({required}) exceeds the physical RAM ({}), the derivation would swap, \
+                 being much slower and writing secret-derived data to disk!",
+                memory::format_bytes(system.total),
+            ));
+        }
+
+        let msg = format!(
+            "Your memory cost ({required}) exceeds the available memory ({})",
+            memory::format_bytes(system.available),
+        );
+        if !self.force_memory {
+            return Err(ErrorKind::Derivation.error(format!(
+                "{msg}! Free some memory, lower the memory cost or use --force-memory to derive anyway."
+            )));
+        }
+
+        term::warn(format!("{msg}, deriving anyway due to --force-memory"));
+        Ok(())
+    }
+
+    /// Whether printed secrets should be terminated with a newline, see `--newline`.
+    fn newline(&self, lines: bool) -> bool {
+        match (self.newline, self.no_newline) {
+            (true, _) => true,
+            (_, true) => false,
+            _ => lines || (self.output_fd.is_none() && io::stdout().is_terminal()),
+        }
+    }
+
+    /// Writes the output to the file at `path`, to `--output-fd` or to stdout.
+    fn write_output(
+        &self,
+        path: Option<&Path>,
+        f: impl FnOnce(&mut dyn Write) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let Some(fd) = self.output_fd else {
+            return output::write_to(path, f);
+        };
+
+        if path.is_some() {
+            return Err(ErrorKind::Usage.error("--output and --output-fd are mutually exclusive"));
+        }
+
+        let file = match self.output_file.get() {
+            Some(file) => file,
+            None => {
+                let file = take_fd(fd).context("--output-fd").kind(ErrorKind::Usage)?;
+                self.output_file.get_or_init(|| file)
+            }
+        };
+        let mut writer = BufWriter::new(file);
+        f(&mut writer)?;
+        writer.flush().kind(ErrorKind::Output)
+    }
+
+    /// Prints the secret, optionally terminated with a newline.
+    ///
+    /// Secrets ending with a newline consist of lines, the last newline is then printed according to `--newline`.
+    fn print_secret(&self, secret: &str) -> anyhow::Result<()> {
+        let (secret, lines) = match secret.strip_suffix('\n') {
+            Some(secret) => (secret, true),
+            None => (secret, false),
+        };
+        let terminator = if self.newline(lines) { "\n" } else { "" };
+        self.write_output(None, |out| {
+            write!(out, "{secret}{terminator}").kind(ErrorKind::Output)
+        })
+    }
+
+    /// Reads the passphrase to be used with the parameters of `source`,
+    /// checking it against the fingerprint bound to the config, if any.
+    fn read_source_passphrase(&self, source: &ParamsSource) -> anyhow::Result<Vec<u8>> {
+        let mut passphrase =
+            Zeroizing::new(self.read_passphrase(self.normalization(source.passphrase_version()))?);
+
+        if let ParamsSource::Config {
+            salt_source,
+            passes,
+            passphrase_fingerprint: Some((params, expected)),
+            ..
+        } = source
+            && !self.ignore_fingerprint
+        {
+            let actual =
+                passphrase_fingerprint(params, passes, salt_source.as_deref(), &passphrase)?;
+            if !bool::from(actual.ct_eq(expected)) {
+                return Err(ErrorKind::Passphrase.error(
+                    "The passphrase doesn't match the fingerprint bound to the config! \
+                     Check that you are using the right config, or use --ignore-fingerprint to proceed anyway.",
+                ));
+            }
+        }
+
+        Ok(std::mem::take(&mut *passphrase))
+    }
+
+    fn read_passphrase(&self, normalization: &str) -> anyhow::Result<Vec<u8>> {
+        if let Some(passphrase) = self.read_raw_passphrase()? {
+            if passphrase.is_empty() {
+                return Err(ErrorKind::Passphrase.error("Empty passphrase!"));
+            }
+            return Ok(passphrase);
+        }
+
+        let mut passphrase = String::new();
+
+        let stdin = io::stdin();
+        let prompt = stdin.is_terminal() || self.passphrase_tty || self.command.reads_stdin();
+        if let Some(fd) = self.passphrase_fd {
+            passphrase = read_line_from_fd(fd)
+                .context("read --passphrase-fd")
+                .kind(ErrorKind::Passphrase)?;
+            passphrase = self.trim_passphrase(passphrase);
+        } else if prompt && !self.stdin_passphrase && !self.passphrase_stdin_all {
+            // stdin may be occupied by the data, so the terminal is accessed directly.
+            let mut tty = open_tty()
+                .map(BufReader::new)
+                .context("No terminal available to prompt for the passphrase")
+                .kind(ErrorKind::Passphrase)?;
+
+            term::prompt("Enter passphrase:")?;
+
+            if self.expose_passphrase {
+                tty.read_line(&mut passphrase).kind(ErrorKind::Passphrase)?;
+                passphrase = self.trim_passphrase(passphrase);
+            } else {
+                passphrase = read_password().kind(ErrorKind::Passphrase)?;
+            }
+        } else if self.passphrase_stdin_all {
+            if self.command.reads_stdin() {
+                return Err(ErrorKind::Usage.error(
+                    "--passphrase-stdin-all can't be used while the data is read from stdin",
+                ));
+            }
+
+            stdin
+                .lock()
+                .read_to_string(&mut passphrase)
+                .kind(ErrorKind::Passphrase)?;
+            passphrase = self.trim_passphrase(passphrase);
+        } else {
+            stdin
+                .read_line(&mut passphrase)
+                .kind(ErrorKind::Passphrase)?;
+            passphrase = self.trim_passphrase(passphrase);
+
+            if !self.command.reads_stdin() && !stdin.is_terminal() && stdin_has_more(&stdin) {
+                term::warn(
+                    "stdin continues after the first line, which is the only one used as the passphrase! \
+                     Use --passphrase-stdin-all to read the whole stdin.",
+                );
+            }
+        }
+
+        if passphrase.is_empty() {
+            return Err(ErrorKind::Passphrase.error("Empty passphrase!"));
+        }
+
+        if self.ascii_only && !passphrase.is_ascii() {
+            return Err(ErrorKind::Passphrase.error(
+                "Passphrase contains non-ASCII characters, which may be encoded differently on other systems! \
+                 Consider --normalize instead of --ascii-only if you intend to use them.",
+            ));
+        }
+
+        Ok(match normalization {
+            "nfc" => passphrase.nfc().collect::<String>().into_bytes(),
+            "nfkc" => passphrase.nfkc().collect::<String>().into_bytes(),
+            _ => passphrase.into_bytes(),
+        })
+    }
+
+    /// Reads the passphrase of `--passphrase-raw-file` or `--passphrase-raw-stdin`, if any.
+    fn read_raw_passphrase(&self) -> anyhow::Result<Option<Vec<u8>>> {
+        // clap misses the conflicts of global args provided before and after the subcommand.
+        let raw = self.passphrase_raw_file.is_some() || self.passphrase_raw_stdin;
+        if raw && (self.ascii_only || self.normalize.is_some()) {
+            return Err(ErrorKind::Usage
+                .error("Raw passphrases are used as is, drop --ascii-only and --normalize"));
+        }
+
+        if let Some(path) = &self.passphrase_raw_file {
+            return fs::read(path)
+                .context("--passphrase-raw-file")
+                .kind(ErrorKind::Passphrase)
+                .map(Some);
+        }
+
+        if !self.passphrase_raw_stdin {
+            return Ok(None);
+        }
+
+        if self.command.reads_stdin() {
+            return Err(ErrorKind::Usage
+                .error("--passphrase-raw-stdin can't be used while the data is read from stdin"));
+        }
+
+        let mut passphrase = Vec::new();
+        io::stdin()
+            .lock()
+            .read_to_end(&mut passphrase)
+            .kind(ErrorKind::Passphrase)?;
+        Ok(Some(passphrase))
+    }
+
+    fn trim_passphrase(&self, mut passphrase: String) -> String {
+        match self.passphrase_trim.as_str() {
+            "newline" => {
+                if passphrase.ends_with('\n') {
+                    passphrase.pop();
+                    if passphrase.ends_with('\r') {
+                        passphrase.pop();
+                    }
+                }
+                passphrase
+            }
+            "all" => passphrase.trim().to_owned(),
+            _ => passphrase,
+        }
+    }
+
+    fn read_config(&self) -> anyhow::Result<Option<config::File>> {
+        let path = self.read_config_path()?;
+        let cfg = match &self.parameters_from {
+            Some(path) => config::File::import(path)
+                .context("config::File::import")
+                .kind(ErrorKind::Config)
+                .map(Some)?,
+            None => config::File::read(&path)
+                .context("config::File::read")
+                .kind(ErrorKind::Config)?,
+        };
+        if let Some(cfg) = &cfg {
+            term::header(format!("Using config ({path:?}):"));
+            cfg.eprint();
+
+            if config::File::is_exposed(&path).kind(ErrorKind::Config)? {
+                term::warn(
+                    "Your config file is readable by other users! Consider `chmod 600` on it.",
+                );
+            }
+        }
+
+        Ok(cfg)
+    }
+
+    /// Writes the config, backing up the existing one if `backup` is set.
+    fn write_config(&self, path: &PathBuf, cfg: &config::File, backup: bool) -> anyhow::Result<()> {
+        if backup && path.exists() {
+            let backup = config::backup(path)
+                .context("config::backup")
+                .kind(ErrorKind::Output)?;
+            term::info(format!("\nPrevious config backed up to {backup:?}"));
+        }
+
+        term::header(format!("Writing config ({path:?}):"));
+        cfg.eprint();
+
+        cfg.write(path)
+            .context("config::File::write")
+            .kind(ErrorKind::Output)?;
+
+        Ok(())
+    }
+
+    /// Path of the file the parameters are read from, `--parameters-from` taking precedence over the config.
+    fn read_config_path(&self) -> anyhow::Result<PathBuf> {
+        match &self.parameters_from {
+            Some(path) => Ok(path.clone()),
+            None => self.config_path(),
+        }
+    }
+
+    /// `--audit-log`, falling back to the `audit_log` of the config.
+    ///
+    /// The config is read silently, as the commands needing it report it on their own.
+    fn audit_log_path(&self) -> Option<PathBuf> {
+        self.audit_log.clone().or_else(|| {
+            let path = self.config_path().ok()?;
+            config::File::read(&path).ok().flatten()?.audit_log
+        })
+    }
+
+    fn config_path(&self) -> anyhow::Result<PathBuf> {
+        self.config
+            .clone()
+            .or_else(config::locate)
+            .context(
+                "Unable to figure out the default config location and neither --config nor $ARGON2DERIVE_CONFIG were provided",
+            )
+            .kind(ErrorKind::Config)
+    }
+}
+
+fn main() -> process::ExitCode {
+    let mut args: Vec<_> = std::env::args_os().collect();
+    // age invokes the plugin by its binary name, `age-plugin-argon2derive --age-plugin=identity-v1`.
+    let plugin = args.first().and_then(|arg0| Path::new(arg0).file_stem());
+    if plugin.is_some_and(|stem| stem == AGE_PLUGIN_BINARY) {
+        args.insert(1, "plugin".into());
+    }
+
+    let matches = Cli::command().get_matches_from(args);
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    cli.config_from_env =
+        matches.value_source("config") == Some(clap::parser::ValueSource::EnvVariable);
+    term::init(cli.no_color, cli.quiet, cli.no_warn);
+    interrupt::install();
+
+    let result = match run(&cli) {
+        // Nothing got derived, so there is nothing to write either.
+        Err(err) if PlanPrinted::is(&err) => Ok(()),
+        result => result.and_then(|()| write_checksums(&cli)),
+    };
+    let command = matches.subcommand_name().unwrap_or_default();
+    match write_audit_log(&cli, command, result) {
+        Ok(()) => process::ExitCode::SUCCESS,
+        Err(err) => {
+            let code = error::exit_code(&err);
+            match (cli.json_errors, cli.error_format.as_str()) {
+                (true, _) | (_, "json") => term::raw(error::to_json(&err)),
+                _ => term::error(format!("Error (code {code}): {err:?}")),
+            }
+            process::ExitCode::from(code)
+        }
+    }
+}
+
+fn run(cli: &Cli) -> anyhow::Result<()> {
+    match &cli.command {
+        Commands::Configure(args) => {
+            let old_path = cli.config_path()?;
+            let path = match &args.format {
+                Some(format) if config::format(&old_path) == format => old_path.clone(),
+                Some(format) if cli.config.is_some() => {
+                    return Err(ErrorKind::Usage.error(format!(
+                        "--format {format} doesn't match the extension of the config ({old_path:?})"
+                    )));
+                }
+                Some(format) => old_path.with_extension(format),
+                None => old_path.clone(),
+            };
+            let old = config::File::read(&old_path)
+                .context("config::File::read")
+                .kind(ErrorKind::Config)?;
+
+            let interactive = io::stderr().is_terminal();
+            if old.is_some() && !args.overwrite && !interactive {
+                return Err(ErrorKind::Usage.error(
+                    "Config file already exists! Use --overwrite if you want to overwrite the file.",
+                ));
+            }
+
+            let mut params = cli.required_params()?;
+            cli.apply_time_budget(&mut params)?;
+            params.validate()?;
+            cli.check_params(&params);
+
+            let mut cfg = config::File::from(params.clone());
+            cfg.salt_source = cli.salt_source.clone().filter(|source| source != "none");
+            cfg.require_salt = cli.require_salt;
+            cfg.passes = cli.cascade_config();
+            // Namespaces can only be defined by editing the config, so they are kept.
+            // So is the passphrase version, not to change the secrets of non-ASCII passphrases.
+            if let Some(old) = &old {
+                cfg.salts = old.salts.clone();
+                cfg.passphrase_version = old.passphrase_version;
+            }
+            // Kept unless replaced, so reconfiguring doesn't silently disable the audit.
+            cfg.audit_log = cli
+                .audit_log
+                .clone()
+                .or_else(|| old.as_ref()?.audit_log.clone());
+
+            if args.bind_passphrase {
+                let source = ParamsSource::Cli;
+                let passphrase = Zeroizing::new(
+                    cli.read_passphrase(cli.normalization(cfg.passphrase_version()))?,
+                );
+                let fingerprint = passphrase_fingerprint(
+                    &params,
+                    cli.cascade(&source),
+                    cfg.salt_source.as_deref(),
+                    &passphrase,
+                )?;
+                cfg.passphrase_fingerprint = Some(hex::encode(fingerprint));
+            }
+            if cfg.require_salt
+                && cfg.salt.is_none()
+                && cfg.salt_b64.is_none()
+                && cfg.salt_source.is_none()
+            {
+                return Err(ErrorKind::Usage.error("--require-salt needs --salt or --salt-source"));
+            }
+            if cfg.salt_source.is_some() {
+                warn_machine_bound();
+            }
+
+            if let Some(old) = old.filter(|_| !args.overwrite) {
+                term::header(format!("Config file already exists ({old_path:?}):"));
+                old.eprint_diff(&cfg);
+
+                if !confirm("Overwrite?")? {
+                    term::info("\nConfig file left intact");
+                    return Ok(());
+                }
+            }
+
+            cli.write_config(&path, &cfg, !args.no_backup)?;
+
+            if path != old_path && old_path.exists() {
+                let backup = config::backup(&old_path)
+                    .context("config::backup")
+                    .kind(ErrorKind::Output)?;
+                fs::remove_file(&old_path)
+                    .context("fs::remove_file")
+                    .kind(ErrorKind::Output)?;
+                term::info(format!("\nPrevious config moved to {backup:?}"));
+            }
+        }
+        Commands::Secret(args) => {
+            // Checked before the (costly) derivation.
+            if let Some(entry) = args.store_keyring.as_ref().filter(|_| !args.force)
+                && keyring::exists(entry).kind(ErrorKind::Output)?
+            {
+                return Err(ErrorKind::Usage.error(format!(
+                    "Keyring entry `{entry}` already exists! Use --force if you want to overwrite it."
+                )));
+            }
+
+            if !args.encrypt_to.is_empty() {
+                if args.format == "systemd-creds" {
+                    return Err(ErrorKind::Usage
+                        .error("--encrypt-to can't be combined with --format systemd-creds"));
+                }
+
+                let to_terminal =
+                    args.output.is_none() && cli.output_fd.is_none() && io::stdout().is_terminal();
+                if to_terminal && !args.armor {
+                    return Err(ErrorKind::Usage.error(
+                        "Refusing to print the binary ciphertext to the terminal, use --armor or --output",
+                    ));
+                }
+            }
+
+            // Checked before the derivation as well, so an invalid value isn't reported as a mismatch.
+            let expected = match (&args.expect, &args.expect_file) {
+                (Some(value), _) => Some(Zeroizing::new(value.clone())),
+                (None, Some(path)) => {
+                    let value = Zeroizing::new(
+                        fs::read_to_string(path)
+                            .context("fs::read_to_string")
+                            .kind(ErrorKind::Input)?,
+                    );
+                    Some(Zeroizing::new(
+                        value.trim_end_matches(['\n', '\r']).to_owned(),
+                    ))
+                }
+                (None, None) => None,
+            };
+            let expected = match expected {
+                Some(value)
+                    if args.template.is_none()
+                        && matches!(args.encoding.as_str(), "hex" | "base64" | "words") =>
+                {
+                    let bytes = Zeroizing::new(
+                        decode(&value, &args.encoding)
+                            .with_context(|| format!("--expect isn't valid {}", args.encoding))
+                            .kind(ErrorKind::Usage)?,
+                    );
+                    Some(Zeroizing::new(encode(&bytes, &args.encoding)?))
+                }
+                expected => expected,
+            };
+
+            let length = match args.bits {
+                Some(bits) if bits % 8 != 0 => {
+                    term::warn(format!(
+                        "{bits} bits isn't a whole number of bytes, \
+                         {} bytes are derived with the last {} bits zeroed",
+                        bits.div_ceil(8),
+                        8 - bits % 8,
+                    ));
+                    bits.div_ceil(8)
+                }
+                Some(bits) => bits / 8,
+                None => args.length,
+            };
+            let (offset, length) = match args.take {
+                Some(take) => {
+                    let offset = args.offset.unwrap_or(0);
+                    let total = offset.checked_add(take).ok_or_else(|| {
+                        ErrorKind::Usage
+                            .error("--offset + --take exceeds the maximum output length")
+                    })?;
+                    (offset as usize, total)
+                }
+                None => (0, length),
+            };
+
+            let encoded = match (&args.template, args.encoding.as_str()) {
+                (Some(template), _) => {
+                    template.render(&Zeroizing::new(cli.derive_secret(&args.name, 32)?))
+                }
+                (None, "words-password") => {
+                    let shape = password::WordsPassword {
+                        words: args.words,
+                        separator: &args.separator,
+                        capitalize: args.capitalize,
+                        suffix: args.suffix,
+                    };
+                    let seed = Zeroizing::new(cli.derive_secret(&args.name, 32)?);
+                    term::detail(format_args!(
+                        "Password entropy: {:.1} bits",
+                        shape.entropy()
+                    ));
+                    shape.render(&seed)
+                }
+                (None, encoding) => {
+                    let mut secret = Zeroizing::new(cli.derive_secret(&args.name, length)?);
+                    if let Some(bits) = args.bits {
+                        mask_bits(&mut secret, bits);
+                    }
+
+                    let ident = || {
+                        args.ident
+                            .clone()
+                            .unwrap_or_else(|| literal::ident(&args.name))
+                    };
+                    match encoding {
+                        "rust-array" => literal::rust_array(&ident(), &secret[offset..]),
+                        "c-array" => literal::c_array(&ident(), &secret[offset..]),
+                        "hexdump" => literal::hexdump(&secret[offset..]),
+                        _ => encode(&secret[offset..], encoding)?,
+                    }
+                }
+            };
+            if let Some(expected) = expected {
+                let derived = encoded.trim_end_matches(['\n', '\r']);
+                if !bool::from(derived.as_bytes().ct_eq(expected.as_bytes())) {
+                    return Err(ErrorKind::Mismatch
+                        .error("Mismatch: the derived secret differs from the expected value"));
+                }
+
+                term::info("Match: the derived secret equals the expected value");
+                return Ok(());
+            }
+
+            let encoded = match (args.format.as_str(), &args.user, &args.k8s_name) {
+                ("htpasswd", Some(user), _) => {
+                    htpasswd::line(user, &encoded, args.bcrypt_cost, args.deterministic_salt)
+                        .context("htpasswd::line")
+                        .kind(ErrorKind::Usage)?
+                }
+                ("k8s-secret", _, Some(k8s_name)) => k8s::secret_manifest(
+                    k8s_name,
+                    args.k8s_namespace.as_deref(),
+                    &[(k8s::key(&args.name), &encoded)],
+                    args.string_data,
+                )?
+                .to_string(),
+                _ => encoded,
+            };
+            let encrypt_credential = |name| {
+                systemd::encrypt(name, encoded.as_bytes())
+                    .context("systemd::encrypt")
+                    .kind(ErrorKind::Output)
+            };
+
+            if !args.encrypt_to.is_empty() {
+                let ciphertext =
+                    age_encrypt::encrypt(&args.encrypt_to, encoded.as_bytes(), args.armor)
+                        .context("age_encrypt::encrypt")
+                        .kind(ErrorKind::Output)?;
+                cli.write_output(args.output.as_deref(), |out| {
+                    out.write_all(&ciphertext).kind(ErrorKind::Output)
+                })?;
+                if let Some(path) = &args.output {
+                    term::info(format!("\nEncrypted secret written to {path:?}"));
+                }
+                return Ok(());
+            }
+
+            if let Some(entry) = &args.store_keyring {
+                keyring::store(entry, &encoded)
+                    .context("keyring::store")
+                    .kind(ErrorKind::Output)?;
+                term::info(format!("\nSecret stored in the keyring as `{entry}`"));
+                return Ok(());
+            }
+
+            if let Some(entry) = &args.pass_insert {
+                pass::insert(&args.pass_command, entry, &encoded)
+                    .context("pass::insert")
+                    .kind(ErrorKind::Output)?;
+                term::info(format!("\nSecret inserted into `{entry}`"));
+                return Ok(());
+            }
+
+            match (&args.dotenv, &args.credential) {
+                (Some(path), _) => {
+                    let key = dotenv::key(&args.name);
+                    dotenv::upsert(path, &key, &encoded)
+                        .context("dotenv::upsert")
+                        .kind(ErrorKind::Output)?;
+                    output::record(path);
+                    term::info(format!("\nSecret written to {path:?} as `{key}`"));
+                }
+                (None, Some(name)) => {
+                    let path = systemd::credential_path(name).kind(ErrorKind::Usage)?;
+                    let data = match args.format.as_str() {
+                        "systemd-creds" => encrypt_credential(name)?,
+                        _ => encoded.as_bytes().to_vec(),
+                    };
+                    output::write_to(Some(&path), |out| {
+                        out.write_all(&data).kind(ErrorKind::Output)
+                    })?;
+                    term::info(format!("\nCredential written to {path:?}"));
+                }
+                (None, None) if args.format == "systemd-creds" => {
+                    let data = encrypt_credential(&args.name)?;
+                    term::header("Encrypted credential:");
+                    cli.write_output(None, |out| out.write_all(&data).kind(ErrorKind::Output))?;
+                }
+                (None, None) => {
+                    term::header("Secret:");
+                    cli.print_secret(&encoded)?;
+                }
+            }
+        }
+        Commands::Age(args) => {
+            let variant = if args.bech32m {
+                age::Variant::Bech32m
+            } else {
+                age::Variant::Bech32
+            };
+            let private_key =
+                cli.derive_curve_key(&args.name, slip10::Curve::Curve25519, args.path.as_ref())?;
+            let mut identity = age::identity(*private_key, variant)?;
+            if let Some(comment) = &args.comment {
+                identity.insert_str(0, &format!("# comment: {comment}\n"));
+            }
+            term::header("Age Identity:");
+            cli.print_secret(&identity)?;
+        }
+        Commands::Plugin(args) => match (&args.age_plugin, &args.keygen) {
+            (Some(state_machine), _) => {
+                if state_machine != age_plugin::IDENTITY_V1 {
+                    return Err(ErrorKind::Usage.error(format!(
+                        "Unsupported age plugin state machine `{state_machine}`"
+                    )));
+                }
+
+                age_plugin::run_identity_v1(
+                    io::stdin().lock(),
+                    io::stdout().lock(),
+                    |identity, passphrase| {
+                        audit::record(&identity.name, 32, &identity.params, &identity.cascade);
+                        let key = Zeroizing::new(cli.hash(
+                            &identity.params,
+                            &identity.cascade,
+                            passphrase,
+                            32,
+                        )?);
+                        Ok(Zeroizing::new(key.as_slice().try_into().unwrap()))
+                    },
+                )
+                .context("age_plugin::run_identity_v1")
+                .kind(ErrorKind::Output)?;
+            }
+            (None, Some(name)) => {
+                if cli.plan_only() {
+                    return Err(ErrorKind::Usage
+                        .error("`plugin --keygen` doesn't support --dry-run nor --describe"));
+                }
+
+                let (base, source) = cli.prepare_params()?;
+                if cli.salt_source(&source) == "machine-id" {
+                    return Err(ErrorKind::Usage.error(
+                        "Plugin identities can't be bound to this machine, drop --salt-source machine-id",
+                    ));
+                }
+
+                let mut params = base.clone();
+                cli.append_name(&mut params, name, cli.generation)?;
+                let identity = age_plugin::Identity {
+                    name: name.clone(),
+                    normalization: cli.normalization(source.passphrase_version()).to_owned(),
+                    params,
+                    cascade: cli.cascade(&source).to_vec(),
+                }
+                .encode()?;
+
+                let passphrase = Zeroizing::new(cli.read_source_passphrase(&source)?);
+                let key = Zeroizing::new(cli.derive_named(
+                    &base,
+                    cli.cascade(&source),
+                    &passphrase,
+                    name,
+                    32,
+                )?);
+                let recipient =
+                    age::recipient(key.as_slice().try_into().unwrap(), age::Variant::Bech32)?;
+
+                term::header("age plugin identity:");
+                cli.print_secret(&format!(
+                    "# name: {}\n# recipient: {recipient}\n{identity}\n",
+                    name.escape_debug()
+                ))?;
+            }
+            (None, None) => unreachable!(),
+        },
+        Commands::Rsa(args) => {
+            term::warn(
+                "Derived RSA keys rely on a custom deterministic prime generation and are only as strong \
+                 as your passphrase, use them only if the consumer supports nothing else!",
+            );
+
+            let seed = Zeroizing::new(cli.derive_secret(&args.name, 32)?);
+            let key = rsa::derive(
+                seed.as_slice().try_into().unwrap(),
+                args.bits.parse().unwrap(),
+            )?;
+
+            // Public material only, so it isn't subject to `--quiet`.
+            if args.public || args.fingerprint {
+                let mut public = Vec::new();
+                if args.public {
+                    public.push(rsa::openssh_public_key(&key, args.comment.as_deref()));
+                }
+                if args.fingerprint {
+                    public.push(rsa::openssh_fingerprint(&key, args.comment.as_deref()));
+                }
+                return cli.print_secret(&lines(&public));
+            }
+
+            term::header("OpenSSH public key:");
+            term::info(rsa::openssh_public_key(&key, args.comment.as_deref()));
+
+            term::header("RSA private key:");
+            cli.print_secret(&rsa::to_pem(&key, &args.format)?)?;
+        }
+        Commands::TlsCert(args) => {
+            let seed =
+                cli.derive_curve_key(&args.name, slip10::Curve::Ed25519, args.path.as_ref())?;
+            let cert = tls::self_signed(
+                &seed,
+                args.subject.as_ref().unwrap_or(&args.name),
+                &args.dns,
+                args.not_before,
+                args.days,
+            )
+            .kind(ErrorKind::Usage)?;
+
+            term::header("Certificate SHA-256 fingerprint:");
+            term::info(&cert.fingerprint);
+
+            match &args.output {
+                Some(dir) => {
+                    fs::create_dir_all(dir)
+                        .context("fs::create_dir_all")
+                        .kind(ErrorKind::Output)?;
+                    for (file, pem) in [("cert.pem", &cert.cert_pem), ("key.pem", &*cert.key_pem)] {
+                        let path = dir.join(file);
+                        output::write_to(Some(&path), |out| {
+                            out.write_all(pem.as_bytes()).kind(ErrorKind::Output)
+                        })?;
+                        term::info(format!("\nWritten to {path:?}"));
+                    }
+                }
+                None => {
+                    term::header("Certificate and private key:");
+                    cli.write_output(None, |out| {
+                        out.write_all(cert.cert_pem.as_bytes())
+                            .and_then(|()| out.write_all(cert.key_pem.as_bytes()))
+                            .kind(ErrorKind::Output)
+                    })?;
+                }
+            }
+        }
+        Commands::GitCrypt(args) => {
+            let key_material =
+                Zeroizing::new(cli.derive_secret(&args.name, git_crypt::KEY_MATERIAL_LEN)?);
+            let key_file = git_crypt::key_file(&key_material, args.key_name.as_deref());
+
+            cli.write_output(args.output.as_deref(), |out| {
+                out.write_all(&key_file).kind(ErrorKind::Output)
+            })?;
+            if let Some(path) = &args.output {
+                term::info(format!("\nKey file written to {path:?}"));
+            }
+        }
+        Commands::Totp(args) => {
+            let key = Zeroizing::new(cli.derive_secret(&args.name, otp::key_len(&args.algo))?);
+
+            term::header("TOTP code:");
+            loop {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .context("SystemTime::duration_since")?
+                    .as_secs()
+                    .checked_add_signed(args.offset)
+                    .context("--offset is out of range")
+                    .kind(ErrorKind::Usage)?;
+                let (step, remaining) = otp::time_step(now, args.period);
+                let code = Zeroizing::new(otp::hotp(&key, step, args.digits, &args.algo));
+
+                if !args.watch {
+                    cli.print_secret(&code)?;
+                    term::detail(format_args!("Valid for {remaining}s"));
+                    break;
+                }
+
+                cli.write_output(None, |out| {
+                    writeln!(out, "{}", *code).kind(ErrorKind::Output)
+                })?;
+                for left in (1..=remaining).rev() {
+                    term::status(format_args!("Next code in {left}s")).kind(ErrorKind::Output)?;
+                    thread::sleep(Duration::from_secs(1));
+                }
+                term::status("").kind(ErrorKind::Output)?;
+            }
+        }
+        Commands::Hotp(args) => {
+            let key = Zeroizing::new(cli.derive_secret(&args.name, otp::key_len(&args.algo))?);
+            let code = Zeroizing::new(otp::hotp(&key, args.counter, args.digits, &args.algo));
+
+            term::header("HOTP code:");
+            cli.print_secret(&code)?;
+        }
+        Commands::Check(args) => {
+            let phc = match (&args.phc, &args.phc_file) {
+                (Some(phc), _) => phc.clone(),
+                (None, Some(path)) => fs::read_to_string(path)
+                    .context("fs::read_to_string")
+                    .kind(ErrorKind::Input)?,
+                (None, None) => unreachable!(),
+            };
+            let phc = phc::Hash::parse(phc.trim()).kind(ErrorKind::Usage)?;
+
+            cli.check_memory(phc.params.memory)?;
+
+            let normalization = cli.normalization(config::LEGACY_PASSPHRASE_VERSION);
+            let passphrase = cli.read_passphrase(normalization)?;
+
+            term::progress("\nDeriving...");
+
+            let hash = argon2::hash(&phc.params, &passphrase, phc.hash.len() as u32)
+                .kind(ErrorKind::Derivation)?;
+            if !bool::from(hash.ct_eq(&phc.hash)) {
+                return Err(ErrorKind::Mismatch.error("Passphrase doesn't match!"));
+            }
+
+            term::info("\nPassphrase matches");
+        }
+        Commands::Encrypt(args) => {
+            let mut input = open_input(args.input.as_deref())?;
+            let key: Zeroizing<[u8; 32]> =
+                Zeroizing::new(cli.derive_secret(&args.name, 32)?.try_into().unwrap());
+            let header = encryption::Header::new(args.name.clone());
+
+            term::progress("\nEncrypting...");
+            let _deferred = interrupt::Deferred::enter();
+            cli.write_output(args.output.as_deref(), |out| {
+                encryption::encrypt(&key, &header, &mut input, out)
+            })?;
+        }
+        Commands::Decrypt(args) => {
+            let mut input = open_input(args.input.as_deref())?;
+            let header = encryption::Header::read(&mut input).kind(ErrorKind::Input)?;
+            let name = args.name.as_ref().unwrap_or(&header.name);
+            let key: Zeroizing<[u8; 32]> =
+                Zeroizing::new(cli.derive_secret(name, 32)?.try_into().unwrap());
+
+            term::progress("\nDecrypting...");
+            let _deferred = interrupt::Deferred::enter();
+            cli.write_output(args.output.as_deref(), |out| {
+                encryption::decrypt(&key, &header, &mut input, out)
+            })?;
+        }
+        Commands::Split(args) => {
+            if args.threshold == 0 || args.threshold > args.shares {
+                return Err(ErrorKind::Usage.error("--threshold should be between 1 and --shares"));
+            }
+
+            let secret = Zeroizing::new(cli.derive_secret(&args.name, args.length)?);
+            let shares = shamir::split(&secret, args.shares, args.threshold)?;
+
+            term::header(format!(
+                "Shares ({} of {} required):",
+                args.threshold, args.shares
+            ));
+            cli.print_secret(&Zeroizing::new(lines(&shares)))?;
+        }
+        Commands::Combine(args) => {
+            let secret = Zeroizing::new(shamir::combine(&args.shares).kind(ErrorKind::Usage)?);
+            term::header("Secret:");
+            cli.print_secret(&encode(&secret, &args.encoding)?)?;
+        }
+        Commands::DecodeWords(args) => {
+            let words = match args.words.as_slice() {
+                [] => io::read_to_string(io::stdin()).kind(ErrorKind::Input)?,
+                words => words.join(" "),
+            };
+
+            let secret = Zeroizing::new(words::decode(&words).kind(ErrorKind::Mismatch)?);
+            term::header("Secret:");
+            cli.print_secret(&encode(&secret, &args.encoding)?)?;
+        }
+        Commands::RecoveryCodes(args) => {
+            let seed = Zeroizing::new(cli.derive_secret(&args.name, 32)?);
+            term::header("Recovery codes:");
+            let codes = password::recovery_codes(&seed, args.count, args.length);
+            cli.print_secret(&Zeroizing::new(lines(&codes)))?;
+        }
+        Commands::GeneratePassphrase(args) => {
+            let passphrase = Zeroizing::new(password::diceware(args.words).join(&args.separator));
+            term::header(format!(
+                "Passphrase ({:.1} bits of entropy):",
+                password::diceware_entropy(args.words)
+            ));
+            cli.print_secret(&passphrase)?;
+        }
+        Commands::Session(args) => {
+            if cli.plan_only() {
+                return Err(
+                    ErrorKind::Usage.error("`session` doesn't support --dry-run nor --describe")
+                );
+            }
+
+            let (params, source) = cli.prepare_params()?;
+            let cascade = cli.cascade(&source);
+            let passphrase = Zeroizing::new(cli.read_source_passphrase(&source)?);
+            let timeout = (args.timeout != 0).then(|| Duration::from_secs(args.timeout));
+
+            // Ends the session gracefully, wiping the passphrase.
+            let _deferred = interrupt::Deferred::enter();
+            let input = session::Input::stdin();
+            let interactive = io::stdin().is_terminal();
+            term::info("\nSession started, type `help` for the list of commands");
+
+            loop {
+                if interactive {
+                    term::prompt(">").kind(ErrorKind::Output)?;
+                }
+
+                let line = match input.next(timeout).kind(ErrorKind::Input)? {
+                    session::Line::Read(line) => line,
+                    session::Line::Eof => break,
+                    session::Line::Timeout => {
+                        term::info("\nSession timed out");
+                        break;
+                    }
+                    session::Line::Interrupted => return Err(interrupt::error()),
+                };
+
+                let result = match session::parse(&line) {
+                    Ok(None) => continue,
+                    Ok(Some(session::Command::Quit)) => break,
+                    Ok(Some(session::Command::Help)) => {
+                        term::info(session::HELP);
+                        continue;
+                    }
+                    Ok(Some(session::Command::Secret {
+                        name,
+                        length,
+                        encoding,
+                    })) => cli
+                        .derive_named(&params, cascade, &passphrase, &name, length)
+                        .and_then(|secret| encode(&Zeroizing::new(secret), encoding)),
+                    Ok(Some(session::Command::Age { name, bech32m })) => {
+                        let variant = if bech32m {
+                            age::Variant::Bech32m
+                        } else {
+                            age::Variant::Bech32
+                        };
+                        cli.derive_named(&params, cascade, &passphrase, &name, 32)
+                            .and_then(|key| age::identity(key.try_into().unwrap(), variant))
+                    }
+                    Err(err) => Err(anyhow::anyhow!(err)),
+                };
+
+                interrupt::check()?;
+                match result {
+                    Ok(output) => {
+                        let output = Zeroizing::new(output);
+                        cli.write_output(None, |out| {
+                            writeln!(out, "{}", output.trim_end()).kind(ErrorKind::Output)
+                        })?;
+                    }
+                    Err(err) => term::error(format!("Error: {err:#}")),
+                }
+            }
+        }
+        Commands::Multi(args) => {
+            if cli.plan_only() {
+                return Err(
+                    ErrorKind::Usage.error("`multi` doesn't support --dry-run nor --describe")
+                );
+            }
+
+            let (params, source) = cli.prepare_params()?;
+            let cascade = cli.cascade(&source);
+            let passphrase = Zeroizing::new(cli.read_source_passphrase(&source)?);
+
+            // (name, length, encoding, value)
+            let mut outputs = Vec::new();
+
+            if args.age {
+                let variant = if args.bech32m {
+                    age::Variant::Bech32m
+                } else {
+                    age::Variant::Bech32
+                };
+                let name = format!("{}:age", args.name);
+                let key = cli.derive_named(&params, cascade, &passphrase, &name, 32)?;
+                let key = key.try_into().unwrap();
+                let value = match args.format.as_str() {
+                    "plain" => age::identity(key, variant)?,
+                    _ => age::secret_key(key, variant)?,
+                };
+                outputs.push((name, 32, "age", Zeroizing::new(value)));
+            }
+
+            for spec in &args.secret {
+                let name = format!("{}:secret", args.name);
+                let secret = Zeroizing::new(cli.derive_named(
+                    &params,
+                    cascade,
+                    &passphrase,
+                    &name,
+                    spec.length,
+                )?);
+                let value = Zeroizing::new(encode(&secret, spec.encoding)?);
+                outputs.push((name, spec.length, spec.encoding, value));
+            }
+
+            if args.format == "plain" {
+                for (name, length, encoding, value) in &outputs {
+                    match *encoding {
+                        "age" => term::header(format!("Age Identity ({name}):")),
+                        _ => term::header(format!("Secret ({name}, {length} bytes, {encoding}):")),
+                    }
+                    cli.write_output(None, |out| {
+                        writeln!(out, "{}", value.trim_end()).kind(ErrorKind::Output)
+                    })?;
+                }
+                return Ok(());
+            }
+
+            let mut table = Zeroizing::new(String::new());
+            if !args.no_header {
+                table.push_str(&table::row(
+                    &["name", "length", "encoding", "value"],
+                    &args.format,
+                )?);
+            }
+            for (name, length, encoding, value) in &outputs {
+                let length = length.to_string();
+                let row = table::row(&[name, &length, encoding, value], &args.format)
+                    .kind(ErrorKind::Usage)?;
+                table.push_str(&Zeroizing::new(row));
+            }
+
+            cli.write_output(None, |out| {
+                out.write_all(table.as_bytes()).kind(ErrorKind::Output)
+            })?;
+        }
+        Commands::Rotate(args) => {
+            if cli.plan_only() {
+                return Err(
+                    ErrorKind::Usage.error("`rotate` doesn't support --dry-run nor --describe")
+                );
+            }
+
+            if cli.generation != 0 {
+                return Err(ErrorKind::Usage.error(
+                    "`rotate` takes --from-generation and --to-generation instead of --generation",
+                ));
+            }
+
+            let (from, to) = args.generations()?;
+
+            let (params, source) = cli.prepare_params()?;
+            let cascade = cli.cascade(&source);
+            let passphrase = Zeroizing::new(cli.read_source_passphrase(&source)?);
+
+            let length = if args.age { 32 } else { args.length };
+            let derive = |generation| {
+                cli.derive_generation(
+                    &params,
+                    cascade,
+                    &passphrase,
+                    &args.name,
+                    generation,
+                    length,
+                )
+                .map(Zeroizing::new)
+            };
+            let (old, new) = (derive(from)?, derive(to)?);
+
+            let variant = if args.bech32m {
+                age::Variant::Bech32m
+            } else {
+                age::Variant::Bech32
+            };
+            let render = |secret: &[u8]| match args.age {
+                true => age::identity(secret.try_into().unwrap(), variant),
+                false => encode(secret, &args.encoding),
+            };
+            let (old_value, new_value) =
+                (Zeroizing::new(render(&old)?), Zeroizing::new(render(&new)?));
+
+            if args.age {
+                term::header("Recipients of both keys (e.g. for `age -R`):");
+                term::info(format!(
+                    "{}\n{}",
+                    age::recipient(old.as_slice().try_into().unwrap(), variant)?,
+                    age::recipient(new.as_slice().try_into().unwrap(), variant)?,
+                ));
+            }
+
+            if args.format == "json" {
+                #[derive(serde::Serialize)]
+                struct Rotation<'a> {
+                    old: &'a str,
+                    new: &'a str,
+                }
+
+                let json = Zeroizing::new(
+                    serde_json::to_string(&Rotation {
+                        old: &old_value,
+                        new: &new_value,
+                    })
+                    .context("serde_json::to_string")?,
+                );
+                return cli.print_secret(&json);
+            }
+
+            // Labeled with comments, so both age identities form a valid identity file.
+            cli.write_output(None, |out| {
+                [("old", from, &old_value), ("new", to, &new_value)]
+                    .iter()
+                    .try_for_each(|(label, generation, value)| {
+                        writeln!(
+                            out,
+                            "# {label} (generation {generation})\n{}",
+                            value.trim_end()
+                        )
+                    })
+                    .kind(ErrorKind::Output)
+            })?;
+        }
+        Commands::Whoami(args) => {
+            let seed: Zeroizing<[u8; 32]> =
+                Zeroizing::new(cli.derive_secret(&args.name, 32)?.try_into().unwrap());
+
+            let mut keys = vec![
+                ("age", age::recipient(*seed, age::Variant::Bech32)?),
+                ("tls-cert (Ed25519)", hex::encode(tls::public_key(&seed)?)),
+            ];
+            if let Some(bits) = &args.rsa_bits {
+                let key = rsa::derive(*seed, bits.parse().unwrap())?;
+                keys.push(("rsa (OpenSSH)", rsa::openssh_public_key(&key, None)));
+            }
+
+            term::header("Public keys:");
+            cli.write_output(None, |out| {
+                keys.iter()
+                    .try_for_each(|(format, key)| writeln!(out, "{format}: {key}"))
+                    .kind(ErrorKind::Output)
+            })?;
+        }
+        Commands::Keyring(args) => match &args.command {
+            KeyringCommand::Delete(args) => {
+                let deleted = keyring::delete(&args.entry)
+                    .context("keyring::delete")
+                    .kind(ErrorKind::Output)?;
+                if !deleted {
+                    return Err(ErrorKind::Usage.error(format!(
+                        "No keyring entry `{}` created by argon2derive",
+                        args.entry
+                    )));
+                }
+
+                term::info(format!("Keyring entry `{}` deleted", args.entry));
+            }
+        },
+        Commands::ConfigSchema => {
+            let schema = serde_json::to_string_pretty(&config::schema())
+                .context("serde_json::to_string_pretty")?;
+            writeln!(io::stdout(), "{schema}").kind(ErrorKind::Output)?;
+        }
+        Commands::Completions(args) => {
+            clap_complete::generate(args.shell, &mut Cli::command(), APP_NAME, &mut io::stdout());
+        }
+        #[cfg(feature = "tui")]
+        Commands::Tune => {
+            if cli.time_budget.is_some() {
+                return Err(ErrorKind::Usage
+                    .error("`tune` measures the time cost interactively, drop --time-budget"));
+            }
+
+            let mut salt_source = cli.salt_source.clone();
+            let mut require_salt = cli.require_salt;
+            let mut passes = None;
+            let mut salts = Default::default();
+            let mut audit_log = cli.audit_log.clone();
+            let mut passphrase_version = Some(config::PASSPHRASE_VERSION);
+            let base = match cli.read_config()? {
+                Some(mut cfg) => {
+                    audit_log = audit_log.or_else(|| cfg.audit_log.take());
+                    passphrase_version = cfg.passphrase_version;
+                    salt_source = salt_source.or_else(|| cfg.salt_source.clone());
+                    require_salt |= cfg.require_salt;
+                    salts = std::mem::take(&mut cfg.salts);
+                    passes = cfg.passes.take();
+                    cfg.try_into().kind(ErrorKind::Config)?
+                }
+                None => tune::default_params(),
+            };
+            let params = cli.merge_params(Some(base))?;
+
+            if let Some(params) = tune::run(params)? {
+                params.validate()?;
+                cli.check_params(&params);
+
+                let mut cfg = config::File::from(params);
+                cfg.salt_source = salt_source.filter(|source| source != "none");
+                cfg.require_salt = require_salt;
+                cfg.passes = cli.cascade_config().or(passes);
+                cfg.salts = salts;
+                cfg.audit_log = audit_log;
+                cfg.passphrase_version = passphrase_version;
+                cli.write_config(&cli.config_path()?, &cfg, true)?;
+            }
+        }
+        Commands::Man(args) => match &args.dir {
+            Some(dir) => clap_mangen::generate_to(Cli::command(), dir).kind(ErrorKind::Output)?,
+            None => clap_mangen::Man::new(Cli::command())
+                .render(&mut io::stdout())
+                .kind(ErrorKind::Output)?,
+        },
+        Commands::Audit(args) => match &args.command {
+            AuditCommand::Show(args) => {
+                let path = cli
+                    .audit_log_path()
+                    .context("No audit log, provide --audit-log or set `audit_log` in the config")
+                    .kind(ErrorKind::Usage)?;
+                let entries = audit::read(&path)
+                    .with_context(|| format!("{path:?}"))
+                    .kind(ErrorKind::Input)?;
+
+                let entries: Vec<_> = entries
+                    .iter()
+                    .filter(|e| args.name.as_ref().is_none_or(|name| &e.name == name))
+                    .filter(|e| args.command.as_ref().is_none_or(|cmd| &e.command == cmd))
+                    .filter(|e| args.since.as_ref().is_none_or(|since| &e.time >= since))
+                    .filter(|e| !args.failed || e.status != 0)
+                    .collect();
+
+                let out = match args.format.as_str() {
+                    "json" => audit::lines(&entries).context("audit::lines")?,
+                    _ => audit::table(&entries),
+                };
+                write!(io::stdout(), "{out}").kind(ErrorKind::Output)?;
+            }
+        },
+        Commands::VerifyFiles => {
+            let Some(list) = &cli.checksum_file else {
+                return Err(ErrorKind::Usage.error("`verify-files` requires --checksum-file"));
+            };
+
+            if !check_files(list)? {
+                return Err(ErrorKind::Mismatch.error("Some files failed the verification"));
+            }
+        }
+        Commands::Hmac(args) => {
+            let key = Zeroizing::new(cli.derive_secret(&args.name, 32)?);
+            let key = key.as_slice().try_into().unwrap();
+
+            if let Some(list) = &args.check {
+                term::info("");
+                if !check_macs(args.algo, key, list)? {
+                    return Err(ErrorKind::Mismatch.error("Some MACs failed the verification"));
+                }
+                return Ok(());
+            }
+
+            let files = match args.files.as_slice() {
+                [] => vec![None],
+                files => files.iter().map(|f| Some(f.as_path())).collect(),
+            };
+
+            term::header(format!("MACs ({}):", args.algo));
+            for path in files {
+                let mac = mac::compute(args.algo, key, &mut open_input(path)?)
+                    .context("mac::compute")
+                    .kind(ErrorKind::Input)?;
+                let filename = path.map_or("-".into(), Path::to_string_lossy);
+                cli.write_output(None, |out| {
+                    writeln!(out, "{}  {filename}", hex::encode(mac)).kind(ErrorKind::Output)
+                })?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds the files written by the command to `--checksum-file`.
+fn write_checksums(cli: &Cli) -> anyhow::Result<()> {
+    let Some(path) = &cli.checksum_file else {
+        return Ok(());
+    };
+
+    if matches!(cli.command, Commands::VerifyFiles) {
+        return Ok(());
+    }
+
+    let files = output::written();
+    if files.is_empty() {
+        term::warn("No files were written, the checksum file is left untouched");
+        return Ok(());
+    }
+
+    checksum::update(path, &files)?;
+    term::info(format!("\nChecksums written to {path:?}"));
+    Ok(())
+}
+
+/// Appends the derivations of the command to `--audit-log` (or the `audit_log` of the config).
+///
+/// A failure to do so fails a successful command, so the derivations can't go unnoticed.
+fn write_audit_log(cli: &Cli, command: &str, result: anyhow::Result<()>) -> anyhow::Result<()> {
+    if audit::is_empty() {
+        return result;
+    }
+
+    let Some(path) = cli.audit_log_path() else {
+        return result;
+    };
+
+    let status = match &result {
+        Ok(()) => 0,
+        Err(err) => error::exit_code(err),
+    };
+    let appended = audit::append(&path, command, status)
+        .context("audit::append")
+        .with_context(|| format!("Failed to append to the audit log ({path:?})"))
+        .kind(ErrorKind::Output);
+
+    match (result, appended) {
+        (Ok(()), appended) => appended,
+        (Err(err), Ok(())) => Err(err),
+        (Err(err), Err(audit_err)) => {
+            term::warn(format!("{audit_err:#}"));
+            Err(err)
+        }
+    }
+}
+
+fn warn_machine_bound() {
+    term::warn(
+        "The salt is bound to this machine, the secrets can't be derived on any other one!\n\
+         Keep them recoverable, e.g. back them up or wrap something you can derive elsewhere.",
+    );
+}
+
+/// Where a resolved parameter came from, see `--describe`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum Origin {
+    Cli,
+    Env,
+    Config,
+    TimeBudget,
+    Namespace,
+    Default,
+}
+
+/// Origins of the resolved parameters, see [`Cli::origins`].
+struct Origins {
+    /// `None` if no config was used.
+    config: Option<Origin>,
+    algorithm: Origin,
+    memory: Origin,
+    time: Origin,
+    parallelism: Origin,
+    salt: Origin,
+    salt_source: Origin,
+    cascade: Origin,
+    normalization: Origin,
+}
+
+/// Where the Argon2 parameters came from.
+enum ParamsSource {
+    Cli,
+    Config {
+        path: PathBuf,
+        passphrase_version: u32,
+        salt_source: Option<String>,
+        require_salt: bool,
+        passes: Vec<argon2::Parameters>,
+        /// Fingerprint bound to the passphrase, along with the parameters of the config it was computed with.
+        passphrase_fingerprint: Option<(argon2::Parameters, [u8; 2])>,
+        /// Whether some of the parameters were overridden via the CLI.
+        overridden: bool,
+    },
+}
+
+impl ParamsSource {
+    fn passphrase_version(&self) -> u32 {
+        match self {
+            Self::Cli => config::LEGACY_PASSPHRASE_VERSION,
+            Self::Config {
+                passphrase_version, ..
+            } => *passphrase_version,
+        }
+    }
+
+    fn require_salt(&self) -> bool {
+        matches!(
+            self,
+            Self::Config {
+                require_salt: true,
+                ..
+            }
+        )
+    }
+}
+
+impl fmt::Display for ParamsSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cli => f.write_str("command line"),
+            Self::Config {
+                path,
+                overridden: true,
+                ..
+            } => write!(f, "config ({path:?}), overridden by the command line"),
+            Self::Config { path, .. } => write!(f, "config ({path:?})"),
+        }
+    }
+}
+
+/// Warnings about likely mistaken parameters on a machine with `cpus` CPUs, see `--no-sanity`.
+fn sanity_warnings(params: &argon2::Parameters, cpus: u32) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    if params.memory / 1024 < params.time.saturating_mul(MIN_MIB_PER_ITERATION) {
+        warnings.push(format!(
+            "Your memory cost ({}) is unusually low for your time cost ({} iterations), \
+             did you swap --memory and --time?",
+            argon2::format_memory(params.memory),
+            params.time,
+        ));
+    }
+
+    // The lanes can't be capped, as they are part of the derived secrets,
+    // and `argon2_kdf` computes each lane by its own thread.
+    if params.parallelism > cpus.saturating_mul(MAX_THREADS_PER_CPU) {
+        warnings.push(format!(
+            "Your parallelism ({} lanes) greatly exceeds the number of CPUs ({cpus}), \
+             the derivation will be slower without being any stronger. \
+             The lanes affect the derived secrets, so decide on the value before deriving any!",
+            params.parallelism,
+        ));
+    } else if params.parallelism > cpus {
+        warnings.push(format!(
+            "Your parallelism ({} lanes) exceeds the number of CPUs ({cpus}), \
+             so the derivation is slower than on a machine with as many CPUs. \
+             The lanes affect the derived secrets, keep the value they were derived with!",
+            params.parallelism,
+        ));
+    }
+
+    warnings
+}
+
+/// Joins the values into newline-terminated lines.
+fn lines(values: &[String]) -> String {
+    values.iter().map(|value| format!("{value}\n")).collect()
+}
+
+/// Rejects comments that would break the line-based key formats.
+fn parse_comment(s: &str) -> anyhow::Result<String> {
+    if s.contains(['\n', '\r']) {
+        return Err(anyhow::anyhow!("Comment can't span multiple lines"));
+    }
+
+    Ok(s.to_owned())
+}
+
+/// Validates an `audit show --since` value, a prefix of an RFC 3339 UTC time (at least the date).
+fn parse_since(s: &str) -> anyhow::Result<String> {
+    const TEMPLATE: &[u8] = b"0000-00-00T00:00:00";
+
+    let valid = s.len() >= 10
+        && s.len() <= TEMPLATE.len()
+        && s.bytes().zip(TEMPLATE).all(|(b, &t)| match t {
+            b'0' => b.is_ascii_digit(),
+            _ => b == t,
+        });
+
+    match valid {
+        true => Ok(s.to_owned()),
+        false => Err(anyhow::anyhow!(
+            "Expected a UTC date or time, e.g. `2025-01-31` or `2025-01-31T12:00`"
+        )),
+    }
+}
+
+/// Parses a `multi --secret` output: `<LENGTH>:<ENCODING>`.
+fn parse_secret_spec(s: &str) -> anyhow::Result<SecretSpec> {
+    let Some((length, encoding)) = s.split_once(':') else {
+        return Err(anyhow::anyhow!("Expected <LENGTH>:<ENCODING>, e.g. 32:hex"));
+    };
+
+    Ok(SecretSpec {
+        length: length.parse().context("Invalid length")?,
+        encoding: ["hex", "base64", "words"]
+            .into_iter()
+            .find(|e| *e == encoding)
+            .context("Invalid encoding, expected one of hex, base64 or words")?,
+    })
+}
+
+/// Parses a `--cascade` pass: `<ALGORITHM>:<MEMORY (GiB)>:<TIME>:<PARALLELISM>`.
+fn parse_pass(s: &str) -> anyhow::Result<argon2::Parameters> {
+    let [algorithm, memory, time, parallelism] = s.split(':').collect::<Vec<_>>()[..] else {
+        return Err(anyhow::anyhow!(
+            "Expected <ALGORITHM>:<MEMORY>:<TIME>:<PARALLELISM>, e.g. argon2d:1:10:4"
+        ));
+    };
+
+    let memory: u32 = memory.parse().context("Invalid memory")?;
+    Ok(argon2::Parameters {
+        algorithm: algorithm.parse()?,
+        memory: memory
+            .checked_mul(1024 * 1024)
+            .context("Memory cost is too high")?,
+        time: time.parse().context("Invalid time")?,
+        parallelism: parallelism.parse().context("Invalid parallelism")?,
+        salt: Vec::new(),
+    })
+}
+
+/// Splits a name into its hierarchy levels at `/`, see `--hierarchical-names`.
+fn parse_name_path(name: &str) -> anyhow::Result<Vec<String>> {
+    let mut levels = vec![String::new()];
+    let mut chars = name.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '/' => levels.push(String::new()),
+            '\\' => match chars.next() {
+                Some(c @ ('/' | '\\')) => levels.last_mut().unwrap().push(c),
+                _ => return Err(anyhow::anyhow!("Only `/` and `\\` can be escaped in names")),
+            },
+            c => levels.last_mut().unwrap().push(c),
+        }
+    }
+
+    if levels.iter().any(String::is_empty) {
+        return Err(anyhow::anyhow!("Name `{name}` contains an empty level"));
+    }
+
+    Ok(levels)
+}
+
+/// Zeroes the bits of `bytes` past the first `bits`, which only ever affects the last byte.
+fn mask_bits(bytes: &mut [u8], bits: u32) {
+    let excess = (bytes.len() * 8).saturating_sub(bits as usize);
+    if let Some(last) = bytes.last_mut()
+        && (1..8).contains(&excess)
+    {
+        *last &= 0xff << excess;
+    }
+}
+
+fn encode(bytes: &[u8], encoding: &str) -> anyhow::Result<String> {
+    Ok(match encoding {
+        "hex" => hex::encode(bytes),
+        "base64" => general_purpose::STANDARD.encode(bytes),
+        "words" => words::encode(bytes)?,
+        _ => unreachable!(),
+    })
+}
+
+/// Inverse of [`encode`].
+fn decode(str: &str, encoding: &str) -> anyhow::Result<Vec<u8>> {
+    match encoding {
+        "hex" => hex::decode(str.trim()).context("invalid hex"),
+        "base64" => general_purpose::STANDARD
+            .decode(str.trim())
+            .context("invalid base64"),
+        "words" => words::decode(str),
+        _ => unreachable!(),
+    }
+}
+
+fn check_macs(algo: mac::Algorithm, key: &[u8; 32], list: &Path) -> anyhow::Result<bool> {
+    let list = fs::read_to_string(list)
+        .context("fs::read_to_string")
+        .kind(ErrorKind::Input)?;
+
+    let mut ok = true;
+    for (idx, line) in list.lines().enumerate().filter(|(_, l)| !l.is_empty()) {
+        let (expected, filename) = match mac::parse_line(line) {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                term::error(format!("line {}: {err:#}", idx + 1));
+                ok = false;
+                continue;
+            }
+        };
+
+        let path = (filename != "-").then(|| Path::new(filename));
+        let result = open_input(path).and_then(|mut input| mac::compute(algo, key, &mut input));
+
+        match result {
+            Ok(mac) if bool::from(mac.ct_eq(&expected)) => term::info(format!("{filename}: OK")),
+            Ok(_) => {
+                term::error(format!("{filename}: FAILED"));
+                ok = false;
+            }
+            Err(err) => {
+                term::error(format!("{filename}: FAILED ({err:#})"));
+                ok = false;
+            }
+        }
+    }
+
+    Ok(ok)
+}
+
+fn check_files(list: &Path) -> anyhow::Result<bool> {
+    let list = fs::read_to_string(list)
+        .context("fs::read_to_string")
+        .kind(ErrorKind::Input)?;
+
+    let mut ok = true;
+    for (idx, line) in list.lines().enumerate().filter(|(_, l)| !l.is_empty()) {
+        let (expected, filename) = match checksum::parse_line(line) {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                term::error(format!("line {}: {err:#}", idx + 1));
+                ok = false;
+                continue;
+            }
+        };
+
+        match checksum::sha256_file(Path::new(filename)) {
+            Ok(hash) if hash.eq_ignore_ascii_case(expected) => {
+                term::info(format!("{filename}: OK"))
+            }
+            Ok(_) => {
+                term::error(format!("{filename}: FAILED"));
+                ok = false;
+            }
+            Err(err) => {
+                term::error(format!("{filename}: FAILED ({err})"));
+                ok = false;
+            }
+        }
+    }
+
+    Ok(ok)
+}
+
+fn open_input(path: Option<&Path>) -> anyhow::Result<Box<dyn Read>> {
+    if let Some(path) = path {
+        let file = File::open(path)
+            .context("File::open")
+            .kind(ErrorKind::Input)?;
+        return Ok(Box::new(BufReader::new(file)));
+    }
+
+    // Not locked, as the passphrase may be read from stdin afterwards.
+    Ok(Box::new(io::stdin()))
+}
+
+/// Asks a yes/no question on the terminal, defaulting to no.
+fn confirm(question: &str) -> anyhow::Result<bool> {
+    let mut tty = open_tty()
+        .map(BufReader::new)
+        .context("No terminal available to ask for the confirmation")
+        .kind(ErrorKind::Usage)?;
+
+    term::prompt(format!("{question} [y/N]"))?;
+
+    let mut answer = String::new();
+    tty.read_line(&mut answer).kind(ErrorKind::Usage)?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Takes the ownership of an inherited file descriptor, which gets closed once the file is dropped.
+#[cfg(unix)]
+fn take_fd(fd: i32) -> anyhow::Result<File> {
+    use std::os::fd::FromRawFd as _;
+
+    if (0..=2).contains(&fd) {
+        return Err(anyhow::anyhow!("fd {fd} is a standard stream"));
+    }
+
+    // Makes sure the descriptor is open before taking the ownership of it.
+    fs::metadata(format!("/dev/fd/{fd}")).with_context(|| format!("invalid fd {fd}"))?;
+
+    // SAFETY: The descriptor is open and it's meant to be consumed by us, nothing else in the process uses it.
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+#[cfg(not(unix))]
+fn take_fd(_fd: i32) -> anyhow::Result<File> {
+    Err(anyhow::anyhow!(
+        "file descriptors are only supported on Unix"
+    ))
+}
+
+/// Reads a line from the inherited file descriptor, closing it afterwards.
+fn read_line_from_fd(fd: i32) -> anyhow::Result<String> {
+    let mut line = String::new();
+    BufReader::new(take_fd(fd)?).read_line(&mut line)?;
+    Ok(line)
+}
+
+/// Fingerprint of the passphrase binding a config to it, see `configure --bind-passphrase`.
+///
+/// The first 16 bits of a derivation using the parameters of the config, with [`PASSPHRASE_FINGERPRINT_CONTEXT`]
+/// following the salt (no name of a secret contains `0x00`). So checking a guess costs as much as
+/// with any derived secret, while 2^-16 of the wrong passphrases pass the check unnoticed.
+fn passphrase_fingerprint(
+    params: &argon2::Parameters,
+    cascade: &[argon2::Parameters],
+    salt_source: Option<&str>,
+    passphrase: &[u8],
+) -> anyhow::Result<[u8; 2]> {
+    let mut params = params.clone();
+    if salt_source == Some("machine-id") {
+        params.salt = machine::bind_salt(&params.salt).context("machine::bind_salt")?;
+    }
+    params
+        .salt
+        .extend_from_slice(PASSPHRASE_FINGERPRINT_CONTEXT);
+
+    term::progress("\nComputing the passphrase fingerprint...");
+    let output = argon2::hash_cascade(&params, cascade, passphrase, argon2::MIN_OUTPUT_LEN)
+        .kind(ErrorKind::Derivation)?;
+    Ok([output[0], output[1]])
+}
+
+/// Checks whether stdin has more data without waiting for it, e.g. the lines following the passphrase.
+#[cfg(unix)]
+fn stdin_has_more(stdin: &io::Stdin) -> bool {
+    use std::os::fd::AsRawFd as _;
+
+    let fd = stdin.as_raw_fd();
+    // SAFETY: Only the status flags of stdin are changed, they are restored right after the read.
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    if flags < 0 || unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) } < 0 {
+        return false;
+    }
+
+    // The data may be buffered already, otherwise the read fails with `WouldBlock` instead of waiting.
+    let more = stdin.lock().fill_buf().is_ok_and(|buf| !buf.is_empty());
+
+    // SAFETY: See above.
+    unsafe { libc::fcntl(fd, libc::F_SETFL, flags) };
+    more
+}
+
+#[cfg(not(unix))]
+fn stdin_has_more(_stdin: &io::Stdin) -> bool {
+    false
+}
+
+/// Opens the terminal of the process, regardless of stdin being redirected.
+fn open_tty() -> io::Result<File> {
+    #[cfg(windows)]
+    const TTY: &str = "CONIN$";
+    #[cfg(not(windows))]
+    const TTY: &str = "/dev/tty";
+
+    File::open(TTY)
+}
+
+impl TryFrom<config::File> for argon2::Parameters {
+    type Error = anyhow::Error;
 
     fn try_from(cfg: config::File) -> anyhow::Result<Self> {
+        if cfg.passphrase_version() > config::PASSPHRASE_VERSION {
+            return Err(anyhow::anyhow!(
+                "Unsupported passphrase_version: {}, please update {APP_NAME}",
+                cfg.passphrase_version()
+            ));
+        }
+
+        match cfg.salt_source.as_deref() {
+            None | Some("machine-id") => {}
+            Some(salt_source) => {
+                return Err(anyhow::anyhow!("Unsupported salt_source: {salt_source}"));
+            }
+        }
+
         Ok(Self {
             algorithm: cfg.algorithm.parse()?,
             memory: cfg.memory,
             time: cfg.time,
             parallelism: cfg.parallelism,
-            salt: cfg.salt.map(|s| s.into_bytes()).unwrap_or_default(),
+            salt: cfg.salt()?,
         })
     }
 }
 
 impl From<argon2::Parameters> for config::File {
     fn from(params: argon2::Parameters) -> Self {
+        let mut cfg = Self {
+            algorithm: params.algorithm.to_string(),
+            memory: params.memory,
+            time: params.time,
+            parallelism: params.parallelism,
+            salt: None,
+            salt_b64: None,
+            salt_source: None,
+            require_salt: false,
+            passphrase_version: Some(config::PASSPHRASE_VERSION),
+            fingerprint: Some(params.fingerprint()),
+            passphrase_fingerprint: None,
+            passes: None,
+            salts: Default::default(),
+            audit_log: None,
+        };
+        cfg.set_salt(params.salt);
+        cfg
+    }
+}
+
+impl TryFrom<&config::Pass> for argon2::Parameters {
+    type Error = anyhow::Error;
+
+    fn try_from(pass: &config::Pass) -> anyhow::Result<Self> {
+        Ok(Self {
+            algorithm: pass.algorithm.parse()?,
+            memory: pass.memory,
+            time: pass.time,
+            parallelism: pass.parallelism,
+            salt: Vec::new(),
+        })
+    }
+}
+
+impl From<&argon2::Parameters> for config::Pass {
+    fn from(params: &argon2::Parameters) -> Self {
         Self {
             algorithm: params.algorithm.to_string(),
             memory: params.memory,
             time: params.time,
             parallelism: params.parallelism,
-            salt: Some(String::from_utf8(params.salt).unwrap()).filter(|s| !s.is_empty()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cli(args: &[&str]) -> Cli {
+        Cli::try_parse_from([APP_NAME].iter().chain(args)).unwrap()
+    }
+
+    #[test]
+    fn missing_params() {
+        let required = |args: &[&str]| cli(args).required_params().map(|_| ());
+
+        assert_eq!(
+            required(&["-m", "1", "-t", "1", "-p", "1", "secret", "x"]),
+            Ok(())
+        );
+        assert_eq!(
+            required(&["-t", "1", "-p", "1", "secret", "x"]),
+            Err(argon2::ParamError::MissingMemory)
+        );
+        assert_eq!(
+            required(&["-m", "1", "-p", "1", "secret", "x"]),
+            Err(argon2::ParamError::MissingTime)
+        );
+        assert_eq!(
+            required(&["-m", "1", "-t", "1", "secret", "x"]),
+            Err(argon2::ParamError::MissingParallelism)
+        );
+    }
+
+    #[test]
+    fn missing_params_are_taken_from_the_base() {
+        let base = argon2::Parameters {
+            algorithm: argon2::Algorithm::Argon2d,
+            memory: 64,
+            time: 2,
+            parallelism: 3,
+            salt: b"base salt".to_vec(),
+        };
+
+        let params = cli(&["-t", "5", "secret", "x"])
+            .merge_params(Some(base))
+            .unwrap();
+        assert!(matches!(params.algorithm, argon2::Algorithm::Argon2d));
+        assert_eq!((params.memory, params.time, params.parallelism), (64, 5, 3));
+        assert_eq!(params.salt, b"base salt");
+    }
+
+    #[test]
+    fn passphrase_stdin_all_conflicts() {
+        let parse = |args: &[&str]| Cli::try_parse_from([APP_NAME].iter().chain(args));
+
+        assert!(cli(&["--passphrase-stdin-all", "secret", "x"]).passphrase_stdin_all);
+        assert!(
+            parse(&[
+                "--passphrase-stdin-all",
+                "--stdin-passphrase",
+                "secret",
+                "x"
+            ])
+            .is_ok()
+        );
+        assert!(parse(&["--passphrase-stdin-all", "--passphrase-tty", "secret", "x"]).is_err());
+        assert!(
+            parse(&[
+                "--passphrase-stdin-all",
+                "--passphrase-fd",
+                "3",
+                "secret",
+                "x"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn raw_passphrase_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("passphrase.bin");
+        fs::write(&path, b"\0\xff\n").unwrap();
+        let path = path.to_str().unwrap();
+
+        let read = |args: &[&str]| {
+            cli(&[&["secret", "x"], args].concat())
+                .read_raw_passphrase()
+                .map_err(|err| ErrorKind::of(&err))
+        };
+        assert_eq!(read(&[]), Ok(None));
+        assert_eq!(
+            read(&["--passphrase-raw-file", path]),
+            Ok(Some(b"\0\xff\n".to_vec()))
+        );
+        assert_eq!(
+            read(&["--passphrase-raw-file", "missing.bin"]),
+            Err(Some(ErrorKind::Passphrase))
+        );
+        assert_eq!(
+            cli(&["--ascii-only", "secret", "x", "--passphrase-raw-file", path])
+                .read_raw_passphrase()
+                .map_err(|err| ErrorKind::of(&err)),
+            Err(Some(ErrorKind::Usage))
+        );
+    }
+
+    #[test]
+    fn passphrase_trimming() {
+        let trim = |mode: &str, passphrase: &str| {
+            cli(&["--passphrase-trim", mode, "secret", "x"]).trim_passphrase(passphrase.to_owned())
+        };
+
+        for passphrase in ["pass", "pass\n", "pass\r\n", " pass \n", "pass\n\n"] {
+            assert_eq!(trim("none", passphrase), passphrase);
+            assert_eq!(
+                cli(&["secret", "x"]).trim_passphrase(passphrase.to_owned()),
+                passphrase
+            );
+        }
+
+        assert_eq!(trim("newline", "pass"), "pass");
+        assert_eq!(trim("newline", "pass\n"), "pass");
+        assert_eq!(trim("newline", "pass\r\n"), "pass");
+        assert_eq!(trim("newline", "pass\n\n"), "pass\n");
+        assert_eq!(trim("newline", "pass\r"), "pass\r");
+        assert_eq!(trim("newline", " pass \n"), " pass ");
+
+        assert_eq!(trim("all", " pass \r\n"), "pass");
+        assert_eq!(trim("all", "\tpass\n\n"), "pass");
+        assert_eq!(trim("all", "pa ss"), "pa ss");
+    }
+
+    #[test]
+    fn salt_round_trip() {
+        // Salt, and whether it's stored as base64.
+        let salts: [(&[u8], bool); 5] = [
+            (b"printable salt", false),
+            (b"", false),
+            (b"line\nbreak", true),
+            (&[0; 16], true),
+            // Used to panic, not being valid UTF-8.
+            (b"\xff\xfe not utf-8", true),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        for (salt, b64) in salts {
+            let cfg = config::File::from(argon2::Parameters {
+                algorithm: argon2::Algorithm::Argon2id,
+                memory: 64,
+                time: 1,
+                parallelism: 1,
+                salt: salt.to_vec(),
+            });
+            assert_eq!(cfg.salt_b64.is_some(), b64, "{salt:?}");
+            assert_eq!(cfg.salt.is_some(), !b64 && !salt.is_empty(), "{salt:?}");
+
+            cfg.write(&path).unwrap();
+            let read = config::File::read(&path).unwrap().unwrap();
+            assert_eq!(argon2::Parameters::try_from(read).unwrap().salt, salt);
+        }
+    }
+
+    #[test]
+    fn cascade_syntax() {
+        let passes = cli(&[
+            "--cascade",
+            "argon2d:1:10:4",
+            "--cascade",
+            "ID:2:1:1",
+            "secret",
+            "x",
+        ])
+        .cascade;
+        let costs: Vec<_> = passes
+            .iter()
+            .map(|p| {
+                (
+                    p.algorithm.to_string(),
+                    p.memory,
+                    p.time,
+                    p.parallelism,
+                    p.salt.len(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            costs,
+            [
+                ("argon2d".to_owned(), 1024 * 1024, 10, 4, 0),
+                ("argon2id".to_owned(), 2 * 1024 * 1024, 1, 1, 0),
+            ]
+        );
+
+        for invalid in [
+            "argon2d:1:10",
+            "argon2d:1:10:4:1",
+            "argon2i:1:10:4",
+            "argon2d:x:10:4",
+            "argon2d:4096:10:4",
+            "argon2d:1:-1:4",
+            "argon2d:1:10:",
+        ] {
+            assert!(parse_pass(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn single_line_comments() {
+        assert_eq!(parse_comment("work laptop").unwrap(), "work laptop");
+        assert_eq!(parse_comment("").unwrap(), "");
+        assert!(parse_comment("two\nlines").is_err());
+        assert!(parse_comment("trailing\r").is_err());
+    }
+
+    #[test]
+    fn newline_policy() {
+        // Line-based outputs end with a newline unless `--no-newline` is set.
+        assert!(cli(&["secret", "x"]).newline(true));
+        assert!(cli(&["secret", "x", "--newline"]).newline(true));
+        assert!(!cli(&["secret", "x", "--no-newline"]).newline(true));
+
+        assert!(cli(&["secret", "x", "--newline"]).newline(false));
+        assert!(!cli(&["secret", "x", "--no-newline"]).newline(false));
+        // Single values written to a descriptor are never terminated by default.
+        assert!(!cli(&["secret", "x", "--output-fd", "3"]).newline(false));
+
+        assert_eq!(lines(&["a".to_owned(), "b".to_owned()]), "a\nb\n");
+        assert_eq!(lines(&[]), "");
+    }
+
+    #[test]
+    fn sanity_checks() {
+        let params = |memory, time, parallelism| argon2::Parameters {
+            algorithm: argon2::Algorithm::Argon2id,
+            memory,
+            time,
+            parallelism,
+            salt: Vec::new(),
+        };
+
+        // RFC 9106 recommendations and the defaults of `configure`.
+        for typical in [
+            params(2 * 1024 * 1024, 1, 4),
+            params(64 * 1024, 3, 4),
+            params(1024 * 1024, 4, 4),
+            params(8 * 1024, 1, 1),
+        ] {
+            assert_eq!(
+                sanity_warnings(&typical, 4),
+                Vec::<String>::new(),
+                "{typical:?}"
+            );
+        }
+
+        // Swapped `--time 1024 --memory 4` (in MiB).
+        let swapped = sanity_warnings(&params(4 * 1024, 1024, 4), 4);
+        assert_eq!(swapped.len(), 1);
+        assert!(swapped[0].contains("did you swap --memory and --time?"));
+
+        let oversubscribed = sanity_warnings(&params(1024 * 1024, 1, 8), 4);
+        assert_eq!(oversubscribed.len(), 1);
+        assert!(oversubscribed[0].contains("exceeds the number of CPUs (4)"));
+
+        let excessive = sanity_warnings(&params(1024 * 1024, 1, 17), 4);
+        assert_eq!(excessive.len(), 1);
+        assert!(excessive[0].contains("greatly exceeds"));
+        assert!(
+            sanity_warnings(&params(1024 * 1024, 1, 16), 4)[0]
+                .starts_with("Your parallelism (16 lanes) exceeds")
+        );
+    }
+
+    #[test]
+    fn secret_specs() {
+        let spec = parse_secret_spec("32:hex").unwrap();
+        assert_eq!((spec.length, spec.encoding), (32, "hex"));
+        let spec = parse_secret_spec("16:words").unwrap();
+        assert_eq!((spec.length, spec.encoding), (16, "words"));
+
+        for invalid in ["32", "32:", ":hex", "32:HEX", "-1:hex", "32:hex:1"] {
+            assert!(parse_secret_spec(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn paranoid_derivation() {
+        let params = argon2::Parameters {
+            algorithm: argon2::Algorithm::Argon2id,
+            memory: 64,
+            time: 1,
+            parallelism: 1,
+            salt: b"somesalt".to_vec(),
+        };
+        let cascade = [argon2::Parameters {
+            time: 2,
+            ..params.clone()
+        }];
+
+        // Inputs of every run, the output of the second one getting a flipped bit.
+        let calls = std::cell::RefCell::new(vec![]);
+        let flaky = |p: &argon2::Parameters, c: &[argon2::Parameters], pw: &[u8], len| {
+            let mut calls = calls.borrow_mut();
+            calls.push(format!("{p:?} {c:?} {pw:?} {len}"));
+            let mut out = argon2::hash_cascade(p, c, pw, len)?;
+            out[0] ^= u8::from(calls.len() == 2);
+            Ok(out)
+        };
+
+        let err = cli(&["--paranoid", "secret", "name"])
+            .hash_with(flaky, &params, &cascade, b"password", 32)
+            .unwrap_err();
+        assert_eq!(error::exit_code(&err), 5);
+        assert!(
+            err.to_string()
+                .contains("memory of this machine may be faulty"),
+            "{err}"
+        );
+        let inputs = calls.take();
+        assert_eq!(inputs.len(), 2);
+        assert_eq!(inputs[0], inputs[1]);
+
+        let secret = cli(&["secret", "name"])
+            .hash_with(flaky, &params, &cascade, b"password", 32)
+            .unwrap();
+        assert_eq!(calls.take().len(), 1);
+
+        let paranoid = cli(&["--paranoid", "secret", "name"])
+            .hash(&params, &cascade, b"password", 32)
+            .unwrap();
+        assert_eq!(paranoid, secret);
+    }
+
+    #[test]
+    fn check_determinism_alias() {
+        assert!(cli(&["--check-determinism", "secret", "name"]).paranoid);
+        assert!(!cli(&["secret", "name"]).paranoid);
+    }
+
+    #[test]
+    fn memory_preflight() {
+        const GIB: u64 = 1024 * 1024 * 1024;
+        let system = Some(memory::Memory {
+            available: 4 * GIB,
+            total: 16 * GIB,
+        });
+        let check = |args: &[&str], cost_gib: u32, system| {
+            cli(args).check_memory_of(cost_gib * 1024 * 1024, system)
+        };
+
+        assert!(check(&["secret", "name"], 4, system).is_ok());
+        assert!(check(&["secret", "name"], 64, None).is_ok());
+
+        let err = check(&["secret", "name"], 8, system).unwrap_err();
+        assert_eq!(error::exit_code(&err), 5);
+        assert!(
+            err.to_string()
+                .contains("Your memory cost (8.0 GiB) exceeds the available memory (4.0 GiB)"),
+            "{err}"
+        );
+        assert!(check(&["secret", "name", "--force-memory"], 8, system).is_ok());
+
+        // Beyond the physical RAM only warns in addition.
+        let err = check(&["secret", "name"], 32, system).unwrap_err();
+        assert_eq!(error::exit_code(&err), 5);
+        assert!(check(&["secret", "name", "--force-memory"], 32, system).is_ok());
+    }
+
+    #[test]
+    fn masked_bits() {
+        let masked = |bits| {
+            let mut bytes = [0xff; 4];
+            mask_bits(&mut bytes, bits);
+            bytes
+        };
+
+        assert_eq!(masked(32), [0xff; 4]);
+        assert_eq!(masked(31), [0xff, 0xff, 0xff, 0xfe]);
+        assert_eq!(masked(28), [0xff, 0xff, 0xff, 0xf0]);
+        assert_eq!(masked(25), [0xff, 0xff, 0xff, 0x80]);
+        // The callers derive the next whole byte, so only a part of the last byte is ever masked.
+        assert_eq!(masked(24), [0xff; 4]);
+        assert_eq!(masked(40), [0xff; 4]);
+
+        mask_bits(&mut [], 3);
+    }
+
+    #[test]
+    fn name_paths() {
+        let path = |name| parse_name_path(name).unwrap();
+        assert_eq!(path("work/aws/prod"), ["work", "aws", "prod"]);
+        assert_eq!(path("email"), ["email"]);
+        assert_eq!(path(r"a\/b/c"), ["a/b", "c"]);
+        assert_eq!(path(r"a\\/b"), [r"a\", "b"]);
+        assert_eq!(path(r"a\\\/b"), [r"a\/b"]);
+
+        for name in ["", "/a", "a/", "a//b", r"a\b", r"a\"] {
+            assert!(parse_name_path(name).is_err(), "{name:?}");
+        }
+    }
+
+    #[test]
+    fn rotation_generations() {
+        let generations = |args: &[&str]| {
+            let Commands::Rotate(args) = cli(&[&["rotate", "email"], args].concat()).command else {
+                unreachable!()
+            };
+            args.generations().map_err(|err| ErrorKind::of(&err))
+        };
+
+        assert_eq!(generations(&["--from-generation", "0"]), Ok((0, 1)));
+        assert_eq!(
+            generations(&["--from-generation", "3", "--to-generation", "1"]),
+            Ok((3, 1))
+        );
+        assert_eq!(
+            generations(&["--from-generation", "4294967295", "--to-generation", "0"]),
+            Ok((u32::MAX, 0))
+        );
+
+        assert_eq!(
+            generations(&["--from-generation", "4294967295"]),
+            Err(Some(ErrorKind::Usage))
+        );
+        assert_eq!(
+            generations(&["--from-generation", "2", "--to-generation", "2"]),
+            Err(Some(ErrorKind::Usage))
+        );
+    }
+
+    #[test]
+    fn kubernetes_and_salt_namespaces() {
+        let cli = cli(&[
+            "secret",
+            "db",
+            "--namespace",
+            "work",
+            "--format",
+            "k8s-secret",
+            "--k8s-name",
+            "app",
+            "--k8s-namespace",
+            "prod",
+        ]);
+        assert_eq!(cli.namespace.as_deref(), Some("work"));
+        let Commands::Secret(args) = cli.command else {
+            unreachable!()
+        };
+        assert_eq!(args.k8s_namespace.as_deref(), Some("prod"));
+    }
+
+    #[test]
+    fn passphrase_fingerprints() {
+        let params = argon2::Parameters {
+            algorithm: argon2::Algorithm::Argon2id,
+            memory: 64,
+            time: 1,
+            parallelism: 1,
+            salt: b"somesalt".to_vec(),
+        };
+        let fingerprint =
+            |passphrase: &[u8]| passphrase_fingerprint(&params, &[], None, passphrase).unwrap();
+
+        let mut salted = params.clone();
+        salted.salt.extend(PASSPHRASE_FINGERPRINT_CONTEXT);
+        let output = argon2::hash(&salted, b"password", 4).unwrap();
+        assert_eq!(fingerprint(b"password"), output[..2]);
+        assert_ne!(fingerprint(b"password"), fingerprint(b"passphrase"));
+
+        // The cascade is part of it.
+        let cascade = [argon2::Parameters {
+            time: 2,
+            ..params.clone()
+        }];
+        assert_ne!(
+            passphrase_fingerprint(&params, &cascade, None, b"password").unwrap(),
+            fingerprint(b"password")
+        );
+    }
+
+    #[test]
+    fn audit_since() {
+        for since in [
+            "2025-01-31",
+            "2025-01-31T12",
+            "2025-01-31T12:00",
+            "2025-01-31T12:00:05",
+        ] {
+            assert_eq!(parse_since(since).unwrap(), since);
+        }
+        for since in [
+            "2025-01",
+            "2025/01/31",
+            "2025-01-31 12:00",
+            "2025-01-31T12:00:05Z",
+            "25-01-31",
+        ] {
+            assert!(parse_since(since).is_err(), "{since}");
+        }
+    }
+
+    #[test]
+    fn decode_inverts_encode() {
+        let bytes: Vec<u8> = (0..32).collect();
+        for encoding in ["hex", "base64", "words"] {
+            let encoded = encode(&bytes, encoding).unwrap();
+            assert_eq!(decode(&encoded, encoding).unwrap(), bytes, "{encoding}");
+        }
+        assert_eq!(decode("0A0b\n", "hex").unwrap(), [0x0a, 0x0b]);
+        assert!(decode("0g", "hex").is_err());
+        assert!(decode("AA=A", "base64").is_err());
+    }
+
+    #[test]
+    fn expect_conflicts() {
+        let Commands::Secret(args) = cli(&["secret", "email", "--expect", "00"]).command else {
+            unreachable!()
+        };
+        assert_eq!(args.expect.as_deref(), Some("00"));
+
+        for args in [
+            &["--expect", "00", "--expect-file", "f"][..],
+            &["--expect", "00", "--format", "htpasswd"],
+            &["--expect-file", "f", "--dotenv", ".env"],
+        ] {
+            let res = Cli::try_parse_from([&[APP_NAME, "secret", "email"][..], args].concat());
+            let err = res.unwrap_err();
+            assert_eq!(
+                err.kind(),
+                clap::error::ErrorKind::ArgumentConflict,
+                "{args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn plugin_modes() {
+        let Commands::Plugin(args) = cli(&["plugin", "--age-plugin=identity-v1"]).command else {
+            unreachable!()
+        };
+        assert_eq!(args.age_plugin.as_deref(), Some(age_plugin::IDENTITY_V1));
+
+        for args in [
+            &["plugin"][..],
+            &["plugin", "--age-plugin=identity-v1", "--keygen", "email"],
+        ] {
+            let res = Cli::try_parse_from([&[APP_NAME][..], args].concat());
+            assert!(res.is_err(), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn describe_origins() {
+        let config = ParamsSource::Config {
+            path: PathBuf::from("config.toml"),
+            passphrase_version: config::LEGACY_PASSPHRASE_VERSION,
+            salt_source: Some("none".to_owned()),
+            require_salt: false,
+            passes: Vec::new(),
+            passphrase_fingerprint: None,
+            overridden: true,
+        };
+        let args = [
+            "--describe",
+            "-t",
+            "3",
+            "--namespace",
+            "work",
+            "secret",
+            "email",
+        ];
+        let origins = cli(&args).origins(&config);
+        assert_eq!(origins.config, Some(Origin::Default));
+        assert_eq!(origins.algorithm, Origin::Config);
+        assert_eq!(origins.time, Origin::Cli);
+        assert_eq!(origins.salt, Origin::Namespace);
+        assert_eq!(origins.salt_source, Origin::Config);
+        assert_eq!(origins.cascade, Origin::Default);
+        assert_eq!(origins.normalization, Origin::Config);
+
+        let args = [
+            "--describe",
+            "--time-budget",
+            "10",
+            "--no-normalize",
+            "secret",
+            "email",
+        ];
+        let origins = cli(&args).origins(&ParamsSource::Cli);
+        assert_eq!(origins.config, None);
+        assert_eq!(origins.memory, Origin::Default);
+        assert_eq!(origins.time, Origin::TimeBudget);
+        assert_eq!(origins.normalization, Origin::Cli);
+
+        assert!(
+            Cli::try_parse_from([APP_NAME, "--describe", "--dry-run", "secret", "email"]).is_err()
+        );
+    }
+
+    #[test]
+    fn dry_run_plan() {
+        let cli = cli(&[
+            "--dry-run",
+            "-m",
+            "1",
+            "-t",
+            "3",
+            "-p",
+            "4",
+            "secret",
+            "email",
+        ]);
+        let params = argon2::Parameters {
+            algorithm: argon2::Algorithm::Argon2id,
+            memory: 1 << 20,
+            time: 3,
+            parallelism: 4,
+            salt: b"somesaltemail".to_vec(),
+        };
+
+        let mut out = Vec::new();
+        cli.print_plan(&mut out, &params, &ParamsSource::Cli, 8, 32)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        for line in [
+            "Source: command line",
+            "Algorithm: argon2id",
+            "Time: 3 (iterations)",
+            "Parallelism: 4 (lanes)",
+            "Salt length: 8 bytes",
+            "Final salt length: 13 bytes",
+            "Output length: 32 bytes",
+            "Encoding: hex",
+            "Name: raw",
+        ] {
+            assert!(out.lines().any(|l| l == line), "{line}: {out}");
+        }
+        assert!(out.ends_with("\n"));
+    }
+
+    #[test]
+    fn completions() {
+        use clap::ValueEnum as _;
+
+        let cmd = Cli::command();
+        let subcommands: Vec<_> = cmd
+            .get_subcommands()
+            .filter(|sub| !sub.is_hide_set())
+            .map(|sub| sub.get_name().to_owned())
+            .collect();
+        assert!(subcommands.iter().any(|name| name == "age"));
+
+        for shell in clap_complete::Shell::value_variants() {
+            let mut out = Vec::new();
+            clap_complete::generate(*shell, &mut Cli::command(), APP_NAME, &mut out);
+            let out = String::from_utf8(out).unwrap();
+
+            for name in &subcommands {
+                assert!(out.contains(name.as_str()), "{shell}: {name}");
+            }
         }
     }
 }
@@ -0,0 +1,99 @@
+//! Pre-flight check of the memory cost against the memory of the system.
+//!
+//! Argon2 allocates its whole memory cost at once, so a cost exceeding the available memory either
+//! fails with an allocation error or gets the process killed, after the passphrase was typed.
+
+use sysinfo::{MemoryRefreshKind, RefreshKind, System};
+
+/// Memory of the system, in bytes.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Memory {
+    /// Memory that can be allocated without swapping (`MemAvailable` on Linux).
+    pub available: u64,
+    /// Physical RAM.
+    pub total: u64,
+}
+
+/// Outcome of comparing the memory cost with the memory of the system.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Verdict {
+    Fits,
+    /// Exceeds the available memory, but not the physical RAM.
+    ExceedsAvailable,
+    /// Exceeds the physical RAM, so the derivation can only succeed by swapping.
+    ExceedsTotal,
+}
+
+/// Queries the memory of the system, `None` if the platform doesn't report it.
+pub(super) fn query() -> Option<Memory> {
+    let system = System::new_with_specifics(
+        RefreshKind::nothing().with_memory(MemoryRefreshKind::nothing().with_ram()),
+    );
+
+    let memory = Memory {
+        available: system.available_memory(),
+        total: system.total_memory(),
+    };
+    (memory.total != 0).then_some(memory)
+}
+
+/// Compares the memory cost (in KiB) with the memory of the system.
+pub(super) fn check(memory_cost: u32, memory: Memory) -> Verdict {
+    let required = u64::from(memory_cost) * 1024;
+
+    if required > memory.total {
+        Verdict::ExceedsTotal
+    } else if required > memory.available {
+        Verdict::ExceedsAvailable
+    } else {
+        Verdict::Fits
+    }
+}
+
+/// Formats a number of bytes in GiB or MiB, e.g. `15.6 GiB`.
+pub(super) fn format_bytes(bytes: u64) -> String {
+    const MIB: f64 = 1024.0 * 1024.0;
+
+    match bytes as f64 / MIB {
+        mib if mib >= 1024.0 => format!("{:.1} GiB", mib / 1024.0),
+        mib => format!("{mib:.0} MiB"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn verdicts() {
+        let memory = Memory {
+            available: 4 * GIB,
+            total: 16 * GIB,
+        };
+        let kib = |gib: u64| (gib * GIB / 1024) as u32;
+
+        assert_eq!(check(kib(1), memory), Verdict::Fits);
+        assert_eq!(check(kib(4), memory), Verdict::Fits);
+        assert_eq!(check(kib(4) + 1, memory), Verdict::ExceedsAvailable);
+        assert_eq!(check(kib(16), memory), Verdict::ExceedsAvailable);
+        assert_eq!(check(kib(16) + 1, memory), Verdict::ExceedsTotal);
+        assert_eq!(check(u32::MAX, memory), Verdict::ExceedsTotal);
+    }
+
+    #[test]
+    fn human_units() {
+        assert_eq!(format_bytes(64 * 1024 * 1024), "64 MiB");
+        assert_eq!(format_bytes(1023 * 1024 * 1024), "1023 MiB");
+        assert_eq!(format_bytes(GIB), "1.0 GiB");
+        assert_eq!(format_bytes(GIB * 156 / 10), "15.6 GiB");
+    }
+
+    #[test]
+    fn queries_the_system() {
+        if let Some(memory) = query() {
+            assert!(memory.available <= memory.total);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! One-time passwords: HOTP ([RFC 4226](https://www.rfc-editor.org/rfc/rfc4226))
+//! and TOTP ([RFC 6238](https://www.rfc-editor.org/rfc/rfc6238)).
+
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use zeroize::Zeroizing;
+
+const HMAC_KEY: &str = "HMAC accepts keys of any length";
+
+/// Supported HMAC algorithms, SHA-1 being the one supported by virtually every authenticator.
+pub(super) const ALGORITHMS: [&str; 3] = ["sha1", "sha256", "sha512"];
+
+/// Length of the key to derive for the algorithm, the output size of its hash as recommended by RFC 6238.
+pub(super) fn key_len(algorithm: &str) -> u32 {
+    match algorithm {
+        "sha1" => 20,
+        "sha256" => 32,
+        "sha512" => 64,
+        _ => unreachable!(),
+    }
+}
+
+/// Computes the HOTP code of the counter, zero-padded to `digits`.
+pub(super) fn hotp(key: &[u8], counter: u64, digits: u32, algorithm: &str) -> String {
+    let counter = counter.to_be_bytes();
+    let hash = Zeroizing::new(match algorithm {
+        "sha1" => {
+            let mut mac = Hmac::<Sha1>::new_from_slice(key).expect(HMAC_KEY);
+            mac.update(&counter);
+            mac.finalize().into_bytes().to_vec()
+        }
+        "sha256" => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(key).expect(HMAC_KEY);
+            mac.update(&counter);
+            mac.finalize().into_bytes().to_vec()
+        }
+        "sha512" => {
+            let mut mac = Hmac::<Sha512>::new_from_slice(key).expect(HMAC_KEY);
+            mac.update(&counter);
+            mac.finalize().into_bytes().to_vec()
+        }
+        _ => unreachable!(),
+    });
+
+    // Dynamic truncation (RFC 4226, section 5.3).
+    let offset = usize::from(hash[hash.len() - 1] & 0x0f);
+    let binary = u32::from_be_bytes(hash[offset..offset + 4].try_into().unwrap()) & 0x7fff_ffff;
+
+    let code = u64::from(binary) % 10u64.pow(digits);
+    format!("{code:0width$}", width = digits as usize)
+}
+
+/// TOTP time step of the Unix time, along with the seconds remaining until the next one.
+pub(super) fn time_step(unix_time: u64, period: u64) -> (u64, u64) {
+    (unix_time / period, period - unix_time % period)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc4226_vectors() {
+        // RFC 4226, appendix D.
+        let codes = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583",
+            "399871", "520489",
+        ];
+        for (counter, code) in codes.into_iter().enumerate() {
+            assert_eq!(
+                hotp(b"12345678901234567890", counter as u64, 6, "sha1"),
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn rfc6238_vectors() {
+        // RFC 6238, appendix B: 8 digits, 30 seconds time steps.
+        let vectors = [
+            (59, ["94287082", "46119246", "90693936"]),
+            (1111111109, ["07081804", "68084774", "25091201"]),
+            (1111111111, ["14050471", "67062674", "99943326"]),
+            (1234567890, ["89005924", "91819424", "93441116"]),
+            (2000000000, ["69279037", "90698825", "38618901"]),
+            (20000000000, ["65353130", "77737706", "47863826"]),
+        ];
+        for (time, codes) in vectors {
+            let (step, _) = time_step(time, 30);
+            for (algorithm, code) in ALGORITHMS.into_iter().zip(codes) {
+                // The seed of the appendix, repeated up to the key length of the algorithm.
+                let key: Vec<_> = b"1234567890"
+                    .iter()
+                    .cycle()
+                    .take(key_len(algorithm) as usize)
+                    .copied()
+                    .collect();
+                assert_eq!(
+                    hotp(&key, step, 8, algorithm),
+                    code,
+                    "{algorithm} at {time}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn time_steps() {
+        assert_eq!(time_step(0, 30), (0, 30));
+        assert_eq!(time_step(29, 30), (0, 1));
+        assert_eq!(time_step(30, 30), (1, 30));
+        assert_eq!(time_step(59, 30), (1, 1));
+        assert_eq!(time_step(100, 1), (100, 1));
+    }
+
+    #[test]
+    fn digits() {
+        // Truncated value 1284755224 of RFC 4226, appendix D, for the counter 0.
+        assert_eq!(hotp(b"12345678901234567890", 0, 6, "sha1"), "755224");
+        assert_eq!(hotp(b"12345678901234567890", 0, 7, "sha1"), "4755224");
+        assert_eq!(hotp(b"12345678901234567890", 0, 8, "sha1"), "84755224");
+    }
+}
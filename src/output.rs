@@ -0,0 +1,75 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt as _;
+
+use crate::{
+    error::{ErrorKind, ResultExt as _},
+    interrupt,
+};
+
+/// Files written by this invocation, in the order they were written.
+static WRITTEN: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Runs `f` against the file at `path`, or against stdout if no path is provided.
+///
+/// Files are written atomically: the data goes into a temporary file next to `path`,
+/// which only gets renamed into place once `f` succeeds, and is removed otherwise.
+/// On Unix the files are only accessible by their owner.
+pub(super) fn write_to(
+    path: Option<&Path>,
+    f: impl FnOnce(&mut dyn Write) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let Some(path) = path else {
+        let mut stdout = io::stdout().lock();
+        f(&mut stdout)?;
+        return stdout.flush().kind(ErrorKind::Output);
+    };
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".tmp-{:016x}", rand::random::<u64>()));
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let _registered = interrupt::TempFile::register(Path::new(&tmp));
+    let result = options.open(&tmp).kind(ErrorKind::Output).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        f(&mut writer)?;
+        writer
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)
+            .and_then(|file| file.sync_all())
+            .and_then(|()| fs::rename(&tmp, path))
+            .kind(ErrorKind::Output)
+    });
+
+    match result {
+        Ok(()) => record(path),
+        Err(_) => {
+            let _ = fs::remove_file(&tmp);
+        }
+    }
+
+    result
+}
+
+/// Records a file written by this invocation, see [`written`].
+pub(super) fn record(path: &Path) {
+    let mut written = WRITTEN.lock().unwrap();
+    if !written.iter().any(|p| p == path) {
+        written.push(path.to_owned());
+    }
+}
+
+/// Files written by this invocation (e.g. for `--checksum-file`).
+pub(super) fn written() -> Vec<PathBuf> {
+    WRITTEN.lock().unwrap().clone()
+}
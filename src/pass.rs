@@ -0,0 +1,42 @@
+//! Insertion into [pass](https://www.passwordstore.org) compatible password stores.
+
+use std::io::{self, Write as _};
+use std::process::{Command, Stdio};
+
+use anyhow::Context as _;
+
+/// Inserts the secret using `<command> insert`, piping it via stdin.
+///
+/// Multiline secrets are inserted using `--multiline`, single line ones using `--echo`.
+pub(super) fn insert(command: &str, entry: &str, secret: &str) -> anyhow::Result<()> {
+    let mode = if secret.contains('\n') {
+        "--multiline"
+    } else {
+        "--echo"
+    };
+
+    let mut child = Command::new(command)
+        .args(["insert", mode, entry])
+        .stdin(Stdio::piped())
+        // Keeps our stdout clean, as it might be captured by the caller.
+        .stdout(io::stderr())
+        .spawn()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => anyhow::anyhow!("`{command}` not found in PATH"),
+            _ => anyhow::Error::new(err).context(format!("spawn {command}")),
+        })?;
+
+    let mut stdin = child.stdin.take().expect("stdin should be piped");
+    let terminator = if secret.ends_with('\n') { "" } else { "\n" };
+    let written = write!(stdin, "{secret}{terminator}");
+    drop(stdin);
+
+    let status = child
+        .wait()
+        .with_context(|| format!("wait for {command}"))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("`{command} insert` failed ({status})"));
+    }
+
+    written.with_context(|| format!("write to {command}"))
+}
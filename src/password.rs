@@ -0,0 +1,417 @@
+use std::str::FromStr;
+
+use bip39::Language;
+use hmac::{Hmac, Mac};
+use rand::{Rng as _, TryRngCore as _, rngs::OsRng};
+use sha2::Sha256;
+
+/// Expands `seed` into `count` numeric codes of `length` digits each.
+///
+/// Every code is produced from its own HMAC-SHA256 keystream (keyed by `seed`, with the code
+/// index mixed in), and digits are picked by rejection sampling so they are uniformly distributed.
+pub(super) fn recovery_codes(seed: &[u8], count: u16, length: u8) -> Vec<String> {
+    (0..count)
+        .map(|index| {
+            keystream(seed, index)
+                // 250 is the largest multiple of 10 fitting into a byte.
+                .filter(|&byte| byte < 250)
+                .take(length.into())
+                .map(|byte| char::from(b'0' + byte % 10))
+                .collect()
+        })
+        .collect()
+}
+
+/// Password pattern, e.g. `Aaaaaaaa-0000-ssss`.
+#[derive(Clone, Debug)]
+pub(super) struct Template(Vec<Slot>);
+
+#[derive(Clone, Copy, Debug)]
+enum Slot {
+    Class(&'static [u8]),
+    Literal(char),
+}
+
+const UPPERCASE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const LOWERCASE: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const DIGITS: &[u8] = b"0123456789";
+/// ASCII punctuation without quotes and backslash, which tend to be rejected or mishandled.
+const SYMBOLS: &[u8] = b"!#$%&()*+,-./:;<=>?@[]^_{|}~";
+
+impl FromStr for Template {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut slots = Vec::new();
+        let mut chars = s.chars().enumerate();
+
+        while let Some((idx, c)) = chars.next() {
+            slots.push(match c {
+                'A' => Slot::Class(UPPERCASE),
+                'a' => Slot::Class(LOWERCASE),
+                '0' => Slot::Class(DIGITS),
+                's' => Slot::Class(SYMBOLS),
+                '\\' => match chars.next() {
+                    Some((_, c)) => Slot::Literal(c),
+                    None => return Err(anyhow::anyhow!("Template ends with a dangling `\\`")),
+                },
+                c if c.is_alphanumeric() => {
+                    return Err(anyhow::anyhow!(
+                        "Unknown character class `{c}` at position {}, expected `A`, `a`, `0` or `s` \
+                         (use `\\{c}` for a literal `{c}`)",
+                        idx + 1
+                    ));
+                }
+                c => Slot::Literal(c),
+            });
+        }
+
+        if !slots.iter().any(|slot| matches!(slot, Slot::Class(_))) {
+            return Err(anyhow::anyhow!(
+                "Template should contain at least one character class"
+            ));
+        }
+
+        Ok(Self(slots))
+    }
+}
+
+impl Template {
+    /// Fills the template using the keystream of `seed`.
+    ///
+    /// Every class character is picked uniformly within its class.
+    pub(super) fn render(&self, seed: &[u8]) -> String {
+        let mut keystream = keystream(seed, 0);
+
+        self.0
+            .iter()
+            .map(|slot| match *slot {
+                Slot::Literal(c) => c,
+                Slot::Class(chars) => pick(&mut keystream, chars),
+            })
+            .collect()
+    }
+}
+
+/// Shape of a password made of words, see `--encoding words-password`.
+pub(super) struct WordsPassword<'a> {
+    pub words: u16,
+    pub separator: &'a str,
+    pub capitalize: bool,
+    /// Whether to append a digit and a symbol.
+    pub suffix: bool,
+}
+
+impl WordsPassword<'_> {
+    /// Picks the words (and the suffix) using the keystream of `seed`.
+    pub(super) fn render(&self, seed: &[u8]) -> String {
+        let wordlist = Language::English.word_list();
+        let mut keystream = keystream(seed, 0);
+
+        let mut parts: Vec<String> = (0..self.words)
+            .map(|_| {
+                let bytes = [(); 2].map(|_| keystream.next().expect("keystream is infinite"));
+                // The wordlist has 2^11 words, so 16-bit values map onto it uniformly.
+                let word = wordlist[usize::from(u16::from_be_bytes(bytes)) % wordlist.len()];
+
+                match self.capitalize {
+                    true => word[..1].to_uppercase() + &word[1..],
+                    false => word.to_owned(),
+                }
+            })
+            .collect();
+
+        if self.suffix {
+            let digit = pick(&mut keystream, DIGITS);
+            let symbol = pick(&mut keystream, SYMBOLS);
+            parts.push(format!("{digit}{symbol}"));
+        }
+
+        parts.join(self.separator)
+    }
+
+    /// Entropy (in bits) of the password, assuming the seed is uniformly random.
+    pub(super) fn entropy(&self) -> f64 {
+        let words = f64::from(self.words) * (Language::English.word_list().len() as f64).log2();
+        let suffix = match self.suffix {
+            true => (DIGITS.len() as f64).log2() + (SYMBOLS.len() as f64).log2(),
+            false => 0.0,
+        };
+
+        words + suffix
+    }
+}
+
+/// Picks a character of the class by rejection sampling, so it's uniformly distributed.
+fn pick(keystream: &mut impl Iterator<Item = u8>, chars: &[u8]) -> char {
+    // Largest multiple of the class size fitting into a byte.
+    let limit = 256 - 256 % chars.len();
+    let byte = keystream
+        .find(|&byte| usize::from(byte) < limit)
+        .expect("keystream is infinite");
+
+    char::from(chars[usize::from(byte) % chars.len()])
+}
+
+/// Infinite stream of `HMAC-SHA256(seed, index || block)` outputs.
+fn keystream(seed: &[u8], index: u16) -> impl Iterator<Item = u8> {
+    (0u32..).flat_map(move |block| {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(seed).expect("HMAC accepts keys of any length");
+        mac.update(&index.to_be_bytes());
+        mac.update(&block.to_be_bytes());
+        mac.finalize().into_bytes()
+    })
+}
+
+/// Picks `count` words uniformly at random from the BIP39 English wordlist.
+///
+/// Uses the OS randomness directly, nothing gets derived.
+pub(super) fn diceware(count: u16) -> Vec<&'static str> {
+    let wordlist = Language::English.word_list();
+    let mut rng = OsRng.unwrap_err();
+
+    (0..count)
+        .map(|_| wordlist[rng.random_range(0..wordlist.len())])
+        .collect()
+}
+
+/// Entropy (in bits) of a passphrase of `count` words produced by [`diceware`].
+pub(super) fn diceware_entropy(count: u16) -> f64 {
+    f64::from(count) * (Language::English.word_list().len() as f64).log2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: &[u8] = b"recovery codes test seed";
+    const PASSWORD_SEED: &[u8] = b"password test seed";
+
+    #[test]
+    fn recovery_codes_vector() {
+        // Computed with Python's `hmac` module.
+        assert_eq!(
+            recovery_codes(SEED, 3, 8),
+            ["41432176", "03571376", "16967450"]
+        );
+
+        // Spans several HMAC blocks.
+        assert_eq!(
+            recovery_codes(SEED, 1, 80),
+            ["41432176569959461184629507453110486453396289847800347501551737558795871892048668"]
+        );
+    }
+
+    #[test]
+    fn recovery_codes_are_deterministic() {
+        assert_eq!(recovery_codes(SEED, 10, 8), recovery_codes(SEED, 10, 8));
+        assert_ne!(
+            recovery_codes(SEED, 10, 8),
+            recovery_codes(b"other seed", 10, 8)
+        );
+    }
+
+    #[test]
+    fn recovery_codes_count_and_length() {
+        for (count, length) in [(0, 8), (1, 1), (10, 8), (100, 12), (3, u8::MAX)] {
+            let codes = recovery_codes(SEED, count, length);
+            assert_eq!(codes.len(), usize::from(count));
+
+            for code in &codes {
+                assert_eq!(code.len(), usize::from(length));
+                assert!(code.bytes().all(|b| b.is_ascii_digit()), "{code}");
+            }
+        }
+    }
+
+    #[test]
+    fn recovery_codes_differ_by_index() {
+        let codes = recovery_codes(SEED, 100, 16);
+        for (i, code) in codes.iter().enumerate() {
+            assert!(!codes[..i].contains(code), "duplicate code #{i}");
+        }
+
+        // A longer set only appends codes, and longer codes only append digits.
+        assert_eq!(
+            recovery_codes(SEED, 5, 8)[..],
+            codes_with_length(&codes[..5], 8)[..]
+        );
+    }
+
+    fn codes_with_length(codes: &[String], length: usize) -> Vec<String> {
+        codes.iter().map(|code| code[..length].to_owned()).collect()
+    }
+
+    #[test]
+    fn template_vectors() {
+        // Computed with Python's `hmac` module.
+        let render = |template: &str| template.parse::<Template>().unwrap().render(PASSWORD_SEED);
+        assert_eq!(render("Aaaaaaaa-0000-ssss"), "Vxebqkwa-9288-]&/.");
+        assert_eq!(render("\\A\\a0s"), "Aa1_");
+    }
+
+    #[test]
+    fn template_classes() {
+        let template: Template = "Aa0s-Aa0s".parse().unwrap();
+        for seed in 0..100_u8 {
+            let password: Vec<_> = template.render(&[seed]).bytes().collect();
+            assert!(password[0].is_ascii_uppercase());
+            assert!(password[1].is_ascii_lowercase());
+            assert!(password[2].is_ascii_digit());
+            assert!(SYMBOLS.contains(&password[3]));
+            assert_eq!(password[4], b'-');
+            assert_eq!(password.len(), 9);
+        }
+
+        // Literals pass through as is, including the escaped classes and letters.
+        let template: Template = "#\\s\\\\\\é0".parse().unwrap();
+        let password = template.render(PASSWORD_SEED);
+        assert!(password.starts_with("#s\\é"), "{password}");
+        assert_eq!(password.chars().count(), 5);
+    }
+
+    #[test]
+    fn template_errors() {
+        let err = |template: &str| template.parse::<Template>().unwrap_err().to_string();
+
+        assert_eq!(
+            err("Aax"),
+            "Unknown character class `x` at position 3, expected `A`, `a`, `0` or `s` (use `\\x` for a literal `x`)"
+        );
+        assert!(err("A1").contains("`1` at position 2"));
+        assert_eq!(err("Aa\\"), "Template ends with a dangling `\\`");
+        assert_eq!(
+            err(""),
+            "Template should contain at least one character class"
+        );
+        assert_eq!(
+            err("-\\A"),
+            "Template should contain at least one character class"
+        );
+    }
+
+    #[test]
+    fn pick_is_uniform() {
+        // Every byte value once, so only the rejected ones skew the counts.
+        for chars in [UPPERCASE, LOWERCASE, DIGITS, SYMBOLS] {
+            let mut bytes = 0..=u8::MAX;
+            let mut counts = vec![0; chars.len()];
+            let accepted = 256 - 256 % chars.len();
+            for _ in 0..accepted {
+                let c = pick(&mut bytes, chars);
+                counts[chars.iter().position(|&x| char::from(x) == c).unwrap()] += 1;
+            }
+
+            assert!(counts.iter().all(|&count| count == accepted / chars.len()));
+        }
+    }
+
+    #[test]
+    fn words_password_vectors() {
+        // Computed with Python's `hmac` module and the BIP39 English wordlist.
+        let plain = WordsPassword {
+            words: 4,
+            separator: "-",
+            capitalize: false,
+            suffix: false,
+        };
+        assert_eq!(plain.render(PASSWORD_SEED), "pepper-sell-atom-rent");
+
+        let shaped = WordsPassword {
+            words: 6,
+            separator: " ",
+            capitalize: true,
+            suffix: true,
+        };
+        assert_eq!(
+            shaped.render(PASSWORD_SEED),
+            "Pepper Sell Atom Rent Suggest Bacon 8]"
+        );
+    }
+
+    #[test]
+    fn words_password_entropy() {
+        let mut shape = WordsPassword {
+            words: 6,
+            separator: "-",
+            capitalize: true,
+            suffix: false,
+        };
+        assert_eq!(shape.entropy(), 66.0);
+
+        // log2(10) + log2(28) bits of the digit and the symbol.
+        shape.suffix = true;
+        assert!(
+            (shape.entropy() - 74.129).abs() < 0.001,
+            "{}",
+            shape.entropy()
+        );
+    }
+
+    #[test]
+    fn words_password_is_unbiased() {
+        // Every 16-bit value maps onto a word, each word being hit 2^16 / 2^11 times.
+        let wordlist = Language::English.word_list();
+        let mut counts = vec![0; wordlist.len()];
+        for value in 0..=u16::MAX {
+            counts[usize::from(value) % wordlist.len()] += 1;
+        }
+        assert!(counts.iter().all(|&count| count == 32));
+
+        let shape = WordsPassword {
+            words: 8,
+            separator: " ",
+            capitalize: false,
+            suffix: false,
+        };
+        assert_eq!(shape.render(PASSWORD_SEED), shape.render(PASSWORD_SEED));
+        assert_ne!(shape.render(PASSWORD_SEED), shape.render(b"other seed"));
+        assert!(
+            shape
+                .render(PASSWORD_SEED)
+                .split(' ')
+                .all(|word| wordlist.contains(&word))
+        );
+    }
+
+    #[test]
+    fn diceware_entropy_of_the_words() {
+        // 2048 words, 11 bits each.
+        assert_eq!(diceware_entropy(1), 11.0);
+        assert_eq!(diceware_entropy(7), 77.0);
+        assert_eq!(diceware_entropy(24), 264.0);
+    }
+
+    #[test]
+    fn diceware_words() {
+        let wordlist = Language::English.word_list();
+        for count in [1, 7, 100] {
+            let words = diceware(count);
+            assert_eq!(words.len(), usize::from(count));
+            assert!(words.iter().all(|word| wordlist.contains(word)));
+        }
+    }
+
+    #[test]
+    fn diceware_is_uniform() {
+        // Chi-squared test of the word indices grouped into 16 buckets of 128 words.
+        const BUCKETS: usize = 16;
+        const SAMPLES: u16 = 16_000;
+
+        let wordlist = Language::English.word_list();
+        let mut counts = [0_u32; BUCKETS];
+        for word in diceware(SAMPLES) {
+            let idx = wordlist.iter().position(|w| *w == word).unwrap();
+            counts[idx * BUCKETS / wordlist.len()] += 1;
+        }
+
+        let expected = f64::from(SAMPLES) / BUCKETS as f64;
+        let chi_squared: f64 = counts
+            .iter()
+            .map(|&count| (f64::from(count) - expected).powi(2) / expected)
+            .sum();
+        // The critical value for 15 degrees of freedom at p = 0.0001 is 44.3.
+        assert!(chi_squared < 44.3, "{chi_squared}: {counts:?}");
+    }
+}
@@ -0,0 +1,173 @@
+use anyhow::Context as _;
+use base64::{Engine as _, engine::general_purpose};
+
+use crate::argon2;
+
+/// Argon2 hash encoded in the PHC string format.
+///
+/// `$argon2id$v=19$m=<memory>,t=<time>,p=<parallelism>$<salt>$<hash>`
+pub(super) struct Hash {
+    pub params: argon2::Parameters,
+    pub hash: Vec<u8>,
+}
+
+impl Hash {
+    pub(super) fn parse(s: &str) -> anyhow::Result<Self> {
+        let mut fields = s
+            .strip_prefix('$')
+            .context("PHC string should start with `$`")?
+            .split('$');
+
+        let mut next = |name| fields.next().with_context(|| format!("missing PHC {name}"));
+
+        let algorithm = next("algorithm")?.parse()?;
+
+        let version: u32 = next("version")?
+            .strip_prefix("v=")
+            .context("invalid PHC version, expected `v=<version>`")?
+            .parse()
+            .context("invalid PHC version")?;
+
+        if version != argon2::VERSION {
+            return Err(anyhow::anyhow!(
+                "Unsupported Argon2 version: {version}, only v={} is supported",
+                argon2::VERSION
+            ));
+        }
+
+        let (mut memory, mut time, mut parallelism) = (None, None, None);
+        for param in next("parameters")?.split(',') {
+            let (key, value) = param
+                .split_once('=')
+                .with_context(|| format!("invalid PHC parameter: {param}"))?;
+
+            let value = value
+                .parse()
+                .with_context(|| format!("invalid PHC parameter value: {param}"))?;
+
+            match key {
+                "m" => memory = Some(value),
+                "t" => time = Some(value),
+                "p" => parallelism = Some(value),
+                other => return Err(anyhow::anyhow!("unknown PHC parameter: {other}")),
+            }
+        }
+
+        let salt = decode(next("salt")?).context("invalid PHC salt")?;
+        let hash = decode(next("hash")?).context("invalid PHC hash")?;
+
+        if fields.next().is_some() {
+            return Err(anyhow::anyhow!("unexpected trailing PHC fields"));
+        }
+
+        if hash.is_empty() {
+            return Err(anyhow::anyhow!("empty PHC hash"));
+        }
+
+        Ok(Self {
+            params: argon2::Parameters {
+                algorithm,
+                memory: memory.context("missing PHC memory cost (m)")?,
+                time: time.context("missing PHC time cost (t)")?,
+                parallelism: parallelism.context("missing PHC parallelism (p)")?,
+                salt,
+            },
+            hash,
+        })
+    }
+}
+
+fn decode(s: &str) -> anyhow::Result<Vec<u8>> {
+    general_purpose::STANDARD_NO_PAD
+        .decode(s)
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test vector of the Argon2 reference implementation, password `password`.
+    const REFERENCE: &str =
+        "$argon2id$v=19$m=65536,t=2,p=1$c29tZXNhbHQ$CTFhFdXPJO1aFaMaO6Mm5c8y7cJHAph8ArZWb2GRPPc";
+
+    fn verify(phc: &Hash, password: &[u8]) -> bool {
+        argon2::hash(&phc.params, password, phc.hash.len() as u32).unwrap() == phc.hash
+    }
+
+    #[test]
+    fn parses_argon2id() {
+        let phc = Hash::parse(REFERENCE).unwrap();
+
+        assert!(matches!(phc.params.algorithm, argon2::Algorithm::Argon2id));
+        assert_eq!(phc.params.memory, 65536);
+        assert_eq!(phc.params.time, 2);
+        assert_eq!(phc.params.parallelism, 1);
+        assert_eq!(phc.params.salt, b"somesalt");
+        assert_eq!(
+            hex::encode(&phc.hash),
+            "09316115d5cf24ed5a15a31a3ba326e5cf32edc24702987c02b6566f61913cf7"
+        );
+    }
+
+    #[test]
+    fn parses_argon2d() {
+        let phc =
+            Hash::parse("$argon2d$v=19$m=64,t=1,p=2$c29tZXNhbHQ$9IOS7mucxkUKwzFxSxAqLA").unwrap();
+
+        assert!(matches!(phc.params.algorithm, argon2::Algorithm::Argon2d));
+        assert_eq!(phc.params.parallelism, 2);
+        assert!(verify(&phc, b"password"));
+    }
+
+    #[test]
+    fn verifies_the_password() {
+        let phc = Hash::parse(REFERENCE).unwrap();
+
+        assert!(verify(&phc, b"password"));
+        assert!(!verify(&phc, b"Password"));
+    }
+
+    #[test]
+    fn rejects_unsupported_versions() {
+        let err = Hash::parse("$argon2id$v=16$m=64,t=1,p=1$c29tZXNhbHQ$9IOS7mucxkUKwzFxSxAqLA")
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Unsupported Argon2 version: 16, only v=19 is supported"
+        );
+    }
+
+    #[test]
+    fn rejects_corrupted_strings() {
+        let corrupted = [
+            // Missing leading `$`.
+            "argon2id$v=19$m=64,t=1,p=1$c29tZXNhbHQ$9IOS7mucxkUKwzFxSxAqLA",
+            // Unsupported algorithm.
+            "$argon2i$v=19$m=64,t=1,p=1$c29tZXNhbHQ$9IOS7mucxkUKwzFxSxAqLA",
+            // Missing version.
+            "$argon2id$m=64,t=1,p=1$c29tZXNhbHQ$9IOS7mucxkUKwzFxSxAqLA",
+            // Missing parallelism.
+            "$argon2id$v=19$m=64,t=1$c29tZXNhbHQ$9IOS7mucxkUKwzFxSxAqLA",
+            // Unknown parameter.
+            "$argon2id$v=19$m=64,t=1,p=1,x=1$c29tZXNhbHQ$9IOS7mucxkUKwzFxSxAqLA",
+            // Invalid parameter value.
+            "$argon2id$v=19$m=sixty-four,t=1,p=1$c29tZXNhbHQ$9IOS7mucxkUKwzFxSxAqLA",
+            // Invalid base64 of the salt.
+            "$argon2id$v=19$m=64,t=1,p=1$c29tZXNhbHQ!$9IOS7mucxkUKwzFxSxAqLA",
+            // Padded base64 of the hash.
+            "$argon2id$v=19$m=64,t=1,p=1$c29tZXNhbHQ$9IOS7mucxkUKwzFxSxAqLA==",
+            // Missing hash.
+            "$argon2id$v=19$m=64,t=1,p=1$c29tZXNhbHQ",
+            // Empty hash.
+            "$argon2id$v=19$m=64,t=1,p=1$c29tZXNhbHQ$",
+            // Trailing field.
+            "$argon2id$v=19$m=64,t=1,p=1$c29tZXNhbHQ$9IOS7mucxkUKwzFxSxAqLA$",
+        ];
+
+        for phc in corrupted {
+            assert!(Hash::parse(phc).is_err(), "{phc}");
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! Interactive session, deriving many secrets from a passphrase typed once.
+
+use std::io::{self, BufRead as _};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::interrupt;
+
+/// How often the wait for the next line checks whether the session got interrupted.
+const INTERRUPT_POLL: Duration = Duration::from_millis(100);
+
+pub(super) const HELP: &str = "\
+Commands:
+  secret <NAME> [LENGTH] [hex|base64|words]  Derive a raw secret (32 bytes, hex by default)
+  age <NAME> [bech32m]                       Derive an age keypair
+  help                                       Print this message
+  quit                                       End the session (also `exit` or Ctrl-D)";
+
+const ENCODINGS: [&str; 3] = ["hex", "base64", "words"];
+
+/// Command typed into the session prompt.
+pub(super) enum Command {
+    Secret {
+        name: String,
+        length: u32,
+        encoding: &'static str,
+    },
+    Age {
+        name: String,
+        bech32m: bool,
+    },
+    Help,
+    Quit,
+}
+
+/// Parses a line of input, returning `None` if it's blank.
+pub(super) fn parse(line: &str) -> Result<Option<Command>, String> {
+    let mut words = line.split_whitespace();
+    let Some(command) = words.next() else {
+        return Ok(None);
+    };
+    let args: Vec<&str> = words.collect();
+
+    let name = |usage: &str| match args.first() {
+        Some(name) => Ok(name.to_string()),
+        None => Err(format!("Missing name, usage: {usage}")),
+    };
+
+    let command = match command {
+        "secret" => {
+            let usage = "secret <NAME> [LENGTH] [hex|base64|words]";
+            if args.len() > 3 {
+                return Err(format!("Too many arguments, usage: {usage}"));
+            }
+
+            let length = match args.get(1) {
+                Some(length) => length
+                    .parse()
+                    .map_err(|_| format!("Invalid length: {length}"))?,
+                None => 32,
+            };
+
+            let encoding = match args.get(2) {
+                Some(encoding) => {
+                    ENCODINGS
+                        .into_iter()
+                        .find(|e| e == encoding)
+                        .ok_or_else(|| {
+                            format!("Invalid encoding: {encoding}, expected one of {ENCODINGS:?}")
+                        })?
+                }
+                None => "hex",
+            };
+
+            Command::Secret {
+                name: name(usage)?,
+                length,
+                encoding,
+            }
+        }
+        "age" => {
+            let usage = "age <NAME> [bech32m]";
+            let bech32m = match &args[..] {
+                [_] | [] => false,
+                [_, "bech32m"] => true,
+                _ => return Err(format!("Invalid arguments, usage: {usage}")),
+            };
+
+            Command::Age {
+                name: name(usage)?,
+                bech32m,
+            }
+        }
+        "help" | "?" if args.is_empty() => Command::Help,
+        "quit" | "exit" if args.is_empty() => Command::Quit,
+        "help" | "?" | "quit" | "exit" => {
+            return Err(format!("`{command}` doesn't take any arguments"));
+        }
+        _ => {
+            return Err(format!(
+                "Unknown command: {command}, type `help` for the list"
+            ));
+        }
+    };
+
+    Ok(Some(command))
+}
+
+/// Lines of stdin, read on a separate thread so they can be waited for with a timeout.
+pub(super) struct Input {
+    lines: mpsc::Receiver<io::Result<String>>,
+}
+
+/// Outcome of waiting for the next line.
+pub(super) enum Line {
+    Read(String),
+    Eof,
+    Timeout,
+    /// Ctrl-C or SIGTERM was received, see [`interrupt::Deferred`].
+    Interrupted,
+}
+
+impl Input {
+    pub(super) fn stdin() -> Self {
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { lines: rx }
+    }
+
+    /// Waits for the next line, forever if the `timeout` is `None`.
+    pub(super) fn next(&self, timeout: Option<Duration>) -> io::Result<Line> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+        loop {
+            if interrupt::cancelled() {
+                return Ok(Line::Interrupted);
+            }
+
+            let wait = match deadline {
+                Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                None => INTERRUPT_POLL,
+            };
+
+            match self.lines.recv_timeout(wait.min(INTERRUPT_POLL)) {
+                Ok(line) => return line.map(Line::Read),
+                Err(RecvTimeoutError::Timeout) if wait > INTERRUPT_POLL => {}
+                Err(RecvTimeoutError::Timeout) if deadline.is_none() => {}
+                Err(RecvTimeoutError::Timeout) => return Ok(Line::Timeout),
+                Err(RecvTimeoutError::Disconnected) => return Ok(Line::Eof),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_ok(line: &str) -> Command {
+        parse(line).unwrap().unwrap()
+    }
+
+    #[test]
+    fn secret() {
+        let Command::Secret {
+            name,
+            length,
+            encoding,
+        } = parse_ok("secret db-pass")
+        else {
+            panic!("not a secret");
+        };
+        assert_eq!((name.as_str(), length, encoding), ("db-pass", 32, "hex"));
+
+        let Command::Secret {
+            name,
+            length,
+            encoding,
+        } = parse_ok("  secret   db-pass 16\tbase64 ")
+        else {
+            panic!("not a secret");
+        };
+        assert_eq!((name.as_str(), length, encoding), ("db-pass", 16, "base64"));
+    }
+
+    #[test]
+    fn age() {
+        assert!(matches!(
+            parse_ok("age laptop"),
+            Command::Age { name, bech32m: false } if name == "laptop"
+        ));
+        assert!(matches!(
+            parse_ok("age laptop bech32m"),
+            Command::Age { name, bech32m: true } if name == "laptop"
+        ));
+    }
+
+    #[test]
+    fn other_commands() {
+        assert!(parse("").unwrap().is_none());
+        assert!(parse(" \t ").unwrap().is_none());
+        for line in ["help", "?"] {
+            assert!(matches!(parse_ok(line), Command::Help));
+        }
+        for line in ["quit", "exit", " quit "] {
+            assert!(matches!(parse_ok(line), Command::Quit));
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let err = |line| parse(line).err().unwrap();
+
+        assert!(err("secret").starts_with("Missing name"));
+        assert!(err("age").starts_with("Missing name"));
+        assert_eq!(err("secret name x"), "Invalid length: x");
+        assert!(err("secret name 32 base32").starts_with("Invalid encoding: base32"));
+        assert!(err("secret name 32 hex extra").starts_with("Too many arguments"));
+        assert!(err("age name bech32").starts_with("Invalid arguments"));
+        assert_eq!(err("quit now"), "`quit` doesn't take any arguments");
+        assert!(err("derive name").starts_with("Unknown command: derive"));
+        // Commands are case-sensitive.
+        assert!(err("SECRET name").starts_with("Unknown command"));
+    }
+
+    #[test]
+    fn input() {
+        let (tx, rx) = mpsc::channel();
+        let input = Input { lines: rx };
+        let timeout = Some(Duration::from_millis(10));
+
+        tx.send(Ok("secret name".to_owned())).unwrap();
+        assert!(matches!(input.next(timeout).unwrap(), Line::Read(line) if line == "secret name"));
+
+        let start = Instant::now();
+        assert!(matches!(input.next(timeout).unwrap(), Line::Timeout));
+        assert!(start.elapsed() >= Duration::from_millis(10));
+
+        drop(tx);
+        assert!(matches!(input.next(None).unwrap(), Line::Eof));
+    }
+}
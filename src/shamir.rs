@@ -0,0 +1,376 @@
+//! Shamir's secret sharing over GF(256).
+//!
+//! Shares are bech32m strings (HRP `a2dshare`) encoding:
+//! ```text
+//! threshold (1) | index (1) | id (4) | share bytes
+//! ```
+//! `id` is drawn at random per split, so combining shares of different splits is detected.
+//! The truncated SHA-256 of the secret is appended to it before the split, so it's only recovered
+//! along with the secret and catches corrupted shares that still pass the bech32m checksum.
+//! Fewer shares than the threshold reveal nothing about either.
+
+use anyhow::Context as _;
+use bech32::{FromBase32 as _, ToBase32 as _, Variant};
+use sha2::{Digest as _, Sha256};
+use zeroize::Zeroizing;
+
+const HRP: &str = "a2dshare";
+const ID_LEN: usize = 4;
+const DIGEST_LEN: usize = 4;
+
+struct Share {
+    threshold: u8,
+    index: u8,
+    /// Random identifier of the split.
+    id: [u8; ID_LEN],
+    /// Share of the secret followed by its digest.
+    bytes: Vec<u8>,
+}
+
+/// Splits `secret` into `shares` shares, `threshold` (`1..=shares`) of which reconstruct it.
+pub(super) fn split(secret: &[u8], shares: u8, threshold: u8) -> anyhow::Result<Vec<String>> {
+    // A share without any bytes wouldn't be decodable.
+    if secret.is_empty() {
+        return Err(anyhow::anyhow!("Can't split an empty secret"));
+    }
+
+    // The digest is split along with the secret, so fewer shares than the threshold hide it as well.
+    let mut checked = Zeroizing::new(secret.to_vec());
+    checked.extend_from_slice(&digest(secret));
+
+    // Random polynomial per byte of the secret, with the byte itself as the constant term.
+    let coefficients: Vec<Vec<u8>> = checked
+        .iter()
+        .map(|&byte| {
+            let mut coefs = vec![byte];
+            coefs.extend((1..threshold).map(|_| rand::random::<u8>()));
+            coefs
+        })
+        .collect();
+
+    let id = rand::random();
+
+    (1..=shares)
+        .map(|index| Share {
+            threshold,
+            index,
+            id,
+            bytes: coefficients.iter().map(|c| eval(c, index)).collect(),
+        })
+        .map(|share| share.encode())
+        .collect()
+}
+
+pub(super) fn combine<S: AsRef<str>>(shares: &[S]) -> anyhow::Result<Vec<u8>> {
+    let shares = shares
+        .iter()
+        .map(|s| Share::decode(s.as_ref()))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let first = shares.first().context("No shares provided")?;
+
+    for (i, share) in shares.iter().enumerate() {
+        if share.threshold != first.threshold
+            || share.id != first.id
+            || share.bytes.len() != first.bytes.len()
+        {
+            return Err(anyhow::anyhow!("Shares belong to different splits"));
+        }
+
+        if shares[..i].iter().any(|s| s.index == share.index) {
+            return Err(anyhow::anyhow!("Duplicate share #{}", share.index));
+        }
+    }
+
+    let threshold = first.threshold.into();
+    if shares.len() < threshold {
+        return Err(anyhow::anyhow!(
+            "Not enough shares: {} provided, {threshold} required",
+            shares.len()
+        ));
+    }
+
+    let shares = &shares[..threshold];
+    let mut secret: Vec<u8> = (0..first.bytes.len())
+        .map(|i| interpolate(shares.iter().map(|s| (s.index, s.bytes[i]))))
+        .collect();
+
+    let checksum = secret.split_off(secret.len() - DIGEST_LEN);
+    if digest(&secret) != *checksum {
+        return Err(anyhow::anyhow!(
+            "Reconstructed secret doesn't match its digest, some shares are invalid"
+        ));
+    }
+
+    Ok(secret)
+}
+
+impl Share {
+    fn encode(&self) -> anyhow::Result<String> {
+        let mut data = vec![self.threshold, self.index];
+        data.extend_from_slice(&self.id);
+        data.extend_from_slice(&self.bytes);
+
+        bech32::encode(HRP, data.to_base32(), Variant::Bech32m).context("bech32::encode")
+    }
+
+    fn decode(s: &str) -> anyhow::Result<Self> {
+        let (hrp, data, variant) =
+            bech32::decode(s).with_context(|| format!("Invalid share: {s}"))?;
+
+        if hrp != HRP || variant != Variant::Bech32m {
+            return Err(anyhow::anyhow!("Not a share: {s}"));
+        }
+
+        let data = Vec::<u8>::from_base32(&data).with_context(|| format!("Invalid share: {s}"))?;
+        match data.as_slice() {
+            [threshold, index, rest @ ..] if rest.len() > ID_LEN + DIGEST_LEN && *index != 0 => {
+                let (id, bytes) = rest.split_at(ID_LEN);
+                Ok(Self {
+                    threshold: *threshold,
+                    index: *index,
+                    id: id.try_into().unwrap(),
+                    bytes: bytes.into(),
+                })
+            }
+            _ => Err(anyhow::anyhow!("Malformed share: {s}")),
+        }
+    }
+}
+
+fn digest(secret: &[u8]) -> [u8; DIGEST_LEN] {
+    Sha256::digest(secret)[..DIGEST_LEN].try_into().unwrap()
+}
+
+/// Evaluates the polynomial at `x` (Horner's method).
+fn eval(coefficients: &[u8], x: u8) -> u8 {
+    coefficients
+        .iter()
+        .rev()
+        .fold(0, |acc, &coef| gf_mul(acc, x) ^ coef)
+}
+
+/// Lagrange interpolation at `x = 0`.
+fn interpolate(points: impl Iterator<Item = (u8, u8)> + Clone) -> u8 {
+    points.clone().fold(0, |acc, (xi, yi)| {
+        let basis = points
+            .clone()
+            .filter(|&(xj, _)| xj != xi)
+            .fold(1, |basis, (xj, _)| gf_mul(basis, gf_div(xj, xj ^ xi)));
+
+        acc ^ gf_mul(yi, basis)
+    })
+}
+
+/// Multiplication in GF(2^8) modulo the AES polynomial (x^8 + x^4 + x^3 + x + 1).
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+fn gf_div(a: u8, b: u8) -> u8 {
+    // b^254 == b^-1 in GF(2^8)
+    let inverse = (0..7).fold((1, b), |(acc, pow), _| {
+        let pow = gf_mul(pow, pow);
+        (gf_mul(acc, pow), pow)
+    });
+
+    gf_mul(a, inverse.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"correct horse battery staple";
+
+    /// Every subset of `items`, in the order of the bitmask selecting them.
+    fn subsets<T: Clone>(items: &[T]) -> impl Iterator<Item = Vec<T>> + '_ {
+        (0..1u32 << items.len()).map(move |mask| {
+            items
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| mask & (1 << i) != 0)
+                .map(|(_, item)| item.clone())
+                .collect()
+        })
+    }
+
+    fn error(shares: &[String]) -> String {
+        combine(shares).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn gf_arithmetic() {
+        // FIPS-197 section 4.2 and the inverse of 0x53 from the AES S-box derivation.
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+        assert_eq!(gf_mul(0x57, 0x13), 0xfe);
+        assert_eq!(gf_mul(0x53, 0xca), 0x01);
+
+        for a in 0..=255 {
+            assert_eq!(gf_mul(a, 0), 0);
+            assert_eq!(gf_mul(a, 1), a);
+            for b in 1..=255 {
+                assert_eq!(gf_mul(a, b), gf_mul(b, a));
+                assert_eq!(gf_mul(gf_div(a, b), b), a, "{a} / {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn polynomial_evaluation() {
+        // 1 + 2x + 3x^2
+        let coefficients = [1, 2, 3];
+        assert_eq!(eval(&coefficients, 0), 1);
+        assert_eq!(eval(&coefficients, 1), 1 ^ 2 ^ 3);
+        assert_eq!(
+            eval(&coefficients, 2),
+            1 ^ gf_mul(2, 2) ^ gf_mul(3, gf_mul(2, 2))
+        );
+
+        let points = (1..=3).map(|x| (x, eval(&coefficients, x)));
+        assert_eq!(interpolate(points.collect::<Vec<_>>().into_iter()), 1);
+    }
+
+    #[test]
+    fn round_trip_over_every_subset() {
+        const SHARES: u8 = 5;
+
+        for threshold in 1..=SHARES {
+            let shares = split(SECRET, SHARES, threshold).unwrap();
+            assert_eq!(shares.len(), usize::from(SHARES));
+
+            for subset in subsets(&shares) {
+                if subset.len() >= threshold.into() {
+                    assert_eq!(combine(&subset).unwrap(), SECRET, "{threshold}: {subset:?}");
+                } else if subset.is_empty() {
+                    assert_eq!(error(&subset), "No shares provided");
+                } else {
+                    assert!(error(&subset).starts_with("Not enough shares"));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip_of_a_single_byte() {
+        let shares = split(&[0x42], 3, 2).unwrap();
+        assert_eq!(combine(&shares[1..]).unwrap(), [0x42]);
+    }
+
+    #[test]
+    fn rejects_an_empty_secret() {
+        assert!(split(&[], 3, 2).is_err());
+    }
+
+    #[test]
+    fn rejects_duplicate_shares() {
+        let shares = split(SECRET, 3, 2).unwrap();
+        let duplicate = [shares[1].clone(), shares[1].clone()];
+        assert_eq!(error(&duplicate), "Duplicate share #2");
+    }
+
+    #[test]
+    fn rejects_mixed_shares() {
+        // Same secret and parameters, different splits.
+        let first = split(SECRET, 3, 2).unwrap();
+        let second = split(SECRET, 3, 2).unwrap();
+        assert_eq!(
+            error(&[first[0].clone(), second[1].clone()]),
+            "Shares belong to different splits"
+        );
+
+        let third = split(b"another secret", 3, 2).unwrap();
+        assert_eq!(
+            error(&[first[0].clone(), third[1].clone()]),
+            "Shares belong to different splits"
+        );
+    }
+
+    #[test]
+    fn detects_corrupted_shares() {
+        let shares = split(SECRET, 3, 2).unwrap();
+
+        // Passes the bech32m checksum, as it's encoded anew, but no longer matches the digest.
+        for byte in [0, SECRET.len()] {
+            let mut corrupted = Share::decode(&shares[0]).unwrap();
+            corrupted.bytes[byte] ^= 1;
+            let corrupted = corrupted.encode().unwrap();
+
+            assert_eq!(
+                error(&[corrupted, shares[1].clone()]),
+                "Reconstructed secret doesn't match its digest, some shares are invalid"
+            );
+        }
+
+        // Same split, a threshold not matching the other shares.
+        let mut corrupted = Share::decode(&shares[0]).unwrap();
+        corrupted.threshold = 3;
+        assert_eq!(
+            error(&[corrupted.encode().unwrap(), shares[1].clone()]),
+            "Shares belong to different splits"
+        );
+    }
+
+    #[test]
+    fn shares_carry_no_function_of_the_secret() {
+        let digest = digest(SECRET);
+        let first = split(SECRET, 3, 2).unwrap();
+        let second = split(SECRET, 3, 2).unwrap();
+
+        for (first, second) in first.iter().zip(&second) {
+            let (first, second) = (
+                Share::decode(first).unwrap(),
+                Share::decode(second).unwrap(),
+            );
+            assert_eq!(first.index, second.index);
+            // Everything but the threshold and the index is drawn anew for every split.
+            assert_ne!(first.id, second.id);
+            assert!(first.bytes.iter().zip(&second.bytes).any(|(a, b)| a != b));
+
+            let mut data = first.id.to_vec();
+            data.extend_from_slice(&first.bytes);
+            assert!(!data.windows(DIGEST_LEN).any(|window| window == digest));
+            assert!(!data.windows(SECRET.len()).any(|window| window == SECRET));
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_shares() {
+        let shares = split(SECRET, 3, 2).unwrap();
+
+        let mut typo = shares[0].clone();
+        let last = typo.pop().unwrap();
+        typo.push(if last == 'q' { 'p' } else { 'q' });
+        assert!(error(&[typo]).starts_with("Invalid share"));
+
+        let other_hrp = bech32::encode("age", [0u8; 8].to_base32(), Variant::Bech32m).unwrap();
+        assert!(error(&[other_hrp]).starts_with("Not a share"));
+
+        let bech32 = Share::decode(&shares[0]).unwrap();
+        let mut data = vec![bech32.threshold, bech32.index];
+        data.extend_from_slice(&bech32.id);
+        data.extend_from_slice(&bech32.bytes);
+        let bech32 = bech32::encode(HRP, data.to_base32(), Variant::Bech32).unwrap();
+        assert!(error(&[bech32]).starts_with("Not a share"));
+
+        let zero_index = Share {
+            threshold: 1,
+            index: 0,
+            id: [0; ID_LEN],
+            bytes: [SECRET, &digest(SECRET)].concat(),
+        };
+        assert!(error(&[zero_index.encode().unwrap()]).starts_with("Malformed share"));
+    }
+}
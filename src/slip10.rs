@@ -0,0 +1,301 @@
+//! SLIP-0010 hierarchical deterministic derivation of curve keys (e.g. `m/44'/0'/0'`).
+//!
+//! Only the Curve25519 based curves are supported, which only allow hardened derivation:
+//! Ed25519 as specified by SLIP-0010, and X25519 (`curve25519 seed`) the way Trezor derives it.
+
+use std::{error, fmt, str::FromStr};
+
+use hmac::{Hmac, Mac as _};
+use sha2::Sha512;
+use zeroize::{Zeroize as _, Zeroizing};
+
+/// Bit marking the hardened indices, e.g. `0'` is `HARDENED | 0`.
+pub const HARDENED: u32 = 1 << 31;
+
+/// Maximum depth of a path, as BIP32 stores it in a single byte.
+pub const MAX_DEPTH: usize = 255;
+
+/// Derivation path, e.g. `m/44'/0'/0'` (`h` and `H` are accepted as the hardened markers too).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path(Vec<u32>);
+
+impl Path {
+    /// Child indices, the hardened ones including [`HARDENED`].
+    pub fn indices(&self) -> &[u32] {
+        &self.0
+    }
+
+    /// Checks that the curve supports the path, i.e. that all of its indices are hardened.
+    pub fn check(&self, curve: Curve) -> Result<(), PathError> {
+        match self.0.iter().find(|&&index| index < HARDENED) {
+            Some(&index) => Err(PathError::NotHardened { curve, index }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Curve {
+    Ed25519,
+    /// X25519, e.g. age keys.
+    Curve25519,
+}
+
+impl Curve {
+    /// HMAC key deriving the master node from the seed.
+    fn seed_key(self) -> &'static [u8] {
+        match self {
+            Self::Ed25519 => b"ed25519 seed",
+            Self::Curve25519 => b"curve25519 seed",
+        }
+    }
+}
+
+impl fmt::Display for Curve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ed25519 => f.write_str("Ed25519"),
+            Self::Curve25519 => f.write_str("X25519"),
+        }
+    }
+}
+
+/// Errors of parsing or deriving a [`Path`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    MissingRoot,
+    InvalidIndex(String),
+    IndexTooLarge(String),
+    TooDeep,
+    NotHardened { curve: Curve, index: u32 },
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingRoot => {
+                f.write_str("Derivation path should start with `m`, e.g. `m/44'/0'`")
+            }
+            Self::InvalidIndex(index) => write!(
+                f,
+                "Invalid index `{index}`, expected a number optionally followed by `'`, `h` or `H`"
+            ),
+            Self::IndexTooLarge(index) => {
+                write!(f, "Index `{index}` is too large, should be < 2^31")
+            }
+            Self::TooDeep => write!(f, "Derivation path is too deep, should be <= {MAX_DEPTH}"),
+            Self::NotHardened { curve, index } => write!(
+                f,
+                "{curve} only supports hardened derivation, use `{index}'` instead of `{index}`"
+            ),
+        }
+    }
+}
+
+impl error::Error for PathError {}
+
+impl FromStr for Path {
+    type Err = PathError;
+
+    fn from_str(s: &str) -> Result<Self, PathError> {
+        let mut parts = s.split('/');
+        if parts.next() != Some("m") {
+            return Err(PathError::MissingRoot);
+        }
+
+        let indices = parts
+            .map(|part| {
+                let (digits, hardened) = match part.strip_suffix(['\'', 'h', 'H']) {
+                    Some(digits) => (digits, true),
+                    None => (part, false),
+                };
+
+                if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(PathError::InvalidIndex(part.to_owned()));
+                }
+
+                let index = digits
+                    .parse()
+                    .ok()
+                    .filter(|&index| index < HARDENED)
+                    .ok_or_else(|| PathError::IndexTooLarge(part.to_owned()))?;
+
+                Ok(if hardened { HARDENED | index } else { index })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if indices.len() > MAX_DEPTH {
+            return Err(PathError::TooDeep);
+        }
+
+        Ok(Self(indices))
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("m")?;
+        for &index in &self.0 {
+            match index & HARDENED {
+                0 => write!(f, "/{index}")?,
+                _ => write!(f, "/{}'", index & !HARDENED)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Extended private key, wiped on drop.
+pub struct Node {
+    pub private_key: [u8; 32],
+    pub chain_code: [u8; 32],
+}
+
+impl Node {
+    fn from_hmac(key: &[u8], data: &[u8]) -> Self {
+        let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts keys of any length");
+        mac.update(data);
+        let output = Zeroizing::new(<[u8; 64]>::from(mac.finalize().into_bytes()));
+
+        Self {
+            private_key: output[..32].try_into().unwrap(),
+            chain_code: output[32..].try_into().unwrap(),
+        }
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        self.private_key.zeroize();
+        self.chain_code.zeroize();
+    }
+}
+
+/// Derives the private key of the path from the seed (16 to 64 bytes) as specified by SLIP-0010.
+///
+/// Test vector 1 of SLIP-0010 for Ed25519:
+///
+/// ```
+/// use argon2derive::slip10::{Curve, Path, derive};
+///
+/// let seed = hex::decode("000102030405060708090a0b0c0d0e0f")?;
+/// let vectors = [
+///     ("m", "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"),
+///     ("m/0'", "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"),
+///     ("m/0'/1'", "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2"),
+///     ("m/0H/1H/2H", "92a5b23c0b8a99e37d07df3fb9966917f5d06e02ddbd909c7e184371463e9fc9"),
+///     ("m/0'/1'/2'/2'", "30d1dc7e5fc04c31219ab25a27ae00b50f6fd66622f6e9c913253d6511d1e662"),
+///     ("m/0'/1'/2'/2'/1000000000'", "8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793"),
+/// ];
+/// for (path, private_key) in vectors {
+///     let node = derive(Curve::Ed25519, &seed, &path.parse()?)?;
+///     assert_eq!(hex::encode(node.private_key), private_key);
+/// }
+///
+/// let node = derive(Curve::Ed25519, &seed, &"m/0'/1'/2'/2'/1000000000'".parse()?)?;
+/// assert_eq!(
+///     hex::encode(node.chain_code),
+///     "68789923a0cac2cd5a29172a475fe9e0fb14cd6adb5ad98a3fa70333e7afa230"
+/// );
+///
+/// // Non-hardened indices aren't defined for Ed25519.
+/// assert!(derive(Curve::Ed25519, &seed, &"m/0'/1".parse()?).is_err());
+/// assert!("m/2147483648'".parse::<Path>().is_err());
+/// assert!("44'/0'".parse::<Path>().is_err());
+/// # anyhow::Ok(())
+/// ```
+pub fn derive(curve: Curve, seed: &[u8], path: &Path) -> Result<Node, PathError> {
+    path.check(curve)?;
+
+    let mut node = Node::from_hmac(curve.seed_key(), seed);
+    for &index in &path.0 {
+        // `0x00 || private key || index (u32 BE)`
+        let mut data = Zeroizing::new([0; 37]);
+        data[1..33].copy_from_slice(&node.private_key);
+        data[33..].copy_from_slice(&index.to_be_bytes());
+        node = Node::from_hmac(&node.chain_code, data.as_slice());
+    }
+
+    Ok(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+    #[test]
+    fn curve25519_vectors() {
+        // Test vector 1 of SLIP-0010 for Curve25519.
+        let vectors = [
+            (
+                "m",
+                "d70a59c2e68b836cc4bbe8bcae425169b9e2384f3905091e3d60b890e90cd92c",
+                "77997ca3588a1a34f3589279ea2962247abfe5277d52770a44c706378c710768",
+            ),
+            (
+                "m/0'",
+                "cd7630d7513cbe80515f7317cdb9a47ad4a56b63c3f1dc29583ab8d4cc25a9b2",
+                "349a3973aad771c628bf1f1b4d5e071f18eff2e492e4aa7972a7e43895d6597f",
+            ),
+            (
+                "m/0'/1'",
+                "a95f97cfc1a61dd833b882c89d36a78a030ea6b2fbe3ae2a70e4f1fc9008d6b1",
+                "2ee5ba14faf2fe9d7ab532451c2be3a0a5375c5e8c44fb31d9ad7edc25cda000",
+            ),
+        ];
+        for (path, private_key, chain_code) in vectors {
+            let node = derive(Curve::Curve25519, &SEED, &path.parse().unwrap()).unwrap();
+            assert_eq!(hex::encode(node.private_key), private_key, "{path}");
+            assert_eq!(hex::encode(node.chain_code), chain_code, "{path}");
+        }
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let path: Path = "m/44'/0h/1H/2147483647'".parse().unwrap();
+        assert_eq!(
+            path.indices(),
+            [
+                HARDENED | 44,
+                HARDENED,
+                HARDENED | 1,
+                HARDENED | (HARDENED - 1)
+            ]
+        );
+        assert_eq!(path.to_string(), "m/44'/0'/1'/2147483647'");
+        assert_eq!("m".parse::<Path>().unwrap().indices(), []);
+        assert_eq!("m/0/1'".parse::<Path>().unwrap().to_string(), "m/0/1'");
+
+        let deepest = format!("m{}", "/0'".repeat(MAX_DEPTH));
+        assert!(deepest.parse::<Path>().is_ok());
+        assert_eq!(
+            format!("{deepest}/0'").parse::<Path>(),
+            Err(PathError::TooDeep)
+        );
+    }
+
+    #[test]
+    fn invalid_paths() {
+        let err = |s: &str| s.parse::<Path>().unwrap_err();
+        assert_eq!(err(""), PathError::MissingRoot);
+        assert_eq!(err("0'/1'"), PathError::MissingRoot);
+        assert_eq!(err("m/"), PathError::InvalidIndex(String::new()));
+        assert_eq!(err("m/'"), PathError::InvalidIndex("'".to_owned()));
+        assert_eq!(err("m/-1'"), PathError::InvalidIndex("-1'".to_owned()));
+        assert_eq!(err("m/1''"), PathError::InvalidIndex("1''".to_owned()));
+        assert_eq!(
+            err("m/2147483648"),
+            PathError::IndexTooLarge("2147483648".to_owned())
+        );
+
+        let path: Path = "m/0'/7".parse().unwrap();
+        for curve in [Curve::Ed25519, Curve::Curve25519] {
+            assert_eq!(
+                path.check(curve),
+                Err(PathError::NotHardened { curve, index: 7 })
+            );
+        }
+    }
+}
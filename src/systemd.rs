@@ -0,0 +1,58 @@
+//! Integration with the [systemd credentials](https://systemd.io/CREDENTIALS/).
+
+use std::env;
+use std::io::{self, Write as _};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use anyhow::Context as _;
+
+const SYSTEMD_CREDS: &str = "systemd-creds";
+
+/// Path of the credential of the provided name within `$CREDENTIALS_DIRECTORY`.
+pub(super) fn credential_path(name: &str) -> anyhow::Result<PathBuf> {
+    if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+        return Err(anyhow::anyhow!("Invalid credential name: {name:?}"));
+    }
+
+    let dir = env::var_os("CREDENTIALS_DIRECTORY")
+        .context("$CREDENTIALS_DIRECTORY is not set, is the command run by systemd?")?;
+
+    Ok(PathBuf::from(dir).join(name))
+}
+
+/// Encrypts the credential using `systemd-creds encrypt`, returning its output.
+///
+/// The plaintext is only passed via a pipe, it never touches the disk.
+pub(super) fn encrypt(name: &str, plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut child = Command::new(SYSTEMD_CREDS)
+        .arg("encrypt")
+        .arg(format!("--name={name}"))
+        .args(["-", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => {
+                anyhow::anyhow!("`{SYSTEMD_CREDS}` not found, is systemd installed?")
+            }
+            _ => anyhow::Error::new(err).context("spawn systemd-creds"),
+        })?;
+
+    // The plaintext is small enough to fit into the pipe buffer, so it can be written before reading the output.
+    let mut stdin = child.stdin.take().expect("stdin should be piped");
+    stdin
+        .write_all(plaintext)
+        .context("write to systemd-creds")?;
+    drop(stdin);
+
+    let output = child.wait_with_output().context("wait for systemd-creds")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "`{SYSTEMD_CREDS} encrypt` failed ({})",
+            output.status
+        ));
+    }
+
+    Ok(output.stdout)
+}
@@ -0,0 +1,104 @@
+//! Tabular output: CSV ([RFC 4180](https://www.rfc-editor.org/rfc/rfc4180))
+//! and TSV ([IANA](https://www.iana.org/assignments/media-types/text/tab-separated-values)).
+
+/// Renders a record including the line terminator (CRLF for CSV, LF for TSV).
+///
+/// CSV fields containing a comma, a quote or a line break are quoted, quotes being doubled.
+/// TSV has no quoting, so fields containing a tab or a line break are rejected.
+pub(super) fn row(fields: &[&str], format: &str) -> anyhow::Result<String> {
+    match format {
+        "csv" => {
+            let fields: Vec<_> = fields.iter().map(|field| csv_field(field)).collect();
+            Ok(fields.join(",") + "\r\n")
+        }
+        "tsv" => {
+            // The field isn't included in the error, as it might be a secret.
+            if fields.iter().any(|f| f.contains(['\t', '\n', '\r'])) {
+                return Err(anyhow::anyhow!(
+                    "TSV fields can't contain tabs or line breaks, use CSV instead"
+                ));
+            }
+            Ok(fields.join("\t") + "\n")
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses CSV records the way RFC 4180 defines them, independently of [`row`].
+    fn parse_csv(csv: &str) -> Vec<Vec<String>> {
+        let mut records = vec![];
+        let (mut record, mut field) = (vec![], String::new());
+        let mut chars = csv.chars().peekable();
+        let mut quoted = false;
+        while let Some(c) = chars.next() {
+            match (quoted, c) {
+                (true, '"') if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                (true, '"') => quoted = false,
+                (true, c) => field.push(c),
+                (false, '"') => {
+                    assert!(field.is_empty(), "quote within an unquoted field");
+                    quoted = true;
+                }
+                (false, ',') => record.push(std::mem::take(&mut field)),
+                (false, '\r') => {
+                    assert_eq!(chars.next(), Some('\n'));
+                    record.push(std::mem::take(&mut field));
+                    records.push(std::mem::take(&mut record));
+                }
+                (false, c) => field.push(c),
+            }
+        }
+        assert!(
+            !quoted && field.is_empty() && record.is_empty(),
+            "unterminated record"
+        );
+        records
+    }
+
+    #[test]
+    fn csv_quoting() {
+        assert_eq!(row(&["a", "b"], "csv").unwrap(), "a,b\r\n");
+        assert_eq!(row(&["a,b"], "csv").unwrap(), "\"a,b\"\r\n");
+        assert_eq!(
+            row(&["say \"hi\""], "csv").unwrap(),
+            "\"say \"\"hi\"\"\"\r\n"
+        );
+        assert_eq!(row(&["two\nlines"], "csv").unwrap(), "\"two\nlines\"\r\n");
+        assert_eq!(row(&["cr\r"], "csv").unwrap(), "\"cr\r\"\r\n");
+        assert_eq!(row(&["", "tab\t"], "csv").unwrap(), ",tab\t\r\n");
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let records = [
+            ["plain", "\"", "\"\"", ","],
+            ["a,\"b\"\nc", "\r\n", " padded ", ""],
+        ];
+        let csv: String = records.iter().map(|r| row(r, "csv").unwrap()).collect();
+        assert_eq!(parse_csv(&csv), records.map(|r| r.map(str::to_owned)));
+    }
+
+    #[test]
+    fn tsv() {
+        assert_eq!(row(&["a,b", "\"c\""], "tsv").unwrap(), "a,b\t\"c\"\n");
+        for field in ["tab\t", "line\n", "cr\r"] {
+            let err = row(&["name", field], "tsv").unwrap_err().to_string();
+            assert!(!err.contains(field));
+        }
+    }
+}
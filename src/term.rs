@@ -0,0 +1,131 @@
+//! Human-readable output to stderr.
+//!
+//! Styles are only applied if stderr is a terminal, unless disabled via `--no-color` or `NO_COLOR`.
+//! Stdout, carrying the secrets, is never styled and isn't written to by this module.
+
+use std::env;
+use std::fmt::Display;
+use std::io::{self, IsTerminal as _, Write as _};
+use std::sync::OnceLock;
+
+use anstyle::{AnsiColor, Color, Style};
+
+const HEADER: Style = Style::new().bold();
+const DETAIL: Style = Style::new().dimmed();
+const WARNING: Style = Style::new().fg_color(Some(Color::Ansi(AnsiColor::Yellow)));
+const ERROR: Style = Style::new().fg_color(Some(Color::Ansi(AnsiColor::Red)));
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+#[derive(Default)]
+struct Settings {
+    color: bool,
+    quiet: bool,
+    no_warn: bool,
+}
+
+/// Configures the output, should be called once before anything gets printed.
+pub(super) fn init(no_color: bool, quiet: bool, no_warn: bool) {
+    let no_color = no_color || env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+
+    let _ = SETTINGS.set(Settings {
+        color: !no_color && io::stderr().is_terminal(),
+        quiet,
+        no_warn,
+    });
+}
+
+fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(Settings::default)
+}
+
+fn paint(style: Style, msg: impl Display) -> String {
+    render(settings().color, style, msg)
+}
+
+fn render(color: bool, style: Style, msg: impl Display) -> String {
+    if color {
+        format!("{}{msg}{}", style.render(), style.render_reset())
+    } else {
+        msg.to_string()
+    }
+}
+
+/// Prints a header introducing the following output, e.g. `Secret:`, unless `--quiet` is specified.
+pub(super) fn header(msg: impl Display) {
+    if !settings().quiet {
+        eprintln!("\n{}", paint(HEADER, msg));
+    }
+}
+
+/// Prints a secondary detail, e.g. a parameter value, unless `--quiet` is specified.
+pub(super) fn detail(msg: impl Display) {
+    if !settings().quiet {
+        eprintln!("{}", paint(DETAIL, msg));
+    }
+}
+
+/// Prints a message about the outcome of an operation, unless `--quiet` is specified.
+pub(super) fn info(msg: impl Display) {
+    if !settings().quiet {
+        eprintln!("{msg}");
+    }
+}
+
+/// Prints a progress message, unless `--quiet` is specified.
+pub(super) fn progress(msg: impl Display) {
+    info(msg);
+}
+
+/// Prints a transient status line (e.g. a countdown) over the previous one,
+/// only if stderr is a terminal and `--quiet` isn't specified.
+pub(super) fn status(msg: impl Display) -> io::Result<()> {
+    if settings().quiet || !io::stderr().is_terminal() {
+        return Ok(());
+    }
+
+    eprint!("\r\x1b[2K{}", paint(DETAIL, msg));
+    io::stderr().flush()
+}
+
+/// Prints a warning, unless `--no-warn` is specified.
+pub(super) fn warn(msg: impl Display) {
+    if !settings().no_warn {
+        eprintln!("\n{}", paint(WARNING, format!("WARNING: {msg}")));
+    }
+}
+
+pub(super) fn error(msg: impl Display) {
+    eprintln!("{}", paint(ERROR, msg));
+}
+
+/// Prints machine-readable output as is, regardless of the settings.
+pub(super) fn raw(msg: impl Display) {
+    eprintln!("{msg}");
+}
+
+/// Prints a prompt, leaving the cursor on the same line.
+pub(super) fn prompt(msg: impl Display) -> io::Result<()> {
+    eprint!("\n{} ", paint(HEADER, msg));
+    io::stderr().flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn styles() {
+        let cases = [
+            (HEADER, "Secret:", "\x1b[1mSecret:\x1b[0m"),
+            (DETAIL, "Memory: 1 GiB", "\x1b[2mMemory: 1 GiB\x1b[0m"),
+            (WARNING, "WARNING: x", "\x1b[33mWARNING: x\x1b[0m"),
+            (ERROR, "Error: x", "\x1b[31mError: x\x1b[0m"),
+        ];
+
+        for (style, msg, colored) in cases {
+            assert_eq!(render(true, style, msg), colored);
+            assert_eq!(render(false, style, msg), msg);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! Measurements of the derivation time, shared by `tune` and `--time-budget`.
+
+use std::time::{Duration, Instant};
+
+use crate::argon2;
+
+/// Only the costs affect the derivation time, so the measurements don't need the real inputs.
+const DUMMY_PASSPHRASE: &[u8] = b"argon2derive tune";
+const DUMMY_SALT: &[u8] = b"argon2derive tune";
+
+/// Measures the derivation time using the costs of the parameters.
+pub(super) fn measure(params: &argon2::Parameters) -> anyhow::Result<Duration> {
+    let params = argon2::Parameters {
+        salt: DUMMY_SALT.into(),
+        ..params.clone()
+    };
+
+    let start = Instant::now();
+    argon2::hash(&params, DUMMY_PASSPHRASE, 32)?;
+    Ok(start.elapsed())
+}
+
+/// Picks the largest time cost (at least 1) fitting the budget, along with the duration of the probe.
+///
+/// A single iteration is measured and the time cost is assumed to scale linearly. The probe includes
+/// the allocation of the memory, which makes the estimate slightly conservative.
+pub(super) fn fit_time(
+    params: &argon2::Parameters,
+    budget: Duration,
+) -> anyhow::Result<(u32, Duration)> {
+    let probe = measure(&argon2::Parameters {
+        time: 1,
+        ..params.clone()
+    })?;
+
+    Ok((scale_time(budget, probe), probe))
+}
+
+/// Number of iterations (at least 1) taking `probe` each fitting the budget.
+fn scale_time(budget: Duration, probe: Duration) -> u32 {
+    let time = budget.as_secs_f64() / probe.as_secs_f64().max(f64::EPSILON);
+    (time as u32).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaled_time() {
+        let ms = Duration::from_millis;
+        assert_eq!(scale_time(ms(1000), ms(100)), 10);
+        assert_eq!(scale_time(ms(1099), ms(100)), 10);
+        assert_eq!(scale_time(ms(50), ms(100)), 1);
+        assert_eq!(scale_time(ms(1000), Duration::ZERO), u32::MAX);
+    }
+
+    #[test]
+    fn fits_the_budget() {
+        let params = argon2::Parameters {
+            algorithm: argon2::Algorithm::Argon2id,
+            memory: 64,
+            time: 7,
+            parallelism: 1,
+            salt: Vec::new(),
+        };
+        let (time, probe) = fit_time(&params, Duration::from_secs(1)).unwrap();
+        assert!(time >= 1);
+        assert!(probe < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,308 @@
+//! Interactive tuning of the Argon2 parameters.
+
+use std::io::{self, IsTerminal as _};
+use std::thread;
+use std::time::Duration;
+
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::Stylize as _,
+    text::Line,
+    widgets::{Block, Paragraph},
+};
+use sysinfo::{CpuRefreshKind, RefreshKind, System};
+
+use crate::argon2;
+use crate::error::ErrorKind;
+use crate::timing;
+
+const FIELDS: [&str; 3] = ["Memory", "Time", "Parallelism"];
+
+/// Parameters to start tuning from if neither the CLI nor the config provide any.
+pub(super) fn default_params() -> argon2::Parameters {
+    argon2::Parameters {
+        algorithm: argon2::Algorithm::default(),
+        memory: argon2::MIN_RECOMMENDED_MEMORY,
+        time: 3,
+        parallelism: thread::available_parallelism().map_or(1, |n| n.get() as u32),
+        salt: Vec::new(),
+    }
+}
+
+/// Runs the TUI, returning the parameters to be saved, if any.
+pub(super) fn run(params: argon2::Parameters) -> anyhow::Result<Option<argon2::Parameters>> {
+    if !io::stdout().is_terminal() {
+        return Err(ErrorKind::Usage.error("`tune` can only be used in an interactive terminal"));
+    }
+
+    let mut terminal = ratatui::init();
+    let result = Tuner::new(params, cpu_model()).run(&mut terminal);
+    ratatui::restore();
+
+    result
+}
+
+/// Brand of the CPU, reported along with the measurements so they can be compared across machines.
+fn cpu_model() -> Option<String> {
+    let system =
+        System::new_with_specifics(RefreshKind::nothing().with_cpu(CpuRefreshKind::nothing()));
+    let brand = system.cpus().first()?.brand().trim();
+    (!brand.is_empty()).then(|| brand.to_owned())
+}
+
+/// Work done per second of the derivation, in MiB × iterations.
+///
+/// Normalizes the measurements, so machines can be compared regardless of the tested parameters.
+fn throughput(params: &argon2::Parameters, duration: Duration) -> f64 {
+    let work = f64::from(params.memory) / 1024.0 * f64::from(params.time);
+    work / duration.as_secs_f64()
+}
+
+struct Tuner {
+    params: argon2::Parameters,
+    cpu_model: Option<String>,
+    selected: usize,
+    /// Derivation time using the current parameters, `None` if it wasn't measured yet.
+    measurement: Option<Result<Duration, String>>,
+}
+
+impl Tuner {
+    fn new(params: argon2::Parameters, cpu_model: Option<String>) -> Self {
+        Self {
+            params,
+            cpu_model,
+            selected: 0,
+            measurement: None,
+        }
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal) -> anyhow::Result<Option<argon2::Parameters>> {
+        loop {
+            if self.measurement.is_none() {
+                terminal.draw(|frame| self.draw(frame))?;
+                self.measurement = Some(self.measure());
+            }
+
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(None),
+                KeyCode::Char('s') | KeyCode::Enter => return Ok(Some(self.params)),
+                KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.selected = (self.selected + 1).min(FIELDS.len() - 1);
+                }
+                KeyCode::Left | KeyCode::Char('h') => self.adjust(false),
+                KeyCode::Right | KeyCode::Char('l') => self.adjust(true),
+                _ => {}
+            }
+        }
+    }
+
+    /// Decreases or increases the selected parameter.
+    ///
+    /// Memory cost gets halved / doubled, the others are changed by one.
+    fn adjust(&mut self, increase: bool) {
+        let params = &mut self.params;
+        let step = |value: u32| match increase {
+            true => value.saturating_add(1),
+            false => value.saturating_sub(1).max(1),
+        };
+
+        match self.selected {
+            0 if increase => params.memory = params.memory.saturating_mul(2),
+            0 => params.memory /= 2,
+            1 => params.time = step(params.time),
+            _ => params.parallelism = step(params.parallelism),
+        }
+
+        // Argon2 requires at least 8 KiB of memory per thread.
+        params.memory = params.memory.max(8 * params.parallelism);
+        self.measurement = None;
+    }
+
+    fn measure(&self) -> Result<Duration, String> {
+        timing::measure(&self.params).map_err(|err| format!("{err:#}"))
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [params_area, result_area, help_area] = Layout::vertical([
+            Constraint::Length(FIELDS.len() as u16 + 2),
+            Constraint::Length(4),
+            Constraint::Min(1),
+        ])
+        .areas(frame.area());
+
+        let values = [
+            argon2::format_memory(self.params.memory),
+            format!("{} (iterations)", self.params.time),
+            format!("{} (lanes)", self.params.parallelism),
+        ];
+
+        let lines: Vec<_> = FIELDS
+            .iter()
+            .zip(values)
+            .enumerate()
+            .map(|(idx, (name, value))| {
+                let line = Line::from(format!("{name:<12} ◀ {value} ▶"));
+                if idx == self.selected {
+                    line.reversed()
+                } else {
+                    line
+                }
+            })
+            .collect();
+
+        let title = format!(" {} ", self.params.algorithm);
+        let params = Paragraph::new(lines).block(Block::bordered().title(title));
+        frame.render_widget(params, params_area);
+
+        let result = match &self.measurement {
+            None => vec![Line::from("Measuring...")],
+            Some(Ok(duration)) => vec![
+                Line::from(format!("Derivation time: {duration:.2?}")),
+                Line::from(format!(
+                    "Throughput: {:.0} MiB × iterations / s",
+                    throughput(&self.params, *duration)
+                )),
+            ],
+            Some(Err(err)) => vec![Line::from(format!("Derivation failed: {err}"))],
+        };
+        let title = match &self.cpu_model {
+            Some(cpu) => format!(" Result ({cpu}) "),
+            None => " Result ".to_owned(),
+        };
+        let result = Paragraph::new(result).block(Block::bordered().title(title));
+        frame.render_widget(result, result_area);
+
+        let help = Paragraph::new("↑/↓ select  ←/→ adjust  s/Enter save  q/Esc quit");
+        frame.render_widget(help, help_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tuner(memory: u32, time: u32, parallelism: u32) -> Tuner {
+        let params = argon2::Parameters {
+            memory,
+            time,
+            parallelism,
+            ..default_params()
+        };
+        Tuner::new(params, None)
+    }
+
+    fn costs(tuner: &Tuner) -> (u32, u32, u32) {
+        let params = &tuner.params;
+        (params.memory, params.time, params.parallelism)
+    }
+
+    #[test]
+    fn adjusts_the_memory() {
+        let mut tuner = tuner(64 * 1024, 3, 4);
+        tuner.measurement = Some(Ok(Duration::ZERO));
+
+        tuner.adjust(true);
+        assert_eq!(costs(&tuner), (128 * 1024, 3, 4));
+        assert!(tuner.measurement.is_none(), "measured anew");
+
+        tuner.adjust(false);
+        tuner.adjust(false);
+        assert_eq!(costs(&tuner), (32 * 1024, 3, 4));
+
+        // Never below 8 KiB per lane.
+        let mut tuner = self::tuner(64, 1, 4);
+        tuner.adjust(false);
+        assert_eq!(costs(&tuner), (32, 1, 4));
+        tuner.adjust(false);
+        assert_eq!(costs(&tuner), (32, 1, 4));
+
+        let mut tuner = self::tuner(u32::MAX, 1, 1);
+        tuner.adjust(true);
+        assert_eq!(costs(&tuner), (u32::MAX, 1, 1));
+    }
+
+    #[test]
+    fn adjusts_the_time_and_parallelism() {
+        let mut tuner = tuner(64, 1, 1);
+
+        tuner.selected = 1;
+        tuner.adjust(true);
+        assert_eq!(costs(&tuner), (64, 2, 1));
+        tuner.adjust(false);
+        tuner.adjust(false);
+        assert_eq!(costs(&tuner), (64, 1, 1));
+
+        tuner.selected = 2;
+        for _ in 0..9 {
+            tuner.adjust(true);
+        }
+        // The memory follows the lanes.
+        assert_eq!(costs(&tuner), (80, 1, 10));
+        tuner.adjust(false);
+        assert_eq!(costs(&tuner), (80, 1, 9));
+    }
+
+    #[test]
+    fn throughput_is_normalized() {
+        let params = tuner(1024 * 1024, 2, 1).params;
+        assert_eq!(throughput(&params, Duration::from_secs(4)), 512.0);
+        assert_eq!(throughput(&params, Duration::from_millis(500)), 4096.0);
+    }
+
+    /// Text of the frame drawn by the tuner, a line per row.
+    fn render(tuner: &Tuner) -> String {
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(60, 12)).unwrap();
+        terminal.draw(|frame| tuner.draw(frame)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let area = buffer.area;
+        (0..area.height)
+            .map(|y| {
+                let row: String = (0..area.width).map(|x| buffer[(x, y)].symbol()).collect();
+                row.trim_end().to_owned() + "\n"
+            })
+            .collect()
+    }
+
+    #[test]
+    fn draws_the_report() {
+        let mut tuner = tuner(1024 * 1024, 2, 4);
+        tuner.cpu_model = Some("Test CPU".to_owned());
+        assert!(render(&tuner).contains("Measuring..."));
+
+        tuner.measurement = Some(Ok(Duration::from_secs(4)));
+        let frame = render(&tuner);
+        for text in [
+            "4 (lanes)",
+            " Result (Test CPU) ",
+            "Derivation time: 4.00s",
+            "Throughput: 512 MiB × iterations / s",
+        ] {
+            assert!(frame.contains(text), "{text}: {frame}");
+        }
+
+        tuner.cpu_model = None;
+        tuner.measurement = Some(Err("out of memory".to_owned()));
+        let frame = render(&tuner);
+        assert!(frame.contains(" Result "), "{frame}");
+        assert!(
+            frame.contains("Derivation failed: out of memory"),
+            "{frame}"
+        );
+        assert!(!frame.contains("Throughput"), "{frame}");
+    }
+}
@@ -0,0 +1,255 @@
+//! Paper-backup friendly encoding of bytes as BIP39 (English) words.
+//!
+//! Every word carries 11 bits. The first data word holds the byte length, followed by the
+//! bytes themselves (zero-padded to a whole word). Data words are split into groups of
+//! `GROUP_LEN`, each followed by a checksum word: the first 11 bits of
+//! `SHA-256(group number || group words)`, so a transcription error can be located.
+//! Every group is printed on its own numbered line.
+
+use anyhow::Context as _;
+use bip39::Language;
+use sha2::{Digest as _, Sha256};
+
+const GROUP_LEN: usize = 4;
+const BITS_PER_WORD: usize = 11;
+const MAX_LEN: usize = (1 << BITS_PER_WORD) - 1;
+
+pub(super) fn encode(bytes: &[u8]) -> anyhow::Result<String> {
+    if bytes.len() > MAX_LEN {
+        return Err(anyhow::anyhow!(
+            "Words encoding supports up to {MAX_LEN} bytes"
+        ));
+    }
+
+    let mut indices = vec![bytes.len() as u16];
+    indices.extend(to_indices(bytes));
+
+    let wordlist = Language::English.word_list();
+    let lines: Vec<String> = indices
+        .chunks(GROUP_LEN)
+        .enumerate()
+        .map(|(n, group)| {
+            let words = group
+                .iter()
+                .chain([&checksum(n, group)])
+                .map(|&idx| wordlist[usize::from(idx)])
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            format!("{:>2}: {words}", n + 1)
+        })
+        .collect();
+
+    Ok(lines.join("\n"))
+}
+
+/// Decodes words produced by [`encode`], ignoring the line numbers.
+pub(super) fn decode(s: &str) -> anyhow::Result<Vec<u8>> {
+    let indices = s
+        .split_whitespace()
+        .filter(|token| !is_line_number(token))
+        .enumerate()
+        .map(|(pos, word)| {
+            Language::English
+                .find_word(&word.to_lowercase())
+                .with_context(|| format!("Unknown word #{}: {word}", pos + 1))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut data = Vec::with_capacity(indices.len());
+    let mut failed = Vec::new();
+    for (n, group) in indices.chunks(GROUP_LEN + 1).enumerate() {
+        let (check, group) = group.split_last().context("Empty input")?;
+        if group.is_empty() || *check != checksum(n, group) {
+            failed.push((n + 1).to_string());
+        }
+        data.extend_from_slice(group);
+    }
+
+    if !failed.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Checksum mismatch in group(s): {}",
+            failed.join(", ")
+        ));
+    }
+
+    let (len, data) = data.split_first().context("Empty input")?;
+    let len = usize::from(*len);
+
+    let bytes = from_indices(data);
+    // Padding to a whole word may produce at most one extra byte.
+    if bytes.len() < len || bytes.len() > len + 1 {
+        return Err(anyhow::anyhow!("Length doesn't match the number of words"));
+    }
+
+    Ok(bytes[..len].to_vec())
+}
+
+fn is_line_number(token: &str) -> bool {
+    token
+        .strip_suffix(':')
+        .is_some_and(|n| n.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn checksum(group_number: usize, group: &[u16]) -> u16 {
+    let mut hasher = Sha256::new();
+    hasher.update((group_number as u32).to_be_bytes());
+    for idx in group {
+        hasher.update(idx.to_be_bytes());
+    }
+
+    let hash = hasher.finalize();
+    u16::from_be_bytes([hash[0], hash[1]]) >> (16 - BITS_PER_WORD)
+}
+
+fn to_indices(bytes: &[u8]) -> Vec<u16> {
+    let mut indices = Vec::with_capacity((bytes.len() * 8).div_ceil(BITS_PER_WORD));
+    let (mut acc, mut bits) = (0u32, 0);
+
+    for &byte in bytes {
+        acc = (acc << 8) | u32::from(byte);
+        bits += 8;
+
+        if bits >= BITS_PER_WORD {
+            bits -= BITS_PER_WORD;
+            indices.push((acc >> bits) as u16 & 0x7ff);
+        }
+    }
+
+    if bits > 0 {
+        indices.push((acc << (BITS_PER_WORD - bits)) as u16 & 0x7ff);
+    }
+
+    indices
+}
+
+fn from_indices(indices: &[u16]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(indices.len() * BITS_PER_WORD / 8);
+    let (mut acc, mut bits) = (0u32, 0);
+
+    for &idx in indices {
+        acc = (acc << BITS_PER_WORD) | u32::from(idx);
+        bits += BITS_PER_WORD;
+
+        while bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+    }
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 37 + 11) as u8).collect()
+    }
+
+    #[track_caller]
+    fn round_trip(bytes: &[u8]) {
+        let words = encode(bytes).unwrap();
+        assert_eq!(decode(&words).unwrap(), bytes, "{words}");
+    }
+
+    /// Replaces the word at `pos` (counting the checksum words, ignoring the line numbers).
+    fn replace_word(words: &str, pos: usize) -> String {
+        let wordlist = Language::English.word_list();
+        let mut tokens: Vec<_> = words
+            .split_whitespace()
+            .filter(|token| !is_line_number(token))
+            .collect();
+
+        let idx = usize::from(Language::English.find_word(tokens[pos]).unwrap());
+        tokens[pos] = wordlist[(idx + 1) % wordlist.len()];
+        tokens.join(" ")
+    }
+
+    #[test]
+    fn round_trips() {
+        for len in [0, 1, 2, 3, 16, 32, 33, 64, MAX_LEN] {
+            round_trip(&bytes(len));
+        }
+
+        round_trip(&[0; 32]);
+        round_trip(&[0xff; 32]);
+    }
+
+    #[test]
+    fn round_trips_with_an_extra_padding_byte() {
+        // 7 bytes take 6 words (66 bits), which decode to 8 bytes.
+        assert_eq!(from_indices(&to_indices(&bytes(7))).len(), 8);
+        round_trip(&bytes(7));
+    }
+
+    #[test]
+    fn empty() {
+        let words = encode(&[]).unwrap();
+        // The length word and its checksum.
+        assert_eq!(words.split_whitespace().count(), 3, "{words}");
+        assert!(decode(&words).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_too_long_input() {
+        assert!(encode(&bytes(MAX_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn numbered_groups() {
+        let words = encode(&bytes(32)).unwrap();
+        let lines: Vec<_> = words.lines().collect();
+
+        // The length word and 24 data words (32 bytes take 256 of their 264 bits).
+        assert_eq!(lines.len(), 25_usize.div_ceil(GROUP_LEN));
+        for (n, line) in lines.iter().enumerate() {
+            assert!(line.starts_with(&format!("{:>2}: ", n + 1)), "{line}");
+        }
+    }
+
+    #[test]
+    fn ignores_line_numbers_case_and_whitespace() {
+        let words = encode(&bytes(32)).unwrap();
+        let bare: Vec<_> = words
+            .split_whitespace()
+            .filter(|token| !is_line_number(token))
+            .collect();
+
+        assert_eq!(decode(&bare.join("  ")).unwrap(), bytes(32));
+        assert_eq!(decode(&words.to_uppercase()).unwrap(), bytes(32));
+    }
+
+    #[test]
+    fn locates_transcription_errors() {
+        let words = encode(&bytes(32)).unwrap();
+
+        // Data words of the 1st, 3rd and last groups, and the checksum words of the first two.
+        for (pos, group) in [(0, 1), (12, 3), (30, 7), (GROUP_LEN, 1), (9, 2)] {
+            let err = decode(&replace_word(&words, pos)).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("Checksum mismatch in group(s): {group}"),
+                "word #{pos}"
+            );
+        }
+
+        let err = decode(&replace_word(&replace_word(&words, 1), 27)).unwrap_err();
+        assert_eq!(err.to_string(), "Checksum mismatch in group(s): 1, 6");
+    }
+
+    #[test]
+    fn detects_missing_and_unknown_words() {
+        let words = encode(&bytes(32)).unwrap();
+
+        let mut missing: Vec<_> = words.split_whitespace().collect();
+        missing.remove(2);
+        assert!(decode(&missing.join(" ")).is_err());
+
+        let err = decode(&words.replacen("1: ", "1: notaword ", 1)).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown word #1: notaword");
+
+        assert!(decode("").is_err());
+    }
+}
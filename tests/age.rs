@@ -0,0 +1,46 @@
+//! `age` subcommand.
+
+mod common;
+
+use common::{Env, assert_code, success};
+
+/// Decodes the secret key line of an identity, returning the key and the bech32 variant.
+fn decode_identity(identity: &str) -> (Vec<u8>, bech32::Variant) {
+    let secret_key = identity
+        .lines()
+        .find(|line| line.starts_with("AGE-SECRET-KEY-1"))
+        .unwrap();
+    let (hrp, data, variant) = bech32::decode(secret_key).unwrap();
+    assert_eq!(hrp, "age-secret-key-");
+    (bech32::FromBase32::from_base32(&data).unwrap(), variant)
+}
+
+#[test]
+fn bech32_by_default() {
+    let env = Env::new();
+    let default = success(env.run(&["age", "laptop"]));
+    let bech32m = success(env.run(&["age", "laptop", "--bech32m"]));
+
+    let (key, variant) = decode_identity(&default);
+    assert_eq!(variant, bech32::Variant::Bech32);
+    assert_eq!(decode_identity(&bech32m), (key, bech32::Variant::Bech32m));
+}
+
+#[test]
+fn comment_is_cosmetic() {
+    let env = Env::new();
+    let plain = success(env.run(&["age", "laptop"]));
+    let commented = success(env.run(&["age", "laptop", "--comment", "work laptop"]));
+
+    assert_eq!(commented, format!("# comment: work laptop\n{plain}"));
+    assert_eq!(decode_identity(&commented), decode_identity(&plain));
+}
+
+#[test]
+fn multi_line_comment() {
+    let env = Env::new();
+
+    for comment in ["two\nlines", "carriage\rreturn"] {
+        assert_code(&env.run(&["age", "laptop", "--comment", comment]), 2);
+    }
+}
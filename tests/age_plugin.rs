@@ -0,0 +1,100 @@
+//! `plugin` subcommand, the age plugin `age-plugin-argon2derive`.
+
+mod common;
+
+use std::process::Output;
+
+use age::{Recipient as _, secrecy::ExposeSecret as _};
+use age_core::format::FileKey;
+use base64::{Engine as _, engine::general_purpose::STANDARD_NO_PAD};
+use common::{Env, PASSPHRASE, assert_code, stdout, success};
+
+/// Keygen output of `email`: the identity line and the recipient.
+fn keygen(env: &Env) -> (String, String) {
+    let out = success(env.run_with_stdin(&["plugin", "--keygen", "email"], PASSPHRASE));
+    let recipient = out
+        .lines()
+        .find_map(|line| line.strip_prefix("# recipient: "))
+        .unwrap();
+    let identity = out
+        .lines()
+        .find(|line| line.starts_with("AGE-PLUGIN-ARGON2DERIVE-1"))
+        .unwrap();
+    (identity.to_owned(), recipient.to_owned())
+}
+
+/// Runs the binary as age does, speaking the `identity-v1` protocol with the scripted messages of age.
+#[cfg(unix)]
+fn plugin(env: &Env, identity: &str, recipient: &str, passphrase: &str) -> Output {
+    use std::os::unix::process::CommandExt as _;
+
+    let recipient: age::x25519::Recipient = recipient.parse().unwrap();
+    let (stanzas, _) = recipient
+        .wrap_file_key(&FileKey::new(Box::new([7; 16])))
+        .unwrap();
+    let stanza = &stanzas[0];
+
+    let messages = format!(
+        "-> add-identity {identity}\n\n\
+         -> recipient-stanza 0 {} {}\n{}\n\
+         -> done\n\n\
+         -> ok\n{}\n\
+         -> ok\n\n",
+        stanza.tag,
+        stanza.args.join(" "),
+        STANDARD_NO_PAD.encode(&stanza.body),
+        STANDARD_NO_PAD.encode(passphrase),
+    );
+
+    let mut cmd = env.command(&["--age-plugin=identity-v1"]);
+    cmd.arg0("age-plugin-argon2derive");
+    common::run(cmd, messages.as_bytes())
+}
+
+#[test]
+fn recipient_of_the_age_command() {
+    let env = Env::new();
+    let (_, recipient) = keygen(&env);
+
+    let age = success(env.run(&["age", "email"]));
+    let key = age
+        .lines()
+        .find(|line| line.starts_with("AGE-SECRET-KEY-1"))
+        .unwrap();
+    let key: age::x25519::Identity = key.parse().unwrap();
+    assert_eq!(key.to_public().to_string(), recipient);
+}
+
+#[cfg(unix)]
+#[test]
+fn unwraps_the_file_key() {
+    let env = Env::new();
+    let (identity, recipient) = keygen(&env);
+    let file_key = STANDARD_NO_PAD.encode(FileKey::new(Box::new([7; 16])).expose_secret());
+
+    let out = plugin(&env, &identity, &recipient, PASSPHRASE);
+    assert_code(&out, 0);
+    let messages = stdout(&out);
+    assert!(messages.starts_with("-> request-secret\n"), "{messages}");
+    assert!(
+        messages.contains(&format!("-> file-key 0\n{file_key}\n")),
+        "{messages}"
+    );
+    assert!(messages.ends_with("-> done\n\n"), "{messages}");
+
+    let out = plugin(&env, &identity, &recipient, "wrong passphrase");
+    assert_code(&out, 0);
+    assert!(!stdout(&out).contains("file-key"), "{}", stdout(&out));
+}
+
+#[test]
+fn invalid_args() {
+    let env = Env::new();
+    assert_code(&env.run(&["plugin"]), 2);
+    assert_code(&env.run(&["plugin", "--age-plugin", "recipient-v1"]), 2);
+    assert_code(&env.run(&["--dry-run", "plugin", "--keygen", "email"]), 2);
+    assert_code(
+        &env.run(&["--salt-source", "machine-id", "plugin", "--keygen", "email"]),
+        2,
+    );
+}
@@ -0,0 +1,126 @@
+//! `--audit-log` option and `audit show` subcommand.
+
+mod common;
+
+use common::{CONFIG, Env, PASSPHRASE, assert_code, stdout, success};
+
+fn entries(env: &Env) -> Vec<serde_json::Value> {
+    env.read("audit.log")
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn appends_the_derivations() {
+    let env = Env::new();
+    let secret = success(env.run(&["--audit-log", "audit.log", "secret", "email"]));
+    success(env.run(&[
+        "--audit-log",
+        "audit.log",
+        "multi",
+        "laptop",
+        "--age",
+        "--secret",
+        "16:hex",
+    ]));
+
+    let entries = entries(&env);
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0]["command"], "secret");
+    assert_eq!(entries[0]["name"], "email");
+    assert_eq!(entries[0]["length"], 32);
+    assert_eq!(entries[0]["kdf"], "argon2id v19");
+    assert_eq!(entries[0]["status"], 0);
+    assert!(entries[1..].iter().all(|e| e["command"] == "multi"));
+
+    let log = env.read("audit.log");
+    assert!(!log.contains(PASSPHRASE), "{log}");
+    assert!(!log.contains(&secret), "{log}");
+    assert!(!log.contains("argon2derive tests"), "{log}");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+        let mode = std::fs::metadata(env.path("audit.log"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
+
+#[test]
+fn records_the_exit_status() {
+    let env = Env::new();
+    let out = env.run(&[
+        "--audit-log",
+        "audit.log",
+        "secret",
+        "email",
+        "--expect",
+        "00",
+    ]);
+    assert_code(&out, 7);
+    assert_eq!(entries(&env)[0]["status"], 7);
+
+    // Nothing derived, nothing logged.
+    let out = env.run(&[
+        "--audit-log",
+        "nothing.log",
+        "secret",
+        "email",
+        "--length",
+        "0",
+    ]);
+    assert_code(&out, 2);
+    assert!(!env.path("nothing.log").exists());
+}
+
+#[test]
+fn show() {
+    let env = Env::with_config(&format!("{CONFIG}audit_log = \"audit.log\"\n"));
+    success(env.run(&["secret", "email"]));
+    success(env.run(&["secret", "bank"]));
+    assert_code(&env.run(&["secret", "bank", "--expect", "00"]), 7);
+
+    let show =
+        |args: &[&str]| success(env.run_with_stdin(&[&["audit", "show"], args].concat(), ""));
+    let table = show(&[]);
+    assert_eq!(table.lines().count(), 4, "{table}");
+    assert!(table.starts_with("TIME"), "{table}");
+
+    assert_eq!(
+        show(&["--name", "bank", "--format", "json"])
+            .lines()
+            .count(),
+        2
+    );
+    assert_eq!(show(&["--failed", "--format", "json"]).lines().count(), 1);
+    assert_eq!(show(&["--command", "age", "--format", "json"]), "");
+    assert_eq!(
+        show(&["--since", "2000-01-01", "--format", "json"])
+            .lines()
+            .count(),
+        3
+    );
+    assert_eq!(show(&["--since", "9999-01-01", "--format", "json"]), "");
+
+    let json = show(&["--format", "json"]);
+    assert_eq!(json, env.read("audit.log"));
+}
+
+#[test]
+fn invalid_logs() {
+    let env = Env::new();
+    let out = env.run_with_stdin(&["audit", "show"], "");
+    assert_code(&out, 2);
+
+    env.write("audit.log", "not json\n");
+    let out = env.run_with_stdin(&["--audit-log", "audit.log", "audit", "show"], "");
+    assert_code(&out, 8);
+    assert_eq!(stdout(&out), "");
+
+    let out = env.run_with_stdin(&["audit", "show", "--since", "yesterday"], "");
+    assert_code(&out, 2);
+}
@@ -0,0 +1,49 @@
+//! `--bind-params` option.
+
+mod common;
+
+use argon2derive::argon2::{self, Algorithm, Parameters};
+use common::{CONFIG, Env, PASSPHRASE, success};
+
+/// Secret of `email` with the costs bound, the salt composed independently of the binary.
+fn expected(time: u32) -> String {
+    let mut salt = b"argon2derive tests".to_vec();
+    salt.extend(b"\0argon2derive bound params v1\0");
+    salt.extend(format!("argon2id$v=19$m=64,t={time},p=1").as_bytes());
+    salt.extend(b"email");
+
+    let params = Parameters {
+        algorithm: Algorithm::Argon2id,
+        memory: 64,
+        time,
+        parallelism: 1,
+        salt,
+    };
+    hex::encode(argon2::hash(&params, PASSPHRASE.as_bytes(), 32).unwrap())
+}
+
+#[test]
+fn costs_are_appended_to_the_salt() {
+    let env = Env::new();
+    let bound = success(env.run(&["secret", "email", "--bind-params"]));
+    assert_eq!(bound, expected(1));
+    assert_ne!(bound, success(env.run(&["secret", "email"])));
+
+    let env = Env::with_config(&CONFIG.replace("time = 1", "time = 2"));
+    assert_eq!(
+        success(env.run(&["--bind-params", "secret", "email"])),
+        expected(2)
+    );
+}
+
+#[test]
+fn dry_run_reports_the_costs() {
+    let env = Env::new();
+    let out = env.run_with_stdin(&["--dry-run", "--bind-params", "secret", "email"], "");
+    let stderr = common::stderr(&out);
+    success(out);
+    assert!(
+        stderr.contains("Bound to the parameters: argon2id$v=19$m=64,t=1,p=1"),
+        "{stderr}"
+    );
+}
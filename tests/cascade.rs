@@ -0,0 +1,67 @@
+//! Cascades of Argon2 passes, configured by `--cascade` or the `[[passes]]` of the config.
+
+mod common;
+
+use argon2derive::argon2::{self, Algorithm, Parameters};
+use common::{CONFIG, Env, PASSPHRASE, assert_code, success};
+
+/// [`CONFIG`] followed by two cheap passes.
+const PASSES: &str = r#"
+[[passes]]
+algorithm = "argon2d"
+memory = 32
+time = 2
+parallelism = 2
+
+[[passes]]
+algorithm = "argon2id"
+memory = 16
+time = 1
+parallelism = 1
+"#;
+
+#[test]
+fn config_passes() {
+    let env = Env::with_config(&format!("{CONFIG}{PASSES}"));
+
+    let mut first = Parameters {
+        algorithm: Algorithm::Argon2id,
+        memory: 64,
+        time: 1,
+        parallelism: 1,
+        salt: b"argon2derive tests".to_vec(),
+    };
+    first.append_name("email", 0).unwrap();
+    let next = [
+        Parameters {
+            algorithm: Algorithm::Argon2d,
+            memory: 32,
+            time: 2,
+            parallelism: 2,
+            salt: Vec::new(),
+        },
+        Parameters {
+            algorithm: Algorithm::Argon2id,
+            memory: 16,
+            time: 1,
+            parallelism: 1,
+            salt: Vec::new(),
+        },
+    ];
+    let expected = argon2::hash_cascade(&first, &next, PASSPHRASE.as_bytes(), 32).unwrap();
+
+    let out = success(env.run(&["secret", "email"]));
+    assert_eq!(out.trim_end(), hex::encode(expected));
+
+    let single = success(Env::new().run(&["secret", "email"]));
+    assert_ne!(out, single);
+}
+
+#[test]
+fn invalid_cascade() {
+    let env = Env::new();
+
+    for spec in ["argon2d:1:10", "argon2i:1:10:4", "argon2d:x:10:4"] {
+        assert_code(&env.run(&["--cascade", spec, "secret", "email"]), 2);
+    }
+}
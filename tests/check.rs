@@ -0,0 +1,36 @@
+//! `check` subcommand.
+
+mod common;
+
+use common::{Env, PASSPHRASE, assert_code};
+
+/// PHC string of [`PASSPHRASE`].
+const PHC: &str =
+    "$argon2id$v=19$m=64,t=1,p=1$c29tZXNhbHQ$P/yFQsy5QJRYEOxcAWsVeGzrYrOiF30wrmg+JtYJPPA";
+
+#[test]
+fn matching_passphrase() {
+    let out = Env::new().run(&["check", PHC]);
+    assert_code(&out, 0);
+    assert!(common::stderr(&out).contains("Passphrase matches"));
+}
+
+#[test]
+fn wrong_passphrase() {
+    let out = Env::new().run_with_stdin(&["check", PHC], format!("{PASSPHRASE}!"));
+    assert_code(&out, 7);
+}
+
+#[test]
+fn phc_file() {
+    let env = Env::new();
+    env.write("hash.txt", format!("{PHC}\n"));
+    assert_code(&env.run(&["check", "--phc-file", "hash.txt"]), 0);
+}
+
+#[test]
+fn corrupted_phc() {
+    let out = Env::new().run(&["check", &PHC.replace("v=19", "v=16")]);
+    assert_code(&out, 2);
+    assert!(common::stderr(&out).contains("Unsupported Argon2 version"));
+}
@@ -0,0 +1,108 @@
+//! `--checksum-file` option and `verify-files` subcommand.
+
+mod common;
+
+use std::process::Command;
+
+use common::{Env, assert_code, stderr, success};
+use sha2::{Digest as _, Sha256};
+
+fn sha256(contents: impl AsRef<[u8]>) -> String {
+    hex::encode(Sha256::digest(contents))
+}
+
+#[test]
+fn sha256sum_compatible() {
+    let env = Env::new();
+    let args = ["--checksum-file", "SHA256SUMS"];
+    success(env.run(&[&["secret", "b", "--dotenv", "b.env"][..], &args].concat()));
+    success(env.run(&[&["secret", "a", "--dotenv", "a.env"][..], &args].concat()));
+
+    let manifest = env.read("SHA256SUMS");
+    let a = env.read("a.env");
+    let b = env.read("b.env");
+    assert_eq!(
+        manifest,
+        format!("{}  a.env\n{}  b.env\n", sha256(&a), sha256(&b))
+    );
+    assert!(!manifest.contains(a.trim_end()) && !manifest.contains(b.trim_end()));
+
+    let out = Command::new("sha256sum")
+        .args(["-c", "SHA256SUMS"])
+        .current_dir(env.path(""))
+        .output();
+    // `sha256sum` is part of coreutils, which isn't installed everywhere.
+    if let Ok(out) = out {
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stdout)
+        );
+    }
+}
+
+#[test]
+fn rewritten_files_replace_their_line() {
+    let env = Env::new();
+    let args = ["--checksum-file", "SHA256SUMS"];
+    success(env.run(&[&["secret", "a", "--dotenv", "a.env"][..], &args].concat()));
+    success(env.run(&[&["secret", "b", "--dotenv", "a.env"][..], &args].concat()));
+
+    assert_eq!(
+        env.read("SHA256SUMS"),
+        format!("{}  a.env\n", sha256(env.read("a.env")))
+    );
+}
+
+#[test]
+fn verify_files() {
+    let env = Env::new();
+    let args = ["--checksum-file", "SHA256SUMS"];
+    success(env.run(&[&["secret", "a", "--dotenv", "a.env"][..], &args].concat()));
+    success(env.run(&[&["secret", "b", "--dotenv", "b.env"][..], &args].concat()));
+
+    // No passphrase is piped, it mustn't be needed.
+    let verify = || env.run_with_stdin(&[&["verify-files"][..], &args].concat(), "");
+    let out = verify();
+    assert_code(&out, 0);
+    assert!(stderr(&out).contains("a.env: OK"), "{}", stderr(&out));
+    assert!(stderr(&out).contains("b.env: OK"), "{}", stderr(&out));
+
+    env.write("a.env", "corrupted");
+    let out = verify();
+    assert_code(&out, 7);
+    assert!(stderr(&out).contains("a.env: FAILED"), "{}", stderr(&out));
+    assert!(stderr(&out).contains("b.env: OK"), "{}", stderr(&out));
+
+    std::fs::remove_file(env.path("b.env")).unwrap();
+    let out = verify();
+    assert_code(&out, 7);
+    assert!(stderr(&out).contains("b.env: FAILED ("), "{}", stderr(&out));
+}
+
+#[test]
+fn nothing_written() {
+    let env = Env::new();
+    let out = env.run(&["secret", "a", "--checksum-file", "SHA256SUMS"]);
+    assert_code(&out, 0);
+    assert!(stderr(&out).contains("No files were written"));
+    assert!(!env.path("SHA256SUMS").exists());
+}
+
+#[test]
+fn invalid_manifests() {
+    let env = Env::new();
+    assert_code(&env.run_with_stdin(&["verify-files"], ""), 2);
+    assert_code(
+        &env.run_with_stdin(&["verify-files", "--checksum-file", "missing"], ""),
+        8,
+    );
+
+    env.write("SHA256SUMS", "not a checksum line\n");
+    let out = env.run_with_stdin(&["verify-files", "--checksum-file", "SHA256SUMS"], "");
+    assert_code(&out, 7);
+    assert!(stderr(&out).contains("line 1:"), "{}", stderr(&out));
+
+    let args = ["--dotenv", "a.env", "--checksum-file", "SHA256SUMS"];
+    assert_code(&env.run(&[&["secret", "a"][..], &args].concat()), 8);
+}
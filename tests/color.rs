@@ -0,0 +1,124 @@
+//! Styling of the stderr output, only applied on a terminal.
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::process::Command;
+
+use common::{Env, stderr, success};
+
+const UNSALTED: &str = r#"
+algorithm = "argon2id"
+memory = 64
+time = 1
+parallelism = 1
+"#;
+
+const COLORED: &str = "\
+\x1b[2mPassphrase version: 1\x1b[0m
+
+\x1b[33mWARNING: Your salt is empty!\x1b[0m
+
+\x1b[33mWARNING: Your memory cost (64 KiB) is dangerously low, consider using at least 65536 KiB!\x1b[0m
+
+\x1b[33mWARNING: Your memory cost (64 KiB) is unusually low for your time cost (1 iterations), did you swap --memory and --time?\x1b[0m
+
+Deriving...
+Derived in <time>
+
+\x1b[1mSecret:\x1b[0m
+";
+
+/// Terminal transcript from the last config value on, stdout being checked to never be styled.
+fn transcript(env: &Env, cmd: Command) -> String {
+    let expected = success(env.run(&["secret", "long-name"]));
+
+    let out = common::run_with_tty(cmd, common::PASSPHRASE.as_bytes(), "");
+    let transcript = stderr(&out);
+    assert_eq!(success(out), expected);
+
+    let start = transcript.find("Passphrase version").unwrap();
+    let start = transcript[..start].rfind('\n').unwrap() + 1;
+    let (head, tail) = transcript[start..].split_once("Derived in ").unwrap();
+    let (_, tail) = tail.split_once('\n').unwrap();
+    format!("{head}Derived in <time>\n{tail}")
+}
+
+#[test]
+fn colored_on_a_terminal() {
+    let env = Env::with_config(UNSALTED);
+
+    let mut cmd = env.command(&["secret", "long-name"]);
+    cmd.env_remove("NO_COLOR");
+    assert_eq!(transcript(&env, cmd), COLORED);
+}
+
+#[test]
+fn no_color() {
+    let env = Env::with_config(UNSALTED);
+    let plain = COLORED
+        .replace("\x1b[1m", "")
+        .replace("\x1b[2m", "")
+        .replace("\x1b[33m", "");
+    let plain = plain.replace("\x1b[0m", "");
+
+    let mut cmd = env.command(&["--no-color", "secret", "long-name"]);
+    cmd.env_remove("NO_COLOR");
+    assert_eq!(transcript(&env, cmd), plain);
+
+    // Set by `Env::command`.
+    let cmd = env.command(&["secret", "long-name"]);
+    assert_eq!(transcript(&env, cmd), plain);
+
+    // An empty `NO_COLOR` doesn't disable the colors.
+    let mut cmd = env.command(&["secret", "long-name"]);
+    cmd.env("NO_COLOR", "");
+    assert_eq!(transcript(&env, cmd), COLORED);
+}
+
+#[test]
+fn errors_are_red() {
+    let env = Env::new();
+
+    let mut cmd = env.command(&["secret", "name", "--length", "0"]);
+    cmd.env_remove("NO_COLOR");
+    let out = common::run_with_tty(cmd, common::PASSPHRASE.as_bytes(), "");
+    common::assert_code(&out, 2);
+    assert!(
+        stderr(&out).contains("\x1b[31mError (code 2): "),
+        "{:?}",
+        stderr(&out)
+    );
+}
+
+#[test]
+fn quiet_regardless_of_color() {
+    let env = Env::with_config(UNSALTED);
+    let warnings: String = COLORED
+        .lines()
+        .filter(|line| line.contains("WARNING"))
+        .map(|line| format!("\n{line}\n"))
+        .collect();
+
+    for color in [true, false] {
+        let mut cmd = env.command(&["--quiet", "secret", "long-name"]);
+        if color {
+            cmd.env_remove("NO_COLOR");
+        }
+        let out = common::run_with_tty(cmd, common::PASSPHRASE.as_bytes(), "");
+        let transcript = stderr(&out);
+        success(out);
+
+        let expected = match color {
+            true => warnings.clone(),
+            false => warnings.replace("\x1b[33m", "").replace("\x1b[0m", ""),
+        };
+        assert_eq!(transcript, expected);
+    }
+
+    let mut cmd = env.command(&["--quiet", "--no-warn", "secret", "long-name"]);
+    cmd.env_remove("NO_COLOR");
+    let out = common::run_with_tty(cmd, common::PASSPHRASE.as_bytes(), "");
+    assert_eq!(stderr(&out), "");
+}
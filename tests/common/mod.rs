@@ -0,0 +1,262 @@
+//! Helpers of the CLI tests, running the binary within a scratch directory holding a cheap config.
+
+// Every test crate only uses some of the helpers.
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    io::Write as _,
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+    thread,
+};
+
+/// Passphrase piped into the binary by [`Env::run`] (without a trailing newline).
+pub const PASSPHRASE: &str = "correct horse battery staple";
+
+/// Parameters cheap enough for the tests not to spend their time in Argon2.
+pub const CONFIG: &str = r#"
+algorithm = "argon2id"
+memory = 64
+time = 1
+parallelism = 1
+salt = "argon2derive tests"
+"#;
+
+/// Scratch directory holding the config, removed once dropped.
+pub struct Env {
+    dir: tempfile::TempDir,
+}
+
+impl Env {
+    /// Creates a scratch directory using [`CONFIG`].
+    pub fn new() -> Self {
+        Self::with_config(CONFIG)
+    }
+
+    /// Creates a scratch directory using the provided `config.toml`.
+    pub fn with_config(config: &str) -> Self {
+        let env = Self {
+            dir: tempfile::tempdir().unwrap(),
+        };
+        env.write("config.toml", config);
+        env
+    }
+
+    /// Path of a file within the scratch directory.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.path().join(name)
+    }
+
+    /// Writes a file into the scratch directory, only accessible by its owner.
+    pub fn write(&self, name: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.path(name);
+        fs::write(&path, contents).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        }
+        path
+    }
+
+    /// Writes an executable script into the `bin` directory, which [`Env::with_bin`] puts first in the `PATH`.
+    #[cfg(unix)]
+    pub fn script(&self, name: &str, body: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        fs::create_dir_all(self.path("bin")).unwrap();
+        let path = self.path("bin").join(name);
+        fs::write(&path, format!("#!/bin/sh\n{body}")).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    /// Command running the binary with the scripts of [`Env::script`] taking precedence over the `PATH`.
+    pub fn with_bin(&self, args: &[&str]) -> Command {
+        let path = std::env::var_os("PATH").unwrap_or_default();
+        let mut paths = vec![self.path("bin")];
+        paths.extend(std::env::split_paths(&path));
+
+        let mut cmd = self.command(args);
+        cmd.env("PATH", std::env::join_paths(paths).unwrap());
+        cmd
+    }
+
+    /// Reads a file of the scratch directory.
+    pub fn read(&self, name: &str) -> String {
+        fs::read_to_string(self.path(name)).unwrap()
+    }
+
+    /// Command running the binary with the config of the scratch directory, isolated from the user's one.
+    pub fn command(&self, args: &[&str]) -> Command {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_argon2derive"));
+        cmd.args(args)
+            .current_dir(self.dir.path())
+            .env("ARGON2DERIVE_CONFIG", self.path("config.toml"))
+            .env("HOME", self.dir.path())
+            .env("XDG_CONFIG_HOME", self.path("xdg"))
+            .env("NO_COLOR", "1")
+            .env("RUST_BACKTRACE", "0")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        cmd
+    }
+
+    /// Runs the binary, piping [`PASSPHRASE`] into it.
+    pub fn run(&self, args: &[&str]) -> Output {
+        self.run_with_stdin(args, PASSPHRASE)
+    }
+
+    /// Runs the binary, piping `stdin` into it.
+    pub fn run_with_stdin(&self, args: &[&str], stdin: impl AsRef<[u8]>) -> Output {
+        run(self.command(args), stdin.as_ref())
+    }
+}
+
+/// Runs the command to completion, feeding `stdin` from another thread so large inputs can't dead-lock.
+pub fn run(mut cmd: Command, stdin: &[u8]) -> Output {
+    let mut child = cmd.spawn().unwrap();
+    let mut pipe = child.stdin.take().unwrap();
+    let stdin = stdin.to_vec();
+    // The binary may exit without reading its stdin, so the write is allowed to fail.
+    let writer = thread::spawn(move || {
+        let _ = pipe.write_all(&stdin);
+    });
+
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    output
+}
+
+/// Exit code of the process, panicking with its stderr if it was killed by a signal.
+pub fn code(output: &Output) -> i32 {
+    output
+        .status
+        .code()
+        .unwrap_or_else(|| panic!("killed by a signal: {}", stderr(output)))
+}
+
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+/// Asserts the exit code, printing stderr on a mismatch.
+#[track_caller]
+pub fn assert_code(output: &Output, expected: i32) {
+    assert_eq!(code(output), expected, "stderr: {}", stderr(output));
+}
+
+/// Stdout of a successful run.
+#[track_caller]
+pub fn success(output: Output) -> String {
+    assert_code(&output, 0);
+    stdout(&output)
+}
+
+/// Runs the command in a new session whose controlling terminal is a pseudo-terminal,
+/// `typed` being typed into it, while `stdin` is piped into the command.
+///
+/// stderr is the terminal as well, so the returned stderr is everything printed to the terminal
+/// (including the echoed input), with the line endings translated back to `\n`.
+#[cfg(target_os = "linux")]
+pub fn run_with_tty(mut cmd: Command, stdin: &[u8], typed: &str) -> Output {
+    use std::{
+        ffi::CStr, fs::File, io::Read as _, os::fd::FromRawFd as _,
+        os::unix::process::CommandExt as _,
+    };
+
+    // SAFETY: Plain libc calls, their results are checked.
+    let (master, slave) = unsafe {
+        let master = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+        assert!(master >= 0, "{}", std::io::Error::last_os_error());
+        assert_eq!(libc::grantpt(master), 0);
+        assert_eq!(libc::unlockpt(master), 0);
+
+        let mut name = [0; 64];
+        assert_eq!(libc::ptsname_r(master, name.as_mut_ptr(), name.len()), 0);
+        (
+            File::from_raw_fd(master),
+            CStr::from_ptr(name.as_ptr()).to_owned(),
+        )
+    };
+
+    // SAFETY: Only async-signal-safe functions are called between `fork` and `exec`.
+    unsafe {
+        cmd.stderr(Stdio::null()).pre_exec(move || {
+            if libc::setsid() < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            // The first terminal opened by a session leader becomes its controlling terminal.
+            let fd = libc::open(slave.as_ptr(), libc::O_RDWR);
+            if fd < 0 || libc::dup2(fd, libc::STDERR_FILENO) < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    let mut child = cmd.spawn().unwrap();
+    (&master).write_all(typed.as_bytes()).unwrap();
+
+    // Reading fails with `EIO` once the process exits, closing its end of the terminal.
+    let reader = thread::spawn(move || {
+        let mut transcript = Vec::new();
+        let _ = (&master).read_to_end(&mut transcript);
+        transcript
+    });
+
+    let mut pipe = child.stdin.take().unwrap();
+    let stdin = stdin.to_vec();
+    let writer = thread::spawn(move || {
+        let _ = pipe.write_all(&stdin);
+    });
+
+    let mut output = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    let transcript = String::from_utf8_lossy(&reader.join().unwrap()).replace("\r\n", "\n");
+    output.stderr = transcript.into_bytes();
+    output
+}
+
+/// Runs the command in a new session without a controlling terminal.
+#[cfg(unix)]
+pub fn run_without_tty(mut cmd: Command, stdin: &[u8]) -> Output {
+    use std::{io, os::unix::process::CommandExt as _};
+
+    // SAFETY: `setsid` is async-signal-safe.
+    unsafe {
+        cmd.pre_exec(|| match libc::setsid() {
+            -1 => Err(io::Error::last_os_error()),
+            _ => Ok(()),
+        });
+    }
+
+    run(cmd, stdin)
+}
+
+/// Makes the command inherit `fd` as the descriptor `target`, e.g. 3 for `--passphrase-fd 3`.
+#[cfg(unix)]
+pub fn inherit_fd(cmd: &mut Command, target: i32, fd: std::os::fd::OwnedFd) {
+    use std::{io, os::fd::AsRawFd as _, os::unix::process::CommandExt as _};
+
+    // SAFETY: Only async-signal-safe functions are called between `fork` and `exec`.
+    unsafe {
+        cmd.pre_exec(move || {
+            let res = match fd.as_raw_fd() {
+                // `dup2` would keep the close-on-exec flag.
+                fd if fd == target => libc::fcntl(fd, libc::F_SETFD, 0),
+                fd => libc::dup2(fd, target),
+            };
+            match res {
+                -1 => Err(io::Error::last_os_error()),
+                _ => Ok(()),
+            }
+        });
+    }
+}
@@ -0,0 +1,16 @@
+//! `completions` subcommand.
+
+mod common;
+
+use common::{Env, assert_code, success};
+
+#[test]
+fn every_shell() {
+    let env = Env::new();
+    for shell in ["bash", "zsh", "fish", "powershell", "elvish"] {
+        let out = success(env.run(&["completions", shell]));
+        assert!(out.contains("secret"), "{shell}");
+    }
+
+    assert_code(&env.run(&["completions", "tcsh"]), 2);
+}
@@ -0,0 +1,24 @@
+//! `config-schema` subcommand.
+
+mod common;
+
+use common::{Env, success};
+
+#[test]
+fn describes_the_config() {
+    let out = success(Env::new().run_with_stdin(&["config-schema"], ""));
+    let schema: serde_json::Value = serde_json::from_str(&out).unwrap();
+
+    assert_eq!(
+        schema["$schema"],
+        "https://json-schema.org/draft/2020-12/schema"
+    );
+    assert_eq!(schema["type"], "object");
+
+    // Every key of the test config is described.
+    let config: toml::Table = common::CONFIG.parse().unwrap();
+    for key in config.keys() {
+        assert!(schema["properties"].get(key).is_some(), "{key}");
+    }
+    assert_eq!(schema["properties"]["memory"]["type"], "integer");
+}
@@ -0,0 +1,89 @@
+//! Config search locations and their precedence.
+
+mod common;
+
+use std::fs;
+
+use common::{CONFIG, Env, stderr};
+
+/// Writes a config with its own salt at `path`, relative to the scratch directory.
+fn config(env: &Env, path: &str) {
+    if let Some(dir) = env.path(path).parent() {
+        fs::create_dir_all(dir).unwrap();
+    }
+
+    let salt = format!("salt of {path}");
+    let contents = match path.rsplit_once('.').unwrap().1 {
+        "json" => format!(
+            r#"{{"algorithm": "argon2id", "memory": 64, "time": 1, "parallelism": 1, "salt": "{salt}"}}"#
+        ),
+        "yaml" | "yml" => {
+            format!("algorithm: argon2id\nmemory: 64\ntime: 1\nparallelism: 1\nsalt: {salt}\n")
+        }
+        _ => CONFIG.replace("argon2derive tests", &salt),
+    };
+    env.write(path, contents);
+}
+
+/// Config used by the invocation, as reported on stderr.
+fn used_config(env: &Env, args: &[&str], from_env: bool) -> String {
+    let mut cmd = env.command(&[args, &["secret", "name"]].concat());
+    if !from_env {
+        cmd.env_remove("ARGON2DERIVE_CONFIG");
+    }
+
+    let out = common::run(cmd, common::PASSPHRASE.as_bytes());
+    common::assert_code(&out, 0);
+
+    let stderr = stderr(&out);
+    let line = stderr
+        .lines()
+        .find(|line| line.starts_with("Using config"))
+        .unwrap_or_else(|| panic!("{stderr}"));
+    let path = line.split('"').nth(1).unwrap();
+    let dir = env.path("").canonicalize().unwrap();
+    path.strip_prefix(dir.to_str().unwrap())
+        .unwrap_or(path)
+        .trim_start_matches('/')
+        .to_owned()
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn precedence() {
+    let env = Env::new();
+    config(&env, "xdg/argon2derive/config.toml");
+    assert_eq!(
+        used_config(&env, &[], false),
+        "xdg/argon2derive/config.toml"
+    );
+
+    config(&env, "argon2derive.json");
+    assert_eq!(used_config(&env, &[], false), "argon2derive.json");
+
+    config(&env, "argon2derive.toml");
+    assert_eq!(used_config(&env, &[], false), "argon2derive.toml");
+
+    // `config.toml` of the scratch directory, via `$ARGON2DERIVE_CONFIG`.
+    assert_eq!(used_config(&env, &[], true), "config.toml");
+
+    config(&env, "explicit.toml");
+    assert_eq!(
+        used_config(&env, &["--config", "explicit.toml"], true),
+        "explicit.toml"
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn default_dir_formats() {
+    let env = Env::new();
+    config(&env, "xdg/argon2derive/config.yml");
+    assert_eq!(used_config(&env, &[], false), "xdg/argon2derive/config.yml");
+
+    config(&env, "xdg/argon2derive/config.json");
+    assert_eq!(
+        used_config(&env, &[], false),
+        "xdg/argon2derive/config.json"
+    );
+}
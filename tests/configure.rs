@@ -0,0 +1,255 @@
+//! `configure` subcommand.
+
+mod common;
+
+use common::{Env, assert_code, stderr, success};
+
+const PARAMS: [&str; 6] = ["-m", "1", "-p", "1", "-s", "somesalt"];
+
+fn configure(env: &Env, args: &[&str]) {
+    success(env.run_with_stdin(&[&PARAMS[..], &["-t", "1", "configure"], args].concat(), ""));
+}
+
+#[cfg(unix)]
+fn mode(env: &Env, name: &str) -> u32 {
+    use std::os::unix::fs::PermissionsExt as _;
+    std::fs::metadata(env.path(name))
+        .unwrap()
+        .permissions()
+        .mode()
+        & 0o777
+}
+
+#[test]
+fn writes_the_config() {
+    let env = Env::new();
+    configure(&env, &["--overwrite", "--no-backup"]);
+
+    let config = env.read("config.toml");
+    assert!(config.contains("memory = 1048576"), "{config}");
+    assert!(config.contains("salt = \"somesalt\""), "{config}");
+
+    // Without `--overwrite` and a terminal, the existing config is kept.
+    let out = env.run_with_stdin(&[&PARAMS[..], &["-t", "2", "configure"]].concat(), "");
+    assert_ne!(common::code(&out), 0);
+    assert_eq!(env.read("config.toml"), config);
+}
+
+#[cfg(unix)]
+#[test]
+fn config_is_private() {
+    use std::{fs, os::unix::fs::PermissionsExt as _};
+
+    let env = Env::new();
+    fs::set_permissions(env.path("config.toml"), fs::Permissions::from_mode(0o644)).unwrap();
+
+    let out = env.run(&["secret", "name"]);
+    assert_code(&out, 0);
+    assert!(
+        stderr(&out).contains("readable by other users"),
+        "{}",
+        stderr(&out)
+    );
+
+    configure(&env, &["--overwrite", "--no-backup"]);
+    assert_eq!(mode(&env, "config.toml"), 0o600);
+
+    // The written parameters are expensive, so nothing gets derived.
+    let out = env.run(&["--dry-run", "secret", "name"]);
+    assert_code(&out, 0);
+    assert!(
+        !stderr(&out).contains("readable by other users"),
+        "{}",
+        stderr(&out)
+    );
+}
+
+/// Files of the scratch directory named like backups of `config.toml`.
+fn backups(env: &Env) -> Vec<String> {
+    std::fs::read_dir(env.path(""))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.starts_with("config.toml.bak-"))
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn configure_interactively(env: &Env, args: &[&str], answer: &str) -> std::process::Output {
+    let cmd = env.command(&[&PARAMS[..], &["-t", "2", "configure"], args].concat());
+    common::run_with_tty(cmd, b"", answer)
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn refused_overwrite() {
+    let env = Env::new();
+    let before = env.read("config.toml");
+
+    let out = configure_interactively(&env, &[], "n\n");
+    assert_code(&out, 0);
+    assert_eq!(env.read("config.toml"), before);
+    assert!(backups(&env).is_empty());
+
+    let stderr = stderr(&out);
+    assert!(stderr.contains("Overwrite? [y/N]"), "{stderr}");
+    assert!(stderr.contains("  Algorithm: argon2id\n"), "{stderr}");
+    assert!(
+        stderr.contains("* Memory (KiB): 64 -> 1048576\n"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("* Time (iterations): 1 -> 2\n"), "{stderr}");
+    assert!(
+        stderr.contains("* Salt: argon2derive tests (18 bytes) -> somesalt (8 bytes)\n"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("Config file left intact"), "{stderr}");
+
+    // Anything but yes refuses.
+    assert_code(&configure_interactively(&env, &[], "\n"), 0);
+    assert_eq!(env.read("config.toml"), before);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn confirmed_overwrite() {
+    let env = Env::new();
+    let before = env.read("config.toml");
+
+    assert_code(&configure_interactively(&env, &[], "y\n"), 0);
+    assert!(env.read("config.toml").contains("time = 2"));
+
+    let backups = backups(&env);
+    assert_eq!(backups.len(), 1, "{backups:?}");
+    let timestamp = backups[0].strip_prefix("config.toml.bak-").unwrap();
+    assert!(timestamp.parse::<u64>().is_ok(), "{timestamp}");
+    assert_eq!(env.read(&backups[0]), before);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn confirmed_overwrite_without_backup() {
+    let env = Env::new();
+    assert_code(&configure_interactively(&env, &["--no-backup"], "yes\n"), 0);
+    assert!(env.read("config.toml").contains("time = 2"));
+    assert!(backups(&env).is_empty());
+}
+
+#[test]
+fn keeps_the_passphrase_version() {
+    let version = |env: &Env| {
+        env.read("config.toml").lines().find_map(|line| {
+            line.strip_prefix("passphrase_version = ")
+                .map(str::to_owned)
+        })
+    };
+
+    // Legacy configs keep deriving the secrets of non-normalized passphrases.
+    let env = Env::new();
+    configure(&env, &["--overwrite", "--no-backup"]);
+    assert_eq!(version(&env), None);
+
+    let env = Env::with_config(&format!("{}passphrase_version = 2\n", common::CONFIG));
+    configure(&env, &["--overwrite", "--no-backup"]);
+    assert_eq!(version(&env).as_deref(), Some("2"));
+
+    // New configs use the current version.
+    let env = Env::new();
+    std::fs::remove_file(env.path("config.toml")).unwrap();
+    configure(&env, &[]);
+    assert_eq!(version(&env).as_deref(), Some("2"));
+}
+
+#[test]
+fn overwrite_backs_up_the_config() {
+    let env = Env::new();
+    let before = env.read("config.toml");
+
+    configure(&env, &["--overwrite"]);
+    let backups = backups(&env);
+    assert_eq!(backups.len(), 1, "{backups:?}");
+    assert_eq!(env.read(&backups[0]), before);
+}
+
+#[test]
+fn invalid_params_are_not_written() {
+    let env = Env::new();
+    let before = env.read("config.toml");
+
+    for params in [
+        &["-m", "1", "-t", "0", "-p", "1", "-s", "somesalt"][..],
+        &["-m", "1", "-t", "1", "-p", "0", "-s", "somesalt"],
+        &["-m", "0", "-t", "1", "-p", "1", "-s", "somesalt"],
+        &["-m", "1", "-t", "1", "-p", "1", "-a", "argon3"],
+    ] {
+        let args = [params, &["configure", "--overwrite"]].concat();
+        let out = env.run_with_stdin(&args, "");
+        assert_code(&out, 2);
+        assert_eq!(env.read("config.toml"), before, "{params:?}");
+        assert!(backups(&env).is_empty(), "{params:?}");
+    }
+}
+
+/// Runs `configure` with the config located in the default directory rather than `--config`.
+fn configure_default_dir(env: &Env, args: &[&str]) -> std::process::Output {
+    let mut cmd =
+        env.command(&[&PARAMS[..], &["-t", "1", "configure", "--overwrite"], args].concat());
+    cmd.env_remove("ARGON2DERIVE_CONFIG");
+    common::run(cmd, b"")
+}
+
+#[test]
+fn format_switch() {
+    let env = Env::new();
+    let dir = "xdg/argon2derive";
+
+    assert_code(&configure_default_dir(&env, &[]), 0);
+    let toml = env.read(&format!("{dir}/config.toml"));
+
+    assert_code(&configure_default_dir(&env, &["--format", "yaml"]), 0);
+    let yaml: serde_yaml::Value =
+        serde_yaml::from_str(&env.read(&format!("{dir}/config.yaml"))).unwrap();
+    assert_eq!(yaml["memory"], 1048576);
+    assert_eq!(yaml["salt"], "somesalt");
+
+    assert_code(&configure_default_dir(&env, &["--format", "json"]), 0);
+    let json: serde_json::Value =
+        serde_json::from_str(&env.read(&format!("{dir}/config.json"))).unwrap();
+    assert_eq!(json["memory"], 1048576);
+    assert_eq!(json["salt"], "somesalt");
+    // Unset fields are skipped rather than `null`.
+    assert!(!env.read(&format!("{dir}/config.json")).contains("null"));
+
+    // The previous configs are moved aside, so only one of them is found.
+    let files: Vec<_> = std::fs::read_dir(env.path(dir))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    assert_eq!(
+        files.iter().filter(|name| !name.contains(".bak-")).count(),
+        1,
+        "{files:?}"
+    );
+    let toml_backup = files
+        .iter()
+        .find(|name| name.starts_with("config.toml.bak-"))
+        .unwrap();
+    assert_eq!(env.read(&format!("{dir}/{toml_backup}")), toml);
+
+    assert_code(&configure_default_dir(&env, &["--format", "toml"]), 0);
+    assert_eq!(env.read(&format!("{dir}/config.toml")), toml);
+}
+
+#[test]
+fn format_must_match_the_config() {
+    let env = Env::new();
+    let before = env.read("config.toml");
+
+    let args = [
+        &PARAMS[..],
+        &["-t", "1", "configure", "--overwrite", "--format", "yaml"],
+    ]
+    .concat();
+    assert_code(&env.run_with_stdin(&args, ""), 2);
+    assert_eq!(env.read("config.toml"), before);
+    assert!(!env.path("config.yaml").exists());
+}
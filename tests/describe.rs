@@ -0,0 +1,52 @@
+//! `--describe` option.
+
+mod common;
+
+use common::{CONFIG, Env, assert_code, success};
+
+fn describe(env: &Env, args: &[&str]) -> serde_json::Value {
+    // No passphrase is piped, it mustn't be prompted for.
+    let out = success(env.run_with_stdin(&[&["--describe"], args].concat(), ""));
+    serde_json::from_str(&out).unwrap()
+}
+
+#[test]
+fn origins_of_the_parameters() {
+    let env = Env::new();
+    let plan = describe(&env, &["secret", "email", "-t", "3"]);
+
+    assert_eq!(plan["config"]["origin"], "env");
+    assert_eq!(plan["memory"]["value"], 64);
+    assert_eq!(plan["memory"]["origin"], "config");
+    assert_eq!(plan["time"]["value"], 3);
+    assert_eq!(plan["time"]["origin"], "cli");
+    assert_eq!(plan["salt_length"]["value"], 18);
+    assert_eq!(plan["final_salt_length"], 23);
+    assert_eq!(plan["name"], "email");
+    assert_eq!(plan["name_mode"], "raw");
+    assert_eq!(plan["output_length"], 32);
+
+    let env = Env::with_config(&format!("{CONFIG}\n[salts.work]\nsalt = \"work salt\"\n"));
+    let plan = describe(&env, &["--namespace", "work", "secret", "email"]);
+    assert_eq!(plan["salt_length"]["origin"], "namespace");
+    assert_eq!(plan["salt_length"]["value"], 9);
+    assert_eq!(plan["namespace"], "work");
+}
+
+#[test]
+fn nothing_gets_derived() {
+    let env = Env::new();
+    describe(&env, &["--audit-log", "audit.log", "secret", "email"]);
+    success(env.run_with_stdin(
+        &["--dry-run", "--audit-log", "audit.log", "secret", "email"],
+        "",
+    ));
+    assert!(!env.path("audit.log").exists());
+}
+
+#[test]
+fn invalid_args() {
+    let env = Env::new();
+    assert_code(&env.run(&["--describe", "--dry-run", "secret", "email"]), 2);
+    assert_code(&env.run(&["--describe", "rotate", "email"]), 2);
+}
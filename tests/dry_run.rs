@@ -0,0 +1,42 @@
+//! `--dry-run` option.
+
+mod common;
+
+use common::{Env, assert_code, success};
+
+#[test]
+fn prints_the_effective_parameters() {
+    let env = Env::new();
+    // No passphrase is piped, it mustn't be prompted for.
+    let out =
+        success(env.run_with_stdin(&["--dry-run", "secret", "name", "-t", "3", "-l", "16"], ""));
+
+    for line in [
+        "Source: config (",
+        "Algorithm: argon2id",
+        "Memory: 64 KiB (64 KiB)",
+        "Time: 3 (iterations)",
+        "Parallelism: 1 (lanes)",
+        "Salt length: 18 bytes",
+        "Final salt length: 22 bytes",
+        "Output length: 16 bytes",
+        "Encoding: hex",
+        "Generation: 0",
+    ] {
+        assert!(out.lines().any(|l| l.starts_with(line)), "{line}: {out}");
+    }
+    assert!(out.contains("overridden by the command line"), "{out}");
+}
+
+#[test]
+fn closed_stdout() {
+    let env = Env::new();
+
+    let mut cmd = env.command(&["--dry-run", "secret", "name"]);
+    // Closed before the process starts.
+    let (reader, writer) = std::io::pipe().unwrap();
+    drop(reader);
+    cmd.stdout(writer);
+
+    assert_code(&common::run(cmd, b""), 6);
+}
@@ -0,0 +1,99 @@
+//! `secret --encrypt-to` option.
+
+mod common;
+
+use std::{fs, str::FromStr as _};
+
+use age::x25519::Identity;
+use common::{Env, assert_code, success};
+
+/// Identity derived by the `age` subcommand.
+fn identity(env: &Env, name: &str) -> Identity {
+    let out = success(env.run(&["age", name]));
+    let secret_key = out
+        .lines()
+        .find(|line| line.starts_with("AGE-SECRET-KEY-1"))
+        .unwrap();
+    Identity::from_str(secret_key).unwrap()
+}
+
+#[test]
+fn decrypts_to_the_secret() {
+    let env = Env::new();
+    let secret = success(env.run(&["secret", "db-pass"]));
+    let (alice, bob) = (identity(&env, "alice"), identity(&env, "bob"));
+
+    let out = success(env.run(&[
+        "secret",
+        "db-pass",
+        "-r",
+        &alice.to_public().to_string(),
+        "--encrypt-to",
+        &bob.to_public().to_string(),
+        "--output",
+        "secret.age",
+    ]));
+    assert_eq!(out, "");
+
+    let ciphertext = fs::read(env.path("secret.age")).unwrap();
+    assert!(!String::from_utf8_lossy(&ciphertext).contains(&secret));
+    for identity in [alice, bob] {
+        let plaintext = age::decrypt(&identity, &ciphertext).unwrap();
+        assert_eq!(String::from_utf8(plaintext).unwrap(), secret);
+    }
+}
+
+#[test]
+fn stdout() {
+    let env = Env::new();
+    let identity = identity(&env, "alice");
+    let recipient = identity.to_public().to_string();
+
+    let out = success(env.run(&["secret", "db-pass", "-r", &recipient, "--armor"]));
+    assert!(
+        out.starts_with("-----BEGIN AGE ENCRYPTED FILE-----\n"),
+        "{out}"
+    );
+    assert_eq!(
+        age::decrypt(&identity, out.as_bytes()).unwrap(),
+        success(env.run(&["secret", "db-pass"])).as_bytes()
+    );
+
+    // Binary unless armored, the secret being formatted the same way as when printed.
+    let out = env.run(&["secret", "db-pass", "-r", &recipient, "-e", "base64"]);
+    assert_code(&out, 0);
+    let plaintext = age::decrypt(&identity, &out.stdout).unwrap();
+    let base64 = success(env.run(&["secret", "db-pass", "-e", "base64"]));
+    assert_eq!(plaintext, base64.as_bytes());
+}
+
+#[test]
+fn invalid_args() {
+    let env = Env::new();
+    let recipient = identity(&env, "alice").to_public().to_string();
+
+    assert_code(&env.run(&["secret", "name", "-r"]), 2);
+    assert_code(&env.run(&["secret", "name", "-r", "age1invalid"]), 2);
+    assert_code(&env.run(&["secret", "name", "--armor"]), 2);
+    assert_code(&env.run(&["secret", "name", "--output", "secret.age"]), 2);
+    assert_code(
+        &env.run(&["secret", "name", "-r", &recipient, "--dotenv", ".env"]),
+        2,
+    );
+    assert_code(
+        &env.run(&["secret", "name", "-r", &recipient, "--expect", "00"]),
+        2,
+    );
+    assert_code(
+        &env.run(&[
+            "secret",
+            "name",
+            "-r",
+            &recipient,
+            "--format",
+            "systemd-creds",
+        ]),
+        2,
+    );
+    assert!(!env.path("secret.age").exists());
+}
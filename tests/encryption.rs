@@ -0,0 +1,70 @@
+//! `encrypt` and `decrypt` subcommands.
+
+mod common;
+
+use std::fs;
+
+use common::{Env, assert_code, success};
+
+/// Encrypts `plain.bin` into `plain.bin.enc` using the key of `name`.
+fn encrypt(env: &Env, name: &str, data: &[u8]) {
+    env.write("plain.bin", data);
+    let out = env.run(&[
+        "encrypt",
+        "--name",
+        name,
+        "plain.bin",
+        "--output",
+        "plain.bin.enc",
+    ]);
+    assert_code(&out, 0);
+}
+
+#[test]
+fn round_trip() {
+    let env = Env::new();
+    // Spans a few 64 KiB chunks.
+    let data: Vec<u8> = (0..200_000).map(|idx| (idx % 251) as u8).collect();
+    encrypt(&env, "backup", &data);
+
+    success(env.run(&["decrypt", "plain.bin.enc", "--output", "decrypted.bin"]));
+    assert_eq!(fs::read(env.path("decrypted.bin")).unwrap(), data);
+}
+
+#[test]
+fn wrong_name() {
+    let env = Env::new();
+    encrypt(&env, "backup", b"data");
+
+    let out = env.run(&[
+        "decrypt",
+        "--name",
+        "other",
+        "plain.bin.enc",
+        "--output",
+        "decrypted.bin",
+    ]);
+    assert_code(&out, 7);
+    // The output only appears once the whole input got decrypted.
+    assert!(!env.path("decrypted.bin").exists());
+}
+
+#[test]
+fn tampered_file() {
+    let env = Env::new();
+    encrypt(&env, "backup", b"data");
+
+    let path = env.path("plain.bin.enc");
+    let mut ciphertext = fs::read(&path).unwrap();
+    *ciphertext.last_mut().unwrap() ^= 1;
+    fs::write(&path, ciphertext).unwrap();
+
+    assert_code(&env.run(&["decrypt", "plain.bin.enc"]), 7);
+}
+
+#[test]
+fn not_an_encrypted_file() {
+    let env = Env::new();
+    env.write("plain.bin", b"definitely not encrypted");
+    assert_code(&env.run(&["decrypt", "plain.bin"]), 8);
+}
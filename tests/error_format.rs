@@ -0,0 +1,76 @@
+//! `--error-format json` option.
+
+mod common;
+
+use common::{Env, assert_code, stderr};
+use serde_json::Value;
+
+/// Runs the binary, asserting its exit code and that stderr holds a single JSON object.
+#[track_caller]
+fn json_error(env: &Env, args: &[&str], stdin: &str, code: i32) -> Value {
+    let out = env.run_with_stdin(&[&["--error-format", "json"], args].concat(), stdin);
+    assert_code(&out, code);
+
+    let stderr = stderr(&out);
+    let line = stderr.lines().last().unwrap();
+    let json: Value = serde_json::from_str(line).unwrap_or_else(|e| panic!("{e}: {stderr}"));
+    assert_eq!(json["code"], code);
+    assert!(json["error"].is_string());
+    json
+}
+
+#[test]
+fn representative_failures() {
+    let env = Env::new();
+
+    let json = json_error(&env, &["secret", "name", "-t", "0"], "", 2);
+    assert_eq!(json["kind"], "usage");
+    assert_eq!(json["hint"], "See `--help` for the usage");
+
+    let json = json_error(&env, &["secret", "name"], "", 4);
+    assert_eq!(json["kind"], "passphrase");
+    assert_eq!(json["error"], "Empty passphrase!");
+    assert!(json.get("hint").is_none());
+
+    let phc = "$argon2id$v=19$m=64,t=1,p=1$c29tZXNhbHQ$P/yFQsy5QJRYEOxcAWsVeGzrYrOiF30wrmg+JtYJPPA";
+    let json = json_error(&env, &["check", phc], "wrong", 7);
+    assert_eq!(json["kind"], "mismatch");
+
+    let json = json_error(&env, &["encrypt", "--name", "name", "missing.txt"], "", 8);
+    assert_eq!(json["kind"], "input");
+}
+
+#[test]
+fn missing_config() {
+    let env = Env::new();
+    let mut cmd = env.command(&["--json-errors", "secret", "name"]);
+    cmd.env("ARGON2DERIVE_CONFIG", env.path("missing.toml"));
+    let out = common::run(cmd, b"");
+
+    assert_code(&out, 3);
+    let json: Value = serde_json::from_str(stderr(&out).trim()).unwrap();
+    assert_eq!(json["kind"], "config");
+    assert!(json["hint"].as_str().unwrap().contains("configure"));
+}
+
+#[test]
+fn success_is_unchanged() {
+    let env = Env::new();
+    let human = common::success(env.run(&["secret", "name"]));
+    let json = common::success(env.run(&["--error-format", "json", "secret", "name"]));
+    assert_eq!(json, human);
+}
+
+#[test]
+fn json_errors_shorthand() {
+    let env = Env::new();
+    let shorthand = env.run_with_stdin(&["--json-errors", "secret", "name"], "");
+    let explicit = env.run_with_stdin(&["--error-format", "json", "secret", "name"], "");
+    assert_code(&shorthand, 4);
+    assert_eq!(stderr(&shorthand), stderr(&explicit));
+
+    let out = env.run(&["--json-errors", "--error-format", "human", "secret", "name"]);
+    assert_code(&out, 2);
+    let out = env.run(&["--json-errors", "--error-format", "json", "secret", "name"]);
+    assert_code(&out, 2);
+}
@@ -0,0 +1,89 @@
+//! Exit codes, see the `--help` output.
+
+mod common;
+
+use common::{Env, assert_code};
+
+#[test]
+fn usage() {
+    let env = Env::new();
+    assert_code(&env.run(&["secret"]), 2);
+    assert_code(&env.run(&["secret", "name", "--no-such-flag"]), 2);
+    assert_code(&env.run(&["secret", "name", "-t", "0"]), 2);
+    assert_code(&env.run(&["secret", "name", "-m", "4096"]), 2);
+}
+
+#[test]
+fn config() {
+    let env = Env::new();
+    let mut cmd = env.command(&["secret", "name"]);
+    cmd.env("ARGON2DERIVE_CONFIG", env.path("missing.toml"));
+    assert_code(&common::run(cmd, b"passphrase"), 3);
+
+    let env = Env::with_config("memory = \"a lot\"");
+    assert_code(&env.run(&["secret", "name"]), 3);
+}
+
+#[test]
+fn passphrase() {
+    let env = Env::new();
+    assert_code(&env.run_with_stdin(&["secret", "name"], ""), 4);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn output() {
+    let env = Env::new();
+    let mut cmd = env.command(&["secret", "name"]);
+    cmd.stdout(
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open("/dev/full")
+            .unwrap(),
+    );
+    assert_code(&common::run(cmd, common::PASSPHRASE.as_bytes()), 6);
+}
+
+#[test]
+fn mismatch() {
+    let env = Env::new();
+    let phc = "$argon2id$v=19$m=64,t=1,p=1$c29tZXNhbHQ$P/yFQsy5QJRYEOxcAWsVeGzrYrOiF30wrmg+JtYJPPA";
+    assert_code(&env.run_with_stdin(&["check", phc], "wrong"), 7);
+}
+
+#[test]
+fn input() {
+    let env = Env::new();
+    assert_code(&env.run(&["encrypt", "--name", "name", "missing.txt"]), 8);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn derivation() {
+    // Exceeds the available memory.
+    let env = Env::new();
+    assert_code(&env.run(&["secret", "name", "-m", "4095"]), 5);
+}
+
+#[cfg(unix)]
+#[test]
+fn interrupted() {
+    use std::io::{BufRead as _, BufReader, Write as _};
+
+    let env = Env::new();
+    let mut child = env.command(&["totp", "name", "--watch"]).spawn().unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(common::PASSPHRASE.as_bytes()).unwrap();
+    drop(stdin);
+
+    // Waits for the first code, so the handler is installed.
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+
+    // SAFETY: Plain libc call, the process is our child.
+    assert_eq!(unsafe { libc::kill(child.id() as i32, libc::SIGINT) }, 0);
+    let output = child.wait_with_output().unwrap();
+    assert_code(&output, 130);
+}
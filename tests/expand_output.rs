@@ -0,0 +1,54 @@
+//! `--expand-output` option.
+
+mod common;
+
+use argon2derive::argon2::{self, Algorithm, EXPANSION_BASE_LEN, EXPANSION_THRESHOLD, Parameters};
+use common::{Env, PASSPHRASE, success};
+
+/// Parameters of the secret `name` of [`common::CONFIG`].
+fn params(name: &str) -> Parameters {
+    let mut params = Parameters {
+        algorithm: Algorithm::Argon2id,
+        memory: 64,
+        time: 1,
+        parallelism: 1,
+        salt: b"argon2derive tests".to_vec(),
+    };
+    params.append_name(name, 0).unwrap();
+    params
+}
+
+fn secret(env: &Env, len: u32, expand: bool) -> String {
+    let len = len.to_string();
+    let mut args = vec!["secret", "file", "--length", &len];
+    if expand {
+        args.push("--expand-output");
+    }
+    success(env.run(&args))
+}
+
+#[test]
+fn unchanged_up_to_the_threshold() {
+    let env = Env::new();
+    let plain = hex::encode(
+        argon2::hash(&params("file"), PASSPHRASE.as_bytes(), EXPANSION_THRESHOLD).unwrap(),
+    );
+    assert_eq!(secret(&env, EXPANSION_THRESHOLD, false), plain);
+    assert_eq!(secret(&env, EXPANSION_THRESHOLD, true), plain);
+    assert_eq!(secret(&env, 32, true), secret(&env, 32, false));
+}
+
+#[test]
+fn expanded_above_the_threshold() {
+    let env = Env::new();
+    let len = EXPANSION_THRESHOLD + 1;
+
+    // Plain Argon2 unless asked for.
+    let plain = hex::encode(argon2::hash(&params("file"), PASSPHRASE.as_bytes(), len).unwrap());
+    assert_eq!(secret(&env, len, false), plain);
+
+    let base = argon2::hash(&params("file"), PASSPHRASE.as_bytes(), EXPANSION_BASE_LEN).unwrap();
+    let expanded = secret(&env, len, true);
+    assert_eq!(expanded, hex::encode(argon2::expand(&base, len)));
+    assert_ne!(expanded, plain);
+}
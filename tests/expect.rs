@@ -0,0 +1,50 @@
+//! `secret --expect` and `--expect-file` options.
+
+mod common;
+
+use common::{Env, assert_code, stderr, stdout, success};
+
+#[test]
+fn match_and_mismatch() {
+    let env = Env::new();
+    let secret = success(env.run(&["secret", "email"]));
+
+    // The case of the hex digits doesn't matter.
+    for expected in [secret.clone(), secret.to_uppercase()] {
+        let out = env.run(&["secret", "email", "--expect", &expected]);
+        assert_code(&out, 0);
+        assert_eq!(stdout(&out), "");
+        assert!(stderr(&out).contains("Match"), "{}", stderr(&out));
+    }
+
+    let out = env.run(&["secret", "other", "--expect", &secret]);
+    assert_code(&out, 7);
+    assert_eq!(stdout(&out), "");
+    assert!(stderr(&out).contains("Mismatch"), "{}", stderr(&out));
+}
+
+#[test]
+fn expect_file() {
+    let env = Env::new();
+    let words = success(env.run(&["secret", "email", "--encoding", "words"]));
+    let path = env.write("expected", format!("{words}\n"));
+    let path = path.to_str().unwrap();
+
+    let expect = |name| env.run(&["secret", name, "--encoding", "words", "--expect-file", path]);
+    assert_code(&expect("email"), 0);
+    assert_code(&expect("other"), 7);
+
+    let out = env.run(&["secret", "email", "--expect-file", "missing"]);
+    assert_code(&out, 8);
+}
+
+#[test]
+fn invalid_args() {
+    let env = Env::new();
+    // Not hex, a usage error rather than a mismatch.
+    assert_code(&env.run(&["secret", "email", "--expect", "zz"]), 2);
+    assert_code(
+        &env.run(&["secret", "email", "--expect", "00", "--dotenv", ".env"]),
+        2,
+    );
+}
@@ -0,0 +1,45 @@
+//! `generate-passphrase` subcommand.
+
+mod common;
+
+use common::{Env, assert_code, stderr, success};
+
+#[test]
+fn generates_words() {
+    let env = Env::new();
+
+    let out = env.run_with_stdin(&["generate-passphrase"], "");
+    assert!(
+        stderr(&out).contains("Passphrase (77.0 bits of entropy):"),
+        "{}",
+        stderr(&out)
+    );
+    let passphrase = success(out);
+    assert_eq!(passphrase.split(' ').count(), 7, "{passphrase}");
+
+    let out = env.run_with_stdin(&["generate-passphrase", "-w", "3", "--separator", "-"], "");
+    assert!(stderr(&out).contains("33.0 bits"), "{}", stderr(&out));
+    let passphrase = success(out);
+    assert_eq!(passphrase.split('-').count(), 3, "{passphrase}");
+    assert!(
+        passphrase
+            .bytes()
+            .all(|b| b == b'-' || b.is_ascii_lowercase())
+    );
+
+    // Random, unlike everything else.
+    let generate = || success(env.run_with_stdin(&["generate-passphrase"], ""));
+    assert_ne!(generate(), generate());
+
+    assert_code(
+        &env.run_with_stdin(&["generate-passphrase", "-w", "0"], ""),
+        2,
+    );
+}
+
+#[test]
+fn needs_no_config() {
+    let env = Env::with_config("not a config");
+    assert_code(&env.run(&["secret", "name"]), 3);
+    success(env.run_with_stdin(&["generate-passphrase"], ""));
+}
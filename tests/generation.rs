@@ -0,0 +1,24 @@
+//! `--generation` option.
+
+mod common;
+
+use common::{Env, success};
+
+#[test]
+fn generation_zero_is_the_default() {
+    let env = Env::new();
+    let secret = success(env.run(&["secret", "email"]));
+
+    assert_eq!(
+        success(env.run(&["secret", "email", "--generation", "0"])),
+        secret
+    );
+    assert_ne!(
+        success(env.run(&["secret", "email", "--generation", "1"])),
+        secret
+    );
+    assert_ne!(
+        success(env.run(&["secret", "email1"])),
+        success(env.run(&["secret", "email", "--generation", "1"]))
+    );
+}
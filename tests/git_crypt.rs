@@ -0,0 +1,36 @@
+//! `git-crypt` subcommand.
+
+mod common;
+
+use std::fs;
+
+use common::{Env, assert_code, success};
+
+#[test]
+fn key_file() {
+    let env = Env::new();
+    let material = hex::decode(success(env.run(&["secret", "repo", "--length", "96"]))).unwrap();
+
+    success(env.run(&["git-crypt", "repo", "--key-name", "deploy", "-o", "key"]));
+    let key_file = fs::read(env.path("key")).unwrap();
+
+    // Magic, format version 2, key name header field, end of the header.
+    let header = b"\0GITCRYPTKEY\0\0\0\x02\0\0\0\x01\0\0\0\x06deploy\0\0\0\0";
+    let (prefix, entry) = key_file.split_at(header.len());
+    assert_eq!(prefix, header);
+
+    // Version 0, the AES key and the HMAC key, end of the entry.
+    let mut expected = b"\0\0\0\x01\0\0\0\x04\0\0\0\0\0\0\0\x03\0\0\0\x20".to_vec();
+    expected.extend_from_slice(&material[..32]);
+    expected.extend_from_slice(b"\0\0\0\x05\0\0\0\x40");
+    expected.extend_from_slice(&material[32..]);
+    expected.extend_from_slice(b"\0\0\0\0");
+    assert_eq!(entry, expected);
+}
+
+#[test]
+fn reserved_key_name() {
+    let env = Env::new();
+    assert_code(&env.run(&["git-crypt", "repo", "--key-name", "default"]), 2);
+    assert_code(&env.run(&["git-crypt", "repo", "--key-name", "a/b"]), 2);
+}
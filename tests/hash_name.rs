@@ -0,0 +1,54 @@
+//! `--hash-name` option.
+
+mod common;
+
+use argon2derive::argon2::{self, Algorithm, Parameters};
+use common::{Env, PASSPHRASE, assert_code, success};
+use sha2::{Digest as _, Sha256};
+
+/// Secret of the name, its salt composed independently of the binary.
+fn expected(name: &str) -> String {
+    let mut salt = b"argon2derive tests".to_vec();
+    salt.extend(Sha256::digest(format!(
+        "argon2derive hashed name v1\0{name}"
+    )));
+    let params = Parameters {
+        algorithm: Algorithm::Argon2id,
+        memory: 64,
+        time: 1,
+        parallelism: 1,
+        salt,
+    };
+    hex::encode(argon2::hash(&params, PASSPHRASE.as_bytes(), 32).unwrap())
+}
+
+#[test]
+fn salt_gets_the_hash_of_the_name() {
+    let env = Env::new();
+    for name in ["email", "two\nlines", &"long".repeat(100)] {
+        let hashed = success(env.run(&["secret", name, "--hash-name"]));
+        assert_eq!(hashed, expected(name), "{name:?}");
+        assert_eq!(success(env.run(&["secret", name, "--hash-name"])), hashed);
+
+        let raw = success(env.run(&["secret", name]));
+        assert_ne!(raw, hashed, "{name:?}");
+    }
+}
+
+#[test]
+fn dry_run_reports_the_naming() {
+    let env = Env::new();
+    let out = success(env.run_with_stdin(&["--dry-run", "secret", "email", "--hash-name"], ""));
+    assert!(out.contains("Name: hashed (SHA-256, v1)"), "{out}");
+    // 18 bytes of salt and 32 of hash.
+    assert!(out.contains("Final salt length: 50 bytes"), "{out}");
+
+    let out = success(env.run_with_stdin(&["--dry-run", "secret", "email"], ""));
+    assert!(out.contains("Name: raw"), "{out}");
+}
+
+#[test]
+fn conflicts_with_hierarchical_names() {
+    let out = Env::new().run(&["secret", "a/b", "--hash-name", "--hierarchical-names"]);
+    assert_code(&out, 2);
+}
@@ -0,0 +1,71 @@
+//! `--hierarchical-names` option.
+
+mod common;
+
+use argon2derive::argon2::{self, Algorithm, Parameters};
+use common::{Env, PASSPHRASE, assert_code, success};
+
+/// Secret of the levels, their salt composed independently of the binary.
+fn expected(levels: &[&str], generation: u32) -> String {
+    let mut salt = b"argon2derive tests".to_vec();
+    let mut path = vec![0];
+    path.extend((levels.len() as u32).to_be_bytes());
+    for level in levels {
+        path.extend((level.len() as u32).to_be_bytes());
+        path.extend(level.as_bytes());
+    }
+    salt.extend(&path);
+    if generation != 0 {
+        salt.push(0);
+        salt.extend((path.len() as u32).to_be_bytes());
+        salt.extend(generation.to_be_bytes());
+    }
+
+    let params = Parameters {
+        algorithm: Algorithm::Argon2id,
+        memory: 64,
+        time: 1,
+        parallelism: 1,
+        salt,
+    };
+    hex::encode(argon2::hash(&params, PASSPHRASE.as_bytes(), 32).unwrap())
+}
+
+#[test]
+fn levels_are_length_prefixed() {
+    let env = Env::new();
+    let secret =
+        |args: &[&str]| success(env.run(&[&["secret", "--hierarchical-names"], args].concat()));
+
+    let path = secret(&["work/aws/prod"]);
+    assert_eq!(path, expected(&["work", "aws", "prod"], 0));
+    assert_ne!(path, success(env.run(&["secret", "work/aws/prod"])));
+
+    assert_eq!(secret(&[r"a\/b"]), expected(&["a/b"], 0));
+    assert_eq!(secret(&[r"a\\/b"]), expected(&[r"a\", "b"], 0));
+    assert_ne!(secret(&[r"a\/b"]), secret(&["a/b"]));
+
+    assert_eq!(
+        secret(&["work/aws/prod", "--generation", "2"]),
+        expected(&["work", "aws", "prod"], 2)
+    );
+}
+
+#[test]
+fn dry_run_reports_the_naming() {
+    let env = Env::new();
+    let out =
+        success(env.run_with_stdin(&["--dry-run", "secret", "a/b", "--hierarchical-names"], ""));
+    assert!(out.contains("Name: hierarchical"), "{out}");
+    // 18 bytes of salt, 5 of header and 5 per level.
+    assert!(out.contains("Final salt length: 33 bytes"), "{out}");
+}
+
+#[test]
+fn invalid_names() {
+    let env = Env::new();
+    for name in ["a//b", "a/", "/a", r"a\b", r"a\"] {
+        let out = env.run(&["secret", name, "--hierarchical-names"]);
+        assert_code(&out, 2);
+    }
+}
@@ -0,0 +1,85 @@
+//! `hmac` subcommand.
+
+mod common;
+
+use common::{Env, assert_code, success};
+
+/// Writes two files and the MACs of them into `macs.txt`.
+fn write_macs(env: &Env) {
+    env.write("a.txt", "a");
+    env.write("b.txt", "b");
+    let macs = success(env.run(&["hmac", "--name", "files", "a.txt", "b.txt"]));
+    assert_eq!(macs.lines().count(), 2);
+    assert!(macs.lines().all(|line| line.len() == 64 + 2 + 5));
+    env.write("macs.txt", macs);
+}
+
+#[test]
+fn check_passes() {
+    let env = Env::new();
+    write_macs(&env);
+
+    let out = env.run(&["hmac", "--name", "files", "--check", "macs.txt"]);
+    assert_code(&out, 0);
+    assert!(common::stderr(&out).contains("a.txt: OK"));
+}
+
+#[test]
+fn check_fails() {
+    let env = Env::new();
+    write_macs(&env);
+    env.write("b.txt", "modified");
+
+    let out = env.run(&["hmac", "--name", "files", "--check", "macs.txt"]);
+    assert_code(&out, 7);
+    assert!(common::stderr(&out).contains("a.txt: OK"));
+    assert!(common::stderr(&out).contains("b.txt: FAILED"));
+
+    // Another key.
+    env.write("b.txt", "b");
+    let out = env.run(&["hmac", "--name", "other", "--check", "macs.txt"]);
+    assert_code(&out, 7);
+}
+
+#[test]
+fn check_reports_missing_files_and_malformed_lines() {
+    let env = Env::new();
+    write_macs(&env);
+    let mut macs = env.read("macs.txt");
+    macs.push_str("not a MAC line\n");
+    env.write("macs.txt", macs);
+    std::fs::remove_file(env.path("a.txt")).unwrap();
+
+    let out = env.run(&["hmac", "--name", "files", "--check", "macs.txt"]);
+    assert_code(&out, 7);
+    let stderr = common::stderr(&out);
+    assert!(stderr.contains("a.txt: FAILED"));
+    assert!(stderr.contains("b.txt: OK"));
+    assert!(stderr.contains("line 3:"));
+}
+
+#[test]
+fn missing_list() {
+    let out = Env::new().run(&["hmac", "--name", "files", "--check", "missing.txt"]);
+    assert_code(&out, 8);
+}
+
+#[cfg(unix)]
+#[test]
+fn closed_stdout() {
+    let env = Env::new();
+    env.write("a.txt", "a");
+
+    let mut child = env
+        .command(&["hmac", "--name", "files", "a.txt"])
+        .spawn()
+        .unwrap();
+    // Closed before anything gets written to it.
+    drop(child.stdout.take());
+    let mut stdin = child.stdin.take().unwrap();
+    std::io::Write::write_all(&mut stdin, common::PASSPHRASE.as_bytes()).unwrap();
+    drop(stdin);
+
+    let out = child.wait_with_output().unwrap();
+    assert_code(&out, 6);
+}
@@ -0,0 +1,68 @@
+//! `secret --format htpasswd`.
+
+mod common;
+
+use common::{Env, assert_code, success};
+
+#[test]
+fn deterministic_line() {
+    let env = Env::new();
+    let args = [
+        "secret",
+        "admin",
+        "--format",
+        "htpasswd",
+        "--user",
+        "alice",
+        "--bcrypt-cost",
+        "4",
+        "--deterministic-salt",
+    ];
+
+    // Checked with libxcrypt's `crypt(3)` against the password printed by `secret admin`.
+    let line = "alice:$2y$04$FWikrQqwAyCUDFzuel5APOtRsdJdSZ6.M8MHmIA8uSkiQ6H/Y6iXi";
+    assert_eq!(success(env.run(&args)), line);
+    assert_eq!(success(env.run(&args)), line);
+
+    let password = success(env.run(&["secret", "admin"]));
+    assert!(bcrypt::verify(password, line.strip_prefix("alice:").unwrap()).unwrap());
+}
+
+#[test]
+fn random_salt() {
+    let env = Env::new();
+    let args = [
+        "secret",
+        "admin",
+        "--format",
+        "htpasswd",
+        "--user",
+        "alice",
+        "--bcrypt-cost",
+        "4",
+    ];
+    let line = success(env.run(&args));
+    assert_ne!(success(env.run(&args)), line);
+
+    let password = success(env.run(&["secret", "admin"]));
+    assert!(bcrypt::verify(password, line.strip_prefix("alice:").unwrap()).unwrap());
+}
+
+#[test]
+fn invalid_args() {
+    let env = Env::new();
+    assert_code(&env.run(&["secret", "admin", "--format", "htpasswd"]), 2);
+    let args = ["secret", "admin", "--format", "htpasswd", "--user", "a:b"];
+    assert_code(&env.run(&args), 2);
+    let args = [
+        "secret",
+        "admin",
+        "--format",
+        "htpasswd",
+        "--user",
+        "alice",
+        "--bcrypt-cost",
+        "3",
+    ];
+    assert_code(&env.run(&args), 2);
+}
@@ -0,0 +1,200 @@
+//! Ctrl-C (SIGINT) and SIGTERM handling.
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::{
+    ffi::CStr,
+    fs::File,
+    io::{Read as _, Write as _},
+    os::{
+        fd::AsRawFd as _, fd::FromRawFd as _, unix::fs::OpenOptionsExt as _,
+        unix::process::CommandExt as _,
+    },
+    process::Stdio,
+    thread,
+    time::{Duration, Instant},
+};
+
+use common::{Env, assert_code, stderr};
+
+/// Settings of the terminal.
+fn termios(tty: &File) -> libc::termios {
+    // SAFETY: `termios` is plain data, filled by `tcgetattr` whose result is checked.
+    unsafe {
+        let mut termios = std::mem::zeroed();
+        assert_eq!(libc::tcgetattr(tty.as_raw_fd(), &mut termios), 0);
+        termios
+    }
+}
+
+/// Waits for the condition, checking it every 10 ms for up to 10 seconds.
+fn wait_for(mut condition: impl FnMut() -> bool) {
+    let start = Instant::now();
+    while !condition() {
+        assert!(start.elapsed() < Duration::from_secs(10), "timed out");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Signals the process waiting for the passphrase on a hidden prompt, via `signal(master, pid)`,
+/// checking that the process exits with 130 and restores the echo of the terminal.
+fn interrupt_the_hidden_prompt(signal: impl FnOnce(&File, u32)) {
+    // SAFETY: Plain libc calls, their results are checked.
+    let (master, slave) = unsafe {
+        let master = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+        assert!(master >= 0, "{}", std::io::Error::last_os_error());
+        assert_eq!(libc::grantpt(master), 0);
+        assert_eq!(libc::unlockpt(master), 0);
+
+        let mut name = [0; 64];
+        assert_eq!(libc::ptsname_r(master, name.as_mut_ptr(), name.len()), 0);
+        (
+            File::from_raw_fd(master),
+            CStr::from_ptr(name.as_ptr()).to_owned(),
+        )
+    };
+    // Kept open to inspect the settings once the process is gone.
+    let tty = File::options()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NOCTTY)
+        .open(slave.to_str().unwrap())
+        .unwrap();
+    assert_ne!(termios(&tty).c_lflag & libc::ECHO, 0);
+
+    let env = Env::new();
+    let mut cmd = env.command(&["secret", "name", "--passphrase-tty"]);
+    // SAFETY: Only async-signal-safe functions are called between `fork` and `exec`.
+    unsafe {
+        cmd.stderr(Stdio::null()).pre_exec(move || {
+            if libc::setsid() < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            // The first terminal opened by a session leader becomes its controlling terminal.
+            let fd = libc::open(slave.as_ptr(), libc::O_RDWR);
+            if fd < 0 || libc::dup2(fd, libc::STDERR_FILENO) < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let child = cmd.spawn().unwrap();
+
+    // Drains the terminal, so the process never blocks on writing to it.
+    let reader = {
+        let master = master.try_clone().unwrap();
+        thread::spawn(move || {
+            let mut transcript = Vec::new();
+            let _ = (&master).read_to_end(&mut transcript);
+            transcript
+        })
+    };
+
+    // The echo is disabled once the hidden prompt waits for the passphrase.
+    wait_for(|| termios(&tty).c_lflag & libc::ECHO == 0);
+    signal(&master, child.id());
+
+    let output = child.wait_with_output().unwrap();
+    assert_code(&output, 130);
+    assert_ne!(termios(&tty).c_lflag & libc::ECHO, 0);
+
+    drop(tty);
+    drop(master);
+    let transcript = String::from_utf8_lossy(&reader.join().unwrap()).into_owned();
+    assert!(transcript.contains("Enter passphrase:"), "{transcript}");
+}
+
+#[test]
+fn ctrl_c_at_the_hidden_prompt() {
+    interrupt_the_hidden_prompt(|master, _| {
+        // Typing Ctrl-C makes the terminal send SIGINT to its foreground process group.
+        (&*master).write_all(b"\x03").unwrap();
+    });
+}
+
+#[test]
+fn sigterm_at_the_hidden_prompt() {
+    interrupt_the_hidden_prompt(|_, pid| kill(pid, libc::SIGTERM));
+}
+
+fn kill(pid: u32, signal: i32) {
+    // SAFETY: Plain libc call, the process is our child.
+    assert_eq!(unsafe { libc::kill(pid as i32, signal) }, 0);
+}
+
+#[test]
+fn encryption_removes_its_temporary_file() {
+    let env = Env::new();
+    let fifo = env.path("input");
+    let path = std::ffi::CString::new(fifo.to_str().unwrap()).unwrap();
+    // SAFETY: The path is a valid C string.
+    assert_eq!(unsafe { libc::mkfifo(path.as_ptr(), 0o600) }, 0);
+
+    let mut child = env
+        .command(&[
+            "encrypt", "--name", "backup", "input", "--output", "out.enc",
+        ])
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(common::PASSPHRASE.as_bytes()).unwrap();
+    drop(stdin);
+
+    // Some data is encrypted, then the input stalls with the output half-written.
+    let mut input = File::options().write(true).open(&fifo).unwrap();
+    input.write_all(&[0; 100_000]).unwrap();
+    let temp_files = || {
+        std::fs::read_dir(env.path(""))
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_str().unwrap().starts_with("out.enc")
+            })
+            .count()
+    };
+    wait_for(|| temp_files() == 1);
+
+    kill(child.id(), libc::SIGINT);
+    let output = child.wait_with_output().unwrap();
+    assert_code(&output, 130);
+    assert_eq!(temp_files(), 0);
+    // Unwound from the deferred region, rather than exited from the handler.
+    assert!(
+        stderr(&output).contains("Interrupted"),
+        "{}",
+        stderr(&output)
+    );
+    drop(input);
+}
+
+#[test]
+fn session_ends_on_sigterm() {
+    let env = Env::new();
+    let mut child = env
+        .command(&[
+            "--stdin-passphrase",
+            "--passphrase-trim",
+            "newline",
+            "session",
+        ])
+        .spawn()
+        .unwrap();
+    // Kept open, so the session waits for the next command.
+    let mut stdin = child.stdin.take().unwrap();
+    writeln!(stdin, "{}\nsecret name", common::PASSPHRASE).unwrap();
+
+    let mut line = String::new();
+    std::io::BufRead::read_line(
+        &mut std::io::BufReader::new(child.stdout.as_mut().unwrap()),
+        &mut line,
+    )
+    .unwrap();
+    assert!(!line.trim_end().is_empty());
+
+    kill(child.id(), libc::SIGTERM);
+    let output = child.wait_with_output().unwrap();
+    assert_code(&output, 130);
+    drop(stdin);
+}
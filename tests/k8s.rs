@@ -0,0 +1,86 @@
+//! `secret --format k8s-secret`.
+
+mod common;
+
+use base64::Engine as _;
+use common::{Env, assert_code, success};
+
+#[test]
+fn manifest() {
+    let env = Env::new();
+    let secret = success(env.run(&["secret", "db/pass"]));
+
+    let args = [
+        "secret",
+        "db/pass",
+        "--format",
+        "k8s-secret",
+        "--k8s-name",
+        "app",
+    ];
+    let doc: serde_yaml::Value = serde_yaml::from_str(&success(env.run(&args))).unwrap();
+    assert_eq!(doc["metadata"]["name"], "app");
+    let data = doc["data"]["db_pass"].as_str().unwrap();
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .unwrap();
+    assert_eq!(data, secret.as_bytes());
+
+    let args = [&args[..], &["--string-data"]].concat();
+    let doc: serde_yaml::Value = serde_yaml::from_str(&success(env.run(&args))).unwrap();
+    assert_eq!(doc["stringData"]["db_pass"].as_str(), Some(secret.as_str()));
+}
+
+#[test]
+fn invalid_names() {
+    let env = Env::new();
+    assert_code(&env.run(&["secret", "db", "--format", "k8s-secret"]), 2);
+    let args = [
+        "secret",
+        "db",
+        "--format",
+        "k8s-secret",
+        "--k8s-name",
+        "App",
+    ];
+    assert_code(&env.run(&args), 2);
+}
+
+#[test]
+fn kubernetes_and_salt_namespaces() {
+    let env = Env::with_config(&format!(
+        "{}\n[salts.work]\nsalt = \"work salt\"\n",
+        common::CONFIG
+    ));
+    let secret = success(env.run(&["secret", "db", "--namespace", "work"]));
+    assert_ne!(secret, success(env.run(&["secret", "db"])));
+
+    let args = [
+        "secret",
+        "db",
+        "--namespace",
+        "work",
+        "--format",
+        "k8s-secret",
+        "--k8s-name",
+        "app",
+        "--k8s-namespace",
+        "prod",
+        "--string-data",
+    ];
+    let doc: serde_yaml::Value = serde_yaml::from_str(&success(env.run(&args))).unwrap();
+    assert_eq!(doc["metadata"]["namespace"], "prod");
+    assert_eq!(doc["stringData"]["db"].as_str(), Some(secret.as_str()));
+
+    let args = [
+        "secret",
+        "db",
+        "--format",
+        "k8s-secret",
+        "--k8s-name",
+        "app",
+        "--k8s-namespace",
+        "Prod",
+    ];
+    assert_code(&env.run(&args), 2);
+}
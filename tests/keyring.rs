@@ -0,0 +1,99 @@
+//! `--store-keyring` of the `secret` subcommand and `keyring delete`, using a fake `secret-tool`.
+
+#![cfg(all(unix, not(target_os = "macos")))]
+
+mod common;
+
+use common::{Env, PASSPHRASE, assert_code, stderr, success};
+
+/// Fake `secret-tool` keeping the entries in `keyring/<service>/<account>`, along with their `application`.
+const SECRET_TOOL: &str = r#"cmd=$1; shift
+[ "$cmd" = store ] && shift
+entry="keyring/$2/$4"
+case $cmd in
+store) mkdir -p "keyring/$2"; cat > "$entry"; echo "$6" > "$entry.app" ;;
+lookup)
+    [ -f "$entry" ] || exit 1
+    if [ -n "$6" ] && [ "$(cat "$entry.app")" != "$6" ]; then exit 1; fi
+    cat "$entry" ;;
+clear) rm -f "$entry" "$entry.app" ;;
+esac
+"#;
+
+fn run(env: &Env, args: &[&str]) -> std::process::Output {
+    common::run(env.with_bin(args), PASSPHRASE.as_bytes())
+}
+
+#[test]
+fn store_and_delete() {
+    let env = Env::new();
+    env.script("secret-tool", SECRET_TOOL);
+    let secret = success(env.run(&["secret", "db-pass"]));
+
+    let out = run(&env, &["secret", "db-pass", "--store-keyring", "work/db"]);
+    assert!(
+        stderr(&out).contains("stored in the keyring as `work/db`"),
+        "{}",
+        stderr(&out)
+    );
+    assert_eq!(success(out), "");
+    assert_eq!(env.read("keyring/work/db"), secret);
+    assert_eq!(env.read("keyring/work/db.app"), "argon2derive\n");
+
+    success(run(&env, &["keyring", "delete", "work/db"]));
+    assert!(!env.path("keyring/work/db").exists());
+    assert_code(&run(&env, &["keyring", "delete", "work/db"]), 2);
+}
+
+#[test]
+fn overwrite_requires_force() {
+    let env = Env::new();
+    env.script("secret-tool", SECRET_TOOL);
+    success(run(&env, &["secret", "old", "--store-keyring", "work/db"]));
+    let old = env.read("keyring/work/db");
+
+    assert_code(
+        &run(&env, &["secret", "new", "--store-keyring", "work/db"]),
+        2,
+    );
+    assert_eq!(env.read("keyring/work/db"), old);
+
+    success(run(
+        &env,
+        &["secret", "new", "--store-keyring", "work/db", "--force"],
+    ));
+    assert_ne!(env.read("keyring/work/db"), old);
+}
+
+#[test]
+fn foreign_entries_are_kept() {
+    let env = Env::new();
+    env.script("secret-tool", SECRET_TOOL);
+    std::fs::create_dir_all(env.path("keyring/work")).unwrap();
+    env.write("keyring/work/db", "not ours");
+    env.write("keyring/work/db.app", "other\n");
+
+    assert_code(&run(&env, &["keyring", "delete", "work/db"]), 2);
+    assert_eq!(env.read("keyring/work/db"), "not ours");
+}
+
+#[test]
+fn failures() {
+    let env = Env::new();
+    // No `secret-tool` in the `PATH`.
+    let mut cmd = env.command(&["secret", "db", "--store-keyring", "work/db"]);
+    cmd.env("PATH", "");
+    assert_code(&common::run(cmd, PASSPHRASE.as_bytes()), 6);
+
+    env.script("secret-tool", "echo 'no keyring' >&2; exit 1");
+    let out = run(
+        &env,
+        &["secret", "db", "--store-keyring", "work/db", "--force"],
+    );
+    assert_code(&out, 6);
+    assert!(stderr(&out).contains("no keyring"), "{}", stderr(&out));
+
+    for entry in ["work", "/db", "work/", "wo\"rk/db"] {
+        assert_code(&run(&env, &["secret", "db", "--store-keyring", entry]), 2);
+    }
+}
@@ -0,0 +1,45 @@
+//! The lanes (`--parallelism`) are part of the derived secrets, the number of CPUs computing them isn't.
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::{io, os::unix::process::CommandExt as _, process::Command};
+
+use common::{Env, PASSPHRASE, stderr, success};
+
+/// Restricts the command to the first CPU.
+fn single_cpu(cmd: &mut Command) {
+    // SAFETY: `sched_setaffinity` is async-signal-safe, the set is initialized by `CPU_ZERO`.
+    unsafe {
+        cmd.pre_exec(|| {
+            let mut set = std::mem::zeroed::<libc::cpu_set_t>();
+            libc::CPU_ZERO(&mut set);
+            libc::CPU_SET(0, &mut set);
+            match libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &set) {
+                0 => Ok(()),
+                _ => Err(io::Error::last_os_error()),
+            }
+        });
+    }
+}
+
+#[test]
+fn output_depends_on_the_lanes_not_the_cpus() {
+    let env = Env::with_config(&common::CONFIG.replace("parallelism = 1", "parallelism = 4"));
+    let args = ["secret", "name"];
+    let all_cpus = success(env.run(&args));
+
+    let mut cmd = env.command(&args);
+    single_cpu(&mut cmd);
+    let out = common::run(cmd, PASSPHRASE.as_bytes());
+    assert!(
+        stderr(&out).contains("Your parallelism (4 lanes) exceeds the number of CPUs (1)"),
+        "{}",
+        stderr(&out)
+    );
+    assert_eq!(success(out), all_cpus);
+
+    let two_lanes = success(env.run(&["secret", "name", "--parallelism", "2"]));
+    assert_ne!(two_lanes, all_cpus);
+}
@@ -0,0 +1,52 @@
+//! Library interface, deriving the same secrets as the CLI.
+
+mod common;
+
+use argon2derive::argon2::{Algorithm, ParamError, Parameters};
+use common::{Env, PASSPHRASE, success};
+
+/// Parameters of [`common::CONFIG`].
+fn params() -> Parameters {
+    Parameters {
+        algorithm: Algorithm::Argon2id,
+        memory: 64,
+        time: 1,
+        parallelism: 1,
+        salt: b"argon2derive tests".to_vec(),
+    }
+}
+
+#[test]
+fn matches_the_cli() {
+    let env = Env::new();
+
+    for (name, length) in [("email", 32), ("db-pass", 16), ("ünïcode", 64)] {
+        let secret = argon2derive::derive(&params(), PASSPHRASE.as_bytes(), name, length).unwrap();
+        let out = success(env.run(&["secret", name, "--length", &length.to_string()]));
+        assert_eq!(out.trim_end(), hex::encode(secret), "{name}");
+    }
+}
+
+#[test]
+fn age_identity_matches_the_cli() {
+    let env = Env::new();
+
+    let key = argon2derive::derive(&params(), PASSPHRASE.as_bytes(), "age", 32).unwrap();
+    let identity =
+        argon2derive::age::identity(key.try_into().unwrap(), argon2derive::age::Variant::Bech32)
+            .unwrap();
+    assert_eq!(success(env.run(&["age", "age"])), identity);
+}
+
+#[test]
+fn invalid_parameters() {
+    let unsalted = Parameters {
+        salt: Vec::new(),
+        ..params()
+    };
+    let err = argon2derive::derive(&unsalted, PASSPHRASE.as_bytes(), "name", 32).unwrap_err();
+    assert_eq!(err.downcast_ref(), Some(&ParamError::SaltTooShort));
+
+    let err = argon2derive::derive(&params(), PASSPHRASE.as_bytes(), "name", 3).unwrap_err();
+    assert!(err.to_string().contains("too short"), "{err}");
+}
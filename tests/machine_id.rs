@@ -0,0 +1,78 @@
+//! `--salt-source machine-id` option, the machine id being injected via `ARGON2DERIVE_MACHINE_ID`.
+
+mod common;
+
+use common::{Env, PASSPHRASE, assert_code, stderr, success};
+
+const ID: &str = "0123456789abcdef0123456789abcdef";
+
+fn run(env: &Env, id: &str, args: &[&str]) -> std::process::Output {
+    let mut cmd = env.command(args);
+    cmd.env("ARGON2DERIVE_MACHINE_ID", id);
+    common::run(cmd, PASSPHRASE.as_bytes())
+}
+
+#[test]
+fn bound_to_the_machine() {
+    let env = Env::new();
+    let args = ["--salt-source", "machine-id", "secret", "name"];
+
+    let out = run(&env, ID, &args);
+    assert!(
+        stderr(&out).contains("The salt is bound to this machine"),
+        "{}",
+        stderr(&out)
+    );
+    let bound = success(out);
+
+    assert_eq!(success(run(&env, &format!("{ID}\n"), &args)), bound);
+    assert_ne!(success(run(&env, "fedcba9876543210", &args)), bound);
+    assert_ne!(success(env.run(&["secret", "name"])), bound);
+
+    // Combined with the salt.
+    let salted = [
+        "-s",
+        "other salt",
+        "--salt-source",
+        "machine-id",
+        "secret",
+        "name",
+    ];
+    assert_ne!(success(run(&env, ID, &salted)), bound);
+
+    let out = run(&env, " ", &args);
+    assert_code(&out, 1);
+    assert!(
+        stderr(&out).contains("Machine id is empty"),
+        "{}",
+        stderr(&out)
+    );
+}
+
+#[test]
+fn stored_in_the_config() {
+    let env = Env::with_config(&format!("{}salt_source = \"machine-id\"\n", common::CONFIG));
+    let bound = success(run(
+        &Env::new(),
+        ID,
+        &["--salt-source", "machine-id", "secret", "name"],
+    ));
+
+    assert_eq!(success(run(&env, ID, &["secret", "name"])), bound);
+
+    let args = ["--salt-source", "none", "secret", "name"];
+    let unbound = success(Env::new().run(&["secret", "name"]));
+    assert_eq!(success(run(&env, ID, &args)), unbound);
+}
+
+#[test]
+fn plan_hides_the_machine_id() {
+    let env = Env::new();
+    let args = ["--dry-run", "--salt-source", "machine-id", "secret", "name"];
+
+    let out = run(&env, ID, &args);
+    let plan = format!("{}{}", common::stdout(&out), stderr(&out));
+    assert_code(&out, 0);
+    assert!(plan.contains("Salt source: machine-id"), "{plan}");
+    assert!(!plan.contains(ID), "{plan}");
+}
@@ -0,0 +1,27 @@
+//! `man` subcommand.
+
+mod common;
+
+use common::{Env, success};
+
+#[test]
+fn main_page() {
+    let out = success(Env::new().run(&["man"]));
+    assert!(out.starts_with(".ie \\n(.g .ds Aq"), "{out}");
+    assert!(out.contains("\\fB\\-\\-memory\\fR"));
+    assert!(out.contains("Argon2 memory cost (in GiB)"));
+}
+
+#[test]
+fn page_per_subcommand() {
+    let env = Env::new();
+    assert_eq!(success(env.run(&["man", "--dir", "."])), "");
+
+    let main = env.read("argon2derive.1");
+    assert!(main.contains("Argon2 memory cost (in GiB)"));
+
+    for sub in ["secret", "age", "audit-show"] {
+        let page = env.read(&format!("argon2derive-{sub}.1"));
+        assert!(!page.is_empty(), "{sub}");
+    }
+}
@@ -0,0 +1,48 @@
+//! Pre-flight check of the memory cost against the memory of the system.
+
+#![cfg(any(target_os = "linux", target_os = "macos", windows))]
+
+mod common;
+
+use common::{Env, assert_code, stderr};
+
+#[test]
+fn fails_before_the_prompt() {
+    let env = Env::new();
+    // No passphrase is piped, it mustn't be prompted for.
+    for args in [
+        &["secret", "name", "-m", "4095"][..],
+        &["secret", "name", "-m", "4095", "--passphrase-tty"],
+    ] {
+        let out = env.run_with_stdin(args, "");
+        assert_code(&out, 5);
+        assert!(
+            stderr(&out).contains("Your memory cost (4095.0 GiB) exceeds the available memory"),
+            "{}",
+            stderr(&out)
+        );
+        assert!(stderr(&out).contains("--force-memory"), "{}", stderr(&out));
+        assert!(
+            !stderr(&out).contains("Enter passphrase"),
+            "{}",
+            stderr(&out)
+        );
+    }
+}
+
+#[test]
+fn swapping_is_warned_about() {
+    let out = Env::new().run_with_stdin(&["secret", "name", "-m", "4095"], "");
+    assert_code(&out, 5);
+    assert!(
+        stderr(&out).contains("exceeds the physical RAM"),
+        "{}",
+        stderr(&out)
+    );
+}
+
+#[test]
+fn dry_run_skips_it() {
+    let out = Env::new().run_with_stdin(&["--dry-run", "secret", "name", "-m", "4095"], "");
+    assert_code(&out, 0);
+}
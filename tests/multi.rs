@@ -0,0 +1,123 @@
+//! `multi` subcommand.
+
+mod common;
+
+use common::{Env, assert_code, stderr, success};
+
+#[test]
+fn matches_the_single_type_subcommands() {
+    let env = Env::new();
+    let args = [
+        "multi",
+        "laptop",
+        "--age",
+        "--secret",
+        "32:hex",
+        "--secret",
+        "16:base64",
+    ];
+    let out = success(env.run(&args));
+    assert_eq!(success(env.run(&args)), out);
+
+    let age = success(env.run(&["age", "laptop:age"]));
+    let hex = success(env.run(&["secret", "laptop:secret"]));
+    let base64 = success(env.run(&["secret", "laptop:secret", "-l", "16", "-e", "base64"]));
+    assert_eq!(out, format!("{age}{hex}\n{base64}\n"));
+}
+
+#[test]
+fn types_dont_share_bytes() {
+    let env = Env::new();
+    let out = success(env.run(&[
+        "multi", "laptop", "--age", "--secret", "32:hex", "--format", "csv",
+    ]));
+    let rows: Vec<Vec<_>> = out
+        .lines()
+        .skip(1)
+        .map(|row| row.split(',').collect())
+        .collect();
+    assert_eq!(rows[0][..3], ["laptop:age", "32", "age"]);
+    assert_eq!(rows[1][..3], ["laptop:secret", "32", "hex"]);
+
+    let age_key = bech32::decode(rows[0][3]).unwrap().1;
+    let age_key: Vec<u8> = bech32::FromBase32::from_base32(&age_key).unwrap();
+    assert_ne!(hex::encode(age_key), rows[1][3]);
+
+    let plain = success(env.run(&["secret", "laptop"]));
+    assert_ne!(plain, rows[1][3]);
+}
+
+#[test]
+fn csv_quoting() {
+    let env = Env::new();
+    let name = "db, \"primary\"\nus-east";
+    let out = success(env.run(&["multi", name, "--secret", "16:hex", "--format", "csv"]));
+    let hex = success(env.run(&["secret", &format!("{name}:secret"), "-l", "16"]));
+
+    assert_eq!(
+        out,
+        format!(
+            "name,length,encoding,value\r\n\"db, \"\"primary\"\"\nus-east:secret\",16,hex,{hex}\r\n"
+        )
+    );
+}
+
+#[test]
+fn tsv_and_no_header() {
+    let env = Env::new();
+    let args = [
+        "multi", "db,1", "--secret", "16:hex", "--secret", "8:base64",
+    ];
+    let csv = success(env.run(&[&args[..], &["--format", "csv"]].concat()));
+    let tsv = success(env.run(&[&args[..], &["--format", "tsv"]].concat()));
+    let no_header = success(env.run(&[&args[..], &["--format", "tsv", "--no-header"]].concat()));
+
+    // TSV has no quoting, the name holding a comma is quoted in CSV only.
+    let csv = csv.replace("\"db,1:secret\"", "db;1:secret");
+    let tsv = tsv.replace("db,1:secret", "db;1:secret");
+    assert_eq!(tsv.replace('\t', ","), csv.replace("\r\n", "\n"));
+
+    assert_eq!(tsv.lines().next(), Some("name\tlength\tencoding\tvalue"));
+    assert_eq!(
+        no_header.replace("db,1:secret", "db;1:secret"),
+        tsv.split_once('\n').unwrap().1
+    );
+    assert_eq!(no_header.lines().count(), 2);
+}
+
+#[test]
+fn tsv_rejects_line_breaks() {
+    let env = Env::new();
+    let out = env.run(&["multi", "laptop", "--secret", "16:words", "--format", "tsv"]);
+    assert_code(&out, 2);
+    assert!(stderr(&out).contains("TSV fields can't contain tabs or line breaks"));
+
+    let words = success(env.run(&["secret", "laptop:secret", "-l", "16", "-e", "words"]));
+    let first_word = words.split_whitespace().nth(1).unwrap();
+    assert!(!stderr(&out).contains(first_word));
+
+    let out = env.run(&[
+        "multi",
+        "tab\tname",
+        "--secret",
+        "16:hex",
+        "--format",
+        "tsv",
+    ]);
+    assert_code(&out, 2);
+}
+
+#[test]
+fn invalid_args() {
+    let env = Env::new();
+    for spec in ["32", "32:base32", "x:hex"] {
+        assert_code(&env.run(&["multi", "laptop", "--secret", spec]), 2);
+    }
+    assert_code(&env.run(&["multi", "laptop"]), 2);
+    assert_code(&env.run(&["multi", "laptop", "--bech32m"]), 2);
+    assert_code(&env.run(&["multi", "laptop", "--age", "--dry-run"]), 2);
+    assert_code(
+        &env.run(&["multi", "laptop", "--age", "--format", "xml"]),
+        2,
+    );
+}
@@ -0,0 +1,52 @@
+//! `--namespace` option, selecting a salt of the config.
+
+mod common;
+
+use common::{CONFIG, Env, assert_code, stderr, success};
+
+fn env() -> Env {
+    Env::with_config(&format!(
+        "{CONFIG}\n[salts.work]\nsalt = \"work salt\"\n\n[salts.personal]\nsalt = \"personal salt\"\n"
+    ))
+}
+
+#[test]
+fn replaces_the_main_salt() {
+    let env = env();
+    let main = success(env.run(&["secret", "email"]));
+    let work = success(env.run(&["secret", "email", "--namespace", "work"]));
+    let personal = success(env.run(&["--namespace", "personal", "secret", "email"]));
+
+    assert_ne!(work, main);
+    assert_ne!(work, personal);
+    assert_eq!(
+        work,
+        success(env.run(&["secret", "email", "--salt", "work salt"]))
+    );
+}
+
+#[test]
+fn invalid_namespaces() {
+    let env = env();
+
+    let out = env.run(&["secret", "email", "--namespace", "home"]);
+    assert_code(&out, 3);
+    assert!(
+        stderr(&out).contains("the config defines: personal, work"),
+        "{}",
+        stderr(&out)
+    );
+
+    let out = env.run(&["secret", "email", "--namespace", "work", "--salt", "x"]);
+    assert_code(&out, 2);
+
+    std::fs::remove_file(env.path("config.toml")).unwrap();
+    let params = ["-m", "1", "-t", "1", "-p", "1"];
+    let out = env.run(&[&params[..], &["secret", "email", "--namespace", "work"]].concat());
+    assert_code(&out, 3);
+    assert!(
+        stderr(&out).contains("--namespace requires a config file"),
+        "{}",
+        stderr(&out)
+    );
+}
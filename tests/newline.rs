@@ -0,0 +1,68 @@
+//! `--newline` and `--no-newline` options.
+
+mod common;
+
+use common::{Env, success};
+
+#[test]
+fn single_value_secrets() {
+    let env = Env::new();
+    let bare = success(env.run(&["secret", "name"]));
+    assert!(!bare.ends_with('\n'));
+    assert_eq!(bare.len(), 64);
+
+    let newline = success(env.run(&["secret", "name", "--newline"]));
+    assert_eq!(newline, format!("{bare}\n"));
+    assert_eq!(success(env.run(&["secret", "name", "--no-newline"])), bare);
+
+    // The last one wins.
+    let args = ["secret", "name", "--no-newline", "--newline"];
+    assert_eq!(success(env.run(&args)), newline);
+    let args = ["secret", "name", "--newline", "--no-newline"];
+    assert_eq!(success(env.run(&args)), bare);
+}
+
+#[test]
+fn line_based_outputs() {
+    let env = Env::new();
+    let identity = success(env.run(&["age", "name"]));
+    assert!(identity.ends_with('\n') && !identity.ends_with("\n\n"));
+
+    assert_eq!(success(env.run(&["age", "name", "--newline"])), identity);
+    let bare = success(env.run(&["age", "name", "--no-newline"]));
+    assert_eq!(format!("{bare}\n"), identity);
+
+    let shares = success(env.run(&[
+        "split",
+        "name",
+        "--shares",
+        "2",
+        "--threshold",
+        "1",
+        "--no-newline",
+    ]));
+    assert_eq!(shares.lines().count(), 2);
+    assert!(!shares.ends_with('\n'));
+}
+
+#[test]
+fn files_are_unaffected() {
+    let env = Env::new();
+    let secret = success(env.run(&["secret", "db-pass"]));
+
+    for flag in ["--newline", "--no-newline"] {
+        success(env.run(&["secret", "db-pass", "--dotenv", ".env", flag]));
+        assert_eq!(env.read(".env"), format!("DB_PASS={secret}\n"));
+    }
+}
+
+#[test]
+fn recovery_codes() {
+    let env = Env::new();
+    let codes = success(env.run(&["recovery-codes", "github", "--count", "3"]));
+    assert_eq!(codes.lines().count(), 3);
+    assert!(codes.ends_with('\n') && !codes.ends_with("\n\n"));
+
+    let bare = success(env.run(&["recovery-codes", "github", "--count", "3", "--no-newline"]));
+    assert_eq!(format!("{bare}\n"), codes);
+}
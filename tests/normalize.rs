@@ -0,0 +1,97 @@
+//! `--ascii-only` and `--normalize` options.
+
+mod common;
+
+use common::{Env, assert_code, success};
+
+/// `é` as a single code point (NFC) and as `e` followed by a combining acute accent (NFD).
+const COMPOSED: &str = "caf\u{e9} au lait";
+const DECOMPOSED: &str = "cafe\u{301} au lait";
+
+fn secret(env: &Env, args: &[&str], passphrase: &str) -> String {
+    success(env.run_with_stdin(&[&["secret", "name"], args].concat(), passphrase))
+}
+
+#[test]
+fn combining_characters() {
+    let env = Env::new();
+
+    // Parameters of a config without `passphrase_version` aren't normalized by default.
+    assert_ne!(secret(&env, &[], COMPOSED), secret(&env, &[], DECOMPOSED));
+
+    for form in ["nfc", "nfkc"] {
+        let args = ["--normalize", form];
+        assert_eq!(
+            secret(&env, &args, COMPOSED),
+            secret(&env, &args, DECOMPOSED)
+        );
+    }
+    assert_eq!(
+        secret(&env, &["--normalize", "nfc"], DECOMPOSED),
+        secret(&env, &[], COMPOSED)
+    );
+    assert_eq!(
+        secret(&env, &["--normalize", "none"], DECOMPOSED),
+        secret(&env, &[], DECOMPOSED)
+    );
+}
+
+#[test]
+fn compatibility_characters() {
+    let env = Env::new();
+    let ligature = "\u{fb01}sh";
+
+    assert_ne!(
+        secret(&env, &["--normalize", "nfc"], ligature),
+        secret(&env, &[], "fish")
+    );
+    assert_eq!(
+        secret(&env, &["--normalize", "nfkc"], ligature),
+        secret(&env, &[], "fish")
+    );
+}
+
+#[test]
+fn ascii_passphrases_are_unaffected() {
+    let env = Env::new();
+    let secret_of = |args: &[&str]| secret(&env, args, common::PASSPHRASE);
+
+    let plain = secret_of(&[]);
+    assert_eq!(secret_of(&["--normalize", "nfc"]), plain);
+    assert_eq!(secret_of(&["--normalize", "nfkc"]), plain);
+    assert_eq!(secret_of(&["--ascii-only"]), plain);
+}
+
+#[test]
+fn ascii_only() {
+    let env = Env::new();
+    for passphrase in [COMPOSED, DECOMPOSED] {
+        let out = env.run_with_stdin(&["secret", "name", "--ascii-only"], passphrase);
+        assert_code(&out, 4);
+        assert!(common::stderr(&out).contains("non-ASCII"));
+    }
+
+    // Checked before the normalization.
+    let args = ["secret", "name", "--ascii-only", "--normalize", "nfkc"];
+    assert_code(&env.run_with_stdin(&args, "\u{fb01}sh"), 4);
+}
+
+#[test]
+fn nfc_by_default_with_passphrase_version_2() {
+    let env = Env::with_config(&format!("{}passphrase_version = 2\n", common::CONFIG));
+    let legacy = Env::new();
+
+    assert_eq!(secret(&env, &[], COMPOSED), secret(&env, &[], DECOMPOSED));
+    assert_eq!(
+        secret(&env, &[], DECOMPOSED),
+        secret(&legacy, &["--normalize", "nfc"], DECOMPOSED)
+    );
+
+    // The escape hatch reproduces the non-normalized secrets.
+    for args in [&["--no-normalize"][..], &["--normalize", "none"]] {
+        assert_eq!(
+            secret(&env, args, DECOMPOSED),
+            secret(&legacy, &[], DECOMPOSED)
+        );
+    }
+}
@@ -0,0 +1,75 @@
+//! `totp` and `hotp` subcommands.
+
+mod common;
+
+use common::{Env, assert_code, success};
+use hmac::{Hmac, Mac as _};
+use sha1::Sha1;
+
+/// HOTP code of the key, computed independently of the binary.
+fn hotp(key: &[u8], counter: u64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).unwrap();
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = usize::from(hash[19] & 0x0f);
+    let binary = u32::from_be_bytes(hash[offset..offset + 4].try_into().unwrap()) & 0x7fff_ffff;
+    format!("{:06}", binary % 1_000_000)
+}
+
+#[test]
+fn hotp_of_the_derived_secret() {
+    let env = Env::new();
+    let key = success(env.run(&["secret", "github", "--length", "20"]));
+    let key = hex::decode(key.trim_end()).unwrap();
+
+    for counter in [0, 1, 42] {
+        let out = success(env.run(&["hotp", "github", "--counter", &counter.to_string()]));
+        assert_eq!(out, hotp(&key, counter));
+    }
+}
+
+#[test]
+fn totp_is_the_hotp_of_the_time_step() {
+    let env = Env::new();
+    // A billion seconds long period, its time step only changes in 2033.
+    let period = ["--period", "1000000000"];
+    let hotp = |counter: &str| success(env.run(&["hotp", "github", "--counter", counter]));
+
+    let totp = success(env.run(&[&["totp", "github"][..], &period].concat()));
+    assert_eq!(totp, hotp("1"));
+
+    let ahead = ["--offset", "1000000000"];
+    let totp = success(env.run(&[&["totp", "github"][..], &period, &ahead].concat()));
+    assert_eq!(totp, hotp("2"));
+}
+
+#[test]
+fn digits_and_algorithms() {
+    let env = Env::new();
+    for (algo, length) in [("sha1", 6), ("sha256", 7), ("sha512", 8)] {
+        let digits = length.to_string();
+        let args = ["--algo", algo, "--digits", &digits];
+        let hotp = success(env.run(&[&["hotp", "github", "--counter", "0"][..], &args].concat()));
+        let totp = success(env.run(&[&["totp", "github"][..], &args].concat()));
+
+        for code in [hotp, totp] {
+            let code = code.trim_end();
+            assert_eq!(code.len(), length, "{code}");
+            assert!(code.bytes().all(|b| b.is_ascii_digit()), "{code}");
+        }
+    }
+}
+
+#[test]
+fn invalid_args() {
+    let env = Env::new();
+    assert_code(&env.run(&["hotp", "github"]), 2);
+    assert_code(&env.run(&["hotp", "github", "--counter", "-1"]), 2);
+    assert_code(&env.run(&["totp", "github", "--digits", "5"]), 2);
+    assert_code(&env.run(&["totp", "github", "--digits", "9"]), 2);
+    assert_code(&env.run(&["totp", "github", "--period", "0"]), 2);
+    assert_code(&env.run(&["totp", "github", "--algo", "md5"]), 2);
+    // Before the Unix epoch.
+    assert_code(&env.run(&["totp", "github", "--offset", "-99999999999"]), 2);
+}
@@ -0,0 +1,139 @@
+//! `--output-fd` option.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::io::{BufRead as _, BufReader, Read as _};
+use std::process::Command;
+
+use common::{Env, PASSPHRASE, assert_code, success};
+
+/// Makes the command write its output to the descriptor 3, returning the reading end of it.
+fn output_fd(cmd: &mut Command) -> std::io::PipeReader {
+    let (reader, writer) = std::io::pipe().unwrap();
+    common::inherit_fd(cmd, 3, writer.into());
+    reader
+}
+
+#[test]
+fn writes_to_the_descriptor() {
+    let env = Env::new();
+    let expected = success(env.run(&["secret", "name"]));
+
+    let mut cmd = env.command(&["--output-fd", "3", "secret", "name"]);
+    let mut reader = output_fd(&mut cmd);
+    assert_eq!(success(common::run(cmd, PASSPHRASE.as_bytes())), "");
+
+    let mut out = String::new();
+    reader.read_to_string(&mut out).unwrap();
+    assert_eq!(out, expected);
+}
+
+#[test]
+fn multiple_writes() {
+    let env = Env::new();
+    let args = [
+        "multi",
+        "name",
+        "--age",
+        "--secret",
+        "16:hex",
+        "--secret",
+        "32:base64",
+    ];
+    let expected = success(env.run(&args));
+
+    let mut cmd = env.command(&[&["--output-fd", "3"], &args[..]].concat());
+    let mut reader = output_fd(&mut cmd);
+    assert_eq!(success(common::run(cmd, PASSPHRASE.as_bytes())), "");
+
+    let mut out = String::new();
+    reader.read_to_string(&mut out).unwrap();
+    assert_eq!(out, expected);
+    assert_eq!(out.lines().count(), 4);
+}
+
+#[test]
+fn watched_totp_codes() {
+    let env = Env::new();
+
+    let args = [
+        "--output-fd",
+        "3",
+        "totp",
+        "name",
+        "--watch",
+        "--period",
+        "1",
+    ];
+    let mut cmd = env.command(&args);
+    let reader = output_fd(&mut cmd);
+    let mut child = cmd.spawn().unwrap();
+    drop(cmd);
+    std::io::Write::write_all(&mut child.stdin.take().unwrap(), PASSPHRASE.as_bytes()).unwrap();
+
+    let codes: Vec<_> = BufReader::new(reader)
+        .lines()
+        .take(2)
+        .map(Result::unwrap)
+        .collect();
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert_eq!(codes.len(), 2);
+    for code in codes {
+        assert!(
+            code.len() == 6 && code.bytes().all(|b| b.is_ascii_digit()),
+            "{code}"
+        );
+    }
+}
+
+#[test]
+fn session_secrets() {
+    let env = Env::new();
+    let secret = success(env.run(&["secret", "db-pass"]));
+
+    let args = [
+        "--output-fd",
+        "3",
+        "--stdin-passphrase",
+        "--passphrase-trim",
+        "newline",
+        "session",
+    ];
+    let mut cmd = env.command(&args);
+    let mut reader = output_fd(&mut cmd);
+    let stdin = format!("{PASSPHRASE}\nsecret db-pass\nsecret db-pass\n");
+    assert_eq!(success(common::run(cmd, stdin.as_bytes())), "");
+
+    let mut out = String::new();
+    reader.read_to_string(&mut out).unwrap();
+    assert_eq!(out, format!("{secret}\n{secret}\n"));
+}
+
+#[test]
+fn hmac_lines() {
+    let env = Env::new();
+    env.write("a.txt", "a");
+    env.write("b.txt", "b");
+    let args = ["hmac", "--name", "files", "a.txt", "b.txt"];
+    let expected = success(env.run(&args));
+
+    let mut cmd = env.command(&[&["--output-fd", "3"], &args[..]].concat());
+    let mut reader = output_fd(&mut cmd);
+    assert_eq!(success(common::run(cmd, PASSPHRASE.as_bytes())), "");
+
+    let mut out = String::new();
+    reader.read_to_string(&mut out).unwrap();
+    assert_eq!(out, expected);
+}
+
+#[test]
+fn invalid_fd() {
+    let env = Env::new();
+
+    assert_code(&env.run(&["--output-fd", "9", "secret", "name"]), 2);
+    assert_code(&env.run(&["--output-fd", "1", "secret", "name"]), 2);
+}
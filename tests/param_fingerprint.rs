@@ -0,0 +1,72 @@
+//! Detection of the parameter changes via the `fingerprint` of the config.
+
+mod common;
+
+use common::{Env, assert_code, stderr, success};
+
+/// Fingerprint of the parameters of [`common::CONFIG`].
+const FINGERPRINT: &str = "6f638a24cabb2481f46d9519a2db2c6ca3ddee76135cbbd5611ceda33f15f727";
+
+const DRIFT: &str = "Parameters differ from your saved profile";
+
+fn env() -> Env {
+    Env::with_config(&format!(
+        "{}fingerprint = \"{FINGERPRINT}\"\n",
+        common::CONFIG
+    ))
+}
+
+#[test]
+fn matching_parameters() {
+    let env = env();
+    let out = env.run(&["secret", "name"]);
+    assert!(!stderr(&out).contains(DRIFT), "{}", stderr(&out));
+    assert_eq!(success(out), success(Env::new().run(&["secret", "name"])));
+}
+
+#[test]
+fn changed_parameters() {
+    let env = env();
+
+    for args in [
+        &["-t", "2"][..],
+        &["-p", "2"],
+        &["-s", "other salt"],
+        &["-a", "argon2d"],
+    ] {
+        let out = env.run(&[args, &["secret", "name"]].concat());
+        assert_code(&out, 2);
+        assert!(
+            stderr(&out).contains("--allow-param-change"),
+            "{}",
+            stderr(&out)
+        );
+        assert!(out.stdout.is_empty());
+    }
+
+    let out = env.run(&["-t", "2", "--allow-param-change", "secret", "name"]);
+    assert!(stderr(&out).contains(DRIFT), "{}", stderr(&out));
+    assert_eq!(
+        success(out),
+        success(Env::new().run(&["-t", "2", "secret", "name"]))
+    );
+
+    // Planning doesn't derive anything.
+    assert_code(&env.run(&["-t", "2", "--dry-run", "secret", "name"]), 0);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn confirmed_change() {
+    let env = env();
+    let expected = success(Env::new().run(&["-t", "2", "secret", "name"]));
+
+    let cmd = env.command(&["-t", "2", "secret", "name"]);
+    let out = common::run_with_tty(cmd, common::PASSPHRASE.as_bytes(), "y\n");
+    assert!(stderr(&out).contains("continue? [y/N]"), "{}", stderr(&out));
+    assert_eq!(success(out), expected);
+
+    let cmd = env.command(&["-t", "2", "secret", "name"]);
+    let out = common::run_with_tty(cmd, common::PASSPHRASE.as_bytes(), "\n");
+    assert_code(&out, 2);
+}
@@ -0,0 +1,103 @@
+//! Resolution of the Argon2 parameters from the CLI and the config, see `--describe`.
+
+mod common;
+
+use common::{Env, assert_code, stderr, success};
+use serde_json::Value;
+
+const IGNORED_SALT: &str = "is ignored, including its salt";
+
+/// `(value, origin)` of the described parameters.
+fn describe(env: &Env, args: &[&str]) -> Value {
+    let out = success(env.run(&[&["--describe"], args, &["secret", "name"]].concat()));
+    serde_json::from_str(&out).unwrap()
+}
+
+fn param(described: &Value, name: &str) -> (Value, String) {
+    let param = &described[name];
+    (
+        param["value"].clone(),
+        param["origin"].as_str().unwrap().to_owned(),
+    )
+}
+
+#[test]
+fn every_combination_of_overrides() {
+    let env = Env::new();
+    // Config values (see `common::CONFIG`) and the overriding CLI ones.
+    let params = [
+        ("memory", 64, ["-m", "1"], 1024 * 1024),
+        ("time", 1, ["-t", "3"], 3),
+        ("parallelism", 1, ["-p", "2"], 2),
+    ];
+
+    for mask in 0..8 {
+        let mut args = vec!["-s", "cli salt"];
+        for (idx, (_, _, cli, _)) in params.iter().enumerate() {
+            if mask & (1 << idx) != 0 {
+                args.extend(cli);
+            }
+        }
+
+        let described = describe(&env, &args);
+        for (idx, (name, config, _, cli)) in params.iter().enumerate() {
+            let expected = match mask & (1 << idx) != 0 {
+                true => (Value::from(*cli), "cli".to_owned()),
+                false => (Value::from(*config), "config".to_owned()),
+            };
+            assert_eq!(param(&described, name), expected, "{args:?}");
+        }
+        assert_eq!(param(&described, "salt_length").0, 8, "{args:?}");
+    }
+}
+
+#[test]
+fn missing_params_without_a_config() {
+    let env = Env::new();
+    std::fs::remove_file(env.path("config.toml")).unwrap();
+
+    for (args, missing) in [
+        (&["-t", "1", "-p", "1"][..], "--memory"),
+        (&["-m", "1", "-p", "1"], "--time"),
+        (&["-m", "1", "-t", "1"], "--parallelism"),
+    ] {
+        let out = env.run(&[args, &["-s", "somesalt", "secret", "name"]].concat());
+        assert_code(&out, 2);
+        assert!(stderr(&out).contains(missing), "{}", stderr(&out));
+    }
+    assert_code(&env.run(&["secret", "name"]), 3);
+}
+
+#[test]
+fn complete_cli_params_ignore_the_config() {
+    let env = Env::new();
+    let complete = ["-m", "1", "-t", "1", "-p", "1"];
+
+    let out = env.run(&[&["--describe"], &complete[..], &["secret", "long-name"]].concat());
+    assert!(stderr(&out).contains(IGNORED_SALT), "{}", stderr(&out));
+    let described: Value = serde_json::from_str(&success(out)).unwrap();
+    assert_eq!(
+        param(&described, "salt_length"),
+        (Value::from(0), "default".into())
+    );
+
+    // The salt is provided as well.
+    let args = [
+        &["--describe", "-s", "somesalt"],
+        &complete[..],
+        &["secret", "long-name"],
+    ]
+    .concat();
+    let out = env.run(&args);
+    assert!(!stderr(&out).contains(IGNORED_SALT), "{}", stderr(&out));
+    assert_code(&out, 0);
+
+    // Nothing to ignore.
+    let env = Env::with_config(&common::CONFIG.replace("salt = \"argon2derive tests\"", ""));
+    let out = env.run(&[&["--describe"], &complete[..], &["secret", "long-name"]].concat());
+    assert!(!stderr(&out).contains(IGNORED_SALT), "{}", stderr(&out));
+
+    std::fs::remove_file(env.path("config.toml")).unwrap();
+    let out = env.run(&[&["--describe"], &complete[..], &["secret", "long-name"]].concat());
+    assert!(!stderr(&out).contains(IGNORED_SALT), "{}", stderr(&out));
+}
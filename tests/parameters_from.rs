@@ -0,0 +1,50 @@
+//! `--parameters-from` option.
+
+mod common;
+
+use common::{Env, assert_code, success};
+
+const JSON: &str = r#"{
+  "algorithm": "argon2id",
+  "memory": 64,
+  "time": 1,
+  "parallelism": 1,
+  "salt": "argon2derive tests"
+}"#;
+
+const YAML: &str = "
+algorithm: argon2id
+memory: 64
+time: 1
+parallelism: 1
+salt: argon2derive tests
+";
+
+#[test]
+fn json_and_yaml() {
+    let env = Env::new();
+    let expected = success(env.run(&["secret", "email"]));
+
+    // The config must not be read, so it holds other parameters.
+    let env = Env::with_config(&common::CONFIG.replace("time = 1", "time = 2"));
+    assert_ne!(success(env.run(&["secret", "email"])), expected);
+
+    env.write("params.json", JSON);
+    env.write("params.yml", YAML);
+    for file in ["params.json", "params.yml"] {
+        let out = success(env.run(&["secret", "email", "--parameters-from", file]));
+        assert_eq!(out, expected, "{file}");
+    }
+}
+
+#[test]
+fn invalid_files() {
+    let env = Env::new();
+    env.write("params.toml", common::CONFIG);
+    env.write("invalid.json", "memory: 64");
+
+    for file in ["params.toml", "invalid.json", "missing.json"] {
+        let out = env.run(&["secret", "email", "--parameters-from", file]);
+        assert_code(&out, 3);
+    }
+}
@@ -0,0 +1,41 @@
+//! `--paranoid` option.
+
+mod common;
+
+use common::{Env, assert_code, stderr, success};
+
+#[test]
+fn same_output_derived_twice() {
+    let env = Env::new();
+    let plain = success(env.run(&["secret", "name"]));
+
+    let out = env.run(&["--paranoid", "secret", "name"]);
+    assert!(stderr(&out).contains("Deriving again to verify the output (--paranoid)..."));
+    assert!(stderr(&out).contains("Verified in "));
+    assert_eq!(success(out), plain);
+}
+
+#[test]
+fn every_secret_of_multi() {
+    let env = Env::new();
+    let args = ["multi", "laptop", "--age", "--secret", "16:hex"];
+    let plain = success(env.run(&args));
+
+    let out = env.run(&[&["--paranoid"][..], &args].concat());
+    assert_eq!(stderr(&out).matches("Verified in ").count(), 2);
+    assert_eq!(success(out), plain);
+}
+
+#[test]
+fn check_determinism_of_the_lanes() {
+    // Four lanes, computed by as many threads.
+    let env = Env::with_config(&common::CONFIG.replace("parallelism = 1", "parallelism = 4"));
+    let plain = success(env.run(&["secret", "name"]));
+
+    let out = env.run(&["--check-determinism", "secret", "name"]);
+    assert!(stderr(&out).contains("Verified in "));
+    assert_eq!(success(out), plain);
+
+    let out = env.run(&["--check-determinism", "--paranoid", "secret", "name"]);
+    assert_code(&out, 2);
+}
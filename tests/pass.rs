@@ -0,0 +1,87 @@
+//! `--pass-insert` of the `secret` subcommand, using a fake `pass`.
+
+#![cfg(unix)]
+
+mod common;
+
+use common::{Env, PASSPHRASE, assert_code, stderr, success};
+
+/// Fake `pass`, recording its arguments and the inserted entry, and chatting on stdout.
+const PASS: &str = r#"echo "$@" > args.txt
+cat > entry.txt
+echo "Inserted"
+"#;
+
+#[test]
+fn inserts_the_secret() {
+    let env = Env::new();
+    let secret = success(env.run(&["secret", "db-pass"]));
+    env.script("pass", PASS);
+
+    let cmd = env.with_bin(&["secret", "db-pass", "--pass-insert", "work/db"]);
+    let out = common::run(cmd, PASSPHRASE.as_bytes());
+    // The chatter of `pass` is kept out of stdout.
+    assert!(stderr(&out).contains("Inserted"), "{}", stderr(&out));
+    assert_eq!(success(out), "");
+    assert_eq!(env.read("args.txt"), "insert --echo work/db\n");
+    assert_eq!(env.read("entry.txt"), format!("{secret}\n"));
+
+    let args = [
+        "secret",
+        "db-pass",
+        "-e",
+        "hexdump",
+        "--pass-insert",
+        "work/db",
+    ];
+    success(common::run(env.with_bin(&args), PASSPHRASE.as_bytes()));
+    assert_eq!(env.read("args.txt"), "insert --multiline work/db\n");
+    assert!(env.read("entry.txt").lines().count() > 1);
+    assert!(env.read("entry.txt").ends_with('\n'));
+}
+
+#[test]
+fn pass_command() {
+    let env = Env::new();
+    env.script("gopass", PASS);
+
+    let args = [
+        "secret",
+        "db-pass",
+        "--pass-insert",
+        "db",
+        "--pass-command",
+        "gopass",
+    ];
+    success(common::run(env.with_bin(&args), PASSPHRASE.as_bytes()));
+    assert_eq!(env.read("args.txt"), "insert --echo db\n");
+
+    // Only used for `--pass-insert`.
+    assert_code(
+        &env.run(&["secret", "db-pass", "--pass-command", "gopass"]),
+        2,
+    );
+}
+
+#[test]
+fn failures() {
+    let env = Env::new();
+    env.script("pass", "cat > /dev/null\necho 'Entry exists' >&2\nexit 1\n");
+
+    let cmd = env.with_bin(&["secret", "db-pass", "--pass-insert", "db"]);
+    let out = common::run(cmd, PASSPHRASE.as_bytes());
+    assert_code(&out, 6);
+    assert!(stderr(&out).contains("Entry exists"), "{}", stderr(&out));
+    assert!(stderr(&out).contains("exit status: 1"), "{}", stderr(&out));
+    assert!(out.stdout.is_empty());
+
+    let mut cmd = env.command(&["secret", "db-pass", "--pass-insert", "db"]);
+    cmd.env("PATH", env.path("empty"));
+    let out = common::run(cmd, PASSPHRASE.as_bytes());
+    assert_code(&out, 6);
+    assert!(
+        stderr(&out).contains("`pass` not found"),
+        "{}",
+        stderr(&out)
+    );
+}
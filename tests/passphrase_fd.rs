@@ -0,0 +1,58 @@
+//! `--passphrase-fd` option.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::io::Write as _;
+
+use common::{Env, PASSPHRASE, assert_code, stderr, success};
+
+/// Runs the binary with `passphrase` readable from the descriptor 3, stdin being empty.
+fn run_with_fd(env: &Env, args: &[&str], passphrase: &str) -> std::process::Output {
+    let (reader, mut writer) = std::io::pipe().unwrap();
+    writer.write_all(passphrase.as_bytes()).unwrap();
+    drop(writer);
+
+    let mut cmd = env.command(&[&["--passphrase-fd", "3"], args].concat());
+    common::inherit_fd(&mut cmd, 3, reader.into());
+    common::run(cmd, b"")
+}
+
+#[test]
+fn reads_the_passphrase() {
+    let env = Env::new();
+    let expected = success(env.run(&["secret", "name"]));
+
+    assert_eq!(
+        success(run_with_fd(&env, &["secret", "name"], PASSPHRASE)),
+        expected
+    );
+
+    // Only the first line is read, trimmed the same way as stdin.
+    let passphrase = format!("{PASSPHRASE}\nsecond line\n");
+    let args = ["--passphrase-trim", "newline", "secret", "name"];
+    assert_eq!(success(run_with_fd(&env, &args, &passphrase)), expected);
+
+    let with_newline = success(env.run_with_stdin(&["secret", "name"], format!("{PASSPHRASE}\n")));
+    assert_eq!(
+        success(run_with_fd(&env, &["secret", "name"], &passphrase)),
+        with_newline
+    );
+}
+
+#[test]
+fn invalid_fd() {
+    let env = Env::new();
+
+    let out = env.run(&["--passphrase-fd", "9", "secret", "name"]);
+    assert_code(&out, 4);
+    assert!(stderr(&out).contains("invalid fd 9"), "{}", stderr(&out));
+
+    let out = env.run(&["--passphrase-fd", "0", "secret", "name"]);
+    assert_code(&out, 4);
+    assert!(stderr(&out).contains("standard stream"), "{}", stderr(&out));
+
+    assert_code(&env.run(&["--passphrase-fd", "x", "secret", "name"]), 2);
+    assert_code(&run_with_fd(&env, &["secret", "name"], ""), 4);
+}
@@ -0,0 +1,61 @@
+//! Passphrase fingerprint bound to the config (`configure --bind-passphrase`).
+
+mod common;
+
+use argon2derive::argon2::{self, Algorithm, Parameters};
+use common::{CONFIG, Env, PASSPHRASE, assert_code, stderr, success};
+
+/// Fingerprint of the passphrase with the parameters of [`CONFIG`], computed independently of the binary.
+fn fingerprint(passphrase: &str) -> String {
+    let mut salt = b"argon2derive tests".to_vec();
+    salt.extend(b"\0argon2derive passphrase fingerprint v1");
+    let params = Parameters {
+        algorithm: Algorithm::Argon2id,
+        memory: 64,
+        time: 1,
+        parallelism: 1,
+        salt,
+    };
+    hex::encode(&argon2::hash(&params, passphrase.as_bytes(), 4).unwrap()[..2])
+}
+
+fn bound(fingerprint: &str) -> Env {
+    Env::with_config(&format!(
+        "{CONFIG}passphrase_fingerprint = \"{fingerprint}\"\n"
+    ))
+}
+
+#[test]
+fn checks_the_passphrase() {
+    let secret = success(Env::new().run(&["secret", "name"]));
+    let env = bound(&fingerprint(PASSPHRASE));
+    assert_eq!(success(env.run(&["secret", "name"])), secret);
+
+    let wrong = "wrong passphrase";
+    assert_ne!(fingerprint(wrong), fingerprint(PASSPHRASE));
+    let out = env.run_with_stdin(&["secret", "name"], wrong);
+    assert_code(&out, 4);
+    assert!(
+        stderr(&out).contains("doesn't match the fingerprint"),
+        "{}",
+        stderr(&out)
+    );
+
+    let out = env.run_with_stdin(&["secret", "name", "--ignore-fingerprint"], wrong);
+    assert_code(&out, 0);
+}
+
+#[test]
+fn unaffected_by_overrides() {
+    let env = bound(&fingerprint(PASSPHRASE));
+    // The fingerprint is checked against the parameters of the config.
+    success(env.run(&["secret", "name", "--salt", "another salt"]));
+}
+
+#[test]
+fn malformed_fingerprint() {
+    for fingerprint in ["abc", "xyzw", "abcdef"] {
+        let out = bound(fingerprint).run(&["secret", "name"]);
+        assert_code(&out, 3);
+    }
+}
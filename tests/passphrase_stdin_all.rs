@@ -0,0 +1,81 @@
+//! `--passphrase-stdin-all` option and the warning about the ignored lines of stdin.
+
+mod common;
+
+use argon2derive::argon2::{self, Algorithm, Parameters};
+use common::{Env, assert_code, stderr, success};
+
+const IGNORED: &str = "stdin continues after the first line";
+
+/// Secret `name` of the passphrase, derived independently of the binary.
+fn expected(passphrase: &str) -> String {
+    let mut params = Parameters {
+        algorithm: Algorithm::Argon2id,
+        memory: 64,
+        time: 1,
+        parallelism: 1,
+        salt: b"argon2derive tests".to_vec(),
+    };
+    params.append_name("name", 0).unwrap();
+    hex::encode(argon2::hash(&params, passphrase.as_bytes(), 32).unwrap())
+}
+
+#[test]
+fn whole_stdin() {
+    let env = Env::new();
+    let passphrase = "first line\nsecond line\n";
+
+    let out = env.run_with_stdin(&["--passphrase-stdin-all", "secret", "name"], passphrase);
+    assert!(!stderr(&out).contains(IGNORED), "{}", stderr(&out));
+    assert_eq!(success(out), expected(passphrase));
+
+    let trimmed = success(env.run_with_stdin(
+        &[
+            "--passphrase-stdin-all",
+            "--passphrase-trim",
+            "newline",
+            "secret",
+            "name",
+        ],
+        passphrase,
+    ));
+    assert_eq!(trimmed, expected("first line\nsecond line"));
+}
+
+#[test]
+fn warns_about_the_ignored_lines() {
+    let env = Env::new();
+
+    let out = env.run_with_stdin(&["secret", "name"], "first line\nsecond line\n");
+    assert!(stderr(&out).contains(IGNORED), "{}", stderr(&out));
+    assert_eq!(success(out), expected("first line\n"));
+
+    for passphrase in ["single line", "single line\n"] {
+        let out = env.run_with_stdin(&["secret", "name"], passphrase);
+        assert!(!stderr(&out).contains(IGNORED), "{}", stderr(&out));
+        success(out);
+    }
+}
+
+#[test]
+fn invalid_args() {
+    let env = Env::new();
+    for args in [
+        &["--passphrase-stdin-all", "hmac", "name"][..],
+        &[
+            "--passphrase-stdin-all",
+            "--passphrase-tty",
+            "secret",
+            "name",
+        ],
+        &[
+            "--passphrase-stdin-all",
+            "--passphrase-fd",
+            "3",
+            "secret",
+            "name",
+        ],
+    ] {
+        assert_code(&env.run(args), 2);
+    }
+}
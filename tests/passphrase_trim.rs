@@ -0,0 +1,60 @@
+//! `--passphrase-trim` option, locking the secrets derived from piped passphrases.
+
+mod common;
+
+use common::{Env, success};
+
+fn secret(env: &Env, trim: Option<&str>, passphrase: &str) -> String {
+    let mut args = vec!["secret", "name"];
+    if let Some(trim) = trim {
+        args.extend(["--passphrase-trim", trim]);
+    }
+    success(env.run_with_stdin(&args, passphrase))
+}
+
+#[test]
+fn untrimmed_by_default() {
+    let env = Env::new();
+    let bare = secret(&env, None, "pass");
+
+    assert_eq!(secret(&env, Some("none"), "pass"), bare);
+    for passphrase in ["pass\n", "pass\r\n", " pass"] {
+        assert_ne!(secret(&env, None, passphrase), bare, "{passphrase:?}");
+        assert_eq!(
+            secret(&env, None, passphrase),
+            secret(&env, Some("none"), passphrase)
+        );
+    }
+}
+
+#[test]
+fn newline() {
+    let env = Env::new();
+    let bare = secret(&env, None, "pass");
+
+    for passphrase in ["pass", "pass\n", "pass\r\n"] {
+        assert_eq!(
+            secret(&env, Some("newline"), passphrase),
+            bare,
+            "{passphrase:?}"
+        );
+    }
+    assert_eq!(
+        secret(&env, Some("newline"), " pass\n"),
+        secret(&env, None, " pass")
+    );
+}
+
+#[test]
+fn all() {
+    let env = Env::new();
+    let bare = secret(&env, None, "pass");
+
+    for passphrase in ["pass", "pass\n", " pass \r\n", "\tpass"] {
+        assert_eq!(
+            secret(&env, Some("all"), passphrase),
+            bare,
+            "{passphrase:?}"
+        );
+    }
+}
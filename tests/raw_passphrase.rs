@@ -0,0 +1,84 @@
+//! `--passphrase-raw-file` and `--passphrase-raw-stdin` options.
+
+mod common;
+
+use argon2derive::argon2::{self, Algorithm, Parameters};
+use common::{Env, PASSPHRASE, assert_code, success};
+
+#[test]
+fn raw_bytes() {
+    let env = Env::new();
+    let secret = success(env.run(&["secret", "name"]));
+
+    env.write("passphrase.bin", PASSPHRASE);
+    let raw = success(env.run_with_stdin(
+        &["secret", "name", "--passphrase-raw-file", "passphrase.bin"],
+        "",
+    ));
+    assert_eq!(raw, secret);
+
+    let args = ["secret", "name", "--passphrase-raw-stdin"];
+    assert_eq!(success(env.run(&args)), secret);
+    // Not trimmed.
+    let newline = success(env.run_with_stdin(&args, format!("{PASSPHRASE}\n")));
+    assert_ne!(newline, secret);
+}
+
+#[test]
+fn conflicts() {
+    let env = Env::new();
+    env.write("passphrase.bin", PASSPHRASE);
+    let raw = ["secret", "name", "--passphrase-raw-file", "passphrase.bin"];
+
+    for conflicting in [
+        &["--normalize", "nfc"][..],
+        &["--ascii-only"],
+        &["--passphrase-raw-stdin"],
+    ] {
+        assert_code(&env.run(&[&raw[..], conflicting].concat()), 2);
+    }
+
+    // Both global and subcommand positions.
+    assert_code(
+        &env.run(&["--ascii-only", "secret", "name", "--passphrase-raw-stdin"]),
+        2,
+    );
+}
+
+#[test]
+fn binary_passphrase() {
+    let env = Env::new();
+    let passphrase = b"\0\xff\xfe\n\r\nend";
+
+    let mut params = Parameters {
+        algorithm: Algorithm::Argon2id,
+        memory: 64,
+        time: 1,
+        parallelism: 1,
+        salt: b"argon2derive tests".to_vec(),
+    };
+    params.append_name("name", 0).unwrap();
+    let expected = hex::encode(argon2::hash(&params, passphrase, 32).unwrap());
+
+    env.write("passphrase.bin", passphrase);
+    let file = success(env.run_with_stdin(
+        &["secret", "name", "--passphrase-raw-file", "passphrase.bin"],
+        "",
+    ));
+    assert_eq!(file, expected);
+
+    let stdin =
+        success(env.run_with_stdin(&["secret", "name", "--passphrase-raw-stdin"], passphrase));
+    assert_eq!(stdin, expected);
+}
+
+#[test]
+fn invalid_args() {
+    let env = Env::new();
+    // The data of `hmac` is read from stdin.
+    assert_code(&env.run(&["hmac", "name", "--passphrase-raw-stdin"]), 2);
+    assert_code(
+        &env.run(&["secret", "name", "--passphrase-raw-file", "missing.bin"]),
+        4,
+    );
+}
@@ -0,0 +1,33 @@
+//! `recovery-codes` subcommand.
+
+mod common;
+
+use common::{Env, assert_code, success};
+
+#[test]
+fn count_and_length() {
+    let env = Env::new();
+
+    let codes = success(env.run(&["recovery-codes", "github"]));
+    let codes: Vec<_> = codes.lines().collect();
+    assert_eq!(codes.len(), 10);
+    assert!(codes.iter().all(|code| code.len() == 8));
+
+    let out = success(env.run(&["recovery-codes", "github", "--count", "3", "-l", "6"]));
+    let expected: Vec<_> = codes[..3].iter().map(|code| &code[..6]).collect();
+    assert_eq!(out.lines().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn deterministic() {
+    let env = Env::new();
+    let codes = success(env.run(&["recovery-codes", "github"]));
+    assert_eq!(success(env.run(&["recovery-codes", "github"])), codes);
+    assert_ne!(success(env.run(&["recovery-codes", "gitlab"])), codes);
+}
+
+#[test]
+fn rejects_empty_codes() {
+    let env = Env::new();
+    assert_code(&env.run(&["recovery-codes", "github", "--length", "0"]), 2);
+}
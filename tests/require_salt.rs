@@ -0,0 +1,77 @@
+//! `--require-salt` option and the `require_salt` config key.
+
+mod common;
+
+use common::{CONFIG, Env, assert_code, stderr, success};
+
+fn without_salt(extra: &str) -> Env {
+    Env::with_config(&format!(
+        "{}{extra}",
+        CONFIG.replace("salt = \"argon2derive tests\"\n", "")
+    ))
+}
+
+#[test]
+fn empty_salt_only_warns_by_default() {
+    let out = without_salt("").run(&["secret", "long enough name"]);
+    assert!(
+        stderr(&out).contains("Your salt is empty!"),
+        "{}",
+        stderr(&out)
+    );
+    success(out);
+}
+
+#[test]
+fn fails_before_reading_the_passphrase() {
+    for (env, args) in [
+        (without_salt(""), &["--require-salt", "secret", "name"][..]),
+        (without_salt("require_salt = true\n"), &["secret", "name"]),
+    ] {
+        // Nothing is piped, so reading the passphrase would fail differently.
+        let out = env.run_with_stdin(args, "");
+        assert_code(&out, 2);
+        assert!(
+            stderr(&out).contains("the salt is required"),
+            "{}",
+            stderr(&out)
+        );
+
+        let out = env.run(&[args, &["--salt", "somesalt"]].concat());
+        assert_code(&out, 0);
+    }
+
+    // A salt in the config satisfies the flag.
+    success(Env::new().run(&["--require-salt", "secret", "name"]));
+}
+
+#[test]
+fn configure() {
+    let params = ["-m", "1", "-t", "1", "-p", "1", "--require-salt"];
+    let env = Env::new();
+
+    let out = env.run_with_stdin(
+        &[&params[..], &["configure", "--overwrite", "--no-backup"]].concat(),
+        "",
+    );
+    assert_code(&out, 2);
+    assert!(
+        stderr(&out).contains("--require-salt needs --salt"),
+        "{}",
+        stderr(&out)
+    );
+    assert_eq!(env.read("config.toml"), CONFIG);
+
+    success(
+        env.run_with_stdin(
+            &[
+                &params[..],
+                &["-s", "somesalt", "configure", "--overwrite", "--no-backup"],
+            ]
+            .concat(),
+            "",
+        ),
+    );
+    let config = env.read("config.toml");
+    assert!(config.contains("require_salt = true"), "{config}");
+}
@@ -0,0 +1,125 @@
+//! `rotate` subcommand.
+
+mod common;
+
+use common::{Env, assert_code, stderr, success};
+
+#[test]
+fn both_generations() {
+    let env = Env::new();
+    let old = success(env.run(&["secret", "email", "--generation", "1"]));
+    let new = success(env.run(&["secret", "email", "--generation", "2"]));
+
+    let out = success(env.run(&["rotate", "email", "--from-generation", "1"]));
+    assert_eq!(
+        out,
+        format!("# old (generation 1)\n{old}\n# new (generation 2)\n{new}\n")
+    );
+
+    let out = success(env.run(&[
+        "rotate",
+        "email",
+        "--from-generation",
+        "2",
+        "--to-generation",
+        "0",
+        "--format",
+        "json",
+    ]));
+    let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+    let zero = success(env.run(&["secret", "email"]));
+    assert_eq!(json, serde_json::json!({ "old": new, "new": zero }));
+}
+
+#[test]
+fn length_and_encoding() {
+    let env = Env::new();
+    let args = ["--length", "16", "--encoding", "base64"];
+    let old = success(env.run(&[&["secret", "email"], &args[..]].concat()));
+    let new = success(env.run(&[&["secret", "email", "--generation", "1"], &args[..]].concat()));
+
+    let out =
+        success(env.run(&[&["rotate", "email", "--from-generation", "0"], &args[..]].concat()));
+    assert_eq!(
+        out,
+        format!("# old (generation 0)\n{old}\n# new (generation 1)\n{new}\n")
+    );
+}
+
+#[test]
+fn age_identities() {
+    let env = Env::new();
+    let old = success(env.run(&["age", "email", "--generation", "3"]));
+    let new = success(env.run(&["age", "email", "--generation", "4"]));
+
+    let out = env.run(&["rotate", "email", "--from-generation", "3", "--age"]);
+    let identities = success(out.clone());
+    assert_eq!(
+        identities,
+        format!(
+            "# old (generation 3)\n{}\n# new (generation 4)\n{}\n",
+            old.trim_end(),
+            new.trim_end()
+        )
+    );
+
+    // Both recipients are printed to stderr, one per line.
+    let recipient = |identity: &str| {
+        identity
+            .lines()
+            .find_map(|line| line.strip_prefix("# public key: "))
+            .unwrap()
+            .to_owned()
+    };
+    let stderr = stderr(&out);
+    assert!(
+        stderr.contains(&format!("{}\n{}\n", recipient(&old), recipient(&new))),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn invalid_args() {
+    let env = Env::new();
+    for args in [
+        &["rotate", "email"][..],
+        &[
+            "rotate",
+            "email",
+            "--from-generation",
+            "1",
+            "--to-generation",
+            "1",
+        ],
+        &["rotate", "email", "--from-generation", "4294967295"],
+        &[
+            "rotate",
+            "email",
+            "--from-generation",
+            "1",
+            "--generation",
+            "2",
+        ],
+        &[
+            "rotate",
+            "email",
+            "--from-generation",
+            "1",
+            "--age",
+            "--length",
+            "16",
+        ],
+        &["rotate", "email", "--from-generation", "1", "--bech32m"],
+        &[
+            "rotate",
+            "email",
+            "--from-generation",
+            "1",
+            "--format",
+            "yaml",
+        ],
+        &["--dry-run", "rotate", "email", "--from-generation", "1"],
+    ] {
+        assert_code(&env.run(args), 2);
+    }
+}
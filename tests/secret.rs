@@ -0,0 +1,298 @@
+//! `secret` subcommand.
+
+mod common;
+
+use common::{Env, assert_code, success};
+
+#[test]
+fn dotenv_is_updated_in_place() {
+    let env = Env::new();
+    let secret = success(env.run(&["secret", "db-pass"]));
+    env.write(".env", "DB_PASS=old\nOTHER=1\n");
+
+    for _ in 0..2 {
+        let out = success(env.run(&["secret", "db-pass", "--dotenv", ".env"]));
+        assert_eq!(out, "");
+    }
+
+    assert_eq!(env.read(".env"), format!("DB_PASS={secret}\nOTHER=1\n"));
+}
+
+#[test]
+fn length_boundaries() {
+    let env = Env::new();
+
+    for length in ["0", "3"] {
+        let out = env.run(&["secret", "name", "--length", length]);
+        assert_code(&out, 2);
+        assert!(common::stderr(&out).contains("Output length is too short"));
+    }
+
+    let secret = success(env.run(&["secret", "name", "--length", "4"]));
+    assert_eq!(secret.len(), 8);
+
+    let out = env.run(&["secret", "name", "--length", "1073741825"]);
+    assert_code(&out, 2);
+    assert!(common::stderr(&out).contains("Output length is too long"));
+}
+
+#[test]
+fn algorithm_aliases() {
+    let env = Env::new();
+    let secret = success(env.run(&["secret", "name"]));
+
+    for alias in ["argon2id", "ARGON2-ID", "id"] {
+        assert_eq!(success(env.run(&["secret", "name", "-a", alias])), secret);
+    }
+    assert_ne!(success(env.run(&["secret", "name", "-a", "D"])), secret);
+    assert_code(&env.run(&["secret", "name", "-a", "argon2i"]), 2);
+}
+
+#[test]
+fn derivation_time() {
+    let env = Env::new();
+
+    let out = env.run(&["secret", "name"]);
+    assert_code(&out, 0);
+    let stderr = common::stderr(&out);
+    let line = stderr
+        .lines()
+        .find(|line| line.starts_with("Derived in "))
+        .unwrap_or_else(|| panic!("{stderr}"));
+    let seconds = line["Derived in ".len()..].strip_suffix('s').unwrap();
+    assert!(seconds.parse::<f64>().is_ok(), "{line}");
+    assert_eq!(seconds.split('.').nth(1).map(str::len), Some(2), "{line}");
+
+    let out = env.run(&["secret", "name", "--quiet"]);
+    assert_code(&out, 0);
+    assert!(!common::stderr(&out).contains("Derived in"));
+}
+
+#[test]
+fn template() {
+    let env = Env::new();
+    let template = |args: &[&str]| {
+        let args = [
+            &["secret", "site", "--template", "Aaaaaaaa-0000-ssss"],
+            args,
+        ]
+        .concat();
+        success(env.run(&args))
+    };
+
+    let password = template(&[]);
+    assert_eq!(password.len(), 18, "{password}");
+    assert_eq!(&password[8..9], "-");
+    assert_eq!(template(&[]), password);
+
+    // Rotated by the generation.
+    let rotated = template(&["--generation", "1"]);
+    assert_ne!(rotated, password);
+    assert_eq!(template(&["--generation", "1"]), rotated);
+
+    let out = env.run(&["secret", "site", "--template", "Aax"]);
+    assert_code(&out, 2);
+    assert!(
+        common::stderr(&out).contains("Unknown character class `x` at position 3"),
+        "{}",
+        common::stderr(&out)
+    );
+    assert_code(
+        &env.run(&["secret", "site", "--template", "A", "--length", "16"]),
+        2,
+    );
+}
+
+#[test]
+fn words_password() {
+    let env = Env::new();
+    let run =
+        |args: &[&str]| env.run(&[&["secret", "site", "-e", "words-password"], args].concat());
+
+    let out = run(&[]);
+    assert!(
+        common::stderr(&out).contains("Password entropy: 66.0 bits"),
+        "{}",
+        common::stderr(&out)
+    );
+    let password = success(out);
+    assert_eq!(password.split('-').count(), 6, "{password}");
+    assert!(
+        password
+            .bytes()
+            .all(|b| b == b'-' || b.is_ascii_lowercase())
+    );
+    assert_eq!(success(run(&[])), password);
+
+    let args = [
+        "--words",
+        "3",
+        "--separator",
+        ".",
+        "--capitalize",
+        "--suffix",
+    ];
+    let out = run(&args);
+    assert!(common::stderr(&out).contains("Password entropy: 41.1 bits"));
+    let shaped = success(out);
+    let parts: Vec<_> = shaped.split('.').collect();
+    assert_eq!(parts.len(), 4, "{shaped}");
+    assert!(
+        parts[..3]
+            .iter()
+            .all(|word| word.starts_with(|c: char| c.is_ascii_uppercase()))
+    );
+    assert_eq!(parts[3].len(), 2);
+    // Same words, shaped differently.
+    assert_eq!(parts[0].to_lowercase(), password.split('-').next().unwrap());
+
+    assert_code(&run(&["--words", "0"]), 2);
+}
+
+#[test]
+fn bits() {
+    let env = Env::new();
+    let bytes = |args: &[&str]| {
+        let out = success(env.run(&[&["secret", "name"][..], args].concat()));
+        hex::decode(out.trim_end()).unwrap()
+    };
+
+    assert_eq!(bytes(&["--bits", "256"]), bytes(&[]));
+    assert_eq!(bytes(&["--bits", "32"]), bytes(&["--length", "4"]));
+
+    // The 4 excess bits of the 5th byte are zeroed.
+    let masked = bytes(&["--bits", "36"]);
+    let whole = bytes(&["--length", "5"]);
+    assert_eq!(masked[..4], whole[..4]);
+    assert_eq!(masked[4], whole[4] & 0xf0);
+
+    let out = env.run(&["secret", "name", "--bits", "36"]);
+    assert_code(&out, 0);
+    assert!(
+        common::stderr(&out).contains(
+            "36 bits isn't a whole number of bytes, 5 bytes are derived with the last 4 bits zeroed"
+        ),
+        "{}",
+        common::stderr(&out)
+    );
+}
+
+#[test]
+fn bits_boundaries() {
+    let env = Env::new();
+    for bits in ["0", "24"] {
+        let out = env.run(&["secret", "name", "--bits", bits]);
+        assert_code(&out, 2);
+        assert!(common::stderr(&out).contains("Output length is too short"));
+    }
+    // Rounded up to the 4-byte minimum.
+    assert_code(&env.run(&["secret", "name", "--bits", "25"]), 0);
+
+    assert_code(
+        &env.run(&["secret", "name", "--bits", "32", "--length", "4"]),
+        2,
+    );
+    assert_code(&env.run(&["secret", "name", "--bits", "-8"]), 2);
+
+    let out = success(env.run_with_stdin(&["--dry-run", "secret", "name", "--bits", "36"], ""));
+    assert!(out.contains("Output length: 5 bytes"), "{out}");
+}
+
+#[test]
+fn window() {
+    let env = Env::new();
+    let bytes = |args: &[&str]| {
+        let out = success(env.run(&[&["secret", "name"][..], args].concat()));
+        hex::decode(out.trim_end()).unwrap()
+    };
+
+    let whole = bytes(&["--take", "32"]);
+    assert_eq!(whole, bytes(&[]));
+    assert_eq!(bytes(&["--offset", "0", "--take", "32"]), whole);
+    assert_eq!(bytes(&["--offset", "8", "--take", "24"]), whole[8..]);
+    // Shorter than the 4-byte minimum, as the derivation is 32 bytes long.
+    assert_eq!(bytes(&["--offset", "30", "--take", "2"]), whole[30..]);
+
+    // Argon2 mixes the total length into the output, so the windows of shorter derivations differ.
+    assert_ne!(bytes(&["--offset", "8", "--take", "8"]), whole[8..16]);
+}
+
+#[test]
+fn window_boundaries() {
+    let env = Env::new();
+    let code = |args: &[&str]| {
+        let out = env.run(&[&["secret", "name"][..], args].concat());
+        common::code(&out)
+    };
+
+    assert_eq!(code(&["--take", "0"]), 2);
+    assert_eq!(code(&["--offset", "1"]), 2);
+    assert_eq!(code(&["--offset", "1", "--take", "2"]), 2);
+    assert_eq!(code(&["--offset", "4294967295", "--take", "1"]), 2);
+    assert_eq!(code(&["--offset", "1073741824", "--take", "1"]), 2);
+    assert_eq!(code(&["--take", "4", "--length", "4"]), 2);
+    assert_eq!(code(&["--take", "4", "--bits", "32"]), 2);
+    assert_eq!(code(&["--take", "4", "--template", "aaaa"]), 2);
+    assert_eq!(code(&["--offset", "2", "--take", "2"]), 0);
+}
+
+#[test]
+fn source_literals() {
+    let env = Env::new();
+    let bytes = hex::decode(success(env.run(&["secret", "db-pass", "--length", "16"]))).unwrap();
+    let array: Vec<_> = bytes.iter().map(|byte| format!("0x{byte:02x},")).collect();
+
+    let rust = success(env.run(&[
+        "secret",
+        "db-pass",
+        "--length",
+        "16",
+        "--encoding",
+        "rust-array",
+    ]));
+    assert_eq!(
+        rust,
+        format!(
+            "const DB_PASS: [u8; 16] = [\n    {}\n    {}\n];\n",
+            array[..12].join(" "),
+            array[12..].join(" ")
+        )
+    );
+
+    let c = success(env.run(&[
+        "secret",
+        "db-pass",
+        "--length",
+        "16",
+        "--encoding",
+        "c-array",
+        "--ident",
+        "key",
+    ]));
+    assert!(c.starts_with("const uint8_t key[16] = {\n"), "{c}");
+    assert!(c.ends_with("};\n"), "{c}");
+
+    let dump = success(env.run(&[
+        "secret",
+        "db-pass",
+        "--length",
+        "16",
+        "--encoding",
+        "hexdump",
+    ]));
+    let groups: Vec<_> = bytes.chunks(2).map(hex::encode).collect();
+    assert!(
+        dump.starts_with(&format!("00000000: {}  ", groups.join(" "))),
+        "{dump}"
+    );
+    assert_eq!(dump.lines().count(), 1);
+}
+
+#[test]
+fn invalid_idents() {
+    let env = Env::new();
+    for ident in ["2FA", "API-KEY", ""] {
+        let out = env.run(&["secret", "x", "--encoding", "rust-array", "--ident", ident]);
+        assert_code(&out, 2);
+    }
+}
@@ -0,0 +1,88 @@
+//! `session` subcommand, the commands following the passphrase on stdin.
+
+mod common;
+
+use std::io::Write as _;
+use std::time::{Duration, Instant};
+
+use common::{Env, PASSPHRASE, assert_code, stderr, success};
+
+/// The passphrase is the first line of stdin.
+const STDIN: [&str; 3] = ["--stdin-passphrase", "--passphrase-trim", "newline"];
+
+#[test]
+fn derives_the_secrets_of_the_commands() {
+    let env = Env::new();
+    let secret = success(env.run(&["secret", "db-pass"]));
+    let short = success(env.run(&["secret", "db-pass", "--length", "16", "-e", "base64"]));
+    let identity = success(env.run(&["age", "laptop"]));
+
+    let stdin = format!(
+        "{PASSPHRASE}\nsecret db-pass\n\nhelp\nsecret db-pass 16 base64\nbogus\nage laptop\nquit\nsecret ignored\n"
+    );
+    let out = env.run_with_stdin(&[&STDIN[..], &["session"]].concat(), stdin);
+    let stderr = stderr(&out);
+    assert_eq!(success(out), format!("{secret}\n{short}\n{identity}"));
+    assert!(stderr.contains("Commands:"), "{stderr}");
+    assert!(stderr.contains("Error: Unknown command: bogus"), "{stderr}");
+}
+
+#[test]
+fn ends_on_eof() {
+    let env = Env::new();
+    let secret = success(env.run(&["secret", "db-pass"]));
+
+    let stdin = format!("{PASSPHRASE}\nsecret db-pass");
+    let out = env.run_with_stdin(&[&STDIN[..], &["session"]].concat(), stdin);
+    assert_eq!(success(out), format!("{secret}\n"));
+}
+
+#[test]
+fn times_out() {
+    let env = Env::new();
+
+    let mut child = env
+        .command(&[&STDIN[..], &["session", "--timeout", "1"]].concat())
+        .spawn()
+        .unwrap();
+    // Kept open, so only the timeout ends the session.
+    let mut stdin = child.stdin.take().unwrap();
+    writeln!(stdin, "{PASSPHRASE}").unwrap();
+
+    let start = Instant::now();
+    let out = child.wait_with_output().unwrap();
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert!(
+        stderr(&out).contains("Session timed out"),
+        "{}",
+        stderr(&out)
+    );
+    assert_code(&out, 0);
+    drop(stdin);
+}
+
+#[test]
+fn rejects_plans() {
+    let env = Env::new();
+    assert_code(&env.run(&["--dry-run", "session"]), 2);
+    assert_code(&env.run(&["--describe", "session"]), 2);
+}
+
+#[cfg(unix)]
+#[test]
+fn closed_stdout() {
+    let env = Env::new();
+
+    let mut child = env
+        .command(&[&STDIN[..], &["session"]].concat())
+        .spawn()
+        .unwrap();
+    // Closed before anything gets written to it.
+    drop(child.stdout.take());
+    let mut stdin = child.stdin.take().unwrap();
+    write!(stdin, "{PASSPHRASE}\nsecret db-pass\nsecret db-pass\n").unwrap();
+    drop(stdin);
+
+    let out = child.wait_with_output().unwrap();
+    assert_code(&out, 6);
+}
@@ -0,0 +1,45 @@
+//! `split` and `combine` subcommands.
+
+mod common;
+
+use common::{Env, assert_code, success};
+
+fn shares(env: &Env, args: &[&str]) -> Vec<String> {
+    let output = success(env.run(&[&["split", "shamir test"], args].concat()));
+    output.lines().map(str::to_owned).collect()
+}
+
+#[test]
+fn split_and_combine() {
+    let env = Env::new();
+    let secret = success(env.run(&["secret", "shamir test", "-e", "hex"]));
+
+    let shares = shares(&env, &["--shares", "3", "--threshold", "2"]);
+    assert_eq!(shares.len(), 3);
+
+    for pair in [[0, 1], [0, 2], [2, 1]] {
+        let args = ["combine", &shares[pair[0]], &shares[pair[1]]];
+        assert_eq!(success(env.run(&args)), secret);
+    }
+
+    assert_code(&env.run(&["combine", &shares[0]]), 2);
+    assert_code(&env.run(&["combine", &shares[0], &shares[0]]), 2);
+
+    // Splits of the same secret don't mix.
+    let other = self::shares(&env, &["--shares", "3", "--threshold", "2"]);
+    assert_ne!(other, shares);
+    assert_code(&env.run(&["combine", &shares[0], &other[1]]), 2);
+}
+
+#[test]
+fn rejects_invalid_thresholds() {
+    let env = Env::new();
+    assert_code(
+        &env.run(&["split", "shamir test", "--shares", "3", "--threshold", "0"]),
+        2,
+    );
+    assert_code(
+        &env.run(&["split", "shamir test", "--shares", "3", "--threshold", "4"]),
+        2,
+    );
+}
@@ -0,0 +1,53 @@
+//! SLIP-0010 `--path` of `age` and `tls-cert`.
+
+mod common;
+
+use argon2derive::{
+    age,
+    slip10::{self, Curve},
+};
+use common::{Env, assert_code, success};
+
+#[test]
+fn age_key_of_the_path() {
+    let env = Env::new();
+    let seed = hex::decode(success(env.run(&["secret", "laptop", "--length", "64"]))).unwrap();
+
+    for path in ["m", "m/0'", "m/44'/0'/1'"] {
+        let node = slip10::derive(Curve::Curve25519, &seed, &path.parse().unwrap()).unwrap();
+        let identity = age::identity(node.private_key, age::Variant::Bech32).unwrap();
+
+        let out = success(env.run(&["age", "laptop", "--path", path]));
+        assert!(out.contains(&identity), "{path}: {out}");
+    }
+
+    // Without a path, the key is the 32-byte output.
+    let plain = success(env.run(&["age", "laptop"]));
+    assert_ne!(plain, success(env.run(&["age", "laptop", "--path", "m"])));
+}
+
+#[test]
+fn tls_key_of_the_path() {
+    let env = Env::new();
+    let cert = |path: &str| success(env.run(&["tls-cert", "host", "--path", path]));
+
+    assert_eq!(cert("m/0'"), cert("m/0h"));
+    assert_ne!(cert("m/0'"), cert("m/1'"));
+    assert_ne!(cert("m/0'"), success(env.run(&["tls-cert", "host"])));
+}
+
+#[test]
+fn invalid_paths() {
+    let env = Env::new();
+    for path in ["m/0", "44'/0'", "m/x'", "m/2147483648'"] {
+        // Rejected before the passphrase is read.
+        assert_code(
+            &env.run_with_stdin(&["age", "laptop", "--path", path], ""),
+            2,
+        );
+        assert_code(
+            &env.run_with_stdin(&["tls-cert", "host", "--path", path], ""),
+            2,
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! systemd credentials of the `secret` subcommand, using a fake `systemd-creds`.
+
+#![cfg(unix)]
+
+mod common;
+
+use common::{Env, PASSPHRASE, assert_code, stderr, success};
+
+/// Fake `systemd-creds`, recording its arguments and prefixing the plaintext.
+const SYSTEMD_CREDS: &str = r#"echo "$@" > args.txt
+printf 'encrypted:'
+cat
+"#;
+
+#[test]
+fn credential_directory() {
+    let env = Env::new();
+    let secret = success(env.run(&["secret", "db-pass"]));
+    let dir = env.path("credentials");
+    std::fs::create_dir(&dir).unwrap();
+
+    let mut cmd = env.command(&["secret", "db-pass", "--credential", "db"]);
+    cmd.env("CREDENTIALS_DIRECTORY", &dir);
+    assert_eq!(success(common::run(cmd, PASSPHRASE.as_bytes())), "");
+    assert_eq!(env.read("credentials/db"), secret.trim_end());
+
+    // Not run by systemd.
+    let out = env.run(&["secret", "db-pass", "--credential", "db"]);
+    assert_code(&out, 2);
+    assert!(
+        stderr(&out).contains("$CREDENTIALS_DIRECTORY is not set"),
+        "{}",
+        stderr(&out)
+    );
+
+    for name in ["", "..", "a/b"] {
+        let mut cmd = env.command(&["secret", "db-pass", "--credential", name]);
+        cmd.env("CREDENTIALS_DIRECTORY", &dir);
+        assert_code(&common::run(cmd, PASSPHRASE.as_bytes()), 2);
+    }
+}
+
+#[test]
+fn encrypted() {
+    let env = Env::new();
+    let secret = success(env.run(&["secret", "db-pass"]));
+    env.script("systemd-creds", SYSTEMD_CREDS);
+
+    let cmd = env.with_bin(&["secret", "db-pass", "--format", "systemd-creds"]);
+    let out = success(common::run(cmd, PASSPHRASE.as_bytes()));
+    assert_eq!(out, format!("encrypted:{}", secret.trim_end()));
+    assert_eq!(env.read("args.txt"), "encrypt --name=db-pass - -\n");
+
+    let dir = env.path("credentials");
+    std::fs::create_dir(&dir).unwrap();
+    let args = [
+        "secret",
+        "db-pass",
+        "--format",
+        "systemd-creds",
+        "--credential",
+        "db",
+    ];
+    let mut cmd = env.with_bin(&args);
+    cmd.env("CREDENTIALS_DIRECTORY", &dir);
+    assert_eq!(success(common::run(cmd, PASSPHRASE.as_bytes())), "");
+    assert_eq!(
+        env.read("credentials/db"),
+        format!("encrypted:{}", secret.trim_end())
+    );
+    assert_eq!(env.read("args.txt"), "encrypt --name=db - -\n");
+}
+
+#[test]
+fn failures() {
+    let env = Env::new();
+    env.script("systemd-creds", "cat > /dev/null\nexit 3\n");
+
+    let cmd = env.with_bin(&["secret", "db-pass", "--format", "systemd-creds"]);
+    let out = common::run(cmd, PASSPHRASE.as_bytes());
+    assert_code(&out, 6);
+    assert!(stderr(&out).contains("exit status: 3"), "{}", stderr(&out));
+    assert!(out.stdout.is_empty());
+
+    // No systemd.
+    let mut cmd = env.command(&["secret", "db-pass", "--format", "systemd-creds"]);
+    cmd.env("PATH", env.path("empty"));
+    let out = common::run(cmd, PASSPHRASE.as_bytes());
+    assert_code(&out, 6);
+    assert!(
+        stderr(&out).contains("`systemd-creds` not found"),
+        "{}",
+        stderr(&out)
+    );
+}
@@ -0,0 +1,49 @@
+//! `--time-budget` option.
+
+mod common;
+
+use common::{Env, assert_code, stderr, success};
+
+/// Time cost picked by the run, as printed in its warning.
+fn picked_time(stderr: &str) -> String {
+    let (_, rest) = stderr.split_once("Picked time cost ").expect(stderr);
+    rest.split_whitespace().next().unwrap().to_owned()
+}
+
+#[test]
+fn picks_a_time_cost_to_pin() {
+    let env = Env::new();
+    let args = ["secret", "name", "--time-budget", "10", "--override-time"];
+    let out = env.run(&args);
+    let time = picked_time(&stderr(&out));
+    let secret = success(out);
+
+    assert_eq!(
+        secret,
+        success(env.run(&["secret", "name", "--time", &time]))
+    );
+}
+
+#[test]
+fn refused_over_the_config() {
+    let env = Env::new();
+    let out = env.run(&["secret", "name", "--time-budget", "10"]);
+    assert_code(&out, 2);
+    assert!(stderr(&out).contains("--override-time"), "{}", stderr(&out));
+
+    let out = env.run(&["secret", "name", "--time-budget", "10", "--override-time"]);
+    assert_code(&out, 0);
+}
+
+#[test]
+fn invalid_args() {
+    let env = Env::new();
+    for args in [
+        &["secret", "name", "--time-budget", "0", "--override-time"][..],
+        &["secret", "name", "--time-budget", "10", "--time", "1"],
+        &["secret", "name", "--override-time"],
+        &["--time-budget", "10", "tune"],
+    ] {
+        assert_code(&env.run(args), 2);
+    }
+}
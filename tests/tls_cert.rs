@@ -0,0 +1,106 @@
+//! `tls-cert` subcommand.
+
+mod common;
+
+use std::time::Duration;
+
+use base64::Engine as _;
+use common::{Env, assert_code, success};
+use rustls_pki_types::{CertificateDer, ServerName, UnixTime};
+use sha2::{Digest as _, Sha256};
+
+/// DER of the first PEM block.
+fn der(pem: &str) -> Vec<u8> {
+    let base64: String = pem
+        .lines()
+        .skip(1)
+        .take_while(|line| !line.starts_with("-----END"))
+        .collect();
+    base64::engine::general_purpose::STANDARD
+        .decode(base64)
+        .unwrap()
+}
+
+#[test]
+fn verified_by_webpki() {
+    let env = Env::new();
+    let args = [
+        "tls-cert",
+        "host",
+        "--dns",
+        "example.test",
+        "--dns",
+        "192.0.2.1",
+    ];
+    success(env.run(&[&args[..], &["--output", "certs"]].concat()));
+
+    let der = CertificateDer::from(der(&env.read("certs/cert.pem")));
+    let cert = webpki::EndEntityCert::try_from(&der).unwrap();
+    let anchors = [webpki::anchor_from_trusted_cert(&der).unwrap()];
+
+    // 2026-01-01, within the default validity period (2025-01-01 + 3650 days).
+    let now = UnixTime::since_unix_epoch(Duration::from_secs(1_767_225_600));
+    cert.verify_for_usage(
+        webpki::ALL_VERIFICATION_ALGS,
+        &anchors,
+        &[],
+        now,
+        webpki::KeyUsage::server_auth(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    // 2035-01-01, past the validity period.
+    let expired = UnixTime::since_unix_epoch(Duration::from_secs(2_051_222_400));
+    assert!(matches!(
+        cert.verify_for_usage(
+            webpki::ALL_VERIFICATION_ALGS,
+            &anchors,
+            &[],
+            expired,
+            webpki::KeyUsage::server_auth(),
+            None,
+            None,
+        ),
+        Err(webpki::Error::CertExpired { .. })
+    ));
+
+    for name in ["example.test", "192.0.2.1"] {
+        let name = ServerName::try_from(name).unwrap();
+        cert.verify_is_valid_for_subject_name(&name).unwrap();
+    }
+    let other = ServerName::try_from("other.test").unwrap();
+    assert!(cert.verify_is_valid_for_subject_name(&other).is_err());
+}
+
+#[test]
+fn stable_fingerprint() {
+    let env = Env::new();
+    let args = [
+        "tls-cert",
+        "host",
+        "--dns",
+        "example.test",
+        "--dns",
+        "192.0.2.1",
+    ];
+
+    // Checked with `openssl x509 -fingerprint -sha256`.
+    let fingerprint = "302663cac8bcc0d2efe001e47b6aff59dbf40e80f398186edb0f2bfd0ac6f5f5";
+    for _ in 0..2 {
+        let out = success(env.run(&args));
+        assert_eq!(hex::encode(Sha256::digest(der(&out))), fingerprint);
+    }
+
+    let later = success(env.run(&[&args[..], &["--not-before", "2025-01-02"]].concat()));
+    assert_ne!(hex::encode(Sha256::digest(der(&later))), fingerprint);
+}
+
+#[test]
+fn invalid_args() {
+    let env = Env::new();
+    for date in ["2025-13-01", "2025-02-30", "2025-01", "01/01/2025"] {
+        assert_code(&env.run(&["tls-cert", "host", "--not-before", date]), 2);
+    }
+}
@@ -0,0 +1,113 @@
+//! Passphrase prompts on the terminal while stdin carries the data.
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::{Env, PASSPHRASE, assert_code, stderr, success};
+
+#[test]
+fn data_on_stdin_passphrase_on_the_terminal() {
+    let env = Env::new();
+    env.write("data.txt", "hello");
+    let expected = success(env.run(&["hmac", "--name", "key", "data.txt"]));
+    let expected = expected.split_whitespace().next().unwrap();
+
+    let out = common::run_with_tty(
+        env.command(&["hmac", "--name", "key"]),
+        b"hello",
+        &format!("{PASSPHRASE}\n"),
+    );
+    let mac = success(out);
+    assert_eq!(mac.split_whitespace().next().unwrap(), expected);
+}
+
+#[test]
+fn passphrase_tty() {
+    let env = Env::new();
+    let expected = success(env.run(&["secret", "name"]));
+
+    let cmd = env.command(&["secret", "name", "--passphrase-tty"]);
+    let out = common::run_with_tty(cmd, b"not the passphrase\n", &format!("{PASSPHRASE}\n"));
+    assert_eq!(success(out), expected);
+}
+
+#[test]
+fn no_terminal() {
+    let env = Env::new();
+
+    let out = common::run_without_tty(env.command(&["hmac", "--name", "key"]), b"hello");
+    assert_code(&out, 4);
+    assert!(
+        stderr(&out).contains("No terminal available"),
+        "{}",
+        stderr(&out)
+    );
+
+    let cmd = env.command(&["secret", "name", "--passphrase-tty"]);
+    assert_code(&common::run_without_tty(cmd, PASSPHRASE.as_bytes()), 4);
+
+    // Piping the passphrase doesn't need a terminal.
+    let cmd = env.command(&["secret", "name"]);
+    assert_code(&common::run_without_tty(cmd, PASSPHRASE.as_bytes()), 0);
+}
+
+#[test]
+fn forced_prompt() {
+    let env = Env::new();
+    let expected = success(env.run(&["secret", "name"]));
+
+    let cmd = env.command(&["secret", "name", "--prompt"]);
+    let out = common::run_with_tty(cmd, b"not the passphrase\n", &format!("{PASSPHRASE}\n"));
+    assert!(
+        stderr(&out).contains("Enter passphrase:"),
+        "{}",
+        stderr(&out)
+    );
+    assert_eq!(success(out), expected);
+
+    let cmd = env.command(&["secret", "name", "--prompt"]);
+    assert_code(&common::run_without_tty(cmd, PASSPHRASE.as_bytes()), 4);
+}
+
+#[test]
+fn forced_stdin_passphrase() {
+    let env = Env::new();
+    let expected = success(env.run(&["secret", "name"]));
+
+    // The terminal is ignored, nothing gets prompted for.
+    let cmd = env.command(&["secret", "name", "--stdin-passphrase"]);
+    let out = common::run_with_tty(cmd, PASSPHRASE.as_bytes(), "not the passphrase\n");
+    assert!(
+        !stderr(&out).contains("Enter passphrase:"),
+        "{}",
+        stderr(&out)
+    );
+    assert_eq!(success(out), expected);
+
+    // The first line is the passphrase (its newline being trimmed), the rest is the data.
+    env.write("data.txt", "hello");
+    let expected = success(env.run(&["hmac", "--name", "key", "data.txt"]));
+    let args = [
+        "hmac",
+        "--name",
+        "key",
+        "--stdin-passphrase",
+        "--passphrase-trim",
+        "newline",
+    ];
+    let cmd = env.command(&args);
+    let stdin = format!("{PASSPHRASE}\nhello");
+    let out = common::run_with_tty(cmd, stdin.as_bytes(), "not the passphrase\n");
+    assert_eq!(
+        success(out).split_whitespace().next(),
+        expected.split_whitespace().next()
+    );
+}
+
+#[test]
+fn forced_modes_conflict() {
+    let env = Env::new();
+    let out = env.run(&["secret", "name", "--prompt", "--stdin-passphrase"]);
+    assert_code(&out, 2);
+}
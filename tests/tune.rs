@@ -0,0 +1,27 @@
+//! `tune` subcommand (`tui` feature).
+
+#![cfg(feature = "tui")]
+
+mod common;
+
+use common::{Env, assert_code, stderr};
+
+#[test]
+fn requires_a_terminal() {
+    let env = Env::new();
+    let before = env.read("config.toml");
+
+    let out = env.run(&["tune"]);
+    assert_code(&out, 2);
+    assert!(
+        stderr(&out).contains("interactive terminal"),
+        "{}",
+        stderr(&out)
+    );
+    assert_eq!(env.read("config.toml"), before);
+}
+
+#[test]
+fn rejects_a_time_budget() {
+    assert_code(&Env::new().run(&["--time-budget", "100", "tune"]), 2);
+}
@@ -0,0 +1,45 @@
+//! Warnings about weak parameters.
+
+mod common;
+
+use common::{CONFIG, Env, assert_code, stderr, stdout};
+
+const LOW_MEMORY: &str = "is dangerously low";
+
+#[test]
+fn low_memory() {
+    let env = Env::new();
+
+    let out = env.run(&["secret", "name"]);
+    assert_code(&out, 0);
+    assert!(stderr(&out).contains(LOW_MEMORY), "{}", stderr(&out));
+
+    let out = env.run(&["secret", "name", "--no-warn"]);
+    assert_code(&out, 0);
+    assert!(!stderr(&out).contains(LOW_MEMORY), "{}", stderr(&out));
+}
+
+const SWAPPED: &str = "did you swap --memory and --time?";
+
+#[test]
+fn sanity() {
+    // 8 MiB per iteration.
+    let typical = Env::with_config(&CONFIG.replace("memory = 64", "memory = 8192"));
+    let out = typical.run(&["secret", "name"]);
+    assert_code(&out, 0);
+    assert!(!stderr(&out).contains(SWAPPED), "{}", stderr(&out));
+
+    let swapped = Env::with_config(
+        &CONFIG
+            .replace("memory = 64", "memory = 8192")
+            .replace("time = 1", "time = 2"),
+    );
+    let out = swapped.run(&["secret", "name"]);
+    assert_code(&out, 0);
+    assert!(stderr(&out).contains(SWAPPED), "{}", stderr(&out));
+
+    // Advisory only.
+    let out = swapped.run(&["secret", "name", "--no-sanity"]);
+    assert!(!stderr(&out).contains(SWAPPED), "{}", stderr(&out));
+    assert_eq!(stdout(&out), stdout(&swapped.run(&["secret", "name"])));
+}
@@ -0,0 +1,73 @@
+//! `whoami` subcommand.
+
+mod common;
+
+use base64::Engine as _;
+use common::{Env, assert_code, success};
+use sha2::{Digest as _, Sha256};
+
+/// Key printed for the format.
+fn key<'a>(out: &'a str, format: &str) -> &'a str {
+    out.lines()
+        .find_map(|line| line.strip_prefix(&format!("{format}: ")))
+        .unwrap_or_else(|| panic!("no {format} key in {out}"))
+}
+
+#[test]
+fn matches_the_subcommands() {
+    let env = Env::new();
+    let out = success(env.run(&["whoami", "laptop"]));
+    assert_eq!(out.lines().count(), 2, "{out}");
+
+    let identity = success(env.run(&["age", "laptop"]));
+    assert!(identity.starts_with(&format!("# public key: {}\n", key(&out, "age"))));
+
+    // The Ed25519 key ends the SubjectPublicKeyInfo of the certificate.
+    success(env.run(&["tls-cert", "laptop", "--output", "certs"]));
+    let pem = env.read("certs/cert.pem");
+    let der = base64::engine::general_purpose::STANDARD
+        .decode(
+            pem.lines()
+                .filter(|line| !line.starts_with("-----"))
+                .collect::<String>(),
+        )
+        .unwrap();
+    let public_key = hex::decode(key(&out, "tls-cert (Ed25519)")).unwrap();
+    assert_eq!(public_key.len(), 32);
+    assert!(der.windows(32).any(|window| window == public_key));
+}
+
+#[test]
+fn only_public_material() {
+    let env = Env::new();
+    let out = success(env.run(&["whoami", "laptop"]));
+    let secret = success(env.run(&["secret", "laptop", "--length", "32"]));
+    let identity = success(env.run(&["age", "laptop"]));
+
+    assert!(!out.contains(secret.trim_end()));
+    assert!(!out.to_uppercase().contains("SECRET"));
+    assert!(identity.contains("AGE-SECRET-KEY-1"));
+}
+
+#[test]
+fn rsa_key() {
+    let env = Env::new();
+    let out = success(env.run(&["whoami", "key", "--rsa-bits", "2048"]));
+
+    // Same fingerprint as the one pinned by `rsa key --bits 2048 --fingerprint`.
+    let line = key(&out, "rsa (OpenSSH)");
+    let blob = line.split(' ').nth(1).unwrap();
+    let blob = base64::engine::general_purpose::STANDARD
+        .decode(blob)
+        .unwrap();
+    let fingerprint = base64::engine::general_purpose::STANDARD_NO_PAD.encode(Sha256::digest(blob));
+    assert!(line.starts_with("ssh-rsa "));
+    assert_eq!(fingerprint, "a3p1oUv1uPaO+EeXcBgxZXi9ki9HuIC37OG8yjVpTZo");
+}
+
+#[test]
+fn invalid_args() {
+    let env = Env::new();
+    assert_code(&env.run(&["whoami"]), 2);
+    assert_code(&env.run(&["whoami", "laptop", "--rsa-bits", "1024"]), 2);
+}
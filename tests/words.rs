@@ -0,0 +1,39 @@
+//! `secret --encoding words` and `decode-words` subcommand.
+
+mod common;
+
+use common::{Env, assert_code, success};
+
+#[test]
+fn round_trip() {
+    let env = Env::new();
+    let hex = success(env.run(&["secret", "paper", "-e", "hex"]));
+    let words = success(env.run(&["secret", "paper", "-e", "words"]));
+
+    assert_eq!(success(env.run_with_stdin(&["decode-words"], &words)), hex);
+
+    let args: Vec<_> = ["decode-words"]
+        .into_iter()
+        .chain(words.split_whitespace())
+        .collect();
+    assert_eq!(success(env.run(&args)), hex);
+}
+
+#[test]
+fn transcription_error() {
+    let env = Env::new();
+    let words = success(env.run(&["secret", "paper", "-e", "words"]));
+
+    let line = words.lines().nth(2).unwrap();
+    let word = line.split_whitespace().nth(1).unwrap();
+    let replacement = if word == "abandon" {
+        "ability"
+    } else {
+        "abandon"
+    };
+    let typo = words.replacen(line, &line.replacen(word, replacement, 1), 1);
+
+    let out = env.run_with_stdin(&["decode-words"], typo);
+    assert_code(&out, 7);
+    assert!(common::stderr(&out).contains("group(s): 3"));
+}